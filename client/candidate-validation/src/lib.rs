@@ -0,0 +1,156 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Validation of parachain candidates against the local validation code.
+//!
+//! Runs the `validate_block` function of the validation code known to this node against a given
+//! proof of validity, the same way the validators of the relay chain do. This helps to investigate
+//! why the relay chain rejected a specific candidate. The validation is exposed through the RPC
+//! methods of [`rpc::CandidateValidationApi`].
+//!
+//! The validation code is read from the parent block of the candidate. If this block is not known
+//! to the node, the validation code of the best block is used instead.
+
+use codec::{Decode, Encode};
+use cumulus_primitives_core::{PersistedValidationData, ValidationParams};
+use polkadot_parachain::primitives::{BlockData, ValidationResult};
+use sc_client_api::{Backend, StateBackend};
+use sc_executor::{WasmExecutionMethod, WasmExecutor};
+use sc_executor_common::runtime_blob::RuntimeBlob;
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::well_known_keys;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT},
+};
+use sp_state_machine::BasicExternalities;
+
+pub mod rpc;
+
+const LOG_TARGET: &str = "candidate-validation";
+
+/// Number of heap pages the validation code is executed with.
+const HEAP_PAGES: u64 = 1024;
+
+/// Errors that can occur while validating a candidate.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+	#[error("Failed to decode the proof of validity: {0}")]
+	InvalidPoV(codec::Error),
+	#[error("Failed to decompress the proof of validity: {0}")]
+	Decompression(sp_maybe_compressed_blob::Error),
+	#[error("Failed to decode the parent head: {0}")]
+	InvalidParentHead(codec::Error),
+	#[error("No validation code found at block `{0}`")]
+	MissingValidationCode(String),
+	#[error("Invalid validation code: {0}")]
+	InvalidValidationCode(String),
+	#[error("Blockchain returned an error: {0}")]
+	Blockchain(#[from] sp_blockchain::Error),
+	#[error("Executing the validation code failed: {0}")]
+	Execution(String),
+	#[error("Failed to decode the validation result: {0}")]
+	InvalidResult(codec::Error),
+}
+
+impl Error {
+	/// Returns `true` if the error is caused by the candidate, i.e. the candidate is invalid.
+	pub fn is_invalid_candidate(&self) -> bool {
+		matches!(self, Self::Decompression(_) | Self::Execution(_) | Self::InvalidResult(_))
+	}
+}
+
+/// Validate the candidate with the given proof of validity `pov` and validation data `pvd`.
+///
+/// `pov` is the SCALE encoded proof of validity as it is distributed between the validators of
+/// the relay chain.
+pub fn validate_candidate<Block, B>(
+	backend: &B,
+	pov: &[u8],
+	pvd: &PersistedValidationData,
+) -> Result<ValidationResult, Error>
+where
+	Block: BlockT,
+	B: Backend<Block>,
+{
+	let block_data = BlockData::decode(&mut &pov[..]).map_err(Error::InvalidPoV)?;
+	let block_data =
+		sp_maybe_compressed_blob::decompress(&block_data.0, pov_bomb_limit(pvd.max_pov_size))
+			.map_err(Error::Decompression)?
+			.into_owned();
+
+	let parent_header =
+		Block::Header::decode(&mut &pvd.parent_head.0[..]).map_err(Error::InvalidParentHead)?;
+	let code = validation_code(backend, parent_header.hash())?;
+
+	let params = ValidationParams {
+		parent_head: pvd.parent_head.clone(),
+		block_data: BlockData(block_data),
+		relay_parent_number: pvd.relay_parent_number,
+		relay_parent_storage_root: pvd.relay_parent_storage_root,
+	};
+
+	let executor = WasmExecutor::<sp_io::SubstrateHostFunctions>::new(
+		WasmExecutionMethod::Interpreted,
+		Some(HEAP_PAGES),
+		1,
+		None,
+		1,
+	);
+	let blob = RuntimeBlob::uncompress_if_needed(&code)
+		.map_err(|e| Error::InvalidValidationCode(e.to_string()))?;
+
+	let mut ext = BasicExternalities::default();
+	let result = executor
+		.uncached_call(blob, &mut ext, false, "validate_block", &params.encode())
+		.map_err(|e| Error::Execution(e.to_string()))?;
+
+	ValidationResult::decode(&mut &result[..]).map_err(Error::InvalidResult)
+}
+
+/// Returns the validation code to validate a candidate built on top of `parent`.
+///
+/// Falls back to the validation code of the best block if `parent` is not known.
+fn validation_code<Block, B>(backend: &B, parent: Block::Hash) -> Result<Vec<u8>, Error>
+where
+	Block: BlockT,
+	B: Backend<Block>,
+{
+	let blockchain = backend.blockchain();
+	let at = if blockchain.header(BlockId::Hash(parent))?.is_some() {
+		parent
+	} else {
+		let best_hash = blockchain.info().best_hash;
+		tracing::debug!(
+			target: LOG_TARGET,
+			?parent,
+			?best_hash,
+			"Parent of the candidate is unknown, using the validation code of the best block.",
+		);
+		best_hash
+	};
+
+	backend
+		.state_at(BlockId::Hash(at))?
+		.storage(well_known_keys::CODE)
+		.map_err(|e| Error::Blockchain(sp_blockchain::Error::Backend(e.to_string())))?
+		.ok_or_else(|| Error::MissingValidationCode(at.to_string()))
+}
+
+/// Maximum size of the decompressed proof of validity, mirroring the limit of the validators.
+fn pov_bomb_limit(max_pov_size: u32) -> usize {
+	max_pov_size as usize * 4
+}
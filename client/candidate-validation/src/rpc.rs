@@ -0,0 +1,150 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC methods to validate candidates against the local validation code.
+//!
+//! All methods are unsafe and only available if unsafe RPC methods are allowed.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Decode;
+use cumulus_primitives_core::PersistedValidationData;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use polkadot_parachain::primitives::ValidationResult;
+use sc_client_api::Backend;
+use sc_rpc_api::DenyUnsafe;
+use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
+use sp_runtime::traits::Block as BlockT;
+
+use crate::{validate_candidate, Error, LOG_TARGET};
+
+/// The outcome of validating a candidate.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CandidateValidation {
+	/// The candidate is valid and produced the given outputs.
+	Valid(ValidCandidate),
+	/// The candidate is invalid.
+	Invalid {
+		/// Why the candidate is invalid.
+		error: String,
+	},
+}
+
+/// The outputs of a valid candidate.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidCandidate {
+	/// The head data of the candidate.
+	pub head_data: Bytes,
+	/// The validation code upgrade signalled by the candidate.
+	pub new_validation_code: Option<Bytes>,
+	/// The upward messages sent by the candidate.
+	pub upward_messages: Vec<Bytes>,
+	/// The horizontal messages sent by the candidate.
+	pub horizontal_messages: Vec<HorizontalMessage>,
+	/// The number of downward messages processed by the candidate.
+	pub processed_downward_messages: u32,
+	/// The relay chain block number up to which horizontal messages were processed.
+	pub hrmp_watermark: u32,
+}
+
+/// A horizontal message sent by a candidate.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HorizontalMessage {
+	/// The parachain the message is sent to.
+	pub recipient: u32,
+	/// The message.
+	pub data: Bytes,
+}
+
+impl From<ValidationResult> for ValidCandidate {
+	fn from(result: ValidationResult) -> Self {
+		Self {
+			head_data: result.head_data.0.into(),
+			new_validation_code: result.new_validation_code.map(|code| code.0.into()),
+			upward_messages: result.upward_messages.into_iter().map(Into::into).collect(),
+			horizontal_messages: result
+				.horizontal_messages
+				.into_iter()
+				.map(|msg| HorizontalMessage {
+					recipient: msg.recipient.into(),
+					data: msg.data.into(),
+				})
+				.collect(),
+			processed_downward_messages: result.processed_downward_messages,
+			hrmp_watermark: result.hrmp_watermark,
+		}
+	}
+}
+
+/// Candidate validation RPC methods.
+#[rpc]
+pub trait CandidateValidationApi {
+	/// Validate a candidate against the validation code known to this node.
+	///
+	/// `pov` is the SCALE encoded proof of validity and `pvd` the SCALE encoded persisted
+	/// validation data of the candidate. The validation code is executed in the interpreter, so
+	/// this call is expensive.
+	#[rpc(name = "parachain_validateCandidate")]
+	fn validate_candidate(&self, pov: Bytes, pvd: Bytes) -> Result<CandidateValidation>;
+}
+
+/// Implementation of [`CandidateValidationApi`].
+pub struct CandidateValidationRpc<Block, B> {
+	backend: Arc<B>,
+	deny_unsafe: DenyUnsafe,
+	_phantom: PhantomData<Block>,
+}
+
+impl<Block, B> CandidateValidationRpc<Block, B> {
+	/// Create a new instance that reads the validation code from `backend`.
+	pub fn new(backend: Arc<B>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { backend, deny_unsafe, _phantom: PhantomData }
+	}
+}
+
+impl<Block, B> CandidateValidationApi for CandidateValidationRpc<Block, B>
+where
+	Block: BlockT,
+	B: Backend<Block> + 'static,
+{
+	fn validate_candidate(&self, pov: Bytes, pvd: Bytes) -> Result<CandidateValidation> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let pvd = PersistedValidationData::decode(&mut &pvd[..]).map_err(|e| {
+			RpcError::invalid_params(format!("Failed to decode the validation data: {}", e))
+		})?;
+
+		match validate_candidate(&*self.backend, &pov, &pvd) {
+			Ok(result) => Ok(CandidateValidation::Valid(result.into())),
+			Err(e) if e.is_invalid_candidate() => {
+				tracing::debug!(target: LOG_TARGET, error = %e, "Candidate is invalid.");
+				Ok(CandidateValidation::Invalid { error: e.to_string() })
+			},
+			Err(e @ (Error::InvalidPoV(_) | Error::InvalidParentHead(_))) =>
+				Err(RpcError::invalid_params(e.to_string())),
+			Err(e) => Err(RpcError {
+				code: ErrorCode::ServerError(1),
+				message: "Failed to validate the candidate".into(),
+				data: Some(e.to_string().into()),
+			}),
+		}
+	}
+}
@@ -19,14 +19,24 @@
 #![warn(missing_docs)]
 
 use clap::Parser;
+use codec::Encode;
+use sc_cli::{RuntimeVersion, SubstrateCli};
 use sc_service::{
 	config::{PrometheusConfig, TelemetryEndpoints},
-	BasePath, TransactionPoolOptions,
+	BasePath, ChainSpec, TransactionPoolOptions,
+};
+use sp_core::{hexdisplay::HexDisplay, storage::well_known_keys};
+use sp_runtime::{
+	traits::{Block as BlockT, Hash as HashT, Header as HeaderT, Zero},
+	StateVersion,
 };
 use std::{
 	fs,
 	io::{self, Write},
+	iter,
+	marker::PhantomData,
 	net::SocketAddr,
+	path::PathBuf,
 };
 use url::Url;
 
@@ -119,6 +129,124 @@ impl sc_cli::CliConfiguration for PurgeChainCmd {
 	}
 }
 
+/// Command for exporting the genesis state of the parachain
+#[derive(Debug, Parser)]
+pub struct ExportGenesisStateCommand {
+	/// Output file name or stdout if unspecified.
+	#[clap(parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Write output in binary. Default is to write in hex.
+	#[clap(short, long)]
+	pub raw: bool,
+
+	/// The name of the chain for that the genesis state should be exported.
+	#[clap(long)]
+	pub chain: Option<String>,
+}
+
+impl ExportGenesisStateCommand {
+	/// Run the export-genesis-state command
+	#[allow(clippy::borrowed_box)]
+	pub fn run<Block: BlockT>(
+		&self,
+		chain_spec: &Box<dyn ChainSpec>,
+		genesis_state_version: StateVersion,
+	) -> sc_cli::Result<()> {
+		let block: Block = generate_genesis_block(chain_spec, genesis_state_version)?;
+		let raw_header = block.header().encode();
+
+		write_output(&self.output, self.raw, raw_header)
+	}
+}
+
+/// Command for exporting the genesis wasm file.
+#[derive(Debug, Parser)]
+pub struct ExportGenesisWasmCommand {
+	/// Output file name or stdout if unspecified.
+	#[clap(parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Write output in binary. Default is to write in hex.
+	#[clap(short, long)]
+	pub raw: bool,
+
+	/// The name of the chain for that the genesis wasm file should be exported.
+	#[clap(long)]
+	pub chain: Option<String>,
+}
+
+impl ExportGenesisWasmCommand {
+	/// Run the export-genesis-wasm command
+	#[allow(clippy::borrowed_box)]
+	pub fn run(&self, chain_spec: &Box<dyn ChainSpec>) -> sc_cli::Result<()> {
+		let raw_wasm_blob = extract_genesis_wasm(chain_spec)?;
+
+		write_output(&self.output, self.raw, raw_wasm_blob)
+	}
+}
+
+/// Generate the genesis block from a given ChainSpec.
+pub fn generate_genesis_block<Block: BlockT>(
+	chain_spec: &Box<dyn ChainSpec>,
+	genesis_state_version: StateVersion,
+) -> Result<Block, String> {
+	let storage = chain_spec.build_storage()?;
+
+	let child_roots = storage.children_default.iter().map(|(sk, child_content)| {
+		let state_root = <<<Block as BlockT>::Header as HeaderT>::Hashing as HashT>::trie_root(
+			child_content.data.clone().into_iter().collect(),
+			genesis_state_version,
+		);
+		(sk.clone(), state_root.encode())
+	});
+	let state_root = <<<Block as BlockT>::Header as HeaderT>::Hashing as HashT>::trie_root(
+		storage.top.clone().into_iter().chain(child_roots).collect(),
+		genesis_state_version,
+	);
+
+	let extrinsics_root = <<<Block as BlockT>::Header as HeaderT>::Hashing as HashT>::trie_root(
+		Vec::new(),
+		StateVersion::V0,
+	);
+
+	Ok(Block::new(
+		<<Block as BlockT>::Header as HeaderT>::new(
+			Zero::zero(),
+			extrinsics_root,
+			state_root,
+			Default::default(),
+			Default::default(),
+		),
+		Default::default(),
+	))
+}
+
+/// Extract the genesis wasm blob, i.e. the `:code` entry, from a given ChainSpec.
+#[allow(clippy::borrowed_box)]
+pub fn extract_genesis_wasm(chain_spec: &Box<dyn ChainSpec>) -> sc_cli::Result<Vec<u8>> {
+	let mut storage = chain_spec.build_storage()?;
+
+	storage
+		.top
+		.remove(well_known_keys::CODE)
+		.ok_or_else(|| "Could not find wasm file in genesis state!".into())
+}
+
+/// Write `data` to `output`, or to stdout if no output is given, hex encoded unless `raw` is set.
+fn write_output(output: &Option<PathBuf>, raw: bool, data: Vec<u8>) -> sc_cli::Result<()> {
+	let output_buf =
+		if raw { data } else { format!("0x{:?}", HexDisplay::from(&data)).into_bytes() };
+
+	if let Some(output) = output {
+		fs::write(output, output_buf)?;
+	} else {
+		io::stdout().write_all(&output_buf)?;
+	}
+
+	Ok(())
+}
+
 fn validate_relay_chain_url(arg: &str) -> Result<(), String> {
 	let url = Url::parse(arg).map_err(|e| e.to_string())?;
 
@@ -132,6 +260,20 @@ fn validate_relay_chain_url(arg: &str) -> Result<(), String> {
 	}
 }
 
+fn validate_nice(arg: &str) -> Result<(), String> {
+	match arg.parse::<i32>() {
+		Ok(nice) if (-20..=19).contains(&nice) => Ok(()),
+		_ => Err(format!("'{}' is not a nice value between -20 and 19", arg)),
+	}
+}
+
+fn validate_io_priority(arg: &str) -> Result<(), String> {
+	match arg.parse::<u8>() {
+		Ok(priority) if priority <= 7 => Ok(()),
+		_ => Err(format!("'{}' is not an I/O priority between 0 and 7", arg)),
+	}
+}
+
 /// The `run` command used to run a node.
 #[derive(Debug, Parser)]
 pub struct RunCmd {
@@ -160,6 +302,47 @@ pub struct RunCmd {
 		conflicts_with = "ferdie"
 	)]
 	pub relay_chain_rpc_url: Option<Url>,
+
+	/// EXPERIMENTAL: Specify an URL to another relay chain full node, used when the node given by
+	/// `--relay-chain-rpc-url` is unreachable.
+	///
+	/// Can be passed multiple times, the nodes are tried in the given order.
+	#[clap(
+		long,
+		parse(try_from_str),
+		validator = validate_relay_chain_url,
+		requires = "relay-chain-rpc-url"
+	)]
+	pub relay_chain_rpc_fallback_url: Vec<Url>,
+
+	/// Number of threads that run the tasks of the embedded relay chain node.
+	///
+	/// If this or any other `--relay-chain-*` resource option is set, the embedded relay chain node
+	/// runs on its own thread pool instead of sharing the one of the parachain node.
+	#[clap(long, conflicts_with = "relay-chain-rpc-url")]
+	pub relay_chain_worker_threads: Option<usize>,
+
+	/// Nice value the threads of the embedded relay chain node are run with, from -20 to 19.
+	///
+	/// Only supported on Linux.
+	#[clap(
+		long,
+		allow_hyphen_values = true,
+		validator = validate_nice,
+		conflicts_with = "relay-chain-rpc-url"
+	)]
+	pub relay_chain_nice: Option<i32>,
+
+	/// Best-effort I/O priority the threads of the embedded relay chain node are run with, from 0
+	/// (highest) to 7 (lowest).
+	///
+	/// Only supported on Linux.
+	#[clap(long, validator = validate_io_priority, conflicts_with = "relay-chain-rpc-url")]
+	pub relay_chain_io_priority: Option<u8>,
+
+	/// Relay chain arguments
+	#[clap(raw = true, conflicts_with = "relay-chain-rpc-url")]
+	pub relay_chain_args: Vec<String>,
 }
 
 /// Options only relevant for collator nodes
@@ -167,6 +350,14 @@ pub struct RunCmd {
 pub struct CollatorOptions {
 	/// Location of relay chain full node
 	pub relay_chain_rpc_url: Option<Url>,
+	/// Locations of relay chain full nodes to fall back to, in order of preference
+	pub relay_chain_rpc_fallback_urls: Vec<Url>,
+	/// Number of threads of the embedded relay chain node
+	pub relay_chain_worker_threads: Option<usize>,
+	/// Nice value of the threads of the embedded relay chain node
+	pub relay_chain_nice: Option<i32>,
+	/// I/O priority of the threads of the embedded relay chain node
+	pub relay_chain_io_priority: Option<u8>,
 }
 
 /// A non-redundant version of the `RunCmd` that sets the `validator` field when the
@@ -189,7 +380,13 @@ impl RunCmd {
 
 	/// Create [`CollatorOptions`] representing options only relevant to parachain collator nodes
 	pub fn collator_options(&self) -> CollatorOptions {
-		CollatorOptions { relay_chain_rpc_url: self.relay_chain_rpc_url.clone() }
+		CollatorOptions {
+			relay_chain_rpc_url: self.relay_chain_rpc_url.clone(),
+			relay_chain_rpc_fallback_urls: self.relay_chain_rpc_fallback_url.clone(),
+			relay_chain_worker_threads: self.relay_chain_worker_threads,
+			relay_chain_nice: self.relay_chain_nice,
+			relay_chain_io_priority: self.relay_chain_io_priority,
+		}
 	}
 }
 
@@ -297,3 +494,211 @@ impl sc_cli::CliConfiguration for NormalizedRunCmd {
 		self.base.base_path()
 	}
 }
+
+/// The command line of the embedded relay chain node, i.e. the arguments after `--`.
+///
+/// The name, version and description are taken from the [`SubstrateCli`] of the parachain node
+/// `Para`, the chain specs are loaded like Polkadot does.
+#[derive(Debug)]
+pub struct RelayChainCli<Para> {
+	/// The actual relay chain cli object.
+	pub base: polkadot_cli::RunCmd,
+
+	/// Optional chain id that should be passed to the relay chain.
+	pub chain_id: Option<String>,
+
+	/// The base path that should be used by the relay chain.
+	pub base_path: Option<PathBuf>,
+
+	_phantom: PhantomData<Para>,
+}
+
+impl<Para: SubstrateCli> RelayChainCli<Para> {
+	/// Parse the relay chain CLI parameters using the para chain `Configuration`.
+	///
+	/// `chain_id` is the relay chain of the parachain, usually read from the extensions of its
+	/// chain spec. `relay_chain_args` are the arguments without the executable name, see
+	/// [`RunCmd::relay_chain_args`].
+	pub fn new<'a>(
+		para_config: &sc_service::Configuration,
+		chain_id: Option<String>,
+		relay_chain_args: impl Iterator<Item = &'a String>,
+	) -> Self {
+		let base_path = para_config.base_path.as_ref().map(|x| x.path().join("polkadot"));
+		let executable_name = Self::executable_name();
+		let base =
+			polkadot_cli::RunCmd::parse_from(iter::once(&executable_name).chain(relay_chain_args));
+		Self { base, chain_id, base_path, _phantom: PhantomData }
+	}
+}
+
+impl<Para: SubstrateCli> SubstrateCli for RelayChainCli<Para> {
+	fn impl_name() -> String {
+		Para::impl_name()
+	}
+
+	fn impl_version() -> String {
+		Para::impl_version()
+	}
+
+	fn description() -> String {
+		Para::description()
+	}
+
+	fn author() -> String {
+		Para::author()
+	}
+
+	fn support_url() -> String {
+		Para::support_url()
+	}
+
+	fn copyright_start_year() -> i32 {
+		Para::copyright_start_year()
+	}
+
+	fn load_spec(&self, id: &str) -> Result<Box<dyn ChainSpec>, String> {
+		polkadot_cli::Cli::from_iter([Self::executable_name()].iter()).load_spec(id)
+	}
+
+	fn native_runtime_version(chain_spec: &Box<dyn ChainSpec>) -> &'static RuntimeVersion {
+		polkadot_cli::Cli::native_runtime_version(chain_spec)
+	}
+}
+
+impl<Para> sc_cli::DefaultConfigurationValues for RelayChainCli<Para> {
+	fn p2p_listen_port() -> u16 {
+		30334
+	}
+
+	fn rpc_ws_listen_port() -> u16 {
+		9945
+	}
+
+	fn rpc_http_listen_port() -> u16 {
+		9934
+	}
+
+	fn prometheus_listen_port() -> u16 {
+		9616
+	}
+}
+
+impl<Para: SubstrateCli> sc_cli::CliConfiguration<Self> for RelayChainCli<Para> {
+	fn shared_params(&self) -> &sc_cli::SharedParams {
+		self.base.base.shared_params()
+	}
+
+	fn import_params(&self) -> Option<&sc_cli::ImportParams> {
+		self.base.base.import_params()
+	}
+
+	fn network_params(&self) -> Option<&sc_cli::NetworkParams> {
+		self.base.base.network_params()
+	}
+
+	fn keystore_params(&self) -> Option<&sc_cli::KeystoreParams> {
+		self.base.base.keystore_params()
+	}
+
+	fn base_path(&self) -> sc_cli::Result<Option<BasePath>> {
+		Ok(self
+			.shared_params()
+			.base_path()
+			.or_else(|| self.base_path.clone().map(Into::into)))
+	}
+
+	fn rpc_http(&self, default_listen_port: u16) -> sc_cli::Result<Option<SocketAddr>> {
+		self.base.base.rpc_http(default_listen_port)
+	}
+
+	fn rpc_ipc(&self) -> sc_cli::Result<Option<String>> {
+		self.base.base.rpc_ipc()
+	}
+
+	fn rpc_ws(&self, default_listen_port: u16) -> sc_cli::Result<Option<SocketAddr>> {
+		self.base.base.rpc_ws(default_listen_port)
+	}
+
+	fn prometheus_config(
+		&self,
+		default_listen_port: u16,
+		chain_spec: &Box<dyn ChainSpec>,
+	) -> sc_cli::Result<Option<PrometheusConfig>> {
+		self.base.base.prometheus_config(default_listen_port, chain_spec)
+	}
+
+	fn init<F>(
+		&self,
+		_support_url: &String,
+		_impl_version: &String,
+		_logger_hook: F,
+		_config: &sc_service::Configuration,
+	) -> sc_cli::Result<()>
+	where
+		F: FnOnce(&mut sc_cli::LoggerBuilder, &sc_service::Configuration),
+	{
+		unreachable!("PolkadotCli is never initialized; qed");
+	}
+
+	fn chain_id(&self, is_dev: bool) -> sc_cli::Result<String> {
+		let chain_id = self.base.base.chain_id(is_dev)?;
+
+		Ok(if chain_id.is_empty() { self.chain_id.clone().unwrap_or_default() } else { chain_id })
+	}
+
+	fn role(&self, is_dev: bool) -> sc_cli::Result<sc_service::Role> {
+		self.base.base.role(is_dev)
+	}
+
+	fn transaction_pool(&self) -> sc_cli::Result<TransactionPoolOptions> {
+		self.base.base.transaction_pool()
+	}
+
+	fn state_cache_child_ratio(&self) -> sc_cli::Result<Option<usize>> {
+		self.base.base.state_cache_child_ratio()
+	}
+
+	fn rpc_methods(&self) -> sc_cli::Result<sc_service::config::RpcMethods> {
+		self.base.base.rpc_methods()
+	}
+
+	fn rpc_ws_max_connections(&self) -> sc_cli::Result<Option<usize>> {
+		self.base.base.rpc_ws_max_connections()
+	}
+
+	fn rpc_cors(&self, is_dev: bool) -> sc_cli::Result<Option<Vec<String>>> {
+		self.base.base.rpc_cors(is_dev)
+	}
+
+	fn default_heap_pages(&self) -> sc_cli::Result<Option<u64>> {
+		self.base.base.default_heap_pages()
+	}
+
+	fn force_authoring(&self) -> sc_cli::Result<bool> {
+		self.base.base.force_authoring()
+	}
+
+	fn disable_grandpa(&self) -> sc_cli::Result<bool> {
+		self.base.base.disable_grandpa()
+	}
+
+	fn max_runtime_instances(&self) -> sc_cli::Result<Option<usize>> {
+		self.base.base.max_runtime_instances()
+	}
+
+	fn announce_block(&self) -> sc_cli::Result<bool> {
+		self.base.base.announce_block()
+	}
+
+	fn telemetry_endpoints(
+		&self,
+		chain_spec: &Box<dyn ChainSpec>,
+	) -> sc_cli::Result<Option<TelemetryEndpoints>> {
+		self.base.base.telemetry_endpoints(chain_spec)
+	}
+
+	fn node_name(&self) -> sc_cli::Result<String> {
+		self.base.base.node_name()
+	}
+}
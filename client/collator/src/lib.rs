@@ -41,19 +41,77 @@ use polkadot_primitives::v2::{CollatorPair, Id as ParaId};
 
 use codec::{Decode, Encode};
 use futures::{channel::oneshot, FutureExt};
+use futures_timer::Delay;
 use parking_lot::Mutex;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
+use std::{sync::Arc, time::Duration};
 use tracing::Instrument;
 
+pub mod rpc;
+
 /// The logging target.
 const LOG_TARGET: &str = "cumulus-collator";
 
+/// How long to wait for a parent block that is still queued for import before checking it again.
+const QUEUED_PARENT_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// The storage proof of a candidate produced by the collator.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CandidateProof<Hash> {
+	/// The relay parent the candidate was produced for.
+	pub relay_parent: PHash,
+	/// The hash of the parachain block of the candidate.
+	pub block_hash: Hash,
+	/// The SCALE encoded compact storage proof of the candidate.
+	pub proof: Bytes,
+}
+
+/// Keeps the storage proof of the candidate that was produced most recently.
+///
+/// Exposed over RPC through [`rpc::CollatorApi`] to analyze the proof of validity of live
+/// collators.
+pub struct LastCandidateProof<Hash>(Arc<Mutex<Option<CandidateProof<Hash>>>>);
+
+impl<Hash> LastCandidateProof<Hash> {
+	/// Create a new instance that does not know any candidate yet.
+	pub fn new() -> Self {
+		Self(Arc::new(Mutex::new(None)))
+	}
+
+	/// Returns the proof of the candidate produced most recently.
+	pub fn get(&self) -> Option<CandidateProof<Hash>>
+	where
+		Hash: Clone,
+	{
+		self.0.lock().clone()
+	}
+
+	fn set(&self, proof: CandidateProof<Hash>) {
+		*self.0.lock() = Some(proof);
+	}
+}
+
+impl<Hash> Clone for LastCandidateProof<Hash> {
+	fn clone(&self) -> Self {
+		Self(self.0.clone())
+	}
+}
+
+impl<Hash> Default for LastCandidateProof<Hash> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 /// The implementation of the Cumulus `Collator`.
 pub struct Collator<Block: BlockT, BS, RA> {
 	block_status: Arc<BS>,
 	parachain_consensus: Box<dyn ParachainConsensus<Block>>,
 	wait_to_announce: Arc<Mutex<WaitToAnnounce<Block>>>,
 	runtime_api: Arc<RA>,
+	last_proof: Option<LastCandidateProof<Block::Hash>>,
 }
 
 impl<Block: BlockT, BS, RA> Clone for Collator<Block, BS, RA> {
@@ -63,6 +121,7 @@ impl<Block: BlockT, BS, RA> Clone for Collator<Block, BS, RA> {
 			wait_to_announce: self.wait_to_announce.clone(),
 			parachain_consensus: self.parachain_consensus.clone(),
 			runtime_api: self.runtime_api.clone(),
+			last_proof: self.last_proof.clone(),
 		}
 	}
 }
@@ -81,10 +140,11 @@ where
 		announce_block: Arc<dyn Fn(Block::Hash, Option<Vec<u8>>) + Send + Sync>,
 		runtime_api: Arc<RA>,
 		parachain_consensus: Box<dyn ParachainConsensus<Block>>,
+		last_proof: Option<LastCandidateProof<Block::Hash>>,
 	) -> Self {
 		let wait_to_announce = Arc::new(Mutex::new(WaitToAnnounce::new(spawner, announce_block)));
 
-		Self { block_status, wait_to_announce, runtime_api, parachain_consensus }
+		Self { block_status, wait_to_announce, runtime_api, parachain_consensus, last_proof }
 	}
 
 	/// Checks the status of the given block hash in the Parachain.
@@ -233,6 +293,21 @@ where
 		};
 
 		let last_head_hash = last_head.hash();
+
+		// The parent may have just arrived and still be waiting in the import queue. Give the
+		// import one more chance to finish instead of skipping the relay parent right away.
+		if matches!(
+			self.block_status.block_status(&BlockId::Hash(last_head_hash)),
+			Ok(BlockStatus::Queued)
+		) {
+			tracing::debug!(
+				target: LOG_TARGET,
+				block_hash = ?last_head_hash,
+				"Parent block is still queued for import, retrying once.",
+			);
+			Delay::new(QUEUED_PARENT_RETRY_DELAY).await;
+		}
+
 		if !self.check_block_status(last_head_hash, &last_head) {
 			return None
 		}
@@ -283,6 +358,15 @@ where
 		);
 
 		let block_hash = b.header().hash();
+
+		if let Some(last_proof) = &self.last_proof {
+			last_proof.set(CandidateProof {
+				relay_parent,
+				block_hash,
+				proof: b.storage_proof().encode().into(),
+			});
+		}
+
 		let collation = self.build_collation(b, block_hash, pov)?;
 
 		let (result_sender, signed_stmt_recv) = oneshot::channel();
@@ -305,6 +389,8 @@ pub struct StartCollatorParams<Block: BlockT, RA, BS, Spawner> {
 	pub spawner: Spawner,
 	pub key: CollatorPair,
 	pub parachain_consensus: Box<dyn ParachainConsensus<Block>>,
+	/// Where to keep the proof of the latest candidate, `None` to not keep it.
+	pub last_proof: Option<LastCandidateProof<Block::Hash>>,
 }
 
 /// Start the collator.
@@ -318,6 +404,7 @@ pub async fn start_collator<Block, RA, BS, Spawner>(
 		key,
 		parachain_consensus,
 		runtime_api,
+		last_proof,
 	}: StartCollatorParams<Block, RA, BS, Spawner>,
 ) where
 	Block: BlockT,
@@ -332,6 +419,7 @@ pub async fn start_collator<Block, RA, BS, Spawner>(
 		announce_block,
 		runtime_api,
 		parachain_consensus,
+		last_proof,
 	);
 
 	let span = tracing::Span::current();
@@ -370,8 +458,13 @@ mod tests {
 	use polkadot_overseer::{dummy::dummy_overseer_builder, HeadSupportsParachains};
 	use sp_consensus::BlockOrigin;
 	use sp_core::{testing::TaskExecutor, Pair};
-	use sp_runtime::traits::BlakeTwo256;
+	use sp_runtime::{
+		generic::SignedBlock,
+		traits::{BlakeTwo256, NumberFor},
+		Justifications,
+	};
 	use sp_state_machine::Backend;
+	use std::sync::atomic::{AtomicBool, Ordering};
 
 	struct AlwaysSupportsParachains;
 	impl HeadSupportsParachains for AlwaysSupportsParachains {
@@ -411,15 +504,82 @@ mod tests {
 		}
 	}
 
-	#[test]
-	fn collates_produces_a_block_and_storage_proof_does_not_contains_code() {
-		sp_tracing::try_init_simple();
+	/// Reports the first requested block as [`BlockStatus::Queued`] and asks [`Client`]
+	/// afterwards, as if the import finished in the meantime.
+	struct QueuedOnce {
+		client: Arc<Client>,
+		queued: AtomicBool,
+	}
+
+	impl BlockBackend<Block> for QueuedOnce {
+		fn block_body(
+			&self,
+			id: &BlockId<Block>,
+		) -> sp_blockchain::Result<Option<Vec<<Block as BlockT>::Extrinsic>>> {
+			self.client.block_body(id)
+		}
+
+		fn block_indexed_body(
+			&self,
+			id: &BlockId<Block>,
+		) -> sp_blockchain::Result<Option<Vec<Vec<u8>>>> {
+			self.client.block_indexed_body(id)
+		}
+
+		fn block(&self, id: &BlockId<Block>) -> sp_blockchain::Result<Option<SignedBlock<Block>>> {
+			self.client.block(id)
+		}
+
+		fn block_status(&self, id: &BlockId<Block>) -> sp_blockchain::Result<BlockStatus> {
+			if self.queued.swap(false, Ordering::SeqCst) {
+				Ok(BlockStatus::Queued)
+			} else {
+				self.client.block_status(id)
+			}
+		}
+
+		fn justifications(
+			&self,
+			id: &BlockId<Block>,
+		) -> sp_blockchain::Result<Option<Justifications>> {
+			self.client.justifications(id)
+		}
+
+		fn block_hash(
+			&self,
+			number: NumberFor<Block>,
+		) -> sp_blockchain::Result<Option<<Block as BlockT>::Hash>> {
+			self.client.block_hash(number)
+		}
+
+		fn indexed_transaction(
+			&self,
+			hash: &<Block as BlockT>::Hash,
+		) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			self.client.indexed_transaction(hash)
+		}
+
+		fn has_indexed_transaction(
+			&self,
+			hash: &<Block as BlockT>::Hash,
+		) -> sp_blockchain::Result<bool> {
+			self.client.has_indexed_transaction(hash)
+		}
+
+		fn requires_full_sync(&self) -> bool {
+			self.client.requires_full_sync()
+		}
+	}
 
+	/// Start a collator on top of `client` and return the config it sent to the overseer.
+	fn start_test_collator<BS: BlockBackend<Block> + Send + Sync + 'static>(
+		client: Arc<Client>,
+		block_status: Arc<BS>,
+		last_proof: Option<LastCandidateProof<PHash>>,
+	) -> CollationGenerationConfig {
 		let spawner = TaskExecutor::new();
 		let para_id = ParaId::from(100);
 		let announce_block = |_, _| ();
-		let client = Arc::new(TestClientBuilder::new().build());
-		let header = client.header(&BlockId::Number(0)).unwrap().unwrap();
 
 		let (sub_tx, sub_rx) = mpsc::channel(64);
 
@@ -434,13 +594,14 @@ mod tests {
 
 		let collator_start = start_collator(StartCollatorParams {
 			runtime_api: client.clone(),
-			block_status: client.clone(),
+			block_status,
 			announce_block: Arc::new(announce_block),
 			overseer_handle: OverseerHandle::new(handle),
 			spawner,
 			para_id,
 			key: CollatorPair::generate().0,
-			parachain_consensus: Box::new(DummyParachainConsensus { client: client.clone() }),
+			parachain_consensus: Box::new(DummyParachainConsensus { client }),
+			last_proof,
 		});
 		block_on(collator_start);
 
@@ -448,9 +609,20 @@ mod tests {
 			.0
 			.expect("message should be send by `start_collator` above.");
 
-		let config = match msg {
+		match msg {
 			CollationGenerationMessage::Initialize(config) => config,
-		};
+		}
+	}
+
+	#[test]
+	fn collates_produces_a_block_and_storage_proof_does_not_contains_code() {
+		sp_tracing::try_init_simple();
+
+		let client = Arc::new(TestClientBuilder::new().build());
+		let header = client.header(&BlockId::Number(0)).unwrap().unwrap();
+
+		let last_proof = LastCandidateProof::new();
+		let config = start_test_collator(client.clone(), client, Some(last_proof.clone()));
 
 		let mut validation_data = PersistedValidationData::default();
 		validation_data.parent_head = header.encode().into();
@@ -469,6 +641,14 @@ mod tests {
 			ParachainBlockData::<Block>::decode(&mut &decompressed[..]).expect("Is a valid block");
 
 		assert_eq!(1, *block.header().number());
+		assert_eq!(
+			last_proof.get(),
+			Some(CandidateProof {
+				relay_parent,
+				block_hash: block.header().hash(),
+				proof: block.storage_proof().encode().into(),
+			}),
+		);
 
 		// Ensure that we did not include `:code` in the proof.
 		let proof = block.storage_proof();
@@ -487,4 +667,34 @@ mod tests {
 			.unwrap_err()
 			.contains("Trie lookup error: Database missing expected key"));
 	}
+
+	#[test]
+	fn collates_on_a_parent_that_finishes_import_during_the_retry() {
+		sp_tracing::try_init_simple();
+
+		let client = Arc::new(TestClientBuilder::new().build());
+		let header = client.header(&BlockId::Number(0)).unwrap().unwrap();
+		let block_status =
+			Arc::new(QueuedOnce { client: client.clone(), queued: AtomicBool::new(true) });
+
+		let config = start_test_collator(client, block_status.clone(), None);
+
+		let mut validation_data = PersistedValidationData::default();
+		validation_data.parent_head = header.encode().into();
+
+		let collation = block_on((config.collator)(Default::default(), &validation_data))
+			.expect("Collation is build after the retry")
+			.collation;
+
+		// The queued status was reported and the collator retried instead of skipping.
+		assert!(!block_status.queued.load(Ordering::SeqCst));
+
+		let pov = collation.proof_of_validity.into_compressed();
+		let decompressed =
+			sp_maybe_compressed_blob::decompress(&pov.block_data.0, 1024 * 1024 * 10).unwrap();
+		let block =
+			ParachainBlockData::<Block>::decode(&mut &decompressed[..]).expect("Is a valid block");
+
+		assert_eq!(1, *block.header().number());
+	}
 }
@@ -0,0 +1,60 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC methods to inspect the candidates produced by the collator.
+//!
+//! All methods are unsafe and only available if unsafe RPC methods are allowed.
+
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use sc_rpc_api::DenyUnsafe;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{CandidateProof, LastCandidateProof};
+
+/// Collator RPC methods.
+#[rpc]
+pub trait CollatorApi<Hash> {
+	/// Returns the storage proof of the candidate this collator produced most recently.
+	///
+	/// Returns `null` if the collator did not produce any candidate since it was started.
+	#[rpc(name = "collator_lastProof")]
+	fn last_proof(&self) -> Result<Option<CandidateProof<Hash>>>;
+}
+
+/// Implementation of [`CollatorApi`].
+pub struct CollatorRpc<Hash> {
+	last_proof: LastCandidateProof<Hash>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<Hash> CollatorRpc<Hash> {
+	/// Create a new instance that exposes `last_proof`.
+	pub fn new(last_proof: LastCandidateProof<Hash>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { last_proof, deny_unsafe }
+	}
+}
+
+impl<Hash> CollatorApi<Hash> for CollatorRpc<Hash>
+where
+	Hash: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+	fn last_proof(&self) -> Result<Option<CandidateProof<Hash>>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		Ok(self.last_proof.get())
+	}
+}
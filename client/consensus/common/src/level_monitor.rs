@@ -0,0 +1,140 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Bounds the number of blocks a node keeps at the same height of the parachain.
+//!
+//! The relay chain includes at most one parachain block per height, but a parachain node imports
+//! every valid block it receives. Without a bound, collators could fill the disk of full nodes by
+//! producing sibling blocks.
+
+use sc_client_api::Backend;
+use sc_consensus::{BlockCheckParams, BlockImport, BlockImportParams, ImportResult};
+use sp_blockchain::{Backend as _, HeaderBackend};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor};
+use std::{
+	collections::{HashMap, HashSet},
+	sync::Arc,
+};
+
+const LOG_TARGET: &str = "level-monitor";
+
+/// The default maximum number of blocks kept at the same height.
+pub const DEFAULT_LEVEL_LIMIT: usize = 32;
+
+/// Block import that bounds the number of blocks imported at the same height.
+///
+/// Before a block is imported at a height that already has `level_limit` blocks, the least
+/// recently imported leaf at that height is removed. The best block, blocks with descendants and
+/// blocks imported before this instance was created are never removed, so the limit may be
+/// exceeded when no block can be removed.
+pub struct LevelLimitedBlockImport<Block: BlockT, I, BE> {
+	inner: I,
+	backend: Arc<BE>,
+	level_limit: usize,
+	/// The blocks imported at each unfinalized height, from the least to the most recently
+	/// imported.
+	levels: HashMap<NumberFor<Block>, Vec<Block::Hash>>,
+}
+
+impl<Block: BlockT, I, BE> LevelLimitedBlockImport<Block, I, BE> {
+	/// Create a new instance that keeps at most `level_limit` blocks at the same height.
+	pub fn new(inner: I, backend: Arc<BE>, level_limit: usize) -> Self {
+		Self { inner, backend, level_limit, levels: HashMap::new() }
+	}
+}
+
+impl<Block: BlockT, I, BE: Backend<Block>> LevelLimitedBlockImport<Block, I, BE> {
+	/// Make room for a new block at height `number` by removing the least recently imported leaf.
+	fn enforce_limit(&mut self, number: NumberFor<Block>) {
+		let blockchain = self.backend.blockchain();
+		let info = blockchain.info();
+		self.levels.retain(|level, _| *level > info.finalized_number);
+
+		let level = match self.levels.get_mut(&number) {
+			Some(level) if level.len() >= self.level_limit => level,
+			_ => return,
+		};
+
+		let leaves = match blockchain.leaves() {
+			Ok(leaves) => leaves.into_iter().collect::<HashSet<_>>(),
+			Err(e) => {
+				tracing::warn!(target: LOG_TARGET, error = ?e, "Could not get the leaves.");
+				return
+			},
+		};
+		let stale = match level.iter().position(|h| *h != info.best_hash && leaves.contains(h)) {
+			Some(index) => level.remove(index),
+			None => {
+				tracing::debug!(
+					target: LOG_TARGET,
+					?number,
+					"Level limit reached, but none of its blocks can be removed.",
+				);
+				return
+			},
+		};
+
+		match self.backend.remove_leaf_block(&stale) {
+			Ok(()) => tracing::debug!(
+				target: LOG_TARGET,
+				?number,
+				block_hash = ?stale,
+				"Removed block to stay within the level limit.",
+			),
+			Err(e) => tracing::warn!(
+				target: LOG_TARGET,
+				error = ?e,
+				block_hash = ?stale,
+				"Could not remove block to stay within the level limit.",
+			),
+		}
+	}
+}
+
+#[async_trait::async_trait]
+impl<Block, I, BE> BlockImport<Block> for LevelLimitedBlockImport<Block, I, BE>
+where
+	Block: BlockT,
+	I: BlockImport<Block> + Send,
+	BE: Backend<Block>,
+{
+	type Error = I::Error;
+	type Transaction = I::Transaction;
+
+	async fn check_block(
+		&mut self,
+		block: BlockCheckParams<Block>,
+	) -> Result<ImportResult, Self::Error> {
+		self.inner.check_block(block).await
+	}
+
+	async fn import_block(
+		&mut self,
+		block_import_params: BlockImportParams<Block, Self::Transaction>,
+		cache: HashMap<sp_consensus::CacheKeyId, Vec<u8>>,
+	) -> Result<ImportResult, Self::Error> {
+		let number = *block_import_params.header.number();
+		let hash = block_import_params.post_hash();
+
+		self.enforce_limit(number);
+
+		let result = self.inner.import_block(block_import_params, cache).await?;
+		if let ImportResult::Imported(_) = result {
+			self.levels.entry(number).or_default().push(hash);
+		}
+		Ok(result)
+	}
+}
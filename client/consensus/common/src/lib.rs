@@ -18,9 +18,11 @@ use polkadot_primitives::v2::{Hash as PHash, PersistedValidationData};
 use sc_consensus::BlockImport;
 use sp_runtime::traits::Block as BlockT;
 
+mod level_monitor;
 mod parachain_consensus;
 #[cfg(test)]
 mod tests;
+pub use level_monitor::{LevelLimitedBlockImport, DEFAULT_LEVEL_LIMIT};
 pub use parachain_consensus::run_parachain_consensus;
 
 /// The result of [`ParachainConsensus::produce_candidate`].
@@ -26,9 +26,9 @@ use cumulus_test_client::{
 use futures::{channel::mpsc, executor::block_on, select, FutureExt, Stream, StreamExt};
 use futures_timer::Delay;
 use polkadot_primitives::v2::Id as ParaId;
-use sc_client_api::UsageProvider;
+use sc_client_api::{Backend as _, UsageProvider};
 use sc_consensus::{BlockImport, BlockImportParams, ForkChoiceStrategy};
-use sp_blockchain::Error as ClientError;
+use sp_blockchain::{Backend as _, Error as ClientError};
 use sp_consensus::BlockOrigin;
 use sp_runtime::generic::BlockId;
 use std::{
@@ -355,3 +355,41 @@ fn do_not_set_best_block_to_older_block() {
 	// Build and import a new best block.
 	build_and_import_block(client2.clone(), true);
 }
+
+#[test]
+fn level_limit_removes_least_recently_imported_leaf() {
+	sp_tracing::try_init_simple();
+
+	let backend = Arc::new(Backend::new_test(1000, 1));
+	let client = Arc::new(TestClientBuilder::with_backend(backend.clone()).build());
+	let mut block_import = LevelLimitedBlockImport::new(&*client, backend.clone(), 2);
+
+	// Siblings at height 1 that only differ in their timestamp.
+	let blocks = (1..=3u64)
+		.map(|i| {
+			let block = client
+				.init_block_builder_with_timestamp(
+					&BlockId::Number(0),
+					None,
+					Default::default(),
+					i * 1_000_000,
+				)
+				.build()
+				.unwrap()
+				.block;
+			let (header, body) = block.clone().deconstruct();
+
+			let mut block_import_params = BlockImportParams::new(BlockOrigin::Own, header);
+			block_import_params.fork_choice = Some(ForkChoiceStrategy::Custom(false));
+			block_import_params.body = Some(body);
+			block_on(block_import.import_block(block_import_params, Default::default())).unwrap();
+
+			block
+		})
+		.collect::<Vec<_>>();
+
+	let leaves = backend.blockchain().leaves().unwrap();
+	assert!(!leaves.contains(&blocks[0].hash()));
+	assert!(leaves.contains(&blocks[1].hash()));
+	assert!(leaves.contains(&blocks[2].hash()));
+}
@@ -0,0 +1,525 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Selection of the block author among the author keys in the keystore.
+//!
+//! Parachains that set the author of their blocks with `pallet-author-inherent` require every
+//! block to be claimed by an author that may author it. A collator may run several authors, so
+//! the [`AuthorSelector`] looks up all [`AuthorId`] keys in the keystore and selects the first one
+//! that is eligible according to the [`AuthorEligibility`], e.g. the `can_author` runtime api of
+//! `pallet-author-slot-filter`. The selected author signs the author inherent of the block.
+//!
+//! The selector is used twice by the relay-chain consensus: as [`DigestsProvider`], which skips
+//! relay parents none of the authors is eligible for, and by the inherent data providers, which
+//! provide the author inherent through [`AuthorSelector::inherent_data_provider`]. The selection
+//! is remembered for the parent and relay parent, so both agree on the author.
+//!
+//! The keystore is searched for author keys whenever an author is selected, so keys that are
+//! inserted or removed while the node is running take effect with the next relay parent. Changes
+//! of the author keys and of the author that blocks are claimed with are logged.
+//!
+//! Collators whose authors are rarely eligible can enable a
+//! [backoff](AuthorSelector::with_backoff), which checks the eligibility of an author less often
+//! the more relay parents in a row it was not eligible for.
+
+use crate::{DigestsProvider, LOG_TARGET};
+use codec::Decode;
+use cumulus_primitives_author::{AuthorId, AuthorSignature, KEY_TYPE};
+use cumulus_primitives_core::{
+	relay_chain::{v2::Hash as PHash, BlockNumber as RelayBlockNumber},
+	PersistedValidationData,
+};
+use pallet_author_inherent::{AuthorInherentData, InherentError, INHERENT_IDENTIFIER};
+use parking_lot::Mutex;
+use sp_core::crypto::Public as _;
+use sp_inherents::{InherentData, InherentIdentifier};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::DigestItem;
+use std::{collections::HashMap, sync::Arc};
+
+/// Decides whether an author may author a block.
+pub trait AuthorEligibility<BlockHash> {
+	/// Returns `true` if `author` may author a block that is built on top of `parent` at the relay
+	/// parent with the given number.
+	fn can_author(
+		&self,
+		parent: BlockHash,
+		author: &AuthorId,
+		relay_parent_number: RelayBlockNumber,
+	) -> bool;
+}
+
+impl<F, BlockHash> AuthorEligibility<BlockHash> for F
+where
+	F: Fn(BlockHash, &AuthorId, RelayBlockNumber) -> bool,
+{
+	fn can_author(
+		&self,
+		parent: BlockHash,
+		author: &AuthorId,
+		relay_parent_number: RelayBlockNumber,
+	) -> bool {
+		(*self)(parent, author, relay_parent_number)
+	}
+}
+
+/// The author that was selected for a parent and relay parent.
+struct Selection<BlockHash> {
+	parent: BlockHash,
+	relay_parent: PHash,
+	author: Option<AuthorId>,
+}
+
+/// The relay parents in a row an author was not eligible for.
+struct Backoff {
+	misses: u32,
+	next_check: RelayBlockNumber,
+}
+
+/// The author keys and the author that were used most recently.
+#[derive(Default)]
+struct Observed {
+	keys: Vec<AuthorId>,
+	author: Option<AuthorId>,
+}
+
+/// Selects the author of a block among the author keys in the keystore.
+///
+/// The `can_author` runtime api of `pallet-author-slot-filter` is only meaningful in its
+/// `EligibilityMode::RelayParentNumber`. In `EligibilityMode::Vrf` the eligibility is claimed with
+/// `pallet_author_slot_filter::vrf::claim_digest` instead.
+pub struct AuthorSelector<BlockHash, E> {
+	keystore: SyncCryptoStorePtr,
+	eligibility: Arc<E>,
+	last_selection: Arc<Mutex<Option<Selection<BlockHash>>>>,
+	backoff: Arc<Mutex<HashMap<AuthorId, Backoff>>>,
+	max_backoff: RelayBlockNumber,
+	observed: Arc<Mutex<Observed>>,
+}
+
+impl<BlockHash, E> Clone for AuthorSelector<BlockHash, E> {
+	fn clone(&self) -> Self {
+		Self {
+			keystore: self.keystore.clone(),
+			eligibility: self.eligibility.clone(),
+			last_selection: self.last_selection.clone(),
+			backoff: self.backoff.clone(),
+			max_backoff: self.max_backoff,
+			observed: self.observed.clone(),
+		}
+	}
+}
+
+impl<BlockHash, E> AuthorSelector<BlockHash, E>
+where
+	BlockHash: Clone + PartialEq + std::fmt::Debug,
+	E: AuthorEligibility<BlockHash>,
+{
+	/// Create a new selector for the author keys in `keystore`.
+	pub fn new(keystore: SyncCryptoStorePtr, eligibility: E) -> Self {
+		Self {
+			keystore,
+			eligibility: Arc::new(eligibility),
+			last_selection: Default::default(),
+			backoff: Default::default(),
+			max_backoff: 0,
+			observed: Default::default(),
+		}
+	}
+
+	/// Back off from checking the eligibility of authors that are repeatedly not eligible.
+	///
+	/// After an author was not eligible for `n` relay parents in a row, its eligibility is not
+	/// checked for the next `2^(n - 1) - 1` relay parents, but at most `max_skipped`. It is reset
+	/// as soon as the author is eligible again. This saves runtime api calls, at the risk of missing
+	/// relay parents the author would have been eligible for, so it should only be used if
+	/// eligibility is rare. `0`, the default, checks every relay parent.
+	pub fn with_backoff(mut self, max_skipped: RelayBlockNumber) -> Self {
+		self.max_backoff = max_skipped;
+		self
+	}
+
+	/// Returns the author keys in the keystore, ordered by their public key.
+	pub fn author_keys(&self) -> Vec<AuthorId> {
+		let mut keys = SyncCryptoStore::sr25519_public_keys(&*self.keystore, KEY_TYPE)
+			.into_iter()
+			.map(AuthorId::from)
+			.collect::<Vec<_>>();
+		keys.sort();
+		keys
+	}
+
+	/// Select the author of a block that is built on top of `parent` at `relay_parent`.
+	///
+	/// Returns the first of the [`author_keys`](Self::author_keys) that may author the block, or
+	/// `None` if none of them may. The selection is remembered, so selecting again for the same
+	/// `parent` and `relay_parent` returns the same author without checking the eligibility again.
+	pub fn select(
+		&self,
+		parent: BlockHash,
+		relay_parent: PHash,
+		relay_parent_number: RelayBlockNumber,
+	) -> Option<AuthorId> {
+		let mut last_selection = self.last_selection.lock();
+		if let Some(selection) = &*last_selection {
+			if selection.parent == parent && selection.relay_parent == relay_parent {
+				return selection.author.clone()
+			}
+		}
+
+		let keys = self.author_keys();
+		let mut backoff = self.backoff.lock();
+		backoff.retain(|author, _| keys.contains(author));
+
+		let author = keys
+			.iter()
+			.find(|author| self.is_eligible(&mut backoff, &parent, author, relay_parent_number))
+			.cloned();
+
+		tracing::debug!(
+			target: LOG_TARGET,
+			?parent,
+			relay_parent_number,
+			author_keys = keys.len(),
+			?author,
+			"Selected block author.",
+		);
+		self.observe(&keys, author.as_ref());
+
+		*last_selection = Some(Selection { parent, relay_parent, author: author.clone() });
+		author
+	}
+
+	/// Check the eligibility of every author key for a block that is built on top of `parent` at the
+	/// relay parent with the given number.
+	///
+	/// Unlike [`Self::select`], all keys are checked, regardless of the backoff, and nothing is
+	/// remembered.
+	pub fn eligibility(
+		&self,
+		parent: BlockHash,
+		relay_parent_number: RelayBlockNumber,
+	) -> Vec<(AuthorId, bool)> {
+		self.author_keys()
+			.into_iter()
+			.map(|author| {
+				let eligible =
+					self.eligibility.can_author(parent.clone(), &author, relay_parent_number);
+				(author, eligible)
+			})
+			.collect()
+	}
+
+	/// Log if the author `keys` or the selected `author` changed since the last selection.
+	///
+	/// Not being eligible does not count as a change of the author.
+	fn observe(&self, keys: &[AuthorId], author: Option<&AuthorId>) {
+		let mut observed = self.observed.lock();
+
+		if observed.keys != keys {
+			tracing::info!(
+				target: LOG_TARGET,
+				author_keys = ?keys,
+				"Author keys in the keystore changed.",
+			);
+			observed.keys = keys.to_vec();
+		}
+
+		if let Some(author) = author {
+			if observed.author.as_ref() != Some(author) {
+				tracing::info!(
+					target: LOG_TARGET,
+					?author,
+					previous = ?observed.author,
+					"Claiming blocks with a different author key.",
+				);
+				observed.author = Some(author.clone());
+			}
+		}
+	}
+
+	/// Check the eligibility of `author`, unless it is backed off for `relay_parent_number`.
+	fn is_eligible(
+		&self,
+		backoff: &mut HashMap<AuthorId, Backoff>,
+		parent: &BlockHash,
+		author: &AuthorId,
+		relay_parent_number: RelayBlockNumber,
+	) -> bool {
+		if let Some(Backoff { misses, next_check }) = backoff.get(author) {
+			if relay_parent_number < *next_check {
+				tracing::trace!(
+					target: LOG_TARGET,
+					?author,
+					misses,
+					next_check,
+					"Skipping eligibility check of backed off author.",
+				);
+				return false
+			}
+		}
+
+		if self.eligibility.can_author(parent.clone(), author, relay_parent_number) {
+			backoff.remove(author);
+			return true
+		}
+
+		if self.max_backoff > 0 {
+			let misses = backoff.get(author).map_or(0, |b| b.misses).saturating_add(1);
+			let skipped = (2u32.saturating_pow(misses - 1) - 1).min(self.max_backoff);
+			let next_check = relay_parent_number.saturating_add(skipped + 1);
+			backoff.insert(author.clone(), Backoff { misses, next_check });
+		}
+
+		false
+	}
+
+	/// Create the provider of the author inherent for a block that is built on top of `parent` at
+	/// `relay_parent`.
+	///
+	/// The inherent is signed by the [selected](Self::select) author. Returns `None` if none of the
+	/// authors may author the block or signing fails.
+	pub fn inherent_data_provider(
+		&self,
+		parent: BlockHash,
+		relay_parent: PHash,
+		validation_data: &PersistedValidationData,
+	) -> Option<AuthorInherentDataProvider> {
+		let author = self.select(parent, relay_parent, validation_data.relay_parent_number)?;
+		let message = AuthorInherentData {
+			author: author.clone(),
+			relay_parent_storage_root: validation_data.relay_parent_storage_root,
+			relay_parent_number: validation_data.relay_parent_number,
+			signature: (),
+		}
+		.signing_message();
+
+		let signature = SyncCryptoStore::sign_with(
+			&*self.keystore,
+			KEY_TYPE,
+			&author.to_public_crypto_pair(),
+			&message,
+		)
+		.map_err(|e| {
+			tracing::error!(
+				target: LOG_TARGET,
+				error = ?e,
+				?author,
+				"Failed to sign the author inherent.",
+			)
+		})
+		.ok()
+		.flatten()
+		.and_then(|signature| AuthorSignature::decode(&mut &signature[..]).ok())?;
+
+		Some(AuthorInherentDataProvider(AuthorInherentData {
+			author,
+			relay_parent_storage_root: validation_data.relay_parent_storage_root,
+			relay_parent_number: validation_data.relay_parent_number,
+			signature,
+		}))
+	}
+}
+
+impl<BlockHash, E> DigestsProvider<BlockHash> for AuthorSelector<BlockHash, E>
+where
+	BlockHash: Clone + PartialEq + std::fmt::Debug,
+	E: AuthorEligibility<BlockHash>,
+{
+	type Digests = [DigestItem; 0];
+
+	fn provide_digests(
+		&self,
+		parent: BlockHash,
+		relay_parent: PHash,
+		validation_data: &PersistedValidationData,
+	) -> Option<Self::Digests> {
+		self.select(parent, relay_parent, validation_data.relay_parent_number)
+			.map(|_| [])
+	}
+}
+
+/// Provides the author inherent of `pallet-author-inherent`.
+pub struct AuthorInherentDataProvider(pub AuthorInherentData<AuthorId, AuthorSignature>);
+
+#[async_trait::async_trait]
+impl sp_inherents::InherentDataProvider for AuthorInherentDataProvider {
+	fn provide_inherent_data(
+		&self,
+		inherent_data: &mut InherentData,
+	) -> Result<(), sp_inherents::Error> {
+		inherent_data.put_data(INHERENT_IDENTIFIER, &self.0)
+	}
+
+	async fn try_handle_error(
+		&self,
+		identifier: &InherentIdentifier,
+		error: &[u8],
+	) -> Option<Result<(), sp_inherents::Error>> {
+		if *identifier != INHERENT_IDENTIFIER {
+			return None
+		}
+
+		let error = InherentError::decode(&mut &error[..]).ok()?;
+		Some(Err(sp_inherents::Error::Application(Box::from(format!("{:?}", error)))))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use cumulus_primitives_author::AuthorPair;
+	use sp_core::{Pair, H256};
+	use sp_keystore::testing::KeyStore;
+	use std::sync::atomic::{AtomicU32, Ordering};
+
+	fn keystore_with_authors(count: usize) -> (SyncCryptoStorePtr, Vec<AuthorId>) {
+		let keystore: SyncCryptoStorePtr = Arc::new(KeyStore::new());
+		let mut authors = (0..count)
+			.map(|_| {
+				SyncCryptoStore::sr25519_generate_new(&*keystore, KEY_TYPE, None)
+					.expect("Generates key")
+					.into()
+			})
+			.collect::<Vec<AuthorId>>();
+		authors.sort();
+
+		(keystore, authors)
+	}
+
+	fn validation_data() -> PersistedValidationData {
+		PersistedValidationData {
+			relay_parent_number: 10,
+			relay_parent_storage_root: H256::repeat_byte(1),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn selects_first_eligible_author() {
+		let (keystore, authors) = keystore_with_authors(3);
+		let eligible = authors[1].clone();
+		let selector = AuthorSelector::new(
+			keystore,
+			move |_: H256, author: &AuthorId, _: RelayBlockNumber| *author == eligible,
+		);
+
+		assert_eq!(selector.author_keys(), authors);
+		assert_eq!(selector.select(H256::zero(), H256::zero(), 10), Some(authors[1].clone()));
+		assert_eq!(
+			selector.eligibility(H256::zero(), 10),
+			vec![
+				(authors[0].clone(), false),
+				(authors[1].clone(), true),
+				(authors[2].clone(), false)
+			],
+		);
+	}
+
+	#[test]
+	fn selects_nobody_if_no_author_is_eligible() {
+		let (keystore, _) = keystore_with_authors(2);
+		let selector =
+			AuthorSelector::new(keystore, |_: H256, _: &AuthorId, _: RelayBlockNumber| false);
+
+		assert_eq!(selector.select(H256::zero(), H256::zero(), 10), None);
+		assert!(selector
+			.provide_digests(H256::zero(), H256::zero(), &validation_data())
+			.is_none());
+		assert!(selector
+			.inherent_data_provider(H256::zero(), H256::zero(), &validation_data())
+			.is_none());
+	}
+
+	#[test]
+	fn selection_is_remembered_per_relay_parent() {
+		let (keystore, _) = keystore_with_authors(2);
+		let checks = Arc::new(AtomicU32::new(0));
+		let selector = AuthorSelector::new(keystore, {
+			let checks = checks.clone();
+			move |_: H256, _: &AuthorId, _: RelayBlockNumber| {
+				checks.fetch_add(1, Ordering::Relaxed);
+				true
+			}
+		});
+
+		let author = selector.select(H256::zero(), H256::zero(), 10);
+		assert_eq!(selector.select(H256::zero(), H256::zero(), 10), author);
+		assert_eq!(checks.load(Ordering::Relaxed), 1);
+
+		selector.select(H256::zero(), H256::repeat_byte(2), 11);
+		assert_eq!(checks.load(Ordering::Relaxed), 2);
+	}
+
+	#[test]
+	fn backoff_skips_eligibility_checks_until_eligible() {
+		let (keystore, _) = keystore_with_authors(1);
+		let checks = Arc::new(Mutex::new(Vec::new()));
+		let selector = AuthorSelector::new(keystore, {
+			let checks = checks.clone();
+			move |_: H256, _: &AuthorId, relay_parent_number: RelayBlockNumber| {
+				checks.lock().push(relay_parent_number);
+				relay_parent_number == 13
+			}
+		})
+		.with_backoff(4);
+
+		for relay_parent_number in 1..=14 {
+			let author = selector.select(
+				H256::zero(),
+				H256::repeat_byte(relay_parent_number as u8),
+				relay_parent_number,
+			);
+			assert_eq!(author.is_some(), relay_parent_number == 13);
+		}
+
+		// Skips 0, 1, 3 and then at most 4 relay parents, until the author is eligible again.
+		assert_eq!(*checks.lock(), vec![1, 2, 4, 8, 13, 14]);
+	}
+
+	#[test]
+	fn inserted_author_keys_are_used_right_away() {
+		let (keystore, authors) = keystore_with_authors(1);
+		let selector = AuthorSelector::new(keystore.clone(), {
+			let ineligible = authors[0].clone();
+			move |_: H256, author: &AuthorId, _: RelayBlockNumber| *author != ineligible
+		});
+
+		assert_eq!(selector.select(H256::zero(), H256::repeat_byte(1), 1), None);
+
+		let inserted: AuthorId = SyncCryptoStore::sr25519_generate_new(&*keystore, KEY_TYPE, None)
+			.expect("Generates key")
+			.into();
+
+		assert_eq!(selector.select(H256::zero(), H256::repeat_byte(2), 2), Some(inserted));
+	}
+
+	#[test]
+	fn inherent_is_signed_by_selected_author() {
+		let (keystore, authors) = keystore_with_authors(2);
+		let selector =
+			AuthorSelector::new(keystore, |_: H256, _: &AuthorId, _: RelayBlockNumber| true);
+
+		let AuthorInherentDataProvider(data) = selector
+			.inherent_data_provider(H256::zero(), H256::zero(), &validation_data())
+			.expect("An author is eligible");
+
+		assert_eq!(data.author, authors[0]);
+		assert_eq!(data.relay_parent_number, 10);
+		assert_eq!(data.relay_parent_storage_root, H256::repeat_byte(1));
+		assert!(AuthorPair::verify(&data.signature, data.signing_message(), &data.author));
+	}
+}
@@ -36,36 +36,163 @@
 use cumulus_client_consensus_common::{
 	ParachainBlockImport, ParachainCandidate, ParachainConsensus,
 };
-use cumulus_primitives_core::{relay_chain::v2::Hash as PHash, ParaId, PersistedValidationData};
+use cumulus_primitives_core::{
+	relay_chain::{
+		v2::{Hash as PHash, OccupiedCoreAssumption},
+		well_known_keys, BlockNumber as RelayBlockNumber,
+	},
+	ParaId, PersistedValidationData,
+};
 use cumulus_relay_chain_interface::RelayChainInterface;
 use parking_lot::Mutex;
 
+use codec::{Decode, Encode};
 use sc_consensus::{BlockImport, BlockImportParams};
+use sc_telemetry::{telemetry, TelemetryHandle, CONSENSUS_INFO};
 use sp_consensus::{
-	BlockOrigin, EnableProofRecording, Environment, ProofRecording, Proposal, Proposer,
+	BlockOrigin, EnableProofRecording, Environment, ProofRecording, Proposal, Proposer, SyncOracle,
 };
 use sp_inherents::{CreateInherentDataProviders, InherentData, InherentDataProvider};
-use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
-use std::{marker::PhantomData, sync::Arc, time::Duration};
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT},
+	Digest, DigestItem,
+};
+use std::{
+	marker::PhantomData,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use substrate_prometheus_endpoint::Registry;
 
+mod author;
 mod import_queue;
+mod metrics;
+pub mod rpc;
+
+pub use author::{AuthorEligibility, AuthorInherentDataProvider, AuthorSelector};
 pub use import_queue::{import_queue, Verifier};
 
+use metrics::Metrics;
+
 const LOG_TARGET: &str = "cumulus-consensus-relay-chain";
 
+/// The longest time the proposer is given to build a block.
+///
+/// Also used if the deadline of a candidate can not be determined.
+const MAX_PROPOSAL_DURATION: Duration = Duration::from_millis(500);
+
+/// The shortest time the proposer is given to build a block, even if the deadline passed already.
+const MIN_PROPOSAL_DURATION: Duration = Duration::from_millis(100);
+
+/// Provides the digests that should be put into the header of a block before it is proposed.
+///
+/// Runtimes may expect pre-runtime digests, e.g. a slot number or the identity of the block
+/// author, which can be supplied through this trait. If the runtime requires a digest the
+/// collator can not provide, e.g. the VRF claim of `pallet-author-slot-filter` on a relay parent
+/// the collator is not eligible for, no candidate is produced on top of this relay parent.
+pub trait DigestsProvider<BlockHash> {
+	/// The digests that are returned by [`Self::provide_digests`].
+	type Digests: IntoIterator<Item = DigestItem>;
+
+	/// Returns the digests for a block that is built on top of `parent` at `relay_parent`.
+	///
+	/// Returns `None` if no candidate should be produced on top of `relay_parent`.
+	fn provide_digests(
+		&self,
+		parent: BlockHash,
+		relay_parent: PHash,
+		validation_data: &PersistedValidationData,
+	) -> Option<Self::Digests>;
+}
+
+impl<BlockHash> DigestsProvider<BlockHash> for () {
+	type Digests = [DigestItem; 0];
+
+	fn provide_digests(
+		&self,
+		_parent: BlockHash,
+		_relay_parent: PHash,
+		_validation_data: &PersistedValidationData,
+	) -> Option<Self::Digests> {
+		Some([])
+	}
+}
+
+impl<F, BlockHash, D> DigestsProvider<BlockHash> for F
+where
+	F: Fn(BlockHash, PHash, &PersistedValidationData) -> Option<D>,
+	D: IntoIterator<Item = DigestItem>,
+{
+	type Digests = D;
+
+	fn provide_digests(
+		&self,
+		parent: BlockHash,
+		relay_parent: PHash,
+		validation_data: &PersistedValidationData,
+	) -> Option<Self::Digests> {
+		(*self)(parent, relay_parent, validation_data)
+	}
+}
+
+/// Decides on top of which relay parents candidates are produced.
+///
+/// Parachains that do not author a block on every relay chain block, e.g. because of the block
+/// cadence of `pallet-author-slot-filter`, skip the other relay parents through this trait.
+pub trait BlockCadence<BlockHash> {
+	/// Returns the cadence for a block that is built on top of `parent`.
+	///
+	/// Candidates are only produced on top of relay parents whose number is a multiple of the
+	/// returned cadence. `0` is treated like `1`.
+	fn block_cadence(&self, parent: BlockHash) -> u32;
+}
+
+impl<BlockHash> BlockCadence<BlockHash> for () {
+	fn block_cadence(&self, _parent: BlockHash) -> u32 {
+		1
+	}
+}
+
+impl<F, BlockHash> BlockCadence<BlockHash> for F
+where
+	F: Fn(BlockHash) -> u32,
+{
+	fn block_cadence(&self, parent: BlockHash) -> u32 {
+		(*self)(parent)
+	}
+}
+
 /// The implementation of the relay-chain provided consensus for parachains.
-pub struct RelayChainConsensus<B, PF, BI, RCInterface, CIDP> {
+///
+/// No candidates are produced while either the relay chain or the parachain is major syncing, on
+/// top of relay parents that are off the [`BlockCadence`] or, if configured, on top of relay
+/// parents that lag too far behind the best relay chain block.
+pub struct RelayChainConsensus<B, PF, BI, RCInterface, CIDP, SO, DP, BC> {
 	para_id: ParaId,
 	_phantom: PhantomData<B>,
 	proposer_factory: Arc<Mutex<PF>>,
 	create_inherent_data_providers: Arc<CIDP>,
 	block_import: Arc<futures::lock::Mutex<ParachainBlockImport<BI>>>,
 	relay_chain_interface: RCInterface,
+	sync_oracle: SO,
+	additional_digests_provider: Arc<DP>,
+	block_cadence: Arc<BC>,
+	major_syncing: Arc<AtomicBool>,
+	max_proof_size: Option<usize>,
+	max_relay_parent_lag: Option<RelayBlockNumber>,
+	relay_slot_duration: Duration,
+	metrics: Option<Metrics>,
+	telemetry: Option<TelemetryHandle>,
 }
 
-impl<B, PF, BI, RCInterface, CIDP> Clone for RelayChainConsensus<B, PF, BI, RCInterface, CIDP>
+impl<B, PF, BI, RCInterface, CIDP, SO, DP, BC> Clone
+	for RelayChainConsensus<B, PF, BI, RCInterface, CIDP, SO, DP, BC>
 where
 	RCInterface: Clone,
+	SO: Clone,
 {
 	fn clone(&self) -> Self {
 		Self {
@@ -75,24 +202,57 @@ where
 			create_inherent_data_providers: self.create_inherent_data_providers.clone(),
 			block_import: self.block_import.clone(),
 			relay_chain_interface: self.relay_chain_interface.clone(),
+			sync_oracle: self.sync_oracle.clone(),
+			additional_digests_provider: self.additional_digests_provider.clone(),
+			block_cadence: self.block_cadence.clone(),
+			major_syncing: self.major_syncing.clone(),
+			max_proof_size: self.max_proof_size,
+			max_relay_parent_lag: self.max_relay_parent_lag,
+			relay_slot_duration: self.relay_slot_duration,
+			metrics: self.metrics.clone(),
+			telemetry: self.telemetry.clone(),
 		}
 	}
 }
 
-impl<B, PF, BI, RCInterface, CIDP> RelayChainConsensus<B, PF, BI, RCInterface, CIDP>
+impl<B, PF, BI, RCInterface, CIDP, SO, DP, BC>
+	RelayChainConsensus<B, PF, BI, RCInterface, CIDP, SO, DP, BC>
 where
 	B: BlockT,
 	RCInterface: RelayChainInterface,
 	CIDP: CreateInherentDataProviders<B, (PHash, PersistedValidationData)>,
+	SO: SyncOracle,
 {
 	/// Create a new instance of relay-chain provided consensus.
 	pub fn new(
-		para_id: ParaId,
-		proposer_factory: PF,
-		create_inherent_data_providers: CIDP,
-		block_import: BI,
-		relay_chain_interface: RCInterface,
+		BuildRelayChainConsensusParams {
+			para_id,
+			proposer_factory,
+			create_inherent_data_providers,
+			block_import,
+			relay_chain_interface,
+			sync_oracle,
+			additional_digests_provider,
+			block_cadence,
+			max_proof_size,
+			max_relay_parent_lag,
+			relay_slot_duration,
+			prometheus_registry,
+			telemetry,
+		}: BuildRelayChainConsensusParams<PF, BI, CIDP, RCInterface, SO, DP, BC>,
 	) -> Self {
+		let metrics = prometheus_registry.as_ref().and_then(|registry| {
+			Metrics::register(registry)
+				.map_err(|e| {
+					tracing::warn!(
+						target: LOG_TARGET,
+						error = ?e,
+						"Failed to register relay-chain consensus metrics.",
+					)
+				})
+				.ok()
+		});
+
 		Self {
 			para_id,
 			proposer_factory: Arc::new(Mutex::new(proposer_factory)),
@@ -101,10 +261,134 @@ where
 				block_import,
 			))),
 			relay_chain_interface,
+			sync_oracle,
+			additional_digests_provider: Arc::new(additional_digests_provider),
+			block_cadence: Arc::new(block_cadence),
+			major_syncing: Arc::new(AtomicBool::new(false)),
+			max_proof_size,
+			max_relay_parent_lag,
+			relay_slot_duration,
+			metrics,
+			telemetry,
 			_phantom: PhantomData,
 		}
 	}
 
+	/// Returns the time the proposer may take to build a block on top of `relay_parent`.
+	///
+	/// The candidate has to be backed before the relay chain block following `relay_parent` is
+	/// authored, which happens at the start of the next relay chain slot. Half of the time left
+	/// until then is given to the proposer, the other half is left for importing, distributing and
+	/// backing the candidate.
+	async fn proposal_duration(&self, relay_parent: PHash) -> Duration {
+		let relay_slot = match self
+			.relay_chain_interface
+			.get_storage_by_key(relay_parent, well_known_keys::CURRENT_SLOT)
+			.await
+		{
+			Ok(Some(encoded)) => u64::decode(&mut &encoded[..]).ok(),
+			Ok(None) => None,
+			Err(e) => {
+				tracing::debug!(
+					target: LOG_TARGET,
+					error = ?e,
+					"Failed to fetch the slot of the relay parent.",
+				);
+				None
+			},
+		};
+
+		let relay_slot = match relay_slot {
+			Some(relay_slot) => relay_slot,
+			None => return MAX_PROPOSAL_DURATION,
+		};
+
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+		let deadline = Duration::from_millis(
+			(self.relay_slot_duration.as_millis() as u64).saturating_mul(relay_slot + 1),
+		);
+		let time_left = deadline.saturating_sub(now);
+		let duration = (time_left / 2).clamp(MIN_PROPOSAL_DURATION, MAX_PROPOSAL_DURATION);
+
+		tracing::debug!(
+			target: LOG_TARGET,
+			relay_slot,
+			?time_left,
+			proposal_duration = ?duration,
+			"Computed proposal duration.",
+		);
+
+		duration
+	}
+
+	/// Returns how many blocks the relay parent with the given number is behind the best relay chain
+	/// block.
+	///
+	/// Returns `None` if the best relay chain block can not be determined.
+	async fn relay_parent_lag(&self, relay_parent_number: RelayBlockNumber) -> Option<u32> {
+		let best_number = async {
+			let best_hash = self.relay_chain_interface.best_block_hash().await?;
+			// The validation data at a relay chain block is built on top of this block, so it
+			// contains its number.
+			self.relay_chain_interface
+				.persisted_validation_data(
+					best_hash,
+					self.para_id,
+					OccupiedCoreAssumption::TimedOut,
+				)
+				.await
+				.map(|validation_data| validation_data.map(|v| v.relay_parent_number))
+		}
+		.await;
+
+		match best_number {
+			Ok(Some(best_number)) => Some(best_number.saturating_sub(relay_parent_number)),
+			Ok(None) => None,
+			Err(e) => {
+				tracing::debug!(
+					target: LOG_TARGET,
+					error = ?e,
+					"Failed to fetch the number of the best relay chain block.",
+				);
+				None
+			},
+		}
+	}
+
+	/// Returns `true` if either the relay chain or the parachain is major syncing.
+	///
+	/// Changes of the sync state are logged once, instead of on every skipped candidate.
+	async fn is_major_syncing(&mut self) -> bool {
+		let relay_chain_syncing =
+			self.relay_chain_interface.is_major_syncing().await.unwrap_or_else(|e| {
+				tracing::warn!(
+					target: LOG_TARGET,
+					error = ?e,
+					"Failed to determine if the relay chain is major syncing.",
+				);
+				false
+			});
+		let major_syncing = relay_chain_syncing || self.sync_oracle.is_major_syncing();
+
+		if self.major_syncing.swap(major_syncing, Ordering::Relaxed) != major_syncing {
+			if major_syncing {
+				tracing::info!(
+					target: LOG_TARGET,
+					relay_chain_syncing,
+					"Pausing candidate production while major syncing.",
+				);
+			} else {
+				tracing::info!(target: LOG_TARGET, "Resuming candidate production after major sync.");
+			}
+		}
+
+		if let Some(metrics) = &self.metrics {
+			metrics.major_syncing.set(major_syncing as u64);
+		}
+
+		major_syncing
+	}
+
 	/// Get the inherent data with validation function parameters injected
 	async fn inherent_data(
 		&self,
@@ -139,8 +423,8 @@ where
 }
 
 #[async_trait::async_trait]
-impl<B, PF, BI, RCInterface, CIDP> ParachainConsensus<B>
-	for RelayChainConsensus<B, PF, BI, RCInterface, CIDP>
+impl<B, PF, BI, RCInterface, CIDP, SO, DP, BC> ParachainConsensus<B>
+	for RelayChainConsensus<B, PF, BI, RCInterface, CIDP, SO, DP, BC>
 where
 	B: BlockT,
 	RCInterface: RelayChainInterface + Clone,
@@ -153,31 +437,105 @@ where
 		Proof = <EnableProofRecording as ProofRecording>::Proof,
 	>,
 	CIDP: CreateInherentDataProviders<B, (PHash, PersistedValidationData)>,
+	SO: SyncOracle + Send + Sync + Clone,
+	DP: DigestsProvider<B::Hash> + Send + Sync,
+	BC: BlockCadence<B::Hash> + Send + Sync,
 {
+	#[tracing::instrument(
+		target = "cumulus-consensus-relay-chain",
+		name = "produce_candidate",
+		level = "debug",
+		skip_all,
+		fields(parent = ?parent.hash(), relay_parent = ?relay_parent),
+	)]
 	async fn produce_candidate(
 		&mut self,
 		parent: &B::Header,
 		relay_parent: PHash,
 		validation_data: &PersistedValidationData,
 	) -> Option<ParachainCandidate<B>> {
+		if self.is_major_syncing().await {
+			return None
+		}
+
+		let cadence = self.block_cadence.block_cadence(parent.hash()).max(1);
+		if validation_data.relay_parent_number % cadence != 0 {
+			tracing::debug!(
+				target: LOG_TARGET,
+				relay_parent_number = validation_data.relay_parent_number,
+				cadence,
+				"Skipping relay parent that is off the block cadence.",
+			);
+			return None
+		}
+
+		if let Some(max_lag) = self.max_relay_parent_lag {
+			match self.relay_parent_lag(validation_data.relay_parent_number).await {
+				Some(lag) if lag > max_lag => {
+					tracing::debug!(
+						target: LOG_TARGET,
+						relay_parent_number = validation_data.relay_parent_number,
+						lag,
+						max_lag,
+						"Skipping relay parent that lags too far behind the best relay chain block.",
+					);
+					return None
+				},
+				_ => (),
+			}
+		}
+
+		let inherent_digests = match self.additional_digests_provider.provide_digests(
+			parent.hash(),
+			relay_parent,
+			validation_data,
+		) {
+			Some(digests) => Digest { logs: digests.into_iter().collect() },
+			None => {
+				tracing::debug!(
+					target: LOG_TARGET,
+					relay_parent_number = validation_data.relay_parent_number,
+					"Skipping relay parent for which no digests were provided.",
+				);
+				return None
+			},
+		};
+
+		if let Some(metrics) = &self.metrics {
+			metrics.candidates_attempted.inc();
+		}
+
 		let proposer_future = self.proposer_factory.lock().init(&parent);
 
 		let proposer = proposer_future
 			.await
-			.map_err(
-				|e| tracing::error!(target: LOG_TARGET, error = ?e, "Could not create proposer."),
-			)
+			.map_err(|e| {
+				if let Some(metrics) = &self.metrics {
+					metrics.proposer_creation_failures.inc();
+				}
+				tracing::error!(target: LOG_TARGET, error = ?e, "Could not create proposer.")
+			})
 			.ok()?;
 
 		let inherent_data =
-			self.inherent_data(parent.hash(), &validation_data, relay_parent).await?;
+			match self.inherent_data(parent.hash(), &validation_data, relay_parent).await {
+				Some(inherent_data) => inherent_data,
+				None => {
+					if let Some(metrics) = &self.metrics {
+						metrics.inherent_data_failures.inc();
+					}
+					return None
+				},
+			};
+
+		let proposal_duration = self.proposal_duration(relay_parent).await;
 
+		let proposal_start = Instant::now();
 		let Proposal { block, storage_changes, proof } = proposer
 			.propose(
 				inherent_data,
-				Default::default(),
-				// TODO: Fix this.
-				Duration::from_millis(500),
+				inherent_digests,
+				proposal_duration,
 				// Set the block limit to 50% of the maximum PoV size.
 				//
 				// TODO: If we got benchmarking that includes that encapsulates the proof size,
@@ -185,9 +543,52 @@ where
 				Some((validation_data.max_pov_size / 2) as usize),
 			)
 			.await
-			.map_err(|e| tracing::error!(target: LOG_TARGET, error = ?e, "Proposing failed."))
+			.map_err(|e| {
+				if let Some(metrics) = &self.metrics {
+					metrics.proposal_failures.inc();
+				}
+				tracing::error!(target: LOG_TARGET, error = ?e, "Proposing failed.")
+			})
 			.ok()?;
 
+		if let Some(metrics) = &self.metrics {
+			metrics.proposal_duration.observe(proposal_start.elapsed().as_secs_f64());
+		}
+
+		let proof_size = proof.encoded_size();
+		let max_proof_size = self
+			.max_proof_size
+			.unwrap_or(usize::MAX)
+			.min(validation_data.max_pov_size as usize);
+
+		tracing::debug!(
+			target: LOG_TARGET,
+			proof_size,
+			max_proof_size,
+			"Proposed block `{:?}`.",
+			block.header().hash(),
+		);
+
+		if let Some(metrics) = &self.metrics {
+			metrics.proof_size.observe(proof_size as f64);
+		}
+
+		if proof_size > max_proof_size {
+			tracing::warn!(
+				target: LOG_TARGET,
+				proof_size,
+				max_proof_size,
+				"Discarding block `{:?}`, because its storage proof is too big.",
+				block.header().hash(),
+			);
+
+			if let Some(metrics) = &self.metrics {
+				metrics.oversized_proofs.inc();
+			}
+
+			return None
+		}
+
 		let (header, extrinsics) = block.clone().deconstruct();
 
 		let mut block_import_params = BlockImportParams::new(BlockOrigin::Own, header);
@@ -210,33 +611,76 @@ where
 				"Error importing build block.",
 			);
 
+			if let Some(metrics) = &self.metrics {
+				metrics.import_failures.inc();
+			}
+
 			return None
 		}
 
+		if let Some(metrics) = &self.metrics {
+			metrics.candidates_produced.inc();
+		}
+
+		telemetry!(
+			self.telemetry;
+			CONSENSUS_INFO;
+			"cumulus.relay_chain.candidate_produced";
+			"number" => ?block.header().number(),
+			"hash" => ?block.header().hash(),
+			"parent_hash" => ?parent.hash(),
+			"proof_size" => proof_size,
+		);
+
 		Some(ParachainCandidate { block, proof })
 	}
 }
 
 /// Parameters of [`build_relay_chain_consensus`].
-pub struct BuildRelayChainConsensusParams<PF, BI, CIDP, RCInterface> {
+pub struct BuildRelayChainConsensusParams<PF, BI, CIDP, RCInterface, SO, DP, BC> {
 	pub para_id: ParaId,
 	pub proposer_factory: PF,
 	pub create_inherent_data_providers: CIDP,
 	pub block_import: BI,
 	pub relay_chain_interface: RCInterface,
+	/// The sync oracle of the parachain.
+	pub sync_oracle: SO,
+	/// Provides the digests that are put into the header of every proposed block.
+	///
+	/// Relay parents for which it provides no digests are skipped. Use `()` if no additional
+	/// digests are required.
+	pub additional_digests_provider: DP,
+	/// Decides on top of which relay parents candidates are produced.
+	///
+	/// Use `()` to produce a candidate on top of every relay parent.
+	pub block_cadence: BC,
+	/// The maximum encoded size of the storage proof of a candidate.
+	///
+	/// Candidates with a bigger proof are discarded. The maximum PoV size of the relay chain is
+	/// always enforced, even if this is `None` or bigger.
+	pub max_proof_size: Option<usize>,
+	/// The number of blocks a relay parent may be behind the best relay chain block.
+	///
+	/// Candidates are only produced on top of relay parents within this window, e.g. to test how a
+	/// parachain copes with collators that build on slightly outdated relay parents. Relay parents
+	/// that lag further behind are skipped. `None` builds on every relay parent the collator is
+	/// asked for.
+	pub max_relay_parent_lag: Option<RelayBlockNumber>,
+	/// The slot duration of the relay chain.
+	///
+	/// Used to determine how much time is left to build a candidate before it needs to be backed.
+	pub relay_slot_duration: Duration,
+	/// The Prometheus registry the consensus metrics should be registered at.
+	pub prometheus_registry: Option<Registry>,
+	/// The telemetry produced candidates should be reported to.
+	pub telemetry: Option<TelemetryHandle>,
 }
 
 /// Build the [`RelayChainConsensus`].
 ///
 /// Returns a boxed [`ParachainConsensus`].
-pub fn build_relay_chain_consensus<Block, PF, BI, CIDP, RCInterface>(
-	BuildRelayChainConsensusParams {
-		para_id,
-		proposer_factory,
-		create_inherent_data_providers,
-		block_import,
-		relay_chain_interface,
-	}: BuildRelayChainConsensusParams<PF, BI, CIDP, RCInterface>,
+pub fn build_relay_chain_consensus<Block, PF, BI, CIDP, RCInterface, SO, DP, BC>(
+	params: BuildRelayChainConsensusParams<PF, BI, CIDP, RCInterface, SO, DP, BC>,
 ) -> Box<dyn ParachainConsensus<Block>>
 where
 	Block: BlockT,
@@ -250,12 +694,9 @@ where
 	BI: BlockImport<Block> + Send + Sync + 'static,
 	CIDP: CreateInherentDataProviders<Block, (PHash, PersistedValidationData)> + 'static,
 	RCInterface: RelayChainInterface + Clone + 'static,
+	SO: SyncOracle + Send + Sync + Clone + 'static,
+	DP: DigestsProvider<Block::Hash> + Send + Sync + 'static,
+	BC: BlockCadence<Block::Hash> + Send + Sync + 'static,
 {
-	Box::new(RelayChainConsensus::new(
-		para_id,
-		proposer_factory,
-		create_inherent_data_providers,
-		block_import,
-		relay_chain_interface,
-	))
+	Box::new(RelayChainConsensus::new(params))
 }
@@ -0,0 +1,128 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prometheus metrics of the relay-chain consensus.
+
+use substrate_prometheus_endpoint::{
+	exponential_buckets, register, Counter, Gauge, Histogram, HistogramOpts, PrometheusError,
+	Registry, U64,
+};
+
+/// Metrics reported by [`RelayChainConsensus`](crate::RelayChainConsensus).
+#[derive(Clone)]
+pub(crate) struct Metrics {
+	/// Number of times the collator was asked to produce a candidate.
+	pub candidates_attempted: Counter<U64>,
+	/// Number of candidates that were built and imported successfully.
+	pub candidates_produced: Counter<U64>,
+	/// Number of times the proposer could not be created.
+	pub proposer_creation_failures: Counter<U64>,
+	/// Number of times the inherent data (providers) could not be created.
+	pub inherent_data_failures: Counter<U64>,
+	/// Number of times proposing a block failed.
+	pub proposal_failures: Counter<U64>,
+	/// Number of times importing a freshly built block failed.
+	pub import_failures: Counter<U64>,
+	/// Time it took to propose a block.
+	pub proposal_duration: Histogram,
+	/// Encoded size of the storage proof of proposed blocks.
+	pub proof_size: Histogram,
+	/// Number of proposed blocks that were discarded, because their storage proof was too big.
+	pub oversized_proofs: Counter<U64>,
+	/// Set to `1` while candidate production is paused, because of a major sync.
+	pub major_syncing: Gauge<U64>,
+}
+
+impl Metrics {
+	/// Register the metrics at the given Prometheus `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			candidates_attempted: register(
+				Counter::new(
+					"cumulus_relay_chain_consensus_candidates_attempted",
+					"Number of times the collator tried to produce a candidate",
+				)?,
+				registry,
+			)?,
+			candidates_produced: register(
+				Counter::new(
+					"cumulus_relay_chain_consensus_candidates_produced",
+					"Number of candidates that were built and imported",
+				)?,
+				registry,
+			)?,
+			proposer_creation_failures: register(
+				Counter::new(
+					"cumulus_relay_chain_consensus_proposer_creation_failures",
+					"Number of times the proposer could not be created",
+				)?,
+				registry,
+			)?,
+			inherent_data_failures: register(
+				Counter::new(
+					"cumulus_relay_chain_consensus_inherent_data_failures",
+					"Number of times the inherent data could not be created",
+				)?,
+				registry,
+			)?,
+			proposal_failures: register(
+				Counter::new(
+					"cumulus_relay_chain_consensus_proposal_failures",
+					"Number of times proposing a block failed",
+				)?,
+				registry,
+			)?,
+			import_failures: register(
+				Counter::new(
+					"cumulus_relay_chain_consensus_import_failures",
+					"Number of times importing a built block failed",
+				)?,
+				registry,
+			)?,
+			proposal_duration: register(
+				Histogram::with_opts(HistogramOpts::new(
+					"cumulus_relay_chain_consensus_proposal_duration",
+					"Time in seconds it took to propose a block",
+				))?,
+				registry,
+			)?,
+			proof_size: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"cumulus_relay_chain_consensus_proof_size",
+						"Encoded size in bytes of the storage proof of proposed blocks",
+					)
+					.buckets(exponential_buckets(1024.0, 2.0, 14)?),
+				)?,
+				registry,
+			)?,
+			oversized_proofs: register(
+				Counter::new(
+					"cumulus_relay_chain_consensus_oversized_proofs",
+					"Number of proposed blocks discarded because of a too big storage proof",
+				)?,
+				registry,
+			)?,
+			major_syncing: register(
+				Gauge::new(
+					"cumulus_relay_chain_consensus_major_syncing",
+					"Whether candidate production is paused because of a major sync",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
@@ -0,0 +1,229 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC methods to debug the candidate production of the relay-chain consensus.
+//!
+//! All methods are unsafe and only available if unsafe RPC methods are allowed.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use codec::{Decode, Encode};
+use cumulus_primitives_author::AuthorId;
+use cumulus_primitives_core::{
+	relay_chain::{
+		v2::{Hash as PHash, OccupiedCoreAssumption},
+		BlockNumber as RelayBlockNumber,
+	},
+	ParaId, PersistedValidationData,
+};
+use cumulus_relay_chain_interface::{RelayChainError, RelayChainInterface};
+use futures::FutureExt;
+use jsonrpc_core::{BoxFuture, Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sc_rpc_api::DenyUnsafe;
+use serde::{de::DeserializeOwned, Serialize};
+use sp_inherents::{CreateInherentDataProviders, InherentDataProvider, InherentIdentifier};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+
+use crate::{AuthorEligibility, AuthorSelector};
+
+/// Relay-chain consensus RPC methods.
+#[rpc]
+pub trait RelayChainConsensusApi<Hash> {
+	/// Check a candidate on top of the best relay chain block, without building it.
+	///
+	/// Checks the eligibility of the author keys and creates the inherent data like the consensus
+	/// does, but does not build, import or announce a block. The parachain block is the one the
+	/// relay chain expects the candidate to be built on, assuming that the candidate pending
+	/// availability, if any, is included.
+	#[rpc(name = "relayChainConsensus_dryRunCandidate")]
+	fn dry_run_candidate(&self) -> BoxFuture<Result<DryRunReport<Hash>>>;
+}
+
+/// The eligibility of an author key in the keystore.
+#[derive(Clone, Debug, Serialize)]
+pub struct AuthorReport {
+	/// The author key.
+	pub author: AuthorId,
+	/// Whether the author may author the candidate.
+	pub eligible: bool,
+}
+
+/// Report of [`RelayChainConsensusApi::dry_run_candidate`].
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunReport<Hash> {
+	/// The parachain block the candidate would be built on.
+	pub parent: Hash,
+	/// The relay parent the candidate would be built on.
+	pub relay_parent: PHash,
+	/// The number of the relay parent.
+	pub relay_parent_number: RelayBlockNumber,
+	/// The author keys in the keystore, empty if the consensus does not select an author.
+	pub authors: Vec<AuthorReport>,
+	/// The encoded size of every inherent, by inherent identifier.
+	pub inherent_sizes: BTreeMap<String, usize>,
+	/// Why the inherent data could not be created, `null` if it was created.
+	pub inherent_data_error: Option<String>,
+	/// The maximum size of the PoV of the candidate.
+	///
+	/// The size of the storage proof is only known once a block is built. See the
+	/// `collator_lastProof` RPC method for the proof of the last candidate.
+	pub max_pov_size: u32,
+}
+
+/// Implementation of [`RelayChainConsensusApi`].
+pub struct RelayChainConsensusRpc<B: BlockT, RCInterface, CIDP, E> {
+	para_id: ParaId,
+	relay_chain_interface: RCInterface,
+	create_inherent_data_providers: Arc<CIDP>,
+	author_selector: Option<AuthorSelector<B::Hash, E>>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<B: BlockT, RCInterface, CIDP, E> RelayChainConsensusRpc<B, RCInterface, CIDP, E> {
+	/// Create a new instance that checks candidates of `para_id`.
+	///
+	/// `create_inherent_data_providers` and `author_selector` should be the ones the consensus of
+	/// the collator uses. Pass `None` as `author_selector` if the consensus does not select an
+	/// author.
+	pub fn new(
+		para_id: ParaId,
+		relay_chain_interface: RCInterface,
+		create_inherent_data_providers: CIDP,
+		author_selector: Option<AuthorSelector<B::Hash, E>>,
+		deny_unsafe: DenyUnsafe,
+	) -> Self {
+		Self {
+			para_id,
+			relay_chain_interface,
+			create_inherent_data_providers: Arc::new(create_inherent_data_providers),
+			author_selector,
+			deny_unsafe,
+		}
+	}
+}
+
+impl<B, RCInterface, CIDP, E> RelayChainConsensusApi<B::Hash>
+	for RelayChainConsensusRpc<B, RCInterface, CIDP, E>
+where
+	B: BlockT,
+	B::Hash: Serialize + DeserializeOwned,
+	RCInterface: RelayChainInterface + Clone + 'static,
+	CIDP: CreateInherentDataProviders<B, (PHash, PersistedValidationData)> + 'static,
+	E: AuthorEligibility<B::Hash> + Send + Sync + 'static,
+{
+	fn dry_run_candidate(&self) -> BoxFuture<Result<DryRunReport<B::Hash>>> {
+		if let Err(e) = self.deny_unsafe.check_if_safe() {
+			return async move { Err(e.into()) }.boxed()
+		}
+
+		let para_id = self.para_id;
+		let relay_chain_interface = self.relay_chain_interface.clone();
+		let create_inherent_data_providers = self.create_inherent_data_providers.clone();
+		let author_selector = self.author_selector.clone();
+
+		async move {
+			let relay_parent =
+				relay_chain_interface.best_block_hash().await.map_err(relay_chain_error)?;
+			let validation_data = relay_chain_interface
+				.persisted_validation_data(relay_parent, para_id, OccupiedCoreAssumption::Included)
+				.await
+				.map_err(relay_chain_error)?
+				.ok_or_else(|| RpcError {
+					code: ErrorCode::ServerError(2),
+					message: "The parachain is not registered at the best relay chain block".into(),
+					data: None,
+				})?;
+			let parent = B::Header::decode(&mut &validation_data.parent_head.0[..])
+				.map_err(|e| RpcError {
+					code: ErrorCode::ServerError(3),
+					message: "Failed to decode the parachain head".into(),
+					data: Some(e.to_string().into()),
+				})?
+				.hash();
+
+			let authors = author_selector
+				.map(|selector| {
+					selector
+						.eligibility(parent, validation_data.relay_parent_number)
+						.into_iter()
+						.map(|(author, eligible)| AuthorReport { author, eligible })
+						.collect()
+				})
+				.unwrap_or_default();
+
+			let (inherent_sizes, inherent_data_error) = match inherent_sizes::<B, _>(
+				&*create_inherent_data_providers,
+				parent,
+				relay_parent,
+				&validation_data,
+			)
+			.await
+			{
+				Ok(inherent_sizes) => (inherent_sizes, None),
+				Err(e) => (Default::default(), Some(e)),
+			};
+
+			Ok(DryRunReport {
+				parent,
+				relay_parent,
+				relay_parent_number: validation_data.relay_parent_number,
+				authors,
+				inherent_sizes,
+				inherent_data_error,
+				max_pov_size: validation_data.max_pov_size,
+			})
+		}
+		.boxed()
+	}
+}
+
+/// Create the inherent data and return the encoded size of every inherent.
+async fn inherent_sizes<B: BlockT, CIDP>(
+	create_inherent_data_providers: &CIDP,
+	parent: B::Hash,
+	relay_parent: PHash,
+	validation_data: &PersistedValidationData,
+) -> std::result::Result<BTreeMap<String, usize>, String>
+where
+	CIDP: CreateInherentDataProviders<B, (PHash, PersistedValidationData)>,
+{
+	let inherent_data = create_inherent_data_providers
+		.create_inherent_data_providers(parent, (relay_parent, validation_data.clone()))
+		.await
+		.map_err(|e| e.to_string())?
+		.create_inherent_data()
+		.map_err(|e| e.to_string())?;
+
+	// `InherentData` does not expose its entries, but it is encoded like the map of them.
+	let inherents =
+		BTreeMap::<InherentIdentifier, Vec<u8>>::decode(&mut &inherent_data.encode()[..])
+			.map_err(|e| e.to_string())?;
+
+	Ok(inherents
+		.into_iter()
+		.map(|(identifier, data)| (String::from_utf8_lossy(&identifier).into_owned(), data.len()))
+		.collect())
+}
+
+fn relay_chain_error(error: RelayChainError) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(1),
+		message: "Failed to query the relay chain".into(),
+		data: Some(error.to_string().into()),
+	}
+}
@@ -0,0 +1,211 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Local extrinsic blocklist of a collator.
+//!
+//! Allows an operator to keep specific extrinsics out of the blocks proposed by their collator,
+//! e.g. to mitigate extrinsics that exploit underpriced weights until a runtime fix is enacted.
+//! Extrinsics are banned either by their hash or by a byte pattern that is contained in their
+//! encoding, e.g. the account id of their sender.
+//!
+//! Banned extrinsics are removed from the local transaction pool as soon as they enter it, so the
+//! proposer never sees them. Blocks authored by other collators that include banned extrinsics are
+//! still imported as usual.
+//!
+//! The blocklist is stored in the auxiliary storage of the client and survives restarts. It is
+//! managed through the RPC methods of [`rpc::BlocklistApi`].
+
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use futures::StreamExt;
+use parking_lot::RwLock;
+use sc_client_api::AuxStore;
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool};
+use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
+
+pub mod rpc;
+
+const LOG_TARGET: &str = "extrinsic-blocklist";
+
+/// Key of the blocklist in the auxiliary storage.
+const BLOCKLIST_KEY: &[u8] = b"cumulus_extrinsic_blocklist";
+
+/// An entry of the [`Blocklist`].
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BlocklistEntry {
+	/// The extrinsic with the given hash.
+	Hash(Bytes),
+	/// Every extrinsic whose encoding contains the given bytes.
+	Pattern(Bytes),
+}
+
+impl BlocklistEntry {
+	/// Returns `true` if the extrinsic with the given hash and encoding matches this entry.
+	fn matches(&self, hash: &[u8], encoded: &[u8]) -> bool {
+		match self {
+			Self::Hash(banned) => &banned[..] == hash,
+			Self::Pattern(pattern) =>
+				!pattern.is_empty() && encoded.windows(pattern.len()).any(|w| w == &pattern[..]),
+		}
+	}
+}
+
+/// The extrinsics that should not be included in locally proposed blocks.
+pub struct Blocklist<C> {
+	client: Arc<C>,
+	entries: RwLock<Vec<BlocklistEntry>>,
+}
+
+impl<C: AuxStore> Blocklist<C> {
+	/// Load the blocklist from the auxiliary storage of `client`.
+	pub fn new(client: Arc<C>) -> sp_blockchain::Result<Self> {
+		let entries = match client.get_aux(BLOCKLIST_KEY)? {
+			Some(encoded) => Vec::<BlocklistEntry>::decode(&mut &encoded[..]).map_err(|e| {
+				let msg = format!("Failed to decode extrinsic blocklist: {}", e);
+				sp_blockchain::Error::Backend(msg)
+			})?,
+			None => Vec::new(),
+		};
+
+		if !entries.is_empty() {
+			tracing::info!(target: LOG_TARGET, count = entries.len(), "Loaded extrinsic blocklist.");
+		}
+
+		Ok(Self { client, entries: RwLock::new(entries) })
+	}
+
+	/// Returns all entries of the blocklist.
+	pub fn entries(&self) -> Vec<BlocklistEntry> {
+		self.entries.read().clone()
+	}
+
+	/// Add `entry` to the blocklist.
+	///
+	/// Returns `false` if the entry was already present.
+	pub fn ban(&self, entry: BlocklistEntry) -> sp_blockchain::Result<bool> {
+		let mut entries = self.entries.write();
+		if entries.contains(&entry) {
+			return Ok(false)
+		}
+
+		entries.push(entry);
+		self.persist(&entries)?;
+		Ok(true)
+	}
+
+	/// Remove `entry` from the blocklist.
+	///
+	/// Returns `false` if the entry was not present.
+	pub fn unban(&self, entry: &BlocklistEntry) -> sp_blockchain::Result<bool> {
+		let mut entries = self.entries.write();
+		let len = entries.len();
+		entries.retain(|e| e != entry);
+		if entries.len() == len {
+			return Ok(false)
+		}
+
+		self.persist(&entries)?;
+		Ok(true)
+	}
+
+	/// Returns `true` if the given pool transaction matches any entry of the blocklist.
+	pub fn is_banned<Tx>(&self, transaction: &Tx) -> bool
+	where
+		Tx: InPoolTransaction,
+		Tx::Transaction: Encode,
+		Tx::Hash: Encode,
+	{
+		let entries = self.entries.read();
+		if entries.is_empty() {
+			return false
+		}
+
+		let hash = transaction.hash().encode();
+		let encoded = transaction.data().encode();
+		entries.iter().any(|entry| entry.matches(&hash, &encoded))
+	}
+
+	/// Remove all banned transactions from the ready queue of `pool`.
+	pub fn purge<P>(&self, pool: &P)
+	where
+		P: TransactionPool,
+		P::Hash: Encode,
+	{
+		let banned = pool
+			.ready()
+			.filter(|tx| self.is_banned(&**tx))
+			.map(|tx| tx.hash().clone())
+			.collect::<Vec<_>>();
+
+		if !banned.is_empty() {
+			tracing::info!(
+				target: LOG_TARGET,
+				count = banned.len(),
+				"Removing banned extrinsics from the transaction pool.",
+			);
+			pool.remove_invalid(&banned);
+		}
+	}
+
+	fn persist(&self, entries: &[BlocklistEntry]) -> sp_blockchain::Result<()> {
+		self.client.insert_aux(&[(BLOCKLIST_KEY, &entries.encode()[..])], &[])
+	}
+}
+
+/// Keep the transactions banned by `blocklist` out of `pool`.
+///
+/// Purges the pool once and then removes every banned transaction that is imported into it.
+pub async fn run_blocklist<C, P>(blocklist: Arc<Blocklist<C>>, pool: Arc<P>)
+where
+	C: AuxStore,
+	P: TransactionPool,
+	P::Hash: Encode,
+{
+	blocklist.purge(&*pool);
+
+	let mut imported = pool.import_notification_stream();
+	while let Some(hash) = imported.next().await {
+		let banned = pool.ready_transaction(&hash).map_or(false, |tx| blocklist.is_banned(&*tx));
+
+		if banned {
+			tracing::debug!(target: LOG_TARGET, ?hash, "Removing banned extrinsic.");
+			pool.remove_invalid(&[hash]);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn entries_match_hash_or_pattern() {
+		let hash = [1u8; 32];
+		let encoded = [4, 5, 6, 7, 8];
+
+		assert!(BlocklistEntry::Hash(Bytes(hash.to_vec())).matches(&hash, &encoded));
+		assert!(!BlocklistEntry::Hash(Bytes(vec![2; 32])).matches(&hash, &encoded));
+
+		assert!(BlocklistEntry::Pattern(Bytes(vec![5, 6])).matches(&hash, &encoded));
+		assert!(BlocklistEntry::Pattern(Bytes(vec![4, 5, 6, 7, 8])).matches(&hash, &encoded));
+		assert!(!BlocklistEntry::Pattern(Bytes(vec![6, 5])).matches(&hash, &encoded));
+		assert!(!BlocklistEntry::Pattern(Bytes(vec![8, 9])).matches(&hash, &encoded));
+		assert!(!BlocklistEntry::Pattern(Bytes(Vec::new())).matches(&hash, &encoded));
+	}
+}
@@ -0,0 +1,108 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC methods to manage the [`Blocklist`].
+//!
+//! All methods are unsafe and only available if unsafe RPC methods are allowed.
+
+use std::sync::Arc;
+
+use codec::Encode;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sc_client_api::AuxStore;
+use sc_rpc_api::DenyUnsafe;
+use sc_transaction_pool_api::TransactionPool;
+
+use crate::{Blocklist, BlocklistEntry, LOG_TARGET};
+
+/// Extrinsic blocklist RPC methods.
+#[rpc]
+pub trait BlocklistApi {
+	/// Ban extrinsics matching `entry` from blocks proposed by this collator.
+	///
+	/// Matching extrinsics are removed from the transaction pool. Returns `false` if the entry was
+	/// banned already.
+	#[rpc(name = "collator_banExtrinsic")]
+	fn ban_extrinsic(&self, entry: BlocklistEntry) -> Result<bool>;
+
+	/// Remove `entry` from the blocklist.
+	///
+	/// Returns `false` if the entry was not banned. Extrinsics that were removed from the
+	/// transaction pool because of the entry may still be rejected by the pool for a while.
+	#[rpc(name = "collator_unbanExtrinsic")]
+	fn unban_extrinsic(&self, entry: BlocklistEntry) -> Result<bool>;
+
+	/// Returns all entries of the blocklist.
+	#[rpc(name = "collator_bannedExtrinsics")]
+	fn banned_extrinsics(&self) -> Result<Vec<BlocklistEntry>>;
+}
+
+/// Implementation of [`BlocklistApi`].
+pub struct BlocklistRpc<C, P> {
+	blocklist: Arc<Blocklist<C>>,
+	pool: Arc<P>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<C, P> BlocklistRpc<C, P> {
+	/// Create a new instance that manages `blocklist` and purges `pool`.
+	pub fn new(blocklist: Arc<Blocklist<C>>, pool: Arc<P>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { blocklist, pool, deny_unsafe }
+	}
+}
+
+impl<C, P> BlocklistApi for BlocklistRpc<C, P>
+where
+	C: AuxStore + Send + Sync + 'static,
+	P: TransactionPool + 'static,
+	P::Hash: Encode,
+{
+	fn ban_extrinsic(&self, entry: BlocklistEntry) -> Result<bool> {
+		self.deny_unsafe.check_if_safe()?;
+
+		if matches!(&entry, BlocklistEntry::Pattern(pattern) if pattern.is_empty()) {
+			return Err(RpcError::invalid_params("The pattern must not be empty"))
+		}
+
+		tracing::info!(target: LOG_TARGET, ?entry, "Banning extrinsics.");
+		let added = self.blocklist.ban(entry).map_err(storage_error)?;
+		self.blocklist.purge(&*self.pool);
+
+		Ok(added)
+	}
+
+	fn unban_extrinsic(&self, entry: BlocklistEntry) -> Result<bool> {
+		self.deny_unsafe.check_if_safe()?;
+
+		tracing::info!(target: LOG_TARGET, ?entry, "Unbanning extrinsics.");
+		self.blocklist.unban(&entry).map_err(storage_error)
+	}
+
+	fn banned_extrinsics(&self) -> Result<Vec<BlocklistEntry>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		Ok(self.blocklist.entries())
+	}
+}
+
+fn storage_error(error: sp_blockchain::Error) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(1),
+		message: "Failed to store the extrinsic blocklist".into(),
+		data: Some(error.to_string().into()),
+	}
+}
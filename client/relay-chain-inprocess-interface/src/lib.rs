@@ -44,6 +44,11 @@ use sp_consensus::SyncOracle;
 use sp_core::{sp_std::collections::btree_map::BTreeMap, Pair};
 use sp_state_machine::{Backend as StateBackend, StorageValue};
 
+mod runtime;
+
+pub use runtime::RelayChainResourceLimits;
+use runtime::RelayChainRuntime;
+
 /// The timeout in seconds after that the waiting for a block should be aborted.
 const TIMEOUT_IN_SECONDS: u64 = 6;
 
@@ -357,12 +362,23 @@ fn build_polkadot_full_node(
 }
 
 /// Builds a relay chain interface by constructing a full relay chain node
+///
+/// If any of the `resource_limits` is set, the tasks of the relay chain node run on a dedicated
+/// runtime that respects these limits.
 pub fn build_inprocess_relay_chain(
-	polkadot_config: Configuration,
+	mut polkadot_config: Configuration,
 	parachain_config: &Configuration,
 	telemetry_worker_handle: Option<TelemetryWorkerHandle>,
 	task_manager: &mut TaskManager,
+	resource_limits: RelayChainResourceLimits,
 ) -> RelayChainResult<(Arc<(dyn RelayChainInterface + 'static)>, Option<CollatorPair>)> {
+	if let Some(runtime) =
+		RelayChainRuntime::new(&resource_limits, parachain_config.prometheus_registry())?
+	{
+		polkadot_config.tokio_handle = runtime.handle();
+		task_manager.keep_alive(runtime);
+	}
+
 	let (full_node, collator_key) =
 		build_polkadot_full_node(polkadot_config, parachain_config, telemetry_worker_handle)?;
 
@@ -0,0 +1,224 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Dedicated tokio runtime for the tasks of the embedded relay chain node.
+//!
+//! By default the embedded relay chain node shares the runtime of the parachain node. On small
+//! machines a major sync of the relay chain can then starve the parachain, e.g. candidate
+//! production. Running the relay chain tasks on their own runtime allows to limit the number of
+//! threads they use and to lower the CPU and I/O priority of these threads.
+
+use std::{cell::Cell, time::Instant};
+
+use cumulus_relay_chain_interface::{RelayChainError, RelayChainResult};
+use substrate_prometheus_endpoint::{
+	register, Counter, Gauge, PrometheusError, Registry, F64, U64,
+};
+
+const LOG_TARGET: &str = "relay-chain-inprocess-interface";
+
+/// Limits on the resources used by the embedded relay chain node.
+///
+/// A dedicated runtime is only created if at least one limit is set.
+#[derive(Clone, Debug, Default)]
+pub struct RelayChainResourceLimits {
+	/// Number of worker threads of the relay chain runtime.
+	///
+	/// Defaults to the number of CPU cores.
+	pub worker_threads: Option<usize>,
+	/// Nice value the threads of the relay chain runtime are run with, from `-20` to `19`.
+	///
+	/// Only supported on Linux.
+	pub nice: Option<i32>,
+	/// Best-effort I/O priority the threads of the relay chain runtime are run with, from `0`
+	/// (highest) to `7` (lowest).
+	///
+	/// Only supported on Linux.
+	pub io_priority: Option<u8>,
+}
+
+impl RelayChainResourceLimits {
+	fn is_empty(&self) -> bool {
+		self.worker_threads.is_none() && self.nice.is_none() && self.io_priority.is_none()
+	}
+}
+
+/// Metrics of the dedicated relay chain runtime.
+#[derive(Clone)]
+struct Metrics {
+	/// Number of threads of the runtime that are alive.
+	threads: Gauge<U64>,
+	/// Time the threads of the runtime spent running tasks.
+	busy_seconds: Counter<F64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			threads: register(
+				Gauge::new(
+					"cumulus_relay_chain_runtime_threads",
+					"Number of threads running the tasks of the embedded relay chain node",
+				)?,
+				registry,
+			)?,
+			busy_seconds: register(
+				Counter::new(
+					"cumulus_relay_chain_runtime_busy_seconds_total",
+					"Time the threads of the embedded relay chain node spent running tasks",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+thread_local! {
+	/// When the current runtime thread was last unparked.
+	static UNPARKED_AT: Cell<Option<Instant>> = Cell::new(None);
+}
+
+/// Tokio runtime running the tasks of the embedded relay chain node.
+///
+/// The runtime is shut down in the background when dropped, so it can also be dropped from within
+/// an asynchronous context.
+pub(crate) struct RelayChainRuntime(Option<tokio::runtime::Runtime>);
+
+impl RelayChainRuntime {
+	/// Create a new runtime respecting `limits`.
+	///
+	/// Returns `None` if no limits are set, in which case the runtime of the parachain should be
+	/// used.
+	pub fn new(
+		limits: &RelayChainResourceLimits,
+		prometheus_registry: Option<&Registry>,
+	) -> RelayChainResult<Option<Self>> {
+		if limits.is_empty() {
+			return Ok(None)
+		}
+
+		let metrics = prometheus_registry.and_then(|registry| {
+			Metrics::register(registry)
+				.map_err(|e| {
+					tracing::warn!(
+						target: LOG_TARGET,
+						error = ?e,
+						"Failed to register relay chain runtime metrics.",
+					)
+				})
+				.ok()
+		});
+
+		if cfg!(not(target_os = "linux")) && (limits.nice.is_some() || limits.io_priority.is_some())
+		{
+			tracing::warn!(
+				target: LOG_TARGET,
+				"Thread priorities of the embedded relay chain node are only supported on Linux.",
+			);
+		}
+
+		let mut builder = tokio::runtime::Builder::new_multi_thread();
+		builder.thread_name("relay-chain-worker").enable_all();
+
+		if let Some(worker_threads) = limits.worker_threads {
+			builder.worker_threads(worker_threads);
+		}
+
+		let (nice, io_priority) = (limits.nice, limits.io_priority);
+		let start_metrics = metrics.clone();
+		builder.on_thread_start(move || {
+			set_thread_priority(nice, io_priority);
+
+			if let Some(metrics) = &start_metrics {
+				metrics.threads.inc();
+			}
+		});
+
+		if let Some(metrics) = metrics {
+			let stop_metrics = metrics.clone();
+			builder.on_thread_stop(move || stop_metrics.threads.dec());
+
+			builder.on_thread_unpark(|| UNPARKED_AT.with(|at| at.set(Some(Instant::now()))));
+			builder.on_thread_park(move || {
+				if let Some(unparked_at) = UNPARKED_AT.with(|at| at.take()) {
+					metrics.busy_seconds.inc_by(unparked_at.elapsed().as_secs_f64());
+				}
+			});
+		}
+
+		let runtime = builder.build().map_err(|e| {
+			RelayChainError::GenericError(format!("Failed to create relay chain runtime: {}", e))
+		})?;
+
+		Ok(Some(Self(Some(runtime))))
+	}
+
+	/// Returns a handle to spawn tasks on the runtime.
+	pub fn handle(&self) -> tokio::runtime::Handle {
+		self.0.as_ref().expect("Only taken on drop; qed").handle().clone()
+	}
+}
+
+impl Drop for RelayChainRuntime {
+	fn drop(&mut self) {
+		if let Some(runtime) = self.0.take() {
+			runtime.shutdown_background();
+		}
+	}
+}
+
+/// Apply the given CPU and I/O priority to the current thread.
+#[cfg(target_os = "linux")]
+fn set_thread_priority(nice: Option<i32>, io_priority: Option<u8>) {
+	/// `ioprio_set` target that selects a single thread.
+	const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+	/// The best-effort scheduling class of the I/O scheduler.
+	const IOPRIO_CLASS_BE: libc::c_int = 2;
+	const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+	// SAFETY: `gettid` has no preconditions.
+	let tid = unsafe { libc::syscall(libc::SYS_gettid) };
+
+	if let Some(nice) = nice {
+		// SAFETY: Only changes the priority of the current thread.
+		if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, nice) } != 0 {
+			tracing::warn!(
+				target: LOG_TARGET,
+				nice,
+				error = %std::io::Error::last_os_error(),
+				"Failed to set the nice value of a relay chain thread.",
+			);
+		}
+	}
+
+	if let Some(io_priority) = io_priority {
+		let priority =
+			(IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | libc::c_int::from(io_priority.min(7));
+
+		// SAFETY: Only changes the I/O priority of the current thread.
+		if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, tid, priority) } != 0 {
+			tracing::warn!(
+				target: LOG_TARGET,
+				io_priority,
+				error = %std::io::Error::last_os_error(),
+				"Failed to set the I/O priority of a relay chain thread.",
+			);
+		}
+	}
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_thread_priority(_: Option<i32>, _: Option<u8>) {}
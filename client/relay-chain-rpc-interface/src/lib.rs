@@ -35,7 +35,9 @@ use jsonrpsee::{
 	types::ParamsSer,
 	ws_client::WsClientBuilder,
 };
+use metrics::Metrics;
 use parity_scale_codec::{Decode, Encode};
+use parking_lot::RwLock;
 use polkadot_service::Handle;
 use sc_client_api::{StorageData, StorageProof};
 use sc_rpc_api::{state::ReadProof, system::Health};
@@ -44,29 +46,171 @@ use sp_runtime::DeserializeOwned;
 use sp_state_machine::StorageValue;
 use sp_storage::StorageKey;
 use std::{pin::Pin, sync::Arc};
+use substrate_prometheus_endpoint::Registry;
 
 pub use url::Url;
 
+mod metrics;
+
 const LOG_TARGET: &str = "relay-chain-rpc-interface";
 const TIMEOUT_IN_SECONDS: u64 = 6;
 
+/// The endpoint that is currently used to talk to the relay chain.
+#[derive(Clone)]
+struct ActiveEndpoint {
+	/// Index of the endpoint in [`Endpoints::urls`].
+	index: usize,
+	/// Websocket client connected to the endpoint.
+	client: Arc<JsonRPCClient>,
+}
+
+/// The configured relay chain endpoints.
+///
+/// Only one endpoint is used at a time. When it becomes unreachable, the next endpoint in the list
+/// that can be connected to is used instead.
+struct Endpoints {
+	/// Urls of the endpoints, in order of preference.
+	urls: Vec<Url>,
+	/// The endpoint that is currently used.
+	active: RwLock<ActiveEndpoint>,
+	metrics: Option<Metrics>,
+}
+
+impl Endpoints {
+	async fn new(urls: Vec<Url>, metrics: Option<Metrics>) -> RelayChainResult<Self> {
+		let active = connect_any(&urls, 0).await?;
+		if let Some(metrics) = &metrics {
+			metrics.active_endpoint.set(active.index as u64);
+		}
+
+		Ok(Self { urls, active: RwLock::new(active), metrics })
+	}
+
+	/// Returns the client of the active endpoint.
+	///
+	/// Switches to another endpoint first if the active one lost its connection.
+	async fn client(&self) -> Result<Arc<JsonRPCClient>, JsonRpseeError> {
+		let client = self.active.read().client.clone();
+
+		if client.is_connected() {
+			Ok(client)
+		} else {
+			self.switch_from(&client).await
+		}
+	}
+
+	/// Switch away from the endpoint `failed` is connected to.
+	///
+	/// The endpoints following the failed one are tried in order, the failed endpoint itself is
+	/// tried last. Nothing is done if the active endpoint was already switched in the meantime.
+	async fn switch_from(
+		&self,
+		failed: &Arc<JsonRPCClient>,
+	) -> Result<Arc<JsonRPCClient>, JsonRpseeError> {
+		let start = {
+			let active = self.active.read();
+			if !Arc::ptr_eq(&active.client, failed) {
+				return Ok(active.client.clone())
+			}
+
+			active.index + 1
+		};
+
+		let endpoint = connect_any(&self.urls, start).await?;
+
+		let mut active = self.active.write();
+		if Arc::ptr_eq(&active.client, failed) {
+			tracing::warn!(
+				target: LOG_TARGET,
+				from = %self.urls[active.index],
+				to = %self.urls[endpoint.index],
+				"Switching relay chain RPC endpoint.",
+			);
+
+			if let Some(metrics) = &self.metrics {
+				metrics.endpoint_switches.inc();
+				metrics.active_endpoint.set(endpoint.index as u64);
+			}
+
+			*active = endpoint;
+		}
+
+		Ok(active.client.clone())
+	}
+}
+
+/// Connect to the first healthy endpoint in `urls`, starting at `start` and wrapping around.
+///
+/// An endpoint is considered unhealthy if the node behind it has no peers, although it should
+/// have some. If no endpoint is healthy, the first one that could be connected to is used.
+async fn connect_any(urls: &[Url], start: usize) -> Result<ActiveEndpoint, JsonRpseeError> {
+	let mut unhealthy = None;
+	let mut last_error = None;
+
+	for offset in 0..urls.len() {
+		let index = (start + offset) % urls.len();
+		let url = &urls[index];
+
+		tracing::info!(target: LOG_TARGET, url = %url.to_string(), "Initializing RPC Client");
+		let client = match WsClientBuilder::default().build(url.as_str()).await {
+			Ok(client) => Arc::new(client),
+			Err(error) => {
+				tracing::warn!(
+					target: LOG_TARGET,
+					%url,
+					%error,
+					"Unable to connect to relay chain RPC endpoint.",
+				);
+				last_error = Some(error);
+				continue
+			},
+		};
+
+		match client.request::<Health>("system_health", None).await {
+			Ok(health) if health.should_have_peers && health.peers == 0 => {
+				tracing::warn!(
+					target: LOG_TARGET,
+					%url,
+					"Relay chain node has no peers, trying the next endpoint.",
+				);
+				if unhealthy.is_none() {
+					unhealthy = Some(ActiveEndpoint { index, client });
+				}
+			},
+			Ok(_) => return Ok(ActiveEndpoint { index, client }),
+			Err(error) => {
+				tracing::warn!(
+					target: LOG_TARGET,
+					%url,
+					%error,
+					"Unable to check health of relay chain RPC endpoint.",
+				);
+				last_error = Some(error);
+			},
+		}
+	}
+
+	unhealthy.ok_or_else(|| {
+		last_error.unwrap_or_else(|| {
+			JsonRpseeError::Custom("No relay chain RPC endpoint configured".into())
+		})
+	})
+}
+
 /// Client that maps RPC methods and deserializes results
 #[derive(Clone)]
 struct RelayChainRPCClient {
-	/// Websocket client to make calls
-	ws_client: Arc<JsonRPCClient>,
+	/// Relay chain endpoints to make calls to
+	endpoints: Arc<Endpoints>,
 
 	/// Retry strategy that should be used for requests and subscriptions
 	retry_strategy: ExponentialBackoff,
 }
 
 impl RelayChainRPCClient {
-	pub async fn new(url: Url) -> RelayChainResult<Self> {
-		tracing::info!(target: LOG_TARGET, url = %url.to_string(), "Initializing RPC Client");
-		let ws_client = WsClientBuilder::default().build(url.as_str()).await?;
-
+	pub async fn new(urls: Vec<Url>, metrics: Option<Metrics>) -> RelayChainResult<Self> {
 		Ok(RelayChainRPCClient {
-			ws_client: Arc::new(ws_client),
+			endpoints: Arc::new(Endpoints::new(urls, metrics).await?),
 			retry_strategy: ExponentialBackoff::default(),
 		})
 	}
@@ -109,12 +253,79 @@ impl RelayChainRPCClient {
 	where
 		R: DeserializeOwned,
 	{
-		self.ws_client
-			.subscribe::<R>(sub_name, params, unsub_name)
+		let subscribe = async {
+			self.endpoints
+				.client()
+				.await?
+				.subscribe::<R>(sub_name, params, unsub_name)
+				.await
+		};
+
+		subscribe
 			.await
 			.map_err(|err| RelayChainError::RPCCallError(sub_name.to_string(), err))
 	}
 
+	/// Subscribe to a notification stream via RPC that survives endpoint switches.
+	///
+	/// When the subscription ends, e.g. because the endpoint went down, it is re-established,
+	/// switching to another endpoint if required. The stream only ends if no endpoint can be
+	/// subscribed to anymore.
+	fn subscribe_with_failover<R>(
+		&self,
+		sub_name: &'static str,
+		unsub_name: &'static str,
+	) -> impl Stream<Item = R> + Send
+	where
+		R: DeserializeOwned + Send + 'static,
+	{
+		futures::stream::unfold(
+			(self.clone(), None::<Subscription<R>>),
+			move |(rpc_client, mut subscription)| async move {
+				loop {
+					if subscription.is_none() {
+						let new_subscription = rpc_client
+							.subscribe::<R>(sub_name, unsub_name, None)
+							.await
+							.map_err(|err| {
+								tracing::error!(
+									target: LOG_TARGET,
+									%err,
+									"Unable to subscribe to notification stream.",
+								)
+							})
+							.ok()?;
+						subscription = Some(new_subscription);
+					}
+
+					let next = subscription
+						.as_mut()
+						.expect("Subscription is established above; qed")
+						.next()
+						.await;
+
+					match next {
+						Some(Ok(item)) => return Some((item, (rpc_client, subscription))),
+						Some(Err(err)) => tracing::error!(
+							target: LOG_TARGET,
+							%err,
+							sub_name,
+							"Encountered error in notification stream.",
+						),
+						None => {
+							tracing::warn!(
+								target: LOG_TARGET,
+								sub_name,
+								"Subscription ended, re-subscribing.",
+							);
+							subscription = None;
+						},
+					}
+				}
+			},
+		)
+	}
+
 	/// Perform RPC request
 	async fn request<'a, R>(
 		&self,
@@ -146,11 +357,21 @@ impl RelayChainRPCClient {
 		retry_notify(
 			self.retry_strategy.clone(),
 			|| async {
-				self.ws_client.request(method, params.clone()).await.map_err(|err| match err {
-					JsonRpseeError::Transport(_) =>
-						backoff::Error::Transient { err, retry_after: None },
-					_ => backoff::Error::Permanent(err),
-				})
+				let client = self
+					.endpoints
+					.client()
+					.await
+					.map_err(|err| backoff::Error::Transient { err, retry_after: None })?;
+
+				match client.request(method, params.clone()).await {
+					Ok(response) => Ok(response),
+					Err(err @ (JsonRpseeError::Transport(_) | JsonRpseeError::RestartNeeded(_))) => {
+						// The next attempt should go to a working endpoint.
+						let _ = self.endpoints.switch_from(&client).await;
+						Err(backoff::Error::Transient { err, retry_after: None })
+					},
+					Err(err) => Err(backoff::Error::Permanent(err)),
+				}
 			},
 			|error, dur| tracing::trace!(target: LOG_TARGET, %error, ?dur, "Encountered transport error, retrying."),
 		)
@@ -263,20 +484,6 @@ impl RelayChainRPCClient {
 		self.subscribe::<PHeader>("chain_subscribeAllHeads", "chain_unsubscribeAllHeads", None)
 			.await
 	}
-
-	async fn subscribe_new_best_heads(&self) -> Result<Subscription<PHeader>, RelayChainError> {
-		self.subscribe::<PHeader>("chain_subscribeNewHeads", "chain_unsubscribeNewHeads", None)
-			.await
-	}
-
-	async fn subscribe_finalized_heads(&self) -> Result<Subscription<PHeader>, RelayChainError> {
-		self.subscribe::<PHeader>(
-			"chain_subscribeFinalizedHeads",
-			"chain_unsubscribeFinalizedHeads",
-			None,
-		)
-		.await
-	}
 }
 
 /// RelayChainRPCInterface is used to interact with a full node that is running locally
@@ -287,8 +494,29 @@ pub struct RelayChainRPCInterface {
 }
 
 impl RelayChainRPCInterface {
-	pub async fn new(url: Url) -> RelayChainResult<Self> {
-		Ok(Self { rpc_client: RelayChainRPCClient::new(url).await? })
+	/// Connect to the relay chain full node at `url`.
+	///
+	/// The nodes at `fallback_urls` are used, in the given order, whenever the currently used node
+	/// becomes unreachable.
+	pub async fn new(
+		url: Url,
+		fallback_urls: Vec<Url>,
+		prometheus_registry: Option<&Registry>,
+	) -> RelayChainResult<Self> {
+		let metrics = prometheus_registry.and_then(|registry| {
+			Metrics::register(registry)
+				.map_err(|e| {
+					tracing::warn!(
+						target: LOG_TARGET,
+						error = ?e,
+						"Failed to register relay chain RPC interface metrics.",
+					)
+				})
+				.ok()
+		});
+
+		let urls = std::iter::once(url).chain(fallback_urls).collect();
+		Ok(Self { rpc_client: RelayChainRPCClient::new(urls, metrics).await? })
 	}
 }
 
@@ -344,17 +572,10 @@ impl RelayChainInterface for RelayChainRPCInterface {
 	async fn import_notification_stream(
 		&self,
 	) -> RelayChainResult<Pin<Box<dyn Stream<Item = PHeader> + Send>>> {
-		let imported_headers_stream =
-			self.rpc_client.subscribe_all_heads().await?.filter_map(|item| async move {
-				item.map_err(|err| {
-					tracing::error!(
-						target: LOG_TARGET,
-						"Encountered error in import notification stream: {}",
-						err
-					)
-				})
-				.ok()
-			});
+		let imported_headers_stream = self.rpc_client.subscribe_with_failover::<PHeader>(
+			"chain_subscribeAllHeads",
+			"chain_unsubscribeAllHeads",
+		);
 
 		Ok(imported_headers_stream.boxed())
 	}
@@ -362,20 +583,10 @@ impl RelayChainInterface for RelayChainRPCInterface {
 	async fn finality_notification_stream(
 		&self,
 	) -> RelayChainResult<Pin<Box<dyn Stream<Item = PHeader> + Send>>> {
-		let imported_headers_stream = self
-			.rpc_client
-			.subscribe_finalized_heads()
-			.await?
-			.filter_map(|item| async move {
-				item.map_err(|err| {
-					tracing::error!(
-						target: LOG_TARGET,
-						"Encountered error in finality notification stream: {}",
-						err
-					)
-				})
-				.ok()
-			});
+		let imported_headers_stream = self.rpc_client.subscribe_with_failover::<PHeader>(
+			"chain_subscribeFinalizedHeads",
+			"chain_unsubscribeFinalizedHeads",
+		);
 
 		Ok(imported_headers_stream.boxed())
 	}
@@ -454,17 +665,10 @@ impl RelayChainInterface for RelayChainRPCInterface {
 	async fn new_best_notification_stream(
 		&self,
 	) -> RelayChainResult<Pin<Box<dyn Stream<Item = PHeader> + Send>>> {
-		let imported_headers_stream =
-			self.rpc_client.subscribe_new_best_heads().await?.filter_map(|item| async move {
-				item.map_err(|err| {
-					tracing::error!(
-						target: LOG_TARGET,
-						"Error in best block notification stream: {}",
-						err
-					)
-				})
-				.ok()
-			});
+		let imported_headers_stream = self.rpc_client.subscribe_with_failover::<PHeader>(
+			"chain_subscribeNewHeads",
+			"chain_unsubscribeNewHeads",
+		);
 
 		Ok(imported_headers_stream.boxed())
 	}
@@ -0,0 +1,50 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prometheus metrics of the relay chain RPC interface.
+
+use substrate_prometheus_endpoint::{register, Counter, Gauge, PrometheusError, Registry, U64};
+
+/// Metrics reported by [`RelayChainRPCInterface`](crate::RelayChainRPCInterface).
+#[derive(Clone)]
+pub(crate) struct Metrics {
+	/// Number of times the client switched to another relay chain endpoint.
+	pub endpoint_switches: Counter<U64>,
+	/// Index of the relay chain endpoint that is currently used.
+	pub active_endpoint: Gauge<U64>,
+}
+
+impl Metrics {
+	/// Register the metrics at the given Prometheus `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			endpoint_switches: register(
+				Counter::new(
+					"cumulus_relay_chain_rpc_endpoint_switches",
+					"Number of times the relay chain RPC endpoint was switched or reconnected to",
+				)?,
+				registry,
+			)?,
+			active_endpoint: register(
+				Gauge::new(
+					"cumulus_relay_chain_rpc_active_endpoint",
+					"Index of the relay chain RPC endpoint that is currently used, 0 being the primary",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
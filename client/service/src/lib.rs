@@ -19,6 +19,7 @@
 //! Provides functions for starting a collator node or a normal full node.
 
 use cumulus_client_cli::CollatorOptions;
+use cumulus_client_collator::LastCandidateProof;
 use cumulus_client_consensus_common::ParachainConsensus;
 use cumulus_primitives_core::{CollectCollationInfo, ParaId};
 use cumulus_relay_chain_interface::RelayChainInterface;
@@ -56,6 +57,8 @@ pub struct StartCollatorParams<'a, Block: BlockT, BS, Client, RCInterface, Spawn
 	pub import_queue: IQ,
 	pub collator_key: CollatorPair,
 	pub relay_chain_slot_duration: Duration,
+	/// Where to keep the storage proof of the latest candidate, `None` to not keep it.
+	pub last_proof: Option<LastCandidateProof<Block::Hash>>,
 }
 
 /// Start a collator node for a parachain.
@@ -76,6 +79,7 @@ pub async fn start_collator<'a, Block, BS, Client, Backend, RCInterface, Spawner
 		import_queue,
 		collator_key,
 		relay_chain_slot_duration,
+		last_proof,
 	}: StartCollatorParams<'a, Block, BS, Client, RCInterface, Spawner, IQ>,
 ) -> sc_service::error::Result<()>
 where
@@ -137,6 +141,7 @@ where
 		para_id,
 		key: collator_key,
 		parachain_consensus,
+		last_proof,
 	})
 	.await;
 
@@ -0,0 +1,56 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarking setup for pallet-author-inherent
+
+use super::*;
+
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+use frame_support::BoundedVec;
+use frame_system::RawOrigin;
+use sp_runtime::traits::Zero;
+
+benchmarks! {
+	set_author {
+		let author = T::BenchmarkHelper::author();
+		let relay_parent_storage_root = RelayChainHash::repeat_byte(1);
+		let relay_parent_number = 1;
+		T::BenchmarkHelper::set_relay_parent(relay_parent_storage_root, relay_parent_number);
+		let signature = author
+			.sign(&(relay_parent_storage_root, relay_parent_number).encode())
+			.expect("The key of the benchmark author is in the keystore");
+
+		// Worst case: the oldest recent author has to be dropped.
+		let recent_authors = (0..T::MaxRecentAuthors::get())
+			.map(|_| (Zero::zero(), author.clone()))
+			.collect::<Vec<_>>();
+		RecentAuthors::<T>::put(
+			BoundedVec::try_from(recent_authors).expect("Has `MaxRecentAuthors` entries"),
+		);
+
+		let data = AuthorInherentData {
+			author: author.clone(),
+			relay_parent_storage_root,
+			relay_parent_number,
+			signature,
+		};
+	}: _(RawOrigin::None, data)
+	verify {
+		assert_eq!(Author::<T>::get(), Some(author));
+	}
+}
+
+impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
@@ -0,0 +1,367 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Author Inherent pallet.
+//!
+//! Sets the author of a parachain block through an inherent.
+//!
+//! ## Overview
+//!
+//! The collator puts an [`AuthorInherentData`] into the inherent data of every block it builds.
+//! It contains the claimed author, the storage root and number of the relay parent the block is
+//! built on and a signature of the author over both. The signature binds the claim to the relay
+//! parent, so it can not be replayed in blocks built on other relay parents.
+//!
+//! The [`Pallet::set_author`] inherent checks the signature, that the relay parent matches the
+//! validation data of the block, as provided by [`Config::RelayChainState`], and that the author
+//! may author according to [`Config::CanAuthor`]. [`CanAuthor`] gets the relay parent number and
+//! the parachain block number, which allows to rotate the authors per relay or parachain block.
+//! The author is then stored for the rest of the block and returned by the [`FindAuthor`]
+//! implementation of the pallet. Every block has to include the inherent.
+//!
+//! The account of the author, as returned by [`Config::AccountLookup`], is passed to
+//! [`Config::EventHandler`] once per block, e.g. to reward it.
+//!
+//! The authors of the last [`Config::MaxRecentAuthors`] blocks are kept in [`RecentAuthors`], so
+//! that other pallets, e.g. to track uptime, can inspect recent authorship. They can also be
+//! queried through the [`AuthorInherentApi`] runtime api.
+//!
+//! The author is only known after the inherent was applied, so the pallet should be placed right
+//! after `ParachainSystem` in the runtime. `FindAuthor` returns `None` in `on_initialize`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use cumulus_primitives_core::{
+	relay_chain::{BlockNumber as RelayChainBlockNumber, Hash as RelayChainHash},
+	RelayChainStateInfo,
+};
+use frame_support::traits::FindAuthor;
+use pallet_authorship::EventHandler;
+use scale_info::TypeInfo;
+use sp_inherents::{InherentIdentifier, IsFatalError};
+use sp_runtime::{traits::One, ConsensusEngineId, RuntimeAppPublic, RuntimeDebug};
+use sp_std::vec::Vec;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+
+/// The identifier of the author inherent.
+pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"author__";
+
+/// The data of the author inherent.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct AuthorInherentData<AuthorId, Signature> {
+	/// The claimed author of the block.
+	pub author: AuthorId,
+	/// The storage root of the relay parent the block is built on.
+	pub relay_parent_storage_root: RelayChainHash,
+	/// The number of the relay parent the block is built on.
+	pub relay_parent_number: RelayChainBlockNumber,
+	/// The signature of `author` over the [`signing_message`](Self::signing_message).
+	pub signature: Signature,
+}
+
+impl<AuthorId, Signature> AuthorInherentData<AuthorId, Signature> {
+	/// The message the author signs, the encoded relay parent storage root and number.
+	pub fn signing_message(&self) -> Vec<u8> {
+		(self.relay_parent_storage_root, self.relay_parent_number).encode()
+	}
+}
+
+/// The [`AuthorInherentData`] of a runtime.
+pub type AuthorInherentDataOf<T> = AuthorInherentData<
+	<T as Config>::AuthorId,
+	<<T as Config>::AuthorId as RuntimeAppPublic>::Signature,
+>;
+
+/// Errors of the author inherent that are reported by [`Pallet::check_inherent`].
+#[derive(Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode))]
+pub enum InherentError {
+	/// The block does not include the author inherent.
+	Missing,
+	/// The signature of the author is invalid.
+	InvalidSignature,
+	/// The author is not allowed to author the block.
+	CannotAuthor,
+}
+
+impl IsFatalError for InherentError {
+	fn is_fatal_error(&self) -> bool {
+		true
+	}
+}
+
+/// Decides whether an author may author a block.
+pub trait CanAuthor<AuthorId, BlockNumber> {
+	/// Returns `true` if `author` may author the parachain block `block_number` on top of the
+	/// relay parent `relay_parent`.
+	fn can_author(
+		author: &AuthorId,
+		relay_parent: RelayChainBlockNumber,
+		block_number: BlockNumber,
+	) -> bool;
+}
+
+/// Every author may author.
+impl<AuthorId, BlockNumber> CanAuthor<AuthorId, BlockNumber> for () {
+	fn can_author(_: &AuthorId, _: RelayChainBlockNumber, _: BlockNumber) -> bool {
+		true
+	}
+}
+
+/// Provides the author and the relay parent that are used to benchmark [`Pallet::set_author`].
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AuthorId> {
+	/// Returns an author that [`Config::CanAuthor`] accepts and whose key is in the keystore.
+	fn author() -> AuthorId;
+
+	/// Make [`Config::RelayChainState`] return the given relay parent, e.g. by setting the
+	/// validation data of parachain-system.
+	fn set_relay_parent(storage_root: RelayChainHash, number: RelayChainBlockNumber);
+}
+
+/// Something that maps an author id to an account.
+pub trait AccountLookup<AuthorId, AccountId> {
+	/// Returns the account `author_id` is mapped to.
+	fn lookup_account(author_id: &AuthorId) -> Option<AccountId>;
+}
+
+/// No author id is mapped to an account.
+impl<AuthorId, AccountId> AccountLookup<AuthorId, AccountId> for () {
+	fn lookup_account(_: &AuthorId) -> Option<AccountId> {
+		None
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	pub use crate::weights::WeightInfo;
+	use frame_support::{
+		inherent::{InherentData, ProvideInherent},
+		pallet_prelude::*,
+	};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The identifier of an author, which is also the key the inherent is signed with.
+		type AuthorId: Member + Parameter + MaxEncodedLen + RuntimeAppPublic;
+
+		/// Provides the relay parent of the current block, which the author has to sign.
+		///
+		/// Usually parachain-system. The relay parent must be available when the inherent is
+		/// applied, otherwise the inherent is rejected.
+		type RelayChainState: RelayChainStateInfo;
+
+		/// Decides whether the claimed author may author the block.
+		type CanAuthor: CanAuthor<Self::AuthorId, Self::BlockNumber>;
+
+		/// Maps the author to the account that is passed to the [`Config::EventHandler`].
+		type AccountLookup: AccountLookup<Self::AuthorId, Self::AccountId>;
+
+		/// Notified with the account of the author of every block.
+		///
+		/// Not notified if the author is not mapped to an account. Uncles are never noted.
+		type EventHandler: EventHandler<Self::AccountId, Self::BlockNumber>;
+
+		/// Number of the most recent blocks whose author is kept in [`RecentAuthors`].
+		#[pallet::constant]
+		type MaxRecentAuthors: Get<u32>;
+
+		/// The weight information of this pallet.
+		///
+		/// Includes [`Config::CanAuthor`] and [`Config::AccountLookup`], so it has to be generated
+		/// with the configuration of the runtime.
+		type WeightInfo: WeightInfo;
+
+		/// Provides the author for the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<Self::AuthorId>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The author of the current block.
+	///
+	/// Set by the inherent and removed in `on_finalize`.
+	#[pallet::storage]
+	#[pallet::getter(fn author)]
+	pub type Author<T: Config> = StorageValue<_, T::AuthorId, OptionQuery>;
+
+	/// The block numbers and authors of the most recent blocks, oldest first.
+	///
+	/// Only the last [`Config::MaxRecentAuthors`] blocks are kept, older ones are dropped.
+	#[pallet::storage]
+	#[pallet::getter(fn recent_authors)]
+	pub type RecentAuthors<T: Config> =
+		StorageValue<_, BoundedVec<(T::BlockNumber, T::AuthorId), T::MaxRecentAuthors>, ValueQuery>;
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The author was already set in this block.
+		AuthorAlreadySet,
+		/// The signature of the author is invalid.
+		InvalidSignature,
+		/// The relay parent of the block is not available.
+		RelayParentUnknown,
+		/// The signed relay parent is not the relay parent of the block.
+		WrongRelayParent,
+		/// The author is not allowed to author the block.
+		CannotAuthor,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_finalize(_: T::BlockNumber) {
+			assert!(
+				Author::<T>::take().is_some(),
+				"The author inherent must be included in every block",
+			);
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Set the author of the current block.
+		///
+		/// This is an inherent and can only be included by the block author.
+		#[pallet::weight((T::WeightInfo::set_author(), DispatchClass::Mandatory))]
+		pub fn set_author(origin: OriginFor<T>, data: AuthorInherentDataOf<T>) -> DispatchResult {
+			ensure_none(origin)?;
+			ensure!(!Author::<T>::exists(), Error::<T>::AuthorAlreadySet);
+
+			let relay_parent_storage_root = T::RelayChainState::relay_parent_storage_root()
+				.ok_or(Error::<T>::RelayParentUnknown)?;
+			let relay_parent_number =
+				T::RelayChainState::relay_parent_number().ok_or(Error::<T>::RelayParentUnknown)?;
+			ensure!(
+				data.relay_parent_storage_root == relay_parent_storage_root &&
+					data.relay_parent_number == relay_parent_number,
+				Error::<T>::WrongRelayParent,
+			);
+			ensure!(
+				data.author.verify(&data.signing_message(), &data.signature),
+				Error::<T>::InvalidSignature,
+			);
+			ensure!(
+				T::CanAuthor::can_author(
+					&data.author,
+					data.relay_parent_number,
+					frame_system::Pallet::<T>::block_number(),
+				),
+				Error::<T>::CannotAuthor,
+			);
+
+			if let Some(account) = T::AccountLookup::lookup_account(&data.author) {
+				T::EventHandler::note_author(account);
+			}
+			Self::note_recent_author(
+				frame_system::Pallet::<T>::block_number(),
+				data.author.clone(),
+			);
+			Author::<T>::put(data.author);
+
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Record `author` as the author of `block_number`, dropping the oldest author if
+		/// [`Config::MaxRecentAuthors`] is reached.
+		fn note_recent_author(block_number: T::BlockNumber, author: T::AuthorId) {
+			RecentAuthors::<T>::mutate(|authors| {
+				if authors.len() >= T::MaxRecentAuthors::get() as usize && !authors.is_empty() {
+					authors.remove(0);
+				}
+
+				// Only fails if `MaxRecentAuthors` is zero, in which case nothing should be kept.
+				let _ = authors.try_push((block_number, author));
+			});
+		}
+	}
+
+	#[pallet::inherent]
+	impl<T: Config> ProvideInherent for Pallet<T> {
+		type Call = Call<T>;
+		type Error = InherentError;
+		const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+
+		fn create_inherent(data: &InherentData) -> Option<Self::Call> {
+			let data = data.get_data(&INHERENT_IDENTIFIER).ok().flatten()?;
+
+			Some(Call::set_author { data })
+		}
+
+		fn is_inherent_required(_: &InherentData) -> Result<Option<Self::Error>, Self::Error> {
+			Ok(Some(InherentError::Missing))
+		}
+
+		fn check_inherent(call: &Self::Call, _: &InherentData) -> Result<(), Self::Error> {
+			let data = match call {
+				Call::set_author { data } => data,
+				_ => return Ok(()),
+			};
+
+			if !data.author.verify(&data.signing_message(), &data.signature) {
+				return Err(InherentError::InvalidSignature)
+			}
+			// Inherents are checked on top of the state of the parent block.
+			let block_number = frame_system::Pallet::<T>::block_number().saturating_add(One::one());
+			if !T::CanAuthor::can_author(&data.author, data.relay_parent_number, block_number) {
+				return Err(InherentError::CannotAuthor)
+			}
+
+			Ok(())
+		}
+
+		fn is_inherent(call: &Self::Call) -> bool {
+			matches!(call, Call::set_author { .. })
+		}
+	}
+}
+
+/// Returns the author set by the inherent, the digests are ignored.
+impl<T: Config> FindAuthor<T::AuthorId> for Pallet<T> {
+	fn find_author<'a, I>(_: I) -> Option<T::AuthorId>
+	where
+		I: 'a + IntoIterator<Item = (ConsensusEngineId, &'a [u8])>,
+	{
+		Author::<T>::get()
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// Runtime api to query the authors of recent blocks.
+	pub trait AuthorInherentApi<BlockNumber: codec::Codec, AuthorId: codec::Codec> {
+		/// Returns the block numbers and authors of the most recent blocks, oldest first.
+		fn recent_authors() -> Vec<(BlockNumber, AuthorId)>;
+	}
+}
@@ -0,0 +1,172 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use crate as author_inherent;
+use frame_support::{
+	parameter_types,
+	traits::{ConstU32, Everything},
+};
+use sp_core::H256;
+use sp_runtime::{
+	testing::{Header, TestSignature, UintAuthorityId},
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		AuthorInherent: author_inherent::{Pallet, Call, Storage, Inherent},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub storage RelayParentStorageRoot: Option<H256> = Some(H256::repeat_byte(1));
+	pub storage RelayParentNumber: Option<u32> = Some(10);
+	pub storage CanAuthorContext: Option<(u32, u64)> = None;
+	pub storage NotedAuthors: Vec<u64> = Vec::new();
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+/// Only authors below 10 may author.
+///
+/// Records the relay parent and block number it was called with in [`CanAuthorContext`].
+pub struct MockCanAuthor;
+impl CanAuthor<UintAuthorityId, u64> for MockCanAuthor {
+	fn can_author(author: &UintAuthorityId, relay_parent: u32, block_number: u64) -> bool {
+		CanAuthorContext::set(&Some((relay_parent, block_number)));
+		author.0 < 10
+	}
+}
+
+/// Returns [`RelayParentStorageRoot`] and [`RelayParentNumber`] as the relay parent.
+pub struct MockRelayChainState;
+impl RelayChainStateInfo for MockRelayChainState {
+	fn relay_parent_number() -> Option<u32> {
+		RelayParentNumber::get()
+	}
+
+	fn relay_parent_storage_root() -> Option<H256> {
+		RelayParentStorageRoot::get()
+	}
+
+	fn relay_state_proof() -> Option<sp_trie::StorageProof> {
+		None
+	}
+}
+
+/// Authors below 5 are mapped to the account with the same id.
+pub struct MockAccountLookup;
+impl AccountLookup<UintAuthorityId, u64> for MockAccountLookup {
+	fn lookup_account(author: &UintAuthorityId) -> Option<u64> {
+		Some(author.0).filter(|id| *id < 5)
+	}
+}
+
+/// Records the noted authors in [`NotedAuthors`].
+pub struct MockEventHandler;
+impl EventHandler<u64, u64> for MockEventHandler {
+	fn note_author(author: u64) {
+		let mut authors = NotedAuthors::get();
+		authors.push(author);
+		NotedAuthors::set(&authors);
+	}
+
+	fn note_uncle(_: u64, _: u64) {}
+}
+
+impl Config for Test {
+	type AuthorId = UintAuthorityId;
+	type RelayChainState = MockRelayChainState;
+	type CanAuthor = MockCanAuthor;
+	type AccountLookup = MockAccountLookup;
+	type EventHandler = MockEventHandler;
+	type MaxRecentAuthors = ConstU32<2>;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBenchmarkHelper;
+}
+
+/// Returns author 1, which [`MockCanAuthor`] accepts, and sets the relay parent of
+/// [`MockRelayChainState`].
+#[cfg(feature = "runtime-benchmarks")]
+pub struct MockBenchmarkHelper;
+#[cfg(feature = "runtime-benchmarks")]
+impl BenchmarkHelper<UintAuthorityId> for MockBenchmarkHelper {
+	fn author() -> UintAuthorityId {
+		UintAuthorityId(1)
+	}
+
+	fn set_relay_parent(storage_root: H256, number: u32) {
+		RelayParentStorageRoot::set(&Some(storage_root));
+		RelayParentNumber::set(&Some(number));
+	}
+}
+
+/// Returns the inherent data of `author` signing the relay parent with `storage_root` and
+/// number 10.
+pub fn signed_data(author: u64, storage_root: H256) -> AuthorInherentDataOf<Test> {
+	sign(AuthorInherentData {
+		author: UintAuthorityId(author),
+		relay_parent_storage_root: storage_root,
+		relay_parent_number: 10,
+		signature: TestSignature(0, Vec::new()),
+	})
+}
+
+/// Replaces the signature of `data` with a valid one of its author.
+pub fn sign(mut data: AuthorInherentDataOf<Test>) -> AuthorInherentDataOf<Test> {
+	data.signature = TestSignature(data.author.0, data.signing_message());
+	data
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+}
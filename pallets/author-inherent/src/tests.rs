@@ -0,0 +1,205 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{mock::*, Call, Error, InherentError, INHERENT_IDENTIFIER};
+use frame_support::{
+	assert_noop, assert_ok,
+	inherent::{InherentData, ProvideInherent},
+	traits::{FindAuthor, Hooks},
+};
+use sp_core::H256;
+use sp_runtime::{
+	testing::{TestSignature, UintAuthorityId},
+	traits::BadOrigin,
+};
+
+#[test]
+fn set_author_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(AuthorInherent::find_author(Vec::new()), None);
+
+		assert_ok!(AuthorInherent::set_author(
+			Origin::none(),
+			signed_data(1, RelayParentStorageRoot::get().unwrap())
+		));
+		assert_eq!(AuthorInherent::author(), Some(UintAuthorityId(1)));
+		assert_eq!(AuthorInherent::find_author(Vec::new()), Some(UintAuthorityId(1)));
+
+		assert_noop!(
+			AuthorInherent::set_author(
+				Origin::none(),
+				signed_data(2, RelayParentStorageRoot::get().unwrap())
+			),
+			Error::<Test>::AuthorAlreadySet,
+		);
+
+		AuthorInherent::on_finalize(1);
+		assert_eq!(AuthorInherent::author(), None);
+	});
+}
+
+#[test]
+fn set_author_rejects_invalid_claims() {
+	new_test_ext().execute_with(|| {
+		let relay_parent = RelayParentStorageRoot::get().unwrap();
+
+		assert_noop!(
+			AuthorInherent::set_author(Origin::signed(1), signed_data(1, relay_parent)),
+			BadOrigin,
+		);
+		assert_noop!(
+			AuthorInherent::set_author(Origin::none(), signed_data(1, H256::repeat_byte(2))),
+			Error::<Test>::WrongRelayParent,
+		);
+		let mut data = signed_data(1, relay_parent);
+		data.relay_parent_number = 11;
+		assert_noop!(
+			AuthorInherent::set_author(Origin::none(), sign(data)),
+			Error::<Test>::WrongRelayParent,
+		);
+
+		let mut data = signed_data(1, relay_parent);
+		data.signature = TestSignature(2, data.signing_message());
+		assert_noop!(
+			AuthorInherent::set_author(Origin::none(), data),
+			Error::<Test>::InvalidSignature,
+		);
+		// The signature has to cover the relay parent number as well.
+		let mut data = signed_data(1, relay_parent);
+		data.relay_parent_number = 11;
+		let mut data = sign(data);
+		data.relay_parent_number = 10;
+		assert_noop!(
+			AuthorInherent::set_author(Origin::none(), data),
+			Error::<Test>::InvalidSignature,
+		);
+
+		assert_noop!(
+			AuthorInherent::set_author(Origin::none(), signed_data(10, relay_parent)),
+			Error::<Test>::CannotAuthor,
+		);
+	});
+}
+
+#[test]
+fn relay_parent_is_required() {
+	new_test_ext().execute_with(|| {
+		let relay_parent = RelayParentStorageRoot::get().unwrap();
+		RelayParentNumber::set(&None);
+		assert_noop!(
+			AuthorInherent::set_author(Origin::none(), signed_data(1, relay_parent)),
+			Error::<Test>::RelayParentUnknown,
+		);
+
+		RelayParentNumber::set(&Some(10));
+		RelayParentStorageRoot::set(&None);
+		assert_noop!(
+			AuthorInherent::set_author(Origin::none(), signed_data(1, relay_parent)),
+			Error::<Test>::RelayParentUnknown,
+		);
+	});
+}
+
+#[test]
+#[should_panic(expected = "The author inherent must be included in every block")]
+fn author_is_required() {
+	new_test_ext().execute_with(|| {
+		AuthorInherent::on_finalize(1);
+	});
+}
+
+#[test]
+fn inherent_is_created_and_checked() {
+	new_test_ext().execute_with(|| {
+		let mut inherent_data = InherentData::new();
+		assert_eq!(AuthorInherent::create_inherent(&inherent_data), None);
+
+		let data = signed_data(1, RelayParentStorageRoot::get().unwrap());
+		inherent_data.put_data(INHERENT_IDENTIFIER, &data).unwrap();
+
+		let call = AuthorInherent::create_inherent(&inherent_data).unwrap();
+		assert_eq!(call, Call::set_author { data });
+		assert!(AuthorInherent::is_inherent(&call));
+		assert!(AuthorInherent::check_inherent(&call, &inherent_data).is_ok());
+
+		let call =
+			Call::set_author { data: signed_data(10, RelayParentStorageRoot::get().unwrap()) };
+		assert!(matches!(
+			AuthorInherent::check_inherent(&call, &inherent_data),
+			Err(InherentError::CannotAuthor)
+		));
+
+		assert!(matches!(
+			AuthorInherent::is_inherent_required(&inherent_data),
+			Ok(Some(InherentError::Missing))
+		));
+	});
+}
+
+#[test]
+fn author_account_is_noted() {
+	new_test_ext().execute_with(|| {
+		let relay_parent = RelayParentStorageRoot::get().unwrap();
+
+		assert_ok!(AuthorInherent::set_author(Origin::none(), signed_data(1, relay_parent)));
+		assert_eq!(NotedAuthors::get(), vec![1]);
+		AuthorInherent::on_finalize(1);
+
+		// 5 is not mapped to an account
+		assert_ok!(AuthorInherent::set_author(Origin::none(), signed_data(5, relay_parent)));
+		assert_eq!(NotedAuthors::get(), vec![1]);
+	});
+}
+
+#[test]
+fn can_author_gets_round_context() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(3);
+
+		assert_ok!(AuthorInherent::set_author(
+			Origin::none(),
+			signed_data(1, RelayParentStorageRoot::get().unwrap())
+		));
+		assert_eq!(CanAuthorContext::get(), Some((10, 3)));
+
+		// inherents are checked on top of the parent block
+		let call =
+			Call::set_author { data: signed_data(1, RelayParentStorageRoot::get().unwrap()) };
+		assert!(AuthorInherent::check_inherent(&call, &InherentData::new()).is_ok());
+		assert_eq!(CanAuthorContext::get(), Some((10, 4)));
+	});
+}
+
+#[test]
+fn recent_authors_are_kept() {
+	new_test_ext().execute_with(|| {
+		let relay_parent = RelayParentStorageRoot::get().unwrap();
+
+		for (block_number, author) in [(1, 1), (2, 2), (3, 1)] {
+			System::set_block_number(block_number);
+			assert_ok!(AuthorInherent::set_author(
+				Origin::none(),
+				signed_data(author, relay_parent)
+			));
+			AuthorInherent::on_finalize(block_number);
+		}
+
+		assert_eq!(
+			AuthorInherent::recent_authors().into_inner(),
+			vec![(2, UintAuthorityId(2)), (3, UintAuthorityId(1))],
+		);
+	});
+}
@@ -0,0 +1,43 @@
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+// Hand-written estimates, to be replaced by weights generated from `benchmarking.rs`.
+//
+// The cost of `set_author` depends on the `CanAuthor` and `AccountLookup` of the runtime. These
+// estimates assume author-mapping for both, which reads `Mappings` once each. Other
+// implementations, e.g. the VRF mode of author-slot-filter, are considerably heavier, so runtimes
+// have to generate the weight with their own configuration. The `EventHandler` is not included,
+// it registers its own weight like collator-selection does.
+pub trait WeightInfo {
+	fn set_author() -> Weight;
+}
+
+pub struct SubstrateWeight<T>(PhantomData<T>);
+
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Checks `Author`, reads the relay parent storage root and number from the validation data of
+	// parachain-system, reads `Mappings` for `CanAuthor` and `AccountLookup` and updates
+	// `RecentAuthors` and `Author`. The base weight is dominated by the sr25519 signature check.
+	fn set_author() -> Weight {
+		(60_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+}
+
+impl WeightInfo for () {
+	// Checks `Author`, reads the relay parent storage root and number from the validation data of
+	// parachain-system, reads `Mappings` for `CanAuthor` and `AccountLookup` and updates
+	// `RecentAuthors` and `Author`. The base weight is dominated by the sr25519 signature check.
+	fn set_author() -> Weight {
+		(60_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(6 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+}
@@ -0,0 +1,90 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarking setup for pallet-author-mapping
+
+use super::*;
+
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite, whitelisted_caller};
+use frame_support::{assert_ok, traits::EnsureOrigin};
+use frame_system::RawOrigin;
+use sp_runtime::traits::{Bounded, TrailingZeroInput};
+
+/// Returns the author id decoded from `seed`.
+fn author_id<T: Config>(seed: u32) -> T::AuthorId {
+	T::AuthorId::decode(&mut TrailingZeroInput::new(&seed.encode()))
+		.expect("Decoding from an infinite input never fails")
+}
+
+/// Returns a caller that can afford the deposits.
+fn funded_caller<T: Config>() -> T::AccountId {
+	let caller: T::AccountId = whitelisted_caller();
+	T::DepositCurrency::make_free_balance_be(&caller, BalanceOf::<T>::max_value() / 2u32.into());
+	caller
+}
+
+benchmarks! {
+	add_association {
+		let caller = funded_caller::<T>();
+		let author_id = author_id::<T>(100);
+	}: _(RawOrigin::Signed(caller.clone()), author_id.clone())
+	verify {
+		assert_eq!(Pallet::<T>::account_id_of(&author_id), Some(caller));
+	}
+
+	update_association {
+		let caller = funded_caller::<T>();
+		let old_author_id = author_id::<T>(100);
+		let new_author_id = author_id::<T>(101);
+		assert_ok!(Pallet::<T>::add_association(
+			RawOrigin::Signed(caller.clone()).into(),
+			old_author_id.clone(),
+		));
+	}: _(RawOrigin::Signed(caller.clone()), old_author_id.clone(), new_author_id.clone())
+	verify {
+		assert_eq!(Pallet::<T>::account_id_of(&old_author_id), None);
+		assert_eq!(Pallet::<T>::account_id_of(&new_author_id), Some(caller));
+	}
+
+	clear_association {
+		let caller = funded_caller::<T>();
+		let author_id = author_id::<T>(100);
+		assert_ok!(Pallet::<T>::add_association(
+			RawOrigin::Signed(caller.clone()).into(),
+			author_id.clone(),
+		));
+	}: _(RawOrigin::Signed(caller), author_id.clone())
+	verify {
+		assert_eq!(Pallet::<T>::account_id_of(&author_id), None);
+	}
+
+	revoke_association {
+		// Worst case: the author id is registered and the deposit has to be returned.
+		let caller = funded_caller::<T>();
+		let author_id = author_id::<T>(100);
+		assert_ok!(Pallet::<T>::add_association(
+			RawOrigin::Signed(caller).into(),
+			author_id.clone(),
+		));
+		let origin = T::RevokeOrigin::successful_origin();
+	}: _<T::Origin>(origin, author_id.clone())
+	verify {
+		assert!(Revoked::<T>::get(&author_id));
+		assert_eq!(Pallet::<T>::account_id_of(&author_id), None);
+	}
+}
+
+impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
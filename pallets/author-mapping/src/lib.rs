@@ -0,0 +1,311 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Author Mapping pallet.
+//!
+//! Maps the author ids of parachain blocks to accounts.
+//!
+//! ## Overview
+//!
+//! Blocks are sealed with a session key of their author, which is usually not the account that
+//! should be rewarded for authoring. This pallet lets an account register an author id, e.g. its
+//! `nmbs` session public key, by reserving a deposit. The runtime can then translate the author
+//! id found in the digest of a block back to the registered account, e.g. with
+//! [`MappedFindAuthor`] as the `FindAuthor` of `pallet-authorship`.
+//! The pallet also implements [`CanAuthor`], which only lets registered author ids author.
+//! Runtimes usually configure `cumulus_primitives_author::AuthorId` as the author id.
+//!
+//! The deposit prevents filling the storage with mappings and is returned when the mapping is
+//! cleared. An account may register several author ids, each with its own deposit.
+//!
+//! If the private key of an author id leaks, [`Config::RevokeOrigin`] can revoke the author id
+//! with [`Pallet::revoke_association`], e.g. the relay chain through an XCM `Transact`. The
+//! revocation takes effect in the block it is executed in. Blocks authored with the author id
+//! after that fail the [`CanAuthor`] check and are rejected on import. A revoked author id can
+//! never be registered again.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use cumulus_primitives_core::relay_chain::BlockNumber as RelayChainBlockNumber;
+use frame_support::traits::{Currency, FindAuthor, ReservableCurrency};
+use pallet_author_inherent::CanAuthor;
+use scale_info::TypeInfo;
+use sp_runtime::{ConsensusEngineId, RuntimeDebug};
+use sp_std::{marker::PhantomData, vec::Vec};
+
+pub use pallet::*;
+pub use pallet_author_inherent::AccountLookup;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+
+type BalanceOf<T> =
+	<<T as Config>::DepositCurrency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+/// The account an author id is registered by.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct RegistrationInfo<AccountId, Balance> {
+	/// The account that registered the author id.
+	pub account: AccountId,
+	/// The deposit reserved from `account` for the registration.
+	pub deposit: Balance,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	pub use crate::weights::WeightInfo;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The identifier of an author, as found in the digest of a block.
+		type AuthorId: Member + Parameter + MaxEncodedLen;
+
+		/// Currency in which the deposits are reserved.
+		type DepositCurrency: ReservableCurrency<Self::AccountId>;
+
+		/// Deposit reserved for every registered author id.
+		#[pallet::constant]
+		type DepositAmount: Get<BalanceOf<Self>>;
+
+		/// Origin that can revoke compromised author ids.
+		///
+		/// For example root, which the relay chain can dispatch with an XCM `Transact` of origin
+		/// kind `Superuser`, or `pallet_xcm::EnsureXcm` to only allow the relay chain.
+		type RevokeOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The weight information of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The account every registered author id is mapped to.
+	#[pallet::storage]
+	#[pallet::getter(fn registration_info)]
+	pub type Mappings<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AuthorId,
+		RegistrationInfo<T::AccountId, BalanceOf<T>>,
+		OptionQuery,
+	>;
+
+	/// The author ids that were revoked and can not be registered anymore.
+	#[pallet::storage]
+	#[pallet::getter(fn is_revoked)]
+	pub type Revoked<T: Config> = StorageMap<_, Blake2_128Concat, T::AuthorId, bool, ValueQuery>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub mappings: Vec<(T::AuthorId, T::AccountId)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { mappings: Default::default() }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			for (author_id, account) in &self.mappings {
+				Pallet::<T>::do_register(author_id.clone(), account.clone())
+					.expect("Invalid mapping in the author mapping genesis config");
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// An author id was registered by an account.
+		AuthorRegistered { author_id: T::AuthorId, account: T::AccountId },
+		/// An account replaced one of its author ids.
+		AuthorRotated {
+			old_author_id: T::AuthorId,
+			new_author_id: T::AuthorId,
+			account: T::AccountId,
+		},
+		/// An author id was deregistered and the deposit returned.
+		AuthorDeregistered { author_id: T::AuthorId, account: T::AccountId },
+		/// A compromised author id was revoked and the deposit returned.
+		AuthorRevoked { author_id: T::AuthorId, account: Option<T::AccountId> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The author id is not registered.
+		AssociationNotFound,
+		/// The author id is registered already.
+		AlreadyAssociated,
+		/// The author id is registered by another account.
+		NotYourAssociation,
+		/// The account can not reserve the deposit.
+		CannotAffordDeposit,
+		/// The author id was revoked.
+		AuthorRevoked,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Register `author_id` for the sender, reserving [`Config::DepositAmount`].
+		#[pallet::weight(T::WeightInfo::add_association())]
+		pub fn add_association(origin: OriginFor<T>, author_id: T::AuthorId) -> DispatchResult {
+			let account = ensure_signed(origin)?;
+
+			Self::do_register(author_id.clone(), account.clone())?;
+
+			Self::deposit_event(Event::AuthorRegistered { author_id, account });
+			Ok(())
+		}
+
+		/// Replace the author id `old_author_id` of the sender with `new_author_id`.
+		///
+		/// The deposit is kept, so this can be used to rotate session keys.
+		#[pallet::weight(T::WeightInfo::update_association())]
+		pub fn update_association(
+			origin: OriginFor<T>,
+			old_author_id: T::AuthorId,
+			new_author_id: T::AuthorId,
+		) -> DispatchResult {
+			let account = ensure_signed(origin)?;
+
+			let info = Self::owned_registration(&old_author_id, &account)?;
+			ensure!(!Mappings::<T>::contains_key(&new_author_id), Error::<T>::AlreadyAssociated);
+			ensure!(!Revoked::<T>::get(&new_author_id), Error::<T>::AuthorRevoked);
+
+			Mappings::<T>::remove(&old_author_id);
+			Mappings::<T>::insert(&new_author_id, info);
+
+			Self::deposit_event(Event::AuthorRotated { old_author_id, new_author_id, account });
+			Ok(())
+		}
+
+		/// Deregister `author_id` of the sender and return the deposit.
+		#[pallet::weight(T::WeightInfo::clear_association())]
+		pub fn clear_association(origin: OriginFor<T>, author_id: T::AuthorId) -> DispatchResult {
+			let account = ensure_signed(origin)?;
+
+			let info = Self::owned_registration(&author_id, &account)?;
+			T::DepositCurrency::unreserve(&account, info.deposit);
+			Mappings::<T>::remove(&author_id);
+
+			Self::deposit_event(Event::AuthorDeregistered { author_id, account });
+			Ok(())
+		}
+
+		/// Revoke the compromised `author_id`.
+		///
+		/// The mapping of `author_id` is removed and the deposit returned to its account. The
+		/// author id can not be registered again. Can also revoke author ids that are not
+		/// registered yet.
+		#[pallet::weight(T::WeightInfo::revoke_association())]
+		pub fn revoke_association(origin: OriginFor<T>, author_id: T::AuthorId) -> DispatchResult {
+			T::RevokeOrigin::ensure_origin(origin)?;
+			ensure!(!Revoked::<T>::get(&author_id), Error::<T>::AuthorRevoked);
+
+			let account = Mappings::<T>::take(&author_id).map(|info| {
+				T::DepositCurrency::unreserve(&info.account, info.deposit);
+				info.account
+			});
+			Revoked::<T>::insert(&author_id, true);
+
+			Self::deposit_event(Event::AuthorRevoked { author_id, account });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Reserve the deposit from `account` and register `author_id` for it.
+		fn do_register(author_id: T::AuthorId, account: T::AccountId) -> DispatchResult {
+			ensure!(!Mappings::<T>::contains_key(&author_id), Error::<T>::AlreadyAssociated);
+			ensure!(!Revoked::<T>::get(&author_id), Error::<T>::AuthorRevoked);
+
+			let deposit = T::DepositAmount::get();
+			T::DepositCurrency::reserve(&account, deposit)
+				.map_err(|_| Error::<T>::CannotAffordDeposit)?;
+
+			Mappings::<T>::insert(author_id, RegistrationInfo { account, deposit });
+			Ok(())
+		}
+
+		/// Returns the registration of `author_id`, if it is registered by `account`.
+		fn owned_registration(
+			author_id: &T::AuthorId,
+			account: &T::AccountId,
+		) -> Result<RegistrationInfo<T::AccountId, BalanceOf<T>>, DispatchError> {
+			let info = Mappings::<T>::get(author_id).ok_or(Error::<T>::AssociationNotFound)?;
+			ensure!(&info.account == account, Error::<T>::NotYourAssociation);
+			Ok(info)
+		}
+
+		/// Returns the account `author_id` is registered by.
+		pub fn account_id_of(author_id: &T::AuthorId) -> Option<T::AccountId> {
+			Mappings::<T>::get(author_id).map(|info| info.account)
+		}
+	}
+}
+
+impl<T: Config> AccountLookup<T::AuthorId, T::AccountId> for Pallet<T> {
+	fn lookup_account(author_id: &T::AuthorId) -> Option<T::AccountId> {
+		Self::account_id_of(author_id)
+	}
+}
+
+/// Only registered author ids may author blocks.
+impl<T: Config> CanAuthor<T::AuthorId, T::BlockNumber> for Pallet<T> {
+	fn can_author(author_id: &T::AuthorId, _: RelayChainBlockNumber, _: T::BlockNumber) -> bool {
+		Mappings::<T>::contains_key(author_id)
+	}
+}
+
+/// Implements [`FindAuthor`] for accounts by mapping the author id found by `Inner`.
+///
+/// Returns `None` if the author id is not registered.
+pub struct MappedFindAuthor<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T: Config, Inner: FindAuthor<T::AuthorId>> FindAuthor<T::AccountId>
+	for MappedFindAuthor<T, Inner>
+{
+	fn find_author<'a, I>(digests: I) -> Option<T::AccountId>
+	where
+		I: 'a + IntoIterator<Item = (ConsensusEngineId, &'a [u8])>,
+	{
+		Inner::find_author(digests).and_then(|author_id| Pallet::<T>::account_id_of(&author_id))
+	}
+}
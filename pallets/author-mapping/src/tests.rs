@@ -0,0 +1,172 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+	mock::*, AccountLookup, Error, Event as AuthorMappingEvent, MappedFindAuthor, RegistrationInfo,
+};
+use codec::Encode;
+use frame_support::{assert_noop, assert_ok, traits::FindAuthor};
+use pallet_author_inherent::CanAuthor;
+use sp_runtime::traits::BadOrigin;
+
+#[test]
+fn genesis_mappings_are_registered() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			AuthorMapping::registration_info(10),
+			Some(RegistrationInfo { account: 1, deposit: 100 }),
+		);
+		assert_eq!(Balances::reserved_balance(1), 100);
+		assert_eq!(AuthorMapping::lookup_account(&10), Some(1));
+		assert_eq!(AuthorMapping::lookup_account(&11), None);
+	});
+}
+
+#[test]
+fn add_association_reserves_deposit() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		assert_noop!(AuthorMapping::add_association(Origin::root(), 11), BadOrigin);
+		assert_noop!(
+			AuthorMapping::add_association(Origin::signed(2), 10),
+			Error::<Test>::AlreadyAssociated,
+		);
+		assert_noop!(
+			AuthorMapping::add_association(Origin::signed(3), 11),
+			Error::<Test>::CannotAffordDeposit,
+		);
+
+		assert_ok!(AuthorMapping::add_association(Origin::signed(2), 11));
+		assert_eq!(AuthorMapping::account_id_of(&11), Some(2));
+		assert_eq!(Balances::reserved_balance(2), 100);
+		System::assert_last_event(
+			AuthorMappingEvent::AuthorRegistered { author_id: 11, account: 2 }.into(),
+		);
+	});
+}
+
+#[test]
+fn update_association_keeps_deposit() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(AuthorMapping::add_association(Origin::signed(2), 11));
+
+		assert_noop!(
+			AuthorMapping::update_association(Origin::signed(2), 10, 12),
+			Error::<Test>::NotYourAssociation,
+		);
+		assert_noop!(
+			AuthorMapping::update_association(Origin::signed(1), 12, 13),
+			Error::<Test>::AssociationNotFound,
+		);
+		assert_noop!(
+			AuthorMapping::update_association(Origin::signed(1), 10, 11),
+			Error::<Test>::AlreadyAssociated,
+		);
+
+		assert_ok!(AuthorMapping::update_association(Origin::signed(1), 10, 12));
+		assert_eq!(AuthorMapping::account_id_of(&10), None);
+		assert_eq!(AuthorMapping::account_id_of(&12), Some(1));
+		assert_eq!(Balances::reserved_balance(1), 100);
+		System::assert_last_event(
+			AuthorMappingEvent::AuthorRotated { old_author_id: 10, new_author_id: 12, account: 1 }
+				.into(),
+		);
+	});
+}
+
+#[test]
+fn clear_association_returns_deposit() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		assert_noop!(
+			AuthorMapping::clear_association(Origin::signed(2), 10),
+			Error::<Test>::NotYourAssociation,
+		);
+		assert_noop!(
+			AuthorMapping::clear_association(Origin::signed(1), 11),
+			Error::<Test>::AssociationNotFound,
+		);
+
+		assert_ok!(AuthorMapping::clear_association(Origin::signed(1), 10));
+		assert_eq!(AuthorMapping::account_id_of(&10), None);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		System::assert_last_event(
+			AuthorMappingEvent::AuthorDeregistered { author_id: 10, account: 1 }.into(),
+		);
+	});
+}
+
+#[test]
+fn revoked_authors_can_not_author_or_register() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		assert_noop!(AuthorMapping::revoke_association(Origin::signed(1), 10), BadOrigin);
+
+		assert_ok!(AuthorMapping::revoke_association(Origin::root(), 10));
+		assert_eq!(AuthorMapping::account_id_of(&10), None);
+		assert!(AuthorMapping::is_revoked(10));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert!(!AuthorMapping::can_author(&10, 1, 1));
+		System::assert_last_event(
+			AuthorMappingEvent::AuthorRevoked { author_id: 10, account: Some(1) }.into(),
+		);
+
+		assert_noop!(
+			AuthorMapping::revoke_association(Origin::root(), 10),
+			Error::<Test>::AuthorRevoked,
+		);
+		assert_noop!(
+			AuthorMapping::add_association(Origin::signed(2), 10),
+			Error::<Test>::AuthorRevoked,
+		);
+
+		assert_ok!(AuthorMapping::revoke_association(Origin::root(), 11));
+		System::assert_last_event(
+			AuthorMappingEvent::AuthorRevoked { author_id: 11, account: None }.into(),
+		);
+		assert_ok!(AuthorMapping::add_association(Origin::signed(1), 12));
+		assert_noop!(
+			AuthorMapping::update_association(Origin::signed(1), 12, 11),
+			Error::<Test>::AuthorRevoked,
+		);
+	});
+}
+
+#[test]
+fn mapped_find_author_works() {
+	new_test_ext().execute_with(|| {
+		let find_author = |author_id: u32| {
+			let data = author_id.encode();
+			MappedFindAuthor::<Test, DigestAuthor>::find_author(vec![(TEST_ENGINE_ID, &data[..])])
+		};
+
+		assert_eq!(find_author(10), Some(1));
+		assert_eq!(find_author(11), None);
+		assert_eq!(MappedFindAuthor::<Test, DigestAuthor>::find_author(Vec::new()), None);
+	});
+}
+
+#[test]
+fn only_registered_authors_can_author() {
+	new_test_ext().execute_with(|| {
+		assert!(AuthorMapping::can_author(&10, 1, 1));
+		assert!(!AuthorMapping::can_author(&11, 1, 1));
+	});
+}
@@ -0,0 +1,74 @@
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+// Hand-written estimates, to be replaced by weights generated from `benchmarking.rs`.
+pub trait WeightInfo {
+	fn add_association() -> Weight;
+	fn update_association() -> Weight;
+	fn clear_association() -> Weight;
+	fn revoke_association() -> Weight;
+}
+
+pub struct SubstrateWeight<T>(PhantomData<T>);
+
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Reads `Revoked` and writes `Mappings` after checking it, plus the account of the reserved
+	// deposit.
+	fn add_association() -> Weight {
+		(28_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	// Reads `Mappings` of both author ids and `Revoked` of the new one, then moves the mapping.
+	fn update_association() -> Weight {
+		(22_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	// Reads and removes `Mappings`, plus the account of the returned deposit.
+	fn clear_association() -> Weight {
+		(27_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	// Reads and writes `Revoked`, takes `Mappings` and writes the account of the returned deposit.
+	fn revoke_association() -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+}
+
+impl WeightInfo for () {
+	// Reads `Revoked` and writes `Mappings` after checking it, plus the account of the reserved
+	// deposit.
+	fn add_association() -> Weight {
+		(28_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+	// Reads `Mappings` of both author ids and `Revoked` of the new one, then moves the mapping.
+	fn update_association() -> Weight {
+		(22_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+	// Reads and removes `Mappings`, plus the account of the returned deposit.
+	fn clear_association() -> Weight {
+		(27_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+	// Reads and writes `Revoked`, takes `Mappings` and writes the account of the returned deposit.
+	fn revoke_association() -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+}
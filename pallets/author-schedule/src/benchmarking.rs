@@ -0,0 +1,46 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarking setup for pallet-author-schedule
+
+use super::*;
+
+use codec::{Decode, Encode};
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+use frame_support::traits::{EnsureOrigin, Get};
+use sp_runtime::traits::TrailingZeroInput;
+
+/// Returns the author id decoded from `seed`.
+fn author_id<T: Config>(seed: u32) -> T::AuthorId {
+	T::AuthorId::decode(&mut TrailingZeroInput::new(&seed.encode()))
+		.expect("Decoding from an infinite input never fails")
+}
+
+benchmarks! {
+	set_authors {
+		let a in 1 .. T::MaxAuthors::get();
+
+		// Worst case: the weights of the authors fill the whole rotation.
+		let weight = (T::MaxRotationLength::get() / a).max(1);
+		let authors = (0..a).map(|i| (author_id::<T>(i), weight)).collect::<Vec<_>>();
+		let origin = T::UpdateOrigin::successful_origin();
+	}: _<T::Origin>(origin, authors)
+	verify {
+		assert_eq!(Authors::<T>::get().len(), a as usize);
+	}
+}
+
+impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
@@ -0,0 +1,323 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Author Schedule pallet.
+//!
+//! A deterministic author schedule for parachains.
+//!
+//! ## Overview
+//!
+//! The pallet keeps a registered set of authors, each with a weight, and assigns every relay chain
+//! block to exactly one of them. The assignment is a smooth weighted round robin: over a rotation
+//! of `sum(weights)` relay chain blocks every author is scheduled `weight` times, and the slots of
+//! an author are spread as evenly as possible over the rotation.
+//!
+//! The rotation is computed once whenever the author set changes and stored in [`Rotation`]. The
+//! author of relay chain block `n` is `Rotation[n % Rotation.len()]`.
+//!
+//! Because the schedule only depends on on-chain state, it can be queried ahead of time through
+//! the [`AuthorScheduleApi`] runtime api. Collators can use this to spin down when they are not
+//! scheduled for the upcoming relay chain blocks.
+//!
+//! If [`Config::AllowBackup`] is set, every relay chain block also has a backup author: the next
+//! author in the rotation that is not the scheduled one. Both may author, but the backup should
+//! only author if it does not see a block of the scheduled author in time. This keeps the chain
+//! live when the scheduled author is offline, without producing duplicate candidates otherwise.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use cumulus_primitives_core::relay_chain::BlockNumber as RelayChainBlockNumber;
+use pallet_author_inherent::CanAuthor;
+use sp_std::vec::Vec;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	pub use crate::weights::WeightInfo;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The identifier of an author.
+		type AuthorId: Member + Parameter + MaxEncodedLen + MaybeSerializeDeserialize;
+
+		/// Origin that can update the registered authors.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Maximum number of registered authors.
+		#[pallet::constant]
+		type MaxAuthors: Get<u32>;
+
+		/// Maximum length of the rotation, aka the maximum sum of all author weights.
+		#[pallet::constant]
+		type MaxRotationLength: Get<u32>;
+
+		/// Whether the backup author of a relay chain block may author as well.
+		#[pallet::constant]
+		type AllowBackup: Get<bool>;
+
+		/// The weight information of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The registered authors with their weights.
+	#[pallet::storage]
+	#[pallet::getter(fn authors)]
+	pub type Authors<T: Config> =
+		StorageValue<_, BoundedVec<(T::AuthorId, u32), T::MaxAuthors>, ValueQuery>;
+
+	/// The author of every relay chain block in one rotation.
+	///
+	/// Relay chain block `n` is assigned to `Rotation[n % Rotation.len()]`.
+	#[pallet::storage]
+	#[pallet::getter(fn rotation)]
+	pub type Rotation<T: Config> =
+		StorageValue<_, BoundedVec<T::AuthorId, T::MaxRotationLength>, ValueQuery>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub authors: Vec<(T::AuthorId, u32)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { authors: Default::default() }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			Pallet::<T>::do_set_authors(self.authors.clone())
+				.expect("Invalid authors in the author schedule genesis config");
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The registered authors were updated and a new rotation was computed.
+		AuthorsSet { authors: Vec<(T::AuthorId, u32)> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// More authors than [`Config::MaxAuthors`] were given.
+		TooManyAuthors,
+		/// The sum of all weights exceeds [`Config::MaxRotationLength`].
+		RotationTooLong,
+		/// An author was given a weight of zero.
+		ZeroWeight,
+		/// The same author was given more than once.
+		DuplicateAuthor,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Replace the registered authors and recompute the rotation.
+		///
+		/// Every author is scheduled `weight` times per rotation.
+		#[pallet::weight(T::WeightInfo::set_authors(authors.len() as u32))]
+		pub fn set_authors(
+			origin: OriginFor<T>,
+			authors: Vec<(T::AuthorId, u32)>,
+		) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			Self::do_set_authors(authors.clone())?;
+
+			Self::deposit_event(Event::AuthorsSet { authors });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Validate `authors`, store them and compute the new rotation.
+		fn do_set_authors(authors: Vec<(T::AuthorId, u32)>) -> DispatchResult {
+			ensure!(authors.iter().all(|(_, weight)| *weight > 0), Error::<T>::ZeroWeight);
+			ensure!(
+				authors
+					.iter()
+					.enumerate()
+					.all(|(i, (author, _))| !authors[..i].iter().any(|(a, _)| a == author)),
+				Error::<T>::DuplicateAuthor,
+			);
+
+			let total_weight = authors
+				.iter()
+				.try_fold(0u32, |total, (_, weight)| total.checked_add(*weight))
+				.ok_or(Error::<T>::RotationTooLong)?;
+			ensure!(total_weight <= T::MaxRotationLength::get(), Error::<T>::RotationTooLong);
+
+			let rotation = weighted_rotation(&authors)
+				.try_into()
+				.map_err(|_| Error::<T>::RotationTooLong)?;
+			let authors = authors.try_into().map_err(|_| Error::<T>::TooManyAuthors)?;
+
+			Authors::<T>::put(authors);
+			Rotation::<T>::put(rotation);
+
+			Ok(())
+		}
+
+		/// Returns the author scheduled for the given relay chain block.
+		///
+		/// Returns `None` if there are no registered authors.
+		pub fn scheduled_author(relay_block_number: RelayChainBlockNumber) -> Option<T::AuthorId> {
+			let rotation = Rotation::<T>::get();
+
+			if rotation.is_empty() {
+				return None
+			}
+
+			rotation.get(relay_block_number as usize % rotation.len()).cloned()
+		}
+
+		/// Returns the backup author of the given relay chain block.
+		///
+		/// The backup is the next author in the rotation that is not the scheduled author. Returns
+		/// `None` if there are less than two distinct authors.
+		pub fn backup_author(relay_block_number: RelayChainBlockNumber) -> Option<T::AuthorId> {
+			let rotation = Rotation::<T>::get();
+
+			if rotation.is_empty() {
+				return None
+			}
+
+			let index = relay_block_number as usize % rotation.len();
+			let primary = &rotation[index];
+			rotation[index + 1..]
+				.iter()
+				.chain(rotation[..index].iter())
+				.find(|author| *author != primary)
+				.cloned()
+		}
+
+		/// Returns `true` if `author` is scheduled for the given relay chain block.
+		pub fn is_scheduled(
+			author: &T::AuthorId,
+			relay_block_number: RelayChainBlockNumber,
+		) -> bool {
+			Self::scheduled_author(relay_block_number).as_ref() == Some(author)
+		}
+
+		/// Returns the schedule for `count` relay chain blocks, starting at `from`.
+		///
+		/// As the schedule repeats after one rotation, at most one rotation is returned.
+		pub fn upcoming_schedule(
+			from: RelayChainBlockNumber,
+			count: u32,
+		) -> Vec<(RelayChainBlockNumber, T::AuthorId)> {
+			let rotation = Rotation::<T>::get();
+
+			if rotation.is_empty() {
+				return Vec::new()
+			}
+
+			let count = count.min(rotation.len() as u32);
+			(0..count)
+				.map(|offset| from.wrapping_add(offset))
+				.map(|n| (n, rotation[n as usize % rotation.len()].clone()))
+				.collect()
+		}
+	}
+}
+
+/// Only the author scheduled for the relay parent and, if [`Config::AllowBackup`] is set, its
+/// backup may author.
+impl<T: Config> CanAuthor<T::AuthorId, T::BlockNumber> for Pallet<T> {
+	fn can_author(
+		author: &T::AuthorId,
+		relay_parent: RelayChainBlockNumber,
+		_: T::BlockNumber,
+	) -> bool {
+		Self::is_scheduled(author, relay_parent) ||
+			(T::AllowBackup::get() && Self::backup_author(relay_parent).as_ref() == Some(author))
+	}
+}
+
+/// Compute one rotation of the smooth weighted round robin over `authors`.
+///
+/// Every author appears `weight` times in the returned rotation. In every step each author gains
+/// its weight, the author with the highest accumulated value is picked and loses the total weight.
+/// Ties are resolved in favour of the author that comes first in `authors`.
+pub fn weighted_rotation<AuthorId: Clone>(authors: &[(AuthorId, u32)]) -> Vec<AuthorId> {
+	let total_weight: i64 = authors.iter().map(|(_, weight)| *weight as i64).sum();
+	let mut current = sp_std::vec![0i64; authors.len()];
+	let mut rotation = Vec::with_capacity(total_weight as usize);
+
+	for _ in 0..total_weight {
+		current
+			.iter_mut()
+			.zip(authors.iter())
+			.for_each(|(current, (_, weight))| *current += *weight as i64);
+
+		let next =
+			current
+				.iter()
+				.enumerate()
+				.fold(0, |best, (i, value)| if *value > current[best] { i } else { best });
+
+		current[next] -= total_weight;
+		rotation.push(authors[next].0.clone());
+	}
+
+	rotation
+}
+
+sp_api::decl_runtime_apis! {
+	/// Runtime api to query the author schedule.
+	pub trait AuthorScheduleApi<AuthorId: codec::Codec> {
+		/// Returns the author scheduled for the given relay chain block.
+		fn scheduled_author(relay_block_number: RelayChainBlockNumber) -> Option<AuthorId>;
+
+		/// Returns the backup author of the given relay chain block.
+		fn backup_author(relay_block_number: RelayChainBlockNumber) -> Option<AuthorId>;
+
+		/// Returns the schedule for `count` relay chain blocks, starting at `from`.
+		///
+		/// At most one full rotation is returned, as the schedule repeats afterwards.
+		fn upcoming_schedule(
+			from: RelayChainBlockNumber,
+			count: u32,
+		) -> Vec<(RelayChainBlockNumber, AuthorId)>;
+	}
+}
@@ -0,0 +1,119 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{mock::*, weighted_rotation, Error, Event as AuthorScheduleEvent};
+use frame_support::{assert_noop, assert_ok};
+use pallet_author_inherent::CanAuthor;
+use sp_runtime::traits::BadOrigin;
+
+#[test]
+fn weighted_rotation_respects_weights() {
+	assert_eq!(weighted_rotation(&[(1, 1), (2, 1)]), vec![1, 2]);
+	assert_eq!(weighted_rotation(&[(1, 3), (2, 1)]), vec![1, 1, 2, 1]);
+	assert_eq!(weighted_rotation(&[(1, 5), (2, 1), (3, 1)]), vec![1, 1, 2, 1, 3, 1, 1]);
+	assert!(weighted_rotation::<u64>(&[]).is_empty());
+}
+
+#[test]
+fn genesis_authors_are_scheduled() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(AuthorSchedule::authors().into_inner(), vec![(1, 1), (2, 1)]);
+		assert_eq!(AuthorSchedule::scheduled_author(10), Some(1));
+		assert_eq!(AuthorSchedule::scheduled_author(11), Some(2));
+		assert!(AuthorSchedule::is_scheduled(&1, 12));
+		assert!(!AuthorSchedule::is_scheduled(&2, 12));
+		assert!(AuthorSchedule::can_author(&1, 12, 1));
+		assert!(!AuthorSchedule::can_author(&2, 12, 1));
+	});
+}
+
+#[test]
+fn set_authors_recomputes_rotation() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		assert_ok!(AuthorSchedule::set_authors(Origin::root(), vec![(3, 2), (4, 1)]));
+		assert_eq!(AuthorSchedule::rotation().into_inner(), vec![3, 4, 3]);
+		assert_eq!(AuthorSchedule::scheduled_author(4), Some(4));
+		System::assert_last_event(
+			AuthorScheduleEvent::AuthorsSet { authors: vec![(3, 2), (4, 1)] }.into(),
+		);
+
+		assert_ok!(AuthorSchedule::set_authors(Origin::root(), vec![]));
+		assert_eq!(AuthorSchedule::scheduled_author(4), None);
+	});
+}
+
+#[test]
+fn set_authors_rejects_invalid_sets() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(AuthorSchedule::set_authors(Origin::signed(1), vec![(3, 1)]), BadOrigin);
+		assert_noop!(
+			AuthorSchedule::set_authors(Origin::root(), vec![(3, 1), (4, 0)]),
+			Error::<Test>::ZeroWeight,
+		);
+		assert_noop!(
+			AuthorSchedule::set_authors(Origin::root(), vec![(3, 1), (3, 2)]),
+			Error::<Test>::DuplicateAuthor,
+		);
+		assert_noop!(
+			AuthorSchedule::set_authors(Origin::root(), vec![(3, 6), (4, 5)]),
+			Error::<Test>::RotationTooLong,
+		);
+		assert_noop!(
+			AuthorSchedule::set_authors(Origin::root(), vec![(3, u32::MAX), (4, 1)]),
+			Error::<Test>::RotationTooLong,
+		);
+		assert_noop!(
+			AuthorSchedule::set_authors(Origin::root(), (1..=5).map(|a| (a, 1)).collect()),
+			Error::<Test>::TooManyAuthors,
+		);
+	});
+}
+
+#[test]
+fn upcoming_schedule_covers_at_most_one_rotation() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AuthorSchedule::set_authors(Origin::root(), vec![(3, 2), (4, 1)]));
+
+		assert_eq!(AuthorSchedule::upcoming_schedule(5, 2), vec![(5, 3), (6, 3)]);
+		assert_eq!(AuthorSchedule::upcoming_schedule(5, 100), vec![(5, 3), (6, 3), (7, 4)]);
+		assert_eq!(
+			AuthorSchedule::upcoming_schedule(u32::MAX, 2),
+			vec![(u32::MAX, AuthorSchedule::scheduled_author(u32::MAX).unwrap()), (0, 3)],
+		);
+	});
+}
+
+#[test]
+fn backup_authors_may_author_if_allowed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AuthorSchedule::set_authors(Origin::root(), vec![(3, 2), (4, 1), (5, 1)]));
+		assert_eq!(AuthorSchedule::rotation().into_inner(), vec![3, 4, 5, 3]);
+		assert_eq!(AuthorSchedule::backup_author(0), Some(4));
+		assert_eq!(AuthorSchedule::backup_author(2), Some(3));
+		assert_eq!(AuthorSchedule::backup_author(3), Some(4));
+
+		assert!(!AuthorSchedule::can_author(&4, 0, 1));
+		AllowBackup::set(&true);
+		assert!(AuthorSchedule::can_author(&3, 0, 1));
+		assert!(AuthorSchedule::can_author(&4, 0, 1));
+		assert!(!AuthorSchedule::can_author(&5, 0, 1));
+
+		assert_ok!(AuthorSchedule::set_authors(Origin::root(), vec![(3, 2)]));
+		assert_eq!(AuthorSchedule::backup_author(0), None);
+	});
+}
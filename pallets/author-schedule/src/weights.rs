@@ -0,0 +1,33 @@
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+// Hand-written estimates, to be replaced by weights generated from `benchmarking.rs`.
+pub trait WeightInfo {
+	fn set_authors(a: u32) -> Weight;
+}
+
+pub struct SubstrateWeight<T>(PhantomData<T>);
+
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Writes `Authors` and `Rotation`, the computation grows with the number of authors.
+	fn set_authors(a: u32) -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add((250_000 as Weight).saturating_mul(a as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+}
+
+impl WeightInfo for () {
+	// Writes `Authors` and `Rotation`, the computation grows with the number of authors.
+	fn set_authors(a: u32) -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add((250_000 as Weight).saturating_mul(a as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+}
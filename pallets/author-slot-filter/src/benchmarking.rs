@@ -0,0 +1,91 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarking setup for pallet-author-slot-filter
+
+use super::*;
+
+use cumulus_primitives_core::relay_chain::Hash as RelayChainHash;
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+use frame_support::{
+	traits::{EnsureOrigin, Get},
+	BoundedVec,
+};
+use sp_runtime::traits::TrailingZeroInput;
+
+/// Returns the author id decoded from `seed`.
+fn author_id<T: Config>(seed: u32) -> T::AuthorId {
+	T::AuthorId::decode(&mut TrailingZeroInput::new(&seed.encode()))
+		.expect("Decoding from an infinite input never fails")
+}
+
+benchmarks! {
+	set_authors {
+		let a in 1 .. T::MaxAuthors::get();
+
+		let authors = (0..a).map(|i| author_id::<T>(1000 + i)).collect::<Vec<_>>();
+		let origin = T::UpdateOrigin::successful_origin();
+	}: _<T::Origin>(origin, authors)
+	verify {
+		assert_eq!(Authors::<T>::get().len(), a as usize);
+	}
+
+	set_eligible_ratio {
+		let origin = T::UpdateOrigin::successful_origin();
+		let ratio = Percent::from_percent(25);
+	}: _<T::Origin>(origin, ratio)
+	verify {
+		assert_eq!(EligibleRatio::<T>::get(), ratio);
+	}
+
+	set_eligibility_mode {
+		let origin = T::UpdateOrigin::successful_origin();
+	}: _<T::Origin>(origin, EligibilityMode::Vrf)
+	verify {
+		assert_eq!(PendingMode::<T>::get(), Some(EligibilityMode::Vrf));
+	}
+
+	set_block_cadence {
+		let origin = T::UpdateOrigin::successful_origin();
+	}: _<T::Origin>(origin, 2)
+	verify {
+		assert_eq!(BlockCadence::<T>::get(), 2);
+	}
+
+	check_vrf_claim {
+		let storage_root = RelayChainHash::repeat_byte(1);
+		T::BenchmarkHelper::set_relay_parent_storage_root(storage_root);
+		let claim = T::BenchmarkHelper::vrf_claim(storage_root);
+		let author = claim.author.clone();
+
+		// Worst case: the author is the last of the maximum number of registered authors, and
+		// every output is eligible so that the whole check runs.
+		let mut authors = (1..T::MaxAuthors::get())
+			.map(|i| author_id::<T>(1000 + i))
+			.collect::<Vec<_>>();
+		authors.push(author.clone());
+		Authors::<T>::put(
+			BoundedVec::<_, T::MaxAuthors>::try_from(authors)
+				.expect("Has `MaxAuthors` entries"),
+		);
+		EligibleRatio::<T>::put(Percent::from_percent(100));
+		frame_system::Pallet::<T>::deposit_log(claim.to_digest_item());
+	}: {
+		assert_eq!(Pallet::<T>::check_vrf_claim(), Ok(author));
+	}
+}
+
+impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
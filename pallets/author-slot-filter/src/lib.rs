@@ -0,0 +1,459 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Author Slot Filter pallet.
+//!
+//! A pseudo-random author eligibility filter for parachains.
+//!
+//! ## Overview
+//!
+//! The pallet keeps a registered set of authors and, for every relay chain block, selects a
+//! configurable fraction of them as eligible to author a parachain block on top of it. The
+//! fraction is stored in [`EligibleRatio`] and at least one author is always eligible.
+//!
+//! The selection is a partial Fisher-Yates shuffle of the registered authors, seeded by the
+//! relay parent block number. It only depends on on-chain state, so every node computes the same
+//! eligible authors and collators can check their eligibility ahead of time through the
+//! [`AuthorFilterApi`] runtime api.
+//!
+//! The relay parent block number is predictable, which allows to target the upcoming authors,
+//! e.g. with a denial of service attack. To prevent this, the pallet can be switched to
+//! [`EligibilityMode::Vrf`], in which every author proves its eligibility with a VRF output. See
+//! the [`vrf`] module for details. The mode only changes between blocks, so a block is always
+//! checked with the mode it was built for.
+//!
+//! Parachains that do not need a block on every relay chain block can set a [`BlockCadence`].
+//! Blocks are then only authored on top of every n-th relay parent, no author is eligible on the
+//! relay parents in between. Collators should skip those relay parents as well, e.g. through the
+//! block cadence of the relay chain consensus, to not waste resources on blocks that are rejected.
+//!
+//! The pallet implements [`CanAuthor`], so it can be used to filter the authors accepted by the
+//! author inherent.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use cumulus_primitives_core::{
+	relay_chain::BlockNumber as RelayChainBlockNumber, RelayChainStateInfo,
+};
+use pallet_author_inherent::CanAuthor;
+use scale_info::TypeInfo;
+use sp_core::sr25519;
+use sp_runtime::{traits::Convert, Percent, RuntimeDebug};
+use sp_std::vec::Vec;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod vrf;
+pub mod weights;
+
+use vrf::{VrfClaim, VrfError};
+
+/// How the eligible authors are selected.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub enum EligibilityMode {
+	/// A fixed number of authors is selected by a shuffle seeded with the relay parent number.
+	RelayParentNumber,
+	/// Every author is eligible if its VRF output for the relay parent is below a threshold.
+	Vrf,
+}
+
+impl Default for EligibilityMode {
+	fn default() -> Self {
+		Self::RelayParentNumber
+	}
+}
+
+/// Provides the VRF claim and the relay parent that are used to benchmark
+/// [`Pallet::check_vrf_claim`].
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AuthorId> {
+	/// Returns a claim for the relay parent with the given storage root, made with the key that
+	/// [`Config::AuthorVrfKey`] returns for its author.
+	fn vrf_claim(
+		relay_parent_storage_root: cumulus_primitives_core::relay_chain::Hash,
+	) -> VrfClaim<AuthorId>;
+
+	/// Make [`Config::RelayChainState`] return the given storage root, e.g. by setting the
+	/// validation data of parachain-system.
+	fn set_relay_parent_storage_root(storage_root: cumulus_primitives_core::relay_chain::Hash);
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	pub use crate::weights::WeightInfo;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The identifier of an author.
+		type AuthorId: Member + Parameter + MaxEncodedLen + MaybeSerializeDeserialize;
+
+		/// Origin that can update the registered authors and the eligible ratio.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Maximum number of registered authors.
+		#[pallet::constant]
+		type MaxAuthors: Get<u32>;
+
+		/// The VRF key of an author, used in [`EligibilityMode::Vrf`].
+		type AuthorVrfKey: Convert<Self::AuthorId, Option<sr25519::Public>>;
+
+		/// Provides the storage root of the current relay parent, used in
+		/// [`EligibilityMode::Vrf`].
+		///
+		/// Usually parachain-system. Must be available when the author inherent is applied.
+		type RelayChainState: RelayChainStateInfo;
+
+		/// The weight information of this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Provides the VRF claim for the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<Self::AuthorId>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The registered authors.
+	#[pallet::storage]
+	#[pallet::getter(fn authors)]
+	pub type Authors<T: Config> =
+		StorageValue<_, BoundedVec<T::AuthorId, T::MaxAuthors>, ValueQuery>;
+
+	#[pallet::type_value]
+	pub fn DefaultEligibleRatio() -> Percent {
+		Percent::from_percent(50)
+	}
+
+	/// The fraction of the registered authors that is eligible for every relay chain block.
+	///
+	/// The number of eligible authors is rounded up.
+	#[pallet::storage]
+	#[pallet::getter(fn eligible_ratio)]
+	pub type EligibleRatio<T: Config> = StorageValue<_, Percent, ValueQuery, DefaultEligibleRatio>;
+
+	/// How the eligible authors are selected.
+	///
+	/// Only changed in `on_initialize`, so it is the same for the whole block.
+	#[pallet::storage]
+	#[pallet::getter(fn eligibility_mode)]
+	pub type Mode<T: Config> = StorageValue<_, EligibilityMode, ValueQuery>;
+
+	/// The eligibility mode that becomes the [`Mode`] with the next block.
+	#[pallet::storage]
+	#[pallet::getter(fn pending_eligibility_mode)]
+	pub type PendingMode<T: Config> = StorageValue<_, EligibilityMode, OptionQuery>;
+
+	#[pallet::type_value]
+	pub fn DefaultBlockCadence() -> u32 {
+		1
+	}
+
+	/// Blocks are only authored on top of relay parents whose number is a multiple of this.
+	///
+	/// Never zero, `1` authors on top of every relay parent.
+	#[pallet::storage]
+	#[pallet::getter(fn block_cadence)]
+	pub type BlockCadence<T: Config> = StorageValue<_, u32, ValueQuery, DefaultBlockCadence>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub authors: Vec<T::AuthorId>,
+		pub eligible_ratio: Percent,
+		pub mode: EligibilityMode,
+		pub block_cadence: u32,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self {
+				authors: Default::default(),
+				eligible_ratio: DefaultEligibleRatio::get(),
+				mode: Default::default(),
+				block_cadence: DefaultBlockCadence::get(),
+			}
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			Pallet::<T>::do_set_authors(self.authors.clone())
+				.expect("Invalid authors in the author slot filter genesis config");
+			EligibleRatio::<T>::put(self.eligible_ratio);
+			Mode::<T>::put(self.mode);
+			assert!(
+				self.block_cadence > 0,
+				"Block cadence in the author slot filter genesis config is zero"
+			);
+			BlockCadence::<T>::put(self.block_cadence);
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The registered authors were updated.
+		AuthorsSet { authors: Vec<T::AuthorId> },
+		/// The fraction of eligible authors was updated.
+		EligibleRatioSet { ratio: Percent },
+		/// The eligibility mode was set, it is used from the next block on.
+		EligibilityModeSet { mode: EligibilityMode },
+		/// The block cadence was updated.
+		BlockCadenceSet { cadence: u32 },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// More authors than [`Config::MaxAuthors`] were given.
+		TooManyAuthors,
+		/// The same author was given more than once.
+		DuplicateAuthor,
+		/// The block cadence is zero.
+		ZeroBlockCadence,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_: T::BlockNumber) -> Weight {
+			let mut weight = T::DbWeight::get().reads(1);
+			let mode = match PendingMode::<T>::take() {
+				Some(mode) => {
+					Mode::<T>::put(mode);
+					weight = weight.saturating_add(T::DbWeight::get().writes(2));
+					mode
+				},
+				None => {
+					weight = weight.saturating_add(T::DbWeight::get().reads(1));
+					Mode::<T>::get()
+				},
+			};
+
+			// The claim is checked by `CanAuthor` when the author inherent is applied.
+			match mode {
+				EligibilityMode::RelayParentNumber => weight,
+				EligibilityMode::Vrf => weight.saturating_add(T::WeightInfo::check_vrf_claim()),
+			}
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Replace the registered authors.
+		#[pallet::weight(T::WeightInfo::set_authors(authors.len() as u32))]
+		pub fn set_authors(origin: OriginFor<T>, authors: Vec<T::AuthorId>) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			Self::do_set_authors(authors.clone())?;
+
+			Self::deposit_event(Event::AuthorsSet { authors });
+			Ok(())
+		}
+
+		/// Set the fraction of the registered authors that is eligible per relay chain block.
+		#[pallet::weight(T::WeightInfo::set_eligible_ratio())]
+		pub fn set_eligible_ratio(origin: OriginFor<T>, ratio: Percent) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			EligibleRatio::<T>::put(ratio);
+
+			Self::deposit_event(Event::EligibleRatioSet { ratio });
+			Ok(())
+		}
+
+		/// Set how the eligible authors are selected.
+		///
+		/// Takes effect with the next block. Switching to [`EligibilityMode::Vrf`] requires the
+		/// collators to include a [`VrfClaim`] in their blocks from then on.
+		#[pallet::weight(T::WeightInfo::set_eligibility_mode())]
+		pub fn set_eligibility_mode(origin: OriginFor<T>, mode: EligibilityMode) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			PendingMode::<T>::put(mode);
+
+			Self::deposit_event(Event::EligibilityModeSet { mode });
+			Ok(())
+		}
+
+		/// Only author blocks on top of every `cadence`-th relay parent.
+		///
+		/// Takes effect for the next block.
+		#[pallet::weight(T::WeightInfo::set_block_cadence())]
+		pub fn set_block_cadence(origin: OriginFor<T>, cadence: u32) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			ensure!(cadence > 0, Error::<T>::ZeroBlockCadence);
+
+			BlockCadence::<T>::put(cadence);
+
+			Self::deposit_event(Event::BlockCadenceSet { cadence });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Validate `authors` and store them.
+		fn do_set_authors(authors: Vec<T::AuthorId>) -> DispatchResult {
+			ensure!(
+				authors.iter().enumerate().all(|(i, author)| !authors[..i].contains(author)),
+				Error::<T>::DuplicateAuthor,
+			);
+
+			let authors = authors.try_into().map_err(|_| Error::<T>::TooManyAuthors)?;
+			Authors::<T>::put(authors);
+
+			Ok(())
+		}
+
+		/// Returns `true` if blocks may be authored on top of the given relay parent according to
+		/// the [`BlockCadence`].
+		pub fn is_on_cadence(relay_parent: RelayChainBlockNumber) -> bool {
+			relay_parent % BlockCadence::<T>::get().max(1) == 0
+		}
+
+		/// Returns the authors that are eligible for the given relay parent.
+		///
+		/// The authors are selected from the [`Authors`] ordered by their encoding, so the result
+		/// only depends on the set of authors and not on the order they are registered in. They
+		/// are returned in the order they were selected in. No author is eligible on relay parents
+		/// that are off the [`BlockCadence`]. Only meaningful in
+		/// [`EligibilityMode::RelayParentNumber`].
+		pub fn eligible_authors(relay_parent: RelayChainBlockNumber) -> Vec<T::AuthorId> {
+			if !Self::is_on_cadence(relay_parent) {
+				return Vec::new()
+			}
+
+			let mut authors = Authors::<T>::get().into_inner();
+			authors.sort_by_cached_key(|author| author.encode());
+			let count = eligible_count(authors.len(), EligibleRatio::<T>::get());
+
+			select(&mut authors, count, relay_parent);
+			authors.truncate(count);
+			authors
+		}
+
+		/// Returns `true` if `author` is eligible for the given relay parent.
+		///
+		/// Only meaningful in [`EligibilityMode::RelayParentNumber`].
+		pub fn can_author(author: &T::AuthorId, relay_parent: RelayChainBlockNumber) -> bool {
+			Self::eligible_authors(relay_parent).contains(author)
+		}
+
+		/// Check the [`VrfClaim`] in the digest of the current block.
+		///
+		/// Returns the author that claimed eligibility.
+		pub fn check_vrf_claim() -> Result<T::AuthorId, VrfError> {
+			let claim = VrfClaim::<T::AuthorId>::find(&frame_system::Pallet::<T>::digest())
+				.ok_or(VrfError::MissingClaim)?;
+			ensure!(Authors::<T>::get().contains(&claim.author), VrfError::UnknownAuthor);
+
+			let public =
+				T::AuthorVrfKey::convert(claim.author.clone()).ok_or(VrfError::InvalidKey)?;
+			let storage_root = T::RelayChainState::relay_parent_storage_root()
+				.ok_or(VrfError::MissingRelayParent)?;
+
+			let inout = vrf::verify(&public, &storage_root, &claim.output, &claim.proof)?;
+			ensure!(vrf::is_eligible(&inout, EligibleRatio::<T>::get()), VrfError::NotEligible);
+
+			Ok(claim.author)
+		}
+	}
+}
+
+/// Only eligible authors may author.
+///
+/// In [`EligibilityMode::Vrf`] the author has to prove its eligibility with the [`VrfClaim`] in
+/// the digest of the block. The claim must be made by the author itself, so that a claim can't be
+/// copied into a block of another author. In both modes, nobody may author on top of relay
+/// parents that are off the [`BlockCadence`].
+impl<T: Config> CanAuthor<T::AuthorId, T::BlockNumber> for Pallet<T> {
+	fn can_author(
+		author: &T::AuthorId,
+		relay_parent: RelayChainBlockNumber,
+		_: T::BlockNumber,
+	) -> bool {
+		match Mode::<T>::get() {
+			EligibilityMode::RelayParentNumber => Self::can_author(author, relay_parent),
+			EligibilityMode::Vrf =>
+				Self::is_on_cadence(relay_parent) &&
+					Self::check_vrf_claim().map_or(false, |claimant| claimant == *author),
+		}
+	}
+}
+
+/// Returns the number of eligible authors out of `total` for the given `ratio`.
+///
+/// Rounds up and is at least one, as long as there are any authors.
+pub fn eligible_count(total: usize, ratio: Percent) -> usize {
+	ratio.mul_ceil(total).clamp(total.min(1), total)
+}
+
+/// Move `count` pseudo-randomly selected elements of `items` to its front.
+///
+/// This is a partial Fisher-Yates shuffle, in which the random numbers are derived from
+/// `relay_parent`. The same input always results in the same selection.
+pub fn select<Item>(items: &mut [Item], count: usize, relay_parent: RelayChainBlockNumber) {
+	for i in 0..count.min(items.len()) {
+		let entropy = sp_io::hashing::blake2_256(&(b"filter", relay_parent, i as u32).encode());
+		let random = u64::from_le_bytes(
+			entropy[..8].try_into().expect("Slice of a 32 byte array has 8 bytes; qed"),
+		);
+
+		let j = i + (random % (items.len() - i) as u64) as usize;
+		items.swap(i, j);
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// Runtime api to query the eligibility of authors.
+	pub trait AuthorFilterApi<AuthorId: codec::Codec> {
+		/// Returns `true` if `author` is eligible to author on top of the given relay parent.
+		fn can_author(author: AuthorId, relay_parent: RelayChainBlockNumber) -> bool;
+
+		/// Returns the authors that are eligible to author on top of the given relay parent.
+		fn eligible_authors(relay_parent: RelayChainBlockNumber) -> Vec<AuthorId>;
+
+		/// Returns how the eligible authors are selected.
+		///
+		/// The two methods above are only meaningful in [`EligibilityMode::RelayParentNumber`].
+		fn eligibility_mode() -> EligibilityMode;
+
+		/// Returns the fraction of the registered authors that is eligible per relay parent.
+		fn eligible_ratio() -> Percent;
+
+		/// Returns the block cadence, blocks are only authored on top of relay parents whose
+		/// number is a multiple of it.
+		fn block_cadence() -> u32;
+	}
+}
@@ -0,0 +1,151 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use crate as author_slot_filter;
+use frame_support::{
+	parameter_types,
+	traits::{ConstU32, Everything, GenesisBuild},
+};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		AuthorSlotFilter: author_slot_filter::{Pallet, Call, Storage, Config<T>, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub storage RelayParentStorageRoot: Option<H256> = Some(H256::repeat_byte(1));
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+/// Returns [`RelayParentStorageRoot`] as the storage root of the relay parent.
+pub struct MockRelayChainState;
+impl RelayChainStateInfo for MockRelayChainState {
+	fn relay_parent_number() -> Option<u32> {
+		None
+	}
+
+	fn relay_parent_storage_root() -> Option<H256> {
+		RelayParentStorageRoot::get()
+	}
+
+	fn relay_state_proof() -> Option<sp_trie::StorageProof> {
+		None
+	}
+}
+
+impl Config for Test {
+	type Event = Event;
+	type AuthorId = u64;
+	type UpdateOrigin = EnsureRoot<u64>;
+	type MaxAuthors = ConstU32<8>;
+	type AuthorVrfKey = AuthorVrfKey;
+	type RelayChainState = MockRelayChainState;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBenchmarkHelper;
+}
+
+/// Returns the VRF key pair of `author`.
+pub fn author_keypair(author: u64) -> schnorrkel::Keypair {
+	schnorrkel::MiniSecretKey::from_bytes(&[author as u8; 32])
+		.expect("32 bytes are a valid mini secret key; qed")
+		.expand_to_keypair(schnorrkel::ExpansionMode::Ed25519)
+}
+
+pub struct AuthorVrfKey;
+impl Convert<u64, Option<sr25519::Public>> for AuthorVrfKey {
+	fn convert(author: u64) -> Option<sr25519::Public> {
+		Some(sr25519::Public::from_raw(author_keypair(author).public.to_bytes()))
+	}
+}
+
+/// Returns the claim of author 1 and sets [`RelayParentStorageRoot`].
+#[cfg(feature = "runtime-benchmarks")]
+pub struct MockBenchmarkHelper;
+#[cfg(feature = "runtime-benchmarks")]
+impl BenchmarkHelper<u64> for MockBenchmarkHelper {
+	fn vrf_claim(relay_parent_storage_root: H256) -> vrf::VrfClaim<u64> {
+		let (inout, proof, _) =
+			author_keypair(1).vrf_sign(vrf::make_transcript(&relay_parent_storage_root));
+		vrf::VrfClaim {
+			author: 1,
+			output: sp_consensus_vrf::schnorrkel::VRFOutput(inout.to_output()),
+			proof: sp_consensus_vrf::schnorrkel::VRFProof(proof),
+		}
+	}
+
+	fn set_relay_parent_storage_root(storage_root: H256) {
+		RelayParentStorageRoot::set(&Some(storage_root));
+	}
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+	author_slot_filter::GenesisConfig::<Test> {
+		authors: vec![1, 2, 3, 4],
+		eligible_ratio: Percent::from_percent(50),
+		mode: EligibilityMode::RelayParentNumber,
+		block_cadence: 1,
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	t.into()
+}
@@ -0,0 +1,296 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+	eligible_count,
+	mock::*,
+	select,
+	vrf::{self, make_transcript, VrfClaim, VrfError},
+	EligibilityMode, Error, Event as AuthorSlotFilterEvent,
+};
+use frame_support::{assert_noop, assert_ok, traits::Hooks};
+use pallet_author_inherent::CanAuthor;
+use sp_consensus_vrf::schnorrkel::{VRFOutput, VRFProof};
+use sp_core::crypto::KeyTypeId;
+use sp_keystore::{testing::KeyStore, SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::{traits::BadOrigin, Digest, Percent};
+use std::sync::Arc;
+
+/// Create a VRF claim of `author`, signed with the key of `signer`.
+fn vrf_claim(author: u64, signer: u64) -> VrfClaim<u64> {
+	let storage_root = RelayParentStorageRoot::get().unwrap();
+	let (inout, proof, _) = author_keypair(signer).vrf_sign(make_transcript(&storage_root));
+
+	VrfClaim { author, output: VRFOutput(inout.to_output()), proof: VRFProof(proof) }
+}
+
+#[test]
+fn eligible_count_rounds_up_and_is_never_zero() {
+	assert_eq!(eligible_count(4, Percent::from_percent(50)), 2);
+	assert_eq!(eligible_count(5, Percent::from_percent(50)), 3);
+	assert_eq!(eligible_count(4, Percent::from_percent(0)), 1);
+	assert_eq!(eligible_count(4, Percent::from_percent(100)), 4);
+	assert_eq!(eligible_count(0, Percent::from_percent(50)), 0);
+}
+
+#[test]
+fn select_is_deterministic_permutation() {
+	let mut first = vec![1, 2, 3, 4, 5];
+	let mut second = first.clone();
+	select(&mut first, 3, 7);
+	select(&mut second, 3, 7);
+	assert_eq!(first, second);
+
+	first.sort();
+	assert_eq!(first, vec![1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn eligible_authors_depend_on_relay_parent() {
+	new_test_ext().execute_with(|| {
+		let eligible = AuthorSlotFilter::eligible_authors(10);
+		assert_eq!(eligible.len(), 2);
+		assert_eq!(eligible, AuthorSlotFilter::eligible_authors(10));
+		assert!(eligible.iter().all(|author| AuthorSlotFilter::can_author(author, 10)));
+		assert!((1..=4)
+			.filter(|author| !eligible.contains(author))
+			.all(|author| !AuthorSlotFilter::can_author(&author, 10)));
+
+		assert!((11..100).any(|n| {
+			let mut other = AuthorSlotFilter::eligible_authors(n);
+			other.sort();
+			let mut eligible = eligible.clone();
+			eligible.sort();
+			other != eligible
+		}));
+	});
+}
+
+#[test]
+fn eligible_authors_do_not_depend_on_registration_order() {
+	new_test_ext().execute_with(|| {
+		let eligible: Vec<_> = (1..100).map(AuthorSlotFilter::eligible_authors).collect();
+
+		assert_ok!(AuthorSlotFilter::set_authors(Origin::root(), vec![4, 2, 3, 1]));
+		assert_eq!((1..100).map(AuthorSlotFilter::eligible_authors).collect::<Vec<_>>(), eligible);
+	});
+}
+
+#[test]
+fn can_author_depends_on_eligibility_mode() {
+	new_test_ext().execute_with(|| {
+		let eligible = AuthorSlotFilter::eligible_authors(10);
+		let ineligible = (1..=4).find(|author| !eligible.contains(author)).unwrap();
+		assert!(<AuthorSlotFilter as CanAuthor<_, _>>::can_author(&eligible[0], 10, 1));
+		assert!(!<AuthorSlotFilter as CanAuthor<_, _>>::can_author(&ineligible, 10, 1));
+
+		assert_ok!(AuthorSlotFilter::set_eligibility_mode(Origin::root(), EligibilityMode::Vrf));
+		AuthorSlotFilter::on_initialize(2);
+		assert_ok!(AuthorSlotFilter::set_eligible_ratio(
+			Origin::root(),
+			Percent::from_percent(100)
+		));
+		System::deposit_log(vrf_claim(ineligible, ineligible).to_digest_item());
+		assert!(<AuthorSlotFilter as CanAuthor<_, _>>::can_author(&ineligible, 10, 2));
+		assert!(!<AuthorSlotFilter as CanAuthor<_, _>>::can_author(&5, 10, 2));
+	});
+}
+
+#[test]
+fn set_eligible_ratio_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		assert_noop!(
+			AuthorSlotFilter::set_eligible_ratio(Origin::signed(1), Percent::from_percent(100)),
+			BadOrigin,
+		);
+
+		assert_ok!(AuthorSlotFilter::set_eligible_ratio(
+			Origin::root(),
+			Percent::from_percent(100)
+		));
+		assert_eq!(AuthorSlotFilter::eligible_authors(10).len(), 4);
+		System::assert_last_event(
+			AuthorSlotFilterEvent::EligibleRatioSet { ratio: Percent::from_percent(100) }.into(),
+		);
+
+		assert_ok!(AuthorSlotFilter::set_eligible_ratio(Origin::root(), Percent::from_percent(0)));
+		assert_eq!(AuthorSlotFilter::eligible_authors(10).len(), 1);
+	});
+}
+
+#[test]
+fn set_authors_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		assert_ok!(AuthorSlotFilter::set_authors(Origin::root(), vec![5, 6]));
+		assert_eq!(AuthorSlotFilter::authors().into_inner(), vec![5, 6]);
+		assert!(!AuthorSlotFilter::can_author(&1, 10));
+		System::assert_last_event(AuthorSlotFilterEvent::AuthorsSet { authors: vec![5, 6] }.into());
+
+		assert_ok!(AuthorSlotFilter::set_authors(Origin::root(), vec![]));
+		assert!(AuthorSlotFilter::eligible_authors(10).is_empty());
+	});
+}
+
+#[test]
+fn set_authors_rejects_invalid_sets() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(AuthorSlotFilter::set_authors(Origin::signed(1), vec![5]), BadOrigin);
+		assert_noop!(
+			AuthorSlotFilter::set_authors(Origin::root(), vec![5, 6, 5]),
+			Error::<Test>::DuplicateAuthor,
+		);
+		assert_noop!(
+			AuthorSlotFilter::set_authors(Origin::root(), (1..=9).collect()),
+			Error::<Test>::TooManyAuthors,
+		);
+	});
+}
+
+#[test]
+fn vrf_claims_are_checked() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_eq!(AuthorSlotFilter::check_vrf_claim(), Err(VrfError::MissingClaim));
+
+		assert_ok!(AuthorSlotFilter::set_eligible_ratio(
+			Origin::root(),
+			Percent::from_percent(100)
+		));
+		System::deposit_log(vrf_claim(1, 1).to_digest_item());
+		assert_eq!(AuthorSlotFilter::check_vrf_claim(), Ok(1));
+
+		assert_ok!(AuthorSlotFilter::set_eligible_ratio(Origin::root(), Percent::from_percent(0)));
+		assert_eq!(AuthorSlotFilter::check_vrf_claim(), Err(VrfError::NotEligible));
+	});
+
+	new_test_ext().execute_with(|| {
+		System::deposit_log(vrf_claim(1, 2).to_digest_item());
+		assert_eq!(AuthorSlotFilter::check_vrf_claim(), Err(VrfError::InvalidProof));
+	});
+
+	new_test_ext().execute_with(|| {
+		System::deposit_log(vrf_claim(5, 5).to_digest_item());
+		assert_eq!(AuthorSlotFilter::check_vrf_claim(), Err(VrfError::UnknownAuthor));
+	});
+
+	new_test_ext().execute_with(|| {
+		RelayParentStorageRoot::set(&None);
+		System::deposit_log(vrf_claim(1, 1).to_digest_item());
+		assert_eq!(AuthorSlotFilter::check_vrf_claim(), Err(VrfError::MissingRelayParent));
+	});
+}
+
+#[test]
+fn claim_digest_is_only_created_for_eligible_authors() {
+	new_test_ext().execute_with(|| {
+		const KEY_TYPE: KeyTypeId = KeyTypeId(*b"asfv");
+		let keystore: SyncCryptoStorePtr = Arc::new(KeyStore::new());
+		let public = SyncCryptoStore::sr25519_generate_new(&*keystore, KEY_TYPE, None).unwrap();
+		let authors = [(1u64, public)];
+		let storage_root = RelayParentStorageRoot::get().unwrap();
+
+		let claim = |ratio| vrf::claim_digest(&keystore, KEY_TYPE, &authors, &storage_root, ratio);
+		assert_eq!(claim(Percent::from_percent(0)), None);
+
+		let digest = claim(Percent::from_percent(100)).unwrap();
+		let claim = VrfClaim::<u64>::find(&Digest { logs: vec![digest] }).unwrap();
+		assert_eq!(claim.author, 1);
+		assert!(vrf::verify(&public, &storage_root, &claim.output, &claim.proof).is_ok());
+	});
+}
+
+#[test]
+fn set_eligibility_mode_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		assert_noop!(
+			AuthorSlotFilter::set_eligibility_mode(Origin::signed(1), EligibilityMode::Vrf),
+			BadOrigin,
+		);
+
+		assert_ok!(AuthorSlotFilter::set_eligibility_mode(Origin::root(), EligibilityMode::Vrf));
+		assert_eq!(AuthorSlotFilter::pending_eligibility_mode(), Some(EligibilityMode::Vrf));
+		System::assert_last_event(
+			AuthorSlotFilterEvent::EligibilityModeSet { mode: EligibilityMode::Vrf }.into(),
+		);
+
+		// The current block was built without a claim and is still checked by the old mode.
+		assert_eq!(AuthorSlotFilter::eligibility_mode(), EligibilityMode::RelayParentNumber);
+		let eligible = AuthorSlotFilter::eligible_authors(10);
+		assert!(<AuthorSlotFilter as CanAuthor<_, _>>::can_author(&eligible[0], 10, 1));
+
+		AuthorSlotFilter::on_initialize(2);
+		assert_eq!(AuthorSlotFilter::eligibility_mode(), EligibilityMode::Vrf);
+		assert_eq!(AuthorSlotFilter::pending_eligibility_mode(), None);
+		assert!(!<AuthorSlotFilter as CanAuthor<_, _>>::can_author(&eligible[0], 10, 2));
+	});
+}
+
+#[test]
+fn block_cadence_limits_authoring() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		assert_noop!(AuthorSlotFilter::set_block_cadence(Origin::signed(1), 2), BadOrigin);
+		assert_noop!(
+			AuthorSlotFilter::set_block_cadence(Origin::root(), 0),
+			Error::<Test>::ZeroBlockCadence,
+		);
+
+		assert_ok!(AuthorSlotFilter::set_block_cadence(Origin::root(), 2));
+		assert_eq!(AuthorSlotFilter::block_cadence(), 2);
+		System::assert_last_event(AuthorSlotFilterEvent::BlockCadenceSet { cadence: 2 }.into());
+
+		let eligible = AuthorSlotFilter::eligible_authors(10);
+		assert_eq!(eligible.len(), 2);
+		assert!(<AuthorSlotFilter as CanAuthor<_, _>>::can_author(&eligible[0], 10, 1));
+		assert!(AuthorSlotFilter::eligible_authors(11).is_empty());
+		assert!((1..=4).all(|author| !AuthorSlotFilter::can_author(&author, 11)));
+
+		assert_ok!(AuthorSlotFilter::set_eligibility_mode(Origin::root(), EligibilityMode::Vrf));
+		AuthorSlotFilter::on_initialize(2);
+		assert_ok!(AuthorSlotFilter::set_eligible_ratio(
+			Origin::root(),
+			Percent::from_percent(100)
+		));
+		System::deposit_log(vrf_claim(1, 1).to_digest_item());
+		assert!(<AuthorSlotFilter as CanAuthor<_, _>>::can_author(&1, 10, 2));
+		assert!(!<AuthorSlotFilter as CanAuthor<_, _>>::can_author(&1, 11, 2));
+	});
+}
+
+#[test]
+fn authors_without_own_vrf_claim_can_not_author() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AuthorSlotFilter::set_eligibility_mode(Origin::root(), EligibilityMode::Vrf));
+		AuthorSlotFilter::on_initialize(2);
+		assert_ok!(AuthorSlotFilter::set_eligible_ratio(
+			Origin::root(),
+			Percent::from_percent(100)
+		));
+		assert!(!<AuthorSlotFilter as CanAuthor<_, _>>::can_author(&2, 10, 2));
+
+		// The valid claim of author 1 can't be used by author 2.
+		System::deposit_log(vrf_claim(1, 1).to_digest_item());
+		assert!(<AuthorSlotFilter as CanAuthor<_, _>>::can_author(&1, 10, 2));
+		assert!(!<AuthorSlotFilter as CanAuthor<_, _>>::can_author(&2, 10, 2));
+	});
+}
@@ -0,0 +1,199 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Eligibility based on verifiable random functions.
+//!
+//! In [`EligibilityMode::Vrf`](crate::EligibilityMode::Vrf) an author evaluates a VRF over the
+//! storage root of the relay parent with its key. The author is eligible if the output is below a
+//! threshold derived from the [`EligibleRatio`](crate::EligibleRatio), so on average this fraction
+//! of the registered authors is eligible for every relay parent. Unlike the storage root, the
+//! output can only be computed by the author, so nobody else learns in advance who is eligible.
+//!
+//! The author proves its eligibility with a [`VrfClaim`] in a pre-runtime digest of the block,
+//! which is checked by the runtime when the author inherent is applied. The claim has to be made
+//! by the author of the block, so a valid claim can't be reused by other authors. Blocks without
+//! a valid claim are rejected, so a collator creates the digest with [`claim_digest`] before it builds a block, e.g.
+//! through the digests provider of the relay chain consensus. If none of its authors is eligible,
+//! it does not build a block on top of this relay parent and tries again on the next one. The
+//! eligibility is evaluated anew for every relay parent, so no author is stuck on ineligibility.
+
+use codec::{Decode, Encode};
+use cumulus_primitives_core::relay_chain::Hash as RelayChainHash;
+use merlin::Transcript;
+use schnorrkel::vrf::VRFInOut;
+use sp_consensus_vrf::schnorrkel::{VRFOutput, VRFProof};
+use sp_core::sr25519;
+use sp_runtime::{ConsensusEngineId, Digest, DigestItem, Percent, RuntimeDebug};
+
+/// The [`ConsensusEngineId`] of the pre-runtime digest carrying the [`VrfClaim`].
+pub const VRF_ENGINE_ID: ConsensusEngineId = *b"asfv";
+
+/// Label of the VRF transcript.
+const VRF_TRANSCRIPT_LABEL: &[u8] = b"author-slot-filter";
+
+/// Label of the relay parent storage root in the VRF transcript.
+const VRF_STORAGE_ROOT_LABEL: &str = "relay parent storage root";
+
+/// Context used to derive the eligibility value from the VRF output.
+const VRF_INOUT_CONTEXT: &[u8] = b"author-slot-filter-eligibility";
+
+/// Errors of checking a [`VrfClaim`].
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum VrfError {
+	/// The block does not contain a claim.
+	MissingClaim,
+	/// The claiming author is not registered.
+	UnknownAuthor,
+	/// The claiming author has no valid VRF key.
+	InvalidKey,
+	/// The storage root of the relay parent is not known yet.
+	MissingRelayParent,
+	/// The VRF proof is invalid.
+	InvalidProof,
+	/// The VRF output is above the eligibility threshold.
+	NotEligible,
+}
+
+/// Proof of an author that it is eligible for the relay parent of the block.
+#[derive(Clone, Encode, Decode, RuntimeDebug)]
+pub struct VrfClaim<AuthorId> {
+	/// The claiming author.
+	pub author: AuthorId,
+	/// The VRF output.
+	pub output: VRFOutput,
+	/// The VRF proof.
+	pub proof: VRFProof,
+}
+
+impl<AuthorId: Encode + Decode> VrfClaim<AuthorId> {
+	/// Convert the claim into a pre-runtime [`DigestItem`].
+	pub fn to_digest_item(&self) -> DigestItem {
+		DigestItem::PreRuntime(VRF_ENGINE_ID, self.encode())
+	}
+
+	/// Find the claim in the given `digest`.
+	pub fn find(digest: &Digest) -> Option<Self> {
+		digest.logs().iter().find_map(|item| item.pre_runtime_try_to(&VRF_ENGINE_ID))
+	}
+}
+
+/// Create the VRF transcript for the relay parent with the given storage root.
+pub fn make_transcript(relay_parent_storage_root: &RelayChainHash) -> Transcript {
+	let mut transcript = Transcript::new(VRF_TRANSCRIPT_LABEL);
+	transcript
+		.append_message(VRF_STORAGE_ROOT_LABEL.as_bytes(), relay_parent_storage_root.as_ref());
+	transcript
+}
+
+/// Create the VRF transcript data for the keystore, equivalent to [`make_transcript`].
+#[cfg(feature = "std")]
+pub fn make_transcript_data(
+	relay_parent_storage_root: &RelayChainHash,
+) -> sp_keystore::vrf::VRFTranscriptData {
+	use sp_keystore::vrf::{VRFTranscriptData, VRFTranscriptValue};
+
+	VRFTranscriptData {
+		label: VRF_TRANSCRIPT_LABEL,
+		items: vec![(
+			VRF_STORAGE_ROOT_LABEL,
+			VRFTranscriptValue::Bytes(relay_parent_storage_root.as_ref().to_vec()),
+		)],
+	}
+}
+
+/// Returns `true` if the VRF `inout` is below the threshold for the given `ratio`.
+pub fn is_eligible(inout: &VRFInOut, ratio: Percent) -> bool {
+	u128::from_le_bytes(inout.make_bytes::<[u8; 16]>(VRF_INOUT_CONTEXT)) < ratio * u128::MAX
+}
+
+/// Verify the VRF `output` and `proof` of `public` for the given relay parent.
+pub fn verify(
+	public: &sr25519::Public,
+	relay_parent_storage_root: &RelayChainHash,
+	output: &VRFOutput,
+	proof: &VRFProof,
+) -> Result<VRFInOut, VrfError> {
+	let public =
+		schnorrkel::PublicKey::from_bytes(public.as_ref()).map_err(|_| VrfError::InvalidKey)?;
+
+	public
+		.vrf_verify(make_transcript(relay_parent_storage_root), output, proof)
+		.map(|(inout, _)| inout)
+		.map_err(|_| VrfError::InvalidProof)
+}
+
+/// Try to claim eligibility for the relay parent with the given storage root.
+///
+/// Evaluates the VRF with the key `public` of `author` in the `keystore`. Returns `None` if the
+/// key is not in the keystore or the author is not eligible at the given `ratio`.
+#[cfg(feature = "std")]
+pub fn claim_eligibility<AuthorId>(
+	keystore: &sp_keystore::SyncCryptoStorePtr,
+	key_type: sp_core::crypto::KeyTypeId,
+	author: AuthorId,
+	public: &sr25519::Public,
+	relay_parent_storage_root: &RelayChainHash,
+	ratio: Percent,
+) -> Option<VrfClaim<AuthorId>> {
+	let signature = sp_keystore::SyncCryptoStore::sr25519_vrf_sign(
+		&**keystore,
+		key_type,
+		public,
+		make_transcript_data(relay_parent_storage_root),
+	)
+	.ok()
+	.flatten()?;
+
+	let inout = signature
+		.output
+		.attach_input_hash(
+			&schnorrkel::PublicKey::from_bytes(public.as_ref()).ok()?,
+			make_transcript(relay_parent_storage_root),
+		)
+		.ok()?;
+
+	is_eligible(&inout, ratio).then(|| VrfClaim {
+		author,
+		output: VRFOutput(signature.output),
+		proof: VRFProof(signature.proof),
+	})
+}
+
+/// Try to claim eligibility for the relay parent with the given storage root with any of the
+/// `authors` whose keys are in the `keystore`.
+///
+/// Returns the pre-runtime [`DigestItem`] with the [`VrfClaim`] of the first eligible author, or
+/// `None` if none of them is eligible at the given `ratio`.
+#[cfg(feature = "std")]
+pub fn claim_digest<AuthorId: Clone + Encode + Decode>(
+	keystore: &sp_keystore::SyncCryptoStorePtr,
+	key_type: sp_core::crypto::KeyTypeId,
+	authors: &[(AuthorId, sr25519::Public)],
+	relay_parent_storage_root: &RelayChainHash,
+	ratio: Percent,
+) -> Option<DigestItem> {
+	authors.iter().find_map(|(author, public)| {
+		claim_eligibility(
+			keystore,
+			key_type,
+			author.clone(),
+			public,
+			relay_parent_storage_root,
+			ratio,
+		)
+		.map(|claim| claim.to_digest_item())
+	})
+}
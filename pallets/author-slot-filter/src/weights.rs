@@ -0,0 +1,71 @@
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+// Hand-written estimates, to be replaced by weights generated from `benchmarking.rs`.
+pub trait WeightInfo {
+	fn set_authors(a: u32) -> Weight;
+	fn set_eligible_ratio() -> Weight;
+	fn set_eligibility_mode() -> Weight;
+	fn set_block_cadence() -> Weight;
+	fn check_vrf_claim() -> Weight;
+}
+
+pub struct SubstrateWeight<T>(PhantomData<T>);
+
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Writes `Authors`, the duplicate check grows with the number of authors.
+	fn set_authors(a: u32) -> Weight {
+		(8_000_000 as Weight)
+			.saturating_add((150_000 as Weight).saturating_mul(a as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Writes `EligibleRatio`.
+	fn set_eligible_ratio() -> Weight {
+		(6_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Writes `PendingMode`.
+	fn set_eligibility_mode() -> Weight {
+		(6_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Writes `BlockCadence`.
+	fn set_block_cadence() -> Weight {
+		(6_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Reads the digest, `Authors` and `EligibleRatio`, plus one read for the relay parent storage
+	// root of `RelayChainState`, which depends on the runtime. Dominated by the VRF verification.
+	fn check_vrf_claim() -> Weight {
+		(95_000_000 as Weight).saturating_add(T::DbWeight::get().reads(4 as Weight))
+	}
+}
+
+impl WeightInfo for () {
+	// Writes `Authors`, the duplicate check grows with the number of authors.
+	fn set_authors(a: u32) -> Weight {
+		(8_000_000 as Weight)
+			.saturating_add((150_000 as Weight).saturating_mul(a as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	// Writes `EligibleRatio`.
+	fn set_eligible_ratio() -> Weight {
+		(6_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	// Writes `PendingMode`.
+	fn set_eligibility_mode() -> Weight {
+		(6_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	// Writes `BlockCadence`.
+	fn set_block_cadence() -> Weight {
+		(6_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	// Reads the digest, `Authors` and `EligibleRatio`, plus one read for the relay parent storage
+	// root of `RelayChainState`, which depends on the runtime. Dominated by the VRF verification.
+	fn check_vrf_claim() -> Weight {
+		(95_000_000 as Weight).saturating_add(RocksDbWeight::get().reads(4 as Weight))
+	}
+}
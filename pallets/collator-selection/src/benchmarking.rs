@@ -28,6 +28,7 @@ use frame_support::{
 use frame_system::{EventRecord, RawOrigin};
 use pallet_authorship::EventHandler;
 use pallet_session::{self as session, SessionManager};
+use sp_runtime::Perbill;
 use sp_std::prelude::*;
 
 pub type BalanceOf<T> =
@@ -144,6 +145,30 @@ benchmarks! {
 		assert_last_event::<T>(Event::NewCandidacyBond(bond).into());
 	}
 
+	set_inactivity_penalty {
+		let penalty = InactivityPenalty::Slash(Perbill::from_percent(10));
+		let origin = T::UpdateOrigin::successful_origin();
+	}: {
+		assert_ok!(
+			<CollatorSelection<T>>::set_inactivity_penalty(origin, penalty)
+		);
+	}
+	verify {
+		assert_last_event::<T>(Event::NewInactivityPenalty(penalty).into());
+	}
+
+	set_grace_period {
+		let grace_period = T::KickThreshold::get();
+		let origin = T::UpdateOrigin::successful_origin();
+	}: {
+		assert_ok!(
+			<CollatorSelection<T>>::set_grace_period(origin, grace_period)
+		);
+	}
+	verify {
+		assert_last_event::<T>(Event::NewGracePeriod(grace_period).into());
+	}
+
 	// worse case is when we have all the max-candidate slots filled except one, and we fill that
 	// one.
 	register_as_candidate {
@@ -214,6 +239,13 @@ benchmarks! {
 		<DesiredCandidates<T>>::put(c);
 		frame_system::Pallet::<T>::set_block_number(0u32.into());
 
+		// Slashing the whole bond into the pot and then removing the candidate is the worst case.
+		<CandidateInactivityPenalty<T>>::put(InactivityPenalty::Slash(Perbill::one()));
+		T::Currency::make_free_balance_be(
+			&<CollatorSelection<T>>::account_id(),
+			T::Currency::minimum_balance(),
+		);
+
 		register_validators::<T>(c);
 		register_candidates::<T>(c);
 
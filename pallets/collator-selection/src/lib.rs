@@ -44,6 +44,19 @@
 //! Candidates will not be allowed to get kicked or leave_intent if the total number of candidates
 //! fall below MinCandidates. This is for potential disaster recovery scenarios.
 //!
+//! ### Inactivity
+//!
+//! Candidates that did not author a block within `KickThreshold` blocks are penalized on the next
+//! session change according to the [`CandidateInactivityPenalty`]. They are either demoted, i.e.
+//! removed from the candidates with their bond returned, or a fraction of their bond is slashed
+//! into the Pot and they get another `KickThreshold` blocks to author. Slashed candidates whose
+//! remaining bond falls below the [`CandidacyBond`] are removed with the remaining bond returned.
+//!
+//! New candidates are only taken into the collator set with a delay of one session. They are
+//! granted `KickThreshold` blocks plus the [`GracePeriod`] before their first block is due.
+//!
+//! The number of blocks authored by every collator is tracked in [`AuthoredBlocks`].
+//!
 //! ### Rewards
 //!
 //! The Collator Selection pallet maintains an on-chain account (the "Pot"). In each block, the
@@ -82,11 +95,11 @@ pub mod pallet {
 		pallet_prelude::*,
 		sp_runtime::{
 			traits::{AccountIdConversion, CheckedSub, Saturating, Zero},
-			RuntimeDebug,
+			Perbill, RuntimeDebug,
 		},
 		traits::{
-			Currency, EnsureOrigin, ExistenceRequirement::KeepAlive, ReservableCurrency,
-			ValidatorRegistration,
+			BalanceStatus, Currency, EnsureOrigin, ExistenceRequirement::KeepAlive,
+			ReservableCurrency, ValidatorRegistration,
 		},
 		weights::DispatchClass,
 		PalletId,
@@ -166,6 +179,29 @@ pub mod pallet {
 		pub deposit: Balance,
 	}
 
+	/// How candidates that did not author a block within the kick threshold are penalized.
+	#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug, scale_info::TypeInfo)]
+	pub enum InactivityPenalty {
+		/// Remove the candidate and return its bond.
+		///
+		/// Candidates are not removed if the number of candidates would fall below
+		/// `MinCandidates`.
+		Demote,
+		/// Move the given fraction of the bond of the candidate into the Pot.
+		///
+		/// The candidate stays a candidate and is penalized again if it does not author a block
+		/// within the next kick threshold. It is removed with the remaining bond returned once
+		/// that falls below the `CandidacyBond`, unless the number of candidates would fall below
+		/// `MinCandidates`.
+		Slash(Perbill),
+	}
+
+	impl Default for InactivityPenalty {
+		fn default() -> Self {
+			Self::Demote
+		}
+	}
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
 	#[pallet::without_storage_info]
@@ -188,6 +224,13 @@ pub mod pallet {
 	pub type LastAuthoredBlock<T: Config> =
 		StorageMap<_, Twox64Concat, T::AccountId, T::BlockNumber, ValueQuery>;
 
+	/// Number of blocks authored by collator.
+	///
+	/// Reset when a candidate is removed.
+	#[pallet::storage]
+	#[pallet::getter(fn authored_blocks)]
+	pub type AuthoredBlocks<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, u32, ValueQuery>;
+
 	/// Desired number of candidates.
 	///
 	/// This should ideally always be less than [`Config::MaxCandidates`] for weights to be correct.
@@ -202,6 +245,17 @@ pub mod pallet {
 	#[pallet::getter(fn candidacy_bond)]
 	pub type CandidacyBond<T> = StorageValue<_, BalanceOf<T>, ValueQuery>;
 
+	/// Penalty for candidates that did not author a block within the kick threshold.
+	#[pallet::storage]
+	#[pallet::getter(fn inactivity_penalty)]
+	pub type CandidateInactivityPenalty<T> = StorageValue<_, InactivityPenalty, ValueQuery>;
+
+	/// Number of blocks new candidates are granted on top of the kick threshold before they are
+	/// penalized for inactivity.
+	#[pallet::storage]
+	#[pallet::getter(fn grace_period)]
+	pub type GracePeriod<T: Config> = StorageValue<_, T::BlockNumber, ValueQuery>;
+
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
 		pub invulnerables: Vec<T::AccountId>,
@@ -253,6 +307,9 @@ pub mod pallet {
 		NewCandidacyBond(BalanceOf<T>),
 		CandidateAdded(T::AccountId, BalanceOf<T>),
 		CandidateRemoved(T::AccountId),
+		NewInactivityPenalty(InactivityPenalty),
+		NewGracePeriod(T::BlockNumber),
+		CandidateSlashed(T::AccountId, BalanceOf<T>),
 	}
 
 	// Errors inform users that something went wrong.
@@ -342,6 +399,30 @@ pub mod pallet {
 			Ok(().into())
 		}
 
+		/// Set the penalty for candidates that did not author a block within the kick threshold.
+		#[pallet::weight(T::WeightInfo::set_inactivity_penalty())]
+		pub fn set_inactivity_penalty(
+			origin: OriginFor<T>,
+			penalty: InactivityPenalty,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			<CandidateInactivityPenalty<T>>::put(&penalty);
+			Self::deposit_event(Event::NewInactivityPenalty(penalty));
+			Ok(().into())
+		}
+
+		/// Set the number of blocks new candidates are granted on top of the kick threshold.
+		#[pallet::weight(T::WeightInfo::set_grace_period())]
+		pub fn set_grace_period(
+			origin: OriginFor<T>,
+			grace_period: T::BlockNumber,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			<GracePeriod<T>>::put(&grace_period);
+			Self::deposit_event(Event::NewGracePeriod(grace_period));
+			Ok(().into())
+		}
+
 		/// Register this account as a collator candidate. The account must (a) already have
 		/// registered session keys and (b) be able to reserve the `CandidacyBond`.
 		///
@@ -363,7 +444,8 @@ pub mod pallet {
 			);
 
 			let deposit = Self::candidacy_bond();
-			// First authored block is current block plus kick threshold to handle session delay
+			// First authored block is current block plus kick threshold to handle session delay,
+			// plus the grace period for new candidates
 			let incoming = CandidateInfo { who: who.clone(), deposit };
 
 			let current_count =
@@ -375,7 +457,8 @@ pub mod pallet {
 						candidates.push(incoming);
 						<LastAuthoredBlock<T>>::insert(
 							who.clone(),
-							frame_system::Pallet::<T>::block_number() + T::KickThreshold::get(),
+							frame_system::Pallet::<T>::block_number() +
+								T::KickThreshold::get() + Self::grace_period(),
 						);
 						Ok(candidates.len())
 					}
@@ -421,6 +504,7 @@ pub mod pallet {
 					let candidate = candidates.remove(index);
 					T::Currency::unreserve(who, candidate.deposit);
 					<LastAuthoredBlock<T>>::remove(who.clone());
+					<AuthoredBlocks<T>>::remove(who);
 					Ok(candidates.len())
 				})?;
 			Self::deposit_event(Event::CandidateRemoved(who.clone()));
@@ -436,33 +520,75 @@ pub mod pallet {
 			collators
 		}
 
-		/// Kicks out candidates that did not produce a block in the kick threshold
-		/// and refund their deposits.
+		/// Penalizes candidates that did not produce a block in the kick threshold according to
+		/// the [`CandidateInactivityPenalty`].
+		///
+		/// Returns the remaining candidates.
 		pub fn kick_stale_candidates(
 			candidates: Vec<CandidateInfo<T::AccountId, BalanceOf<T>>>,
 		) -> Vec<T::AccountId> {
 			let now = frame_system::Pallet::<T>::block_number();
 			let kick_threshold = T::KickThreshold::get();
+			let penalty = Self::inactivity_penalty();
 			candidates
 				.into_iter()
 				.filter_map(|c| {
 					let last_block = <LastAuthoredBlock<T>>::get(c.who.clone());
 					let since_last = now.saturating_sub(last_block);
-					if since_last < kick_threshold ||
-						Self::candidates().len() as u32 <= T::MinCandidates::get()
-					{
-						Some(c.who)
-					} else {
-						let outcome = Self::try_remove_candidate(&c.who);
-						if let Err(why) = outcome {
-							log::warn!("Failed to remove candidate {:?}", why);
-							debug_assert!(false, "failed to remove candidate {:?}", why);
-						}
-						None
+					if since_last < kick_threshold {
+						return Some(c.who)
+					}
+
+					let demote = match penalty {
+						InactivityPenalty::Slash(fraction) =>
+							Self::slash_candidate(&c.who, fraction) < Self::candidacy_bond(),
+						InactivityPenalty::Demote => true,
+					};
+					if !demote || Self::candidates().len() as u32 <= T::MinCandidates::get() {
+						return Some(c.who)
 					}
+
+					let outcome = Self::try_remove_candidate(&c.who);
+					if let Err(why) = outcome {
+						log::warn!("Failed to remove candidate {:?}", why);
+						debug_assert!(false, "failed to remove candidate {:?}", why);
+					}
+					None
 				})
 				.collect()
 		}
+
+		/// Moves `fraction` of the bond of candidate `who` into the Pot and starts a new kick
+		/// threshold for it.
+		///
+		/// Returns the remaining bond of the candidate.
+		fn slash_candidate(who: &T::AccountId, fraction: Perbill) -> BalanceOf<T> {
+			let deposit = <Candidates<T>>::mutate(|candidates| {
+				let candidate = match candidates.iter_mut().find(|c| c.who == *who) {
+					Some(candidate) => candidate,
+					None => return Zero::zero(),
+				};
+
+				let amount = fraction * candidate.deposit;
+				match T::Currency::repatriate_reserved(
+					who,
+					&Self::account_id(),
+					amount,
+					BalanceStatus::Free,
+				) {
+					Ok(remaining) => {
+						let slashed = amount.saturating_sub(remaining);
+						candidate.deposit = candidate.deposit.saturating_sub(slashed);
+						Self::deposit_event(Event::CandidateSlashed(who.clone(), slashed));
+					},
+					// The pot does not exist, i.e. no ED was transferred to it.
+					Err(why) => log::warn!("Failed to slash candidate {:?}", why),
+				}
+				candidate.deposit
+			});
+			<LastAuthoredBlock<T>>::insert(who.clone(), frame_system::Pallet::<T>::block_number());
+			deposit
+		}
 	}
 
 	/// Keep track of number of authored blocks per authority, uncles are counted as well since
@@ -480,6 +606,7 @@ pub mod pallet {
 			// `reward` is half of pot account minus ED, this should never fail.
 			let _success = T::Currency::transfer(&pot, &author, reward, KeepAlive);
 			debug_assert!(_success.is_ok());
+			<AuthoredBlocks<T>>::mutate(&author, |count| *count = count.saturating_add(1));
 			<LastAuthoredBlock<T>>::insert(author, frame_system::Pallet::<T>::block_number());
 
 			frame_system::Pallet::<T>::register_extra_weight_unchecked(
@@ -14,13 +14,13 @@
 // limitations under the License.
 
 use crate as collator_selection;
-use crate::{mock::*, CandidateInfo, Error};
+use crate::{mock::*, CandidateInfo, Error, InactivityPenalty};
 use frame_support::{
 	assert_noop, assert_ok,
 	traits::{Currency, GenesisBuild, OnInitialize},
 };
 use pallet_balances::Error as BalancesError;
-use sp_runtime::traits::BadOrigin;
+use sp_runtime::{traits::BadOrigin, Perbill};
 
 #[test]
 fn basic_setup_works() {
@@ -374,6 +374,120 @@ fn should_not_kick_mechanism_too_few() {
 	});
 }
 
+#[test]
+fn set_inactivity_parameters() {
+	new_test_ext().execute_with(|| {
+		// given
+		assert_eq!(CollatorSelection::inactivity_penalty(), InactivityPenalty::Demote);
+		assert_eq!(CollatorSelection::grace_period(), 0);
+
+		// can set
+		let penalty = InactivityPenalty::Slash(Perbill::from_percent(10));
+		assert_ok!(CollatorSelection::set_inactivity_penalty(
+			Origin::signed(RootAccount::get()),
+			penalty
+		));
+		assert_eq!(CollatorSelection::inactivity_penalty(), penalty);
+		assert_ok!(CollatorSelection::set_grace_period(Origin::signed(RootAccount::get()), 5));
+		assert_eq!(CollatorSelection::grace_period(), 5);
+
+		// rejects bad origin.
+		assert_noop!(
+			CollatorSelection::set_inactivity_penalty(Origin::signed(1), InactivityPenalty::Demote),
+			BadOrigin
+		);
+		assert_noop!(CollatorSelection::set_grace_period(Origin::signed(1), 0), BadOrigin);
+	});
+}
+
+#[test]
+fn slash_mechanism() {
+	new_test_ext().execute_with(|| {
+		// the slashed bond goes to the pot, which needs an ED.
+		Balances::make_free_balance_be(&CollatorSelection::account_id(), 5);
+		assert_ok!(CollatorSelection::set_inactivity_penalty(
+			Origin::signed(RootAccount::get()),
+			InactivityPenalty::Slash(Perbill::from_percent(50))
+		));
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(3)));
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(4)));
+		// the remaining bond has to cover the candidacy bond.
+		assert_ok!(CollatorSelection::set_candidacy_bond(Origin::signed(RootAccount::get()), 5));
+		initialize_to_block(20);
+		// 3 did not author and is slashed, but stays a candidate
+		assert_eq!(
+			CollatorSelection::candidates(),
+			vec![CandidateInfo { who: 3, deposit: 5 }, CandidateInfo { who: 4, deposit: 10 }]
+		);
+		assert_eq!(Balances::reserved_balance(3), 5);
+		assert_eq!(Balances::free_balance(3), 90);
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event ==
+				Event::CollatorSelection(crate::Event::CandidateSlashed(3, 5))));
+		// and gets another kick threshold to author
+		assert_eq!(CollatorSelection::last_authored_block(3), 20);
+		initialize_to_block(30);
+		assert_eq!(SessionHandlerCollators::get(), vec![1, 2, 3, 4]);
+	});
+}
+
+#[test]
+fn slash_below_candidacy_bond_removes_candidate() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&CollatorSelection::account_id(), 5);
+		assert_ok!(CollatorSelection::set_inactivity_penalty(
+			Origin::signed(RootAccount::get()),
+			InactivityPenalty::Slash(Perbill::from_percent(50))
+		));
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(3)));
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(4)));
+		initialize_to_block(20);
+		// 3 is slashed below the candidacy bond and removed with the remaining bond returned
+		assert_eq!(CollatorSelection::candidates(), vec![CandidateInfo { who: 4, deposit: 10 }]);
+		assert_eq!(Balances::reserved_balance(3), 0);
+		assert_eq!(Balances::free_balance(3), 95);
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event ==
+				Event::CollatorSelection(crate::Event::CandidateRemoved(3))));
+		initialize_to_block(30);
+		assert_eq!(SessionHandlerCollators::get(), vec![1, 2, 4]);
+	});
+}
+
+#[test]
+fn grace_period_delays_kick() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(CollatorSelection::set_grace_period(Origin::signed(RootAccount::get()), 10));
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(3)));
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(4)));
+		assert_eq!(CollatorSelection::last_authored_block(3), 20);
+		initialize_to_block(20);
+		// 3 is still in its grace period
+		assert_eq!(CollatorSelection::candidates().len(), 2);
+		initialize_to_block(30);
+		// 3 was kicked
+		assert_eq!(CollatorSelection::candidates(), vec![CandidateInfo { who: 4, deposit: 10 }]);
+		assert_eq!(Balances::free_balance(3), 100);
+	});
+}
+
+#[test]
+fn authored_blocks_are_counted() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(3)));
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(4)));
+		// 4 authors every block
+		initialize_to_block(3);
+		assert_eq!(CollatorSelection::authored_blocks(4), 3);
+		assert_eq!(CollatorSelection::authored_blocks(3), 0);
+		// the count is reset when leaving
+		assert_ok!(CollatorSelection::leave_intent(Origin::signed(4)));
+		assert_eq!(CollatorSelection::authored_blocks(4), 0);
+	});
+}
+
 #[test]
 #[should_panic = "duplicate invulnerables in genesis."]
 fn cannot_set_genesis_value_twice() {
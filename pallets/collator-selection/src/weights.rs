@@ -29,6 +29,8 @@ pub trait WeightInfo {
 	fn set_invulnerables(_b: u32) -> Weight;
 	fn set_desired_candidates() -> Weight;
 	fn set_candidacy_bond() -> Weight;
+	fn set_inactivity_penalty() -> Weight;
+	fn set_grace_period() -> Weight;
 	fn register_as_candidate(_c: u32) -> Weight;
 	fn leave_intent(_c: u32) -> Weight;
 	fn note_author() -> Weight;
@@ -50,6 +52,14 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	fn set_candidacy_bond() -> Weight {
 		(16_840_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
 	}
+	// Not benchmarked yet. `set_inactivity_penalty` and `set_grace_period` write a single value
+	// like `set_candidacy_bond`, so they use its weight until generated from their benchmarks.
+	fn set_inactivity_penalty() -> Weight {
+		(16_840_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn set_grace_period() -> Weight {
+		(16_840_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
 	fn register_as_candidate(c: u32) -> Weight {
 		(71_196_000 as Weight)
 			// Standard Error: 0
@@ -96,6 +106,14 @@ impl WeightInfo for () {
 	fn set_candidacy_bond() -> Weight {
 		(16_840_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
 	}
+	// Not benchmarked yet. `set_inactivity_penalty` and `set_grace_period` write a single value
+	// like `set_candidacy_bond`, so they use its weight until generated from their benchmarks.
+	fn set_inactivity_penalty() -> Weight {
+		(16_840_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn set_grace_period() -> Weight {
+		(16_840_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
 	fn register_as_candidate(c: u32) -> Weight {
 		(71_196_000 as Weight)
 			// Standard Error: 0
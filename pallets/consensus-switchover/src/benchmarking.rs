@@ -0,0 +1,35 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarking setup for cumulus-pallet-consensus-switchover
+
+use super::*;
+
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+use frame_support::traits::EnsureOrigin;
+use sp_runtime::traits::One;
+
+benchmarks! {
+	schedule_switchover {
+		let origin = T::UpdateOrigin::successful_origin();
+		let block = frame_system::Pallet::<T>::block_number() + One::one();
+	}: _<T::Origin>(origin, block, Default::default())
+	verify {
+		assert_eq!(SwitchoverBlock::<T>::get(), Some(block));
+	}
+}
+
+impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(None), crate::mock::Test);
@@ -0,0 +1,234 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Consensus Switchover pallet.
+//!
+//! Switches the block validation of a parachain to a new consensus at a given block.
+//!
+//! ## Overview
+//!
+//! A live parachain can migrate from one consensus to another, e.g. from the relay chain
+//! consensus to Aura. The nodes and the validation of the blocks on the relay chain have to
+//! switch at the same block, otherwise the candidates around the switchover are rejected.
+//!
+//! The switchover is scheduled by [`Config::UpdateOrigin`] with [`Pallet::schedule_switchover`],
+//! together with the parameters of the new consensus, e.g. the eligible ratio of an author
+//! filter. The pallet stores the block in [`SwitchoverBlock`] and the parameters in
+//! [`SwitchoverParams`]. The parameters are handed to [`Config::OnSwitchover`] when the block is
+//! initialized. The switchover can be rescheduled until it is reached.
+//!
+//! The [`BlockExecutor`] executes all blocks before the switchover block with the block executor
+//! of the old consensus and all blocks from it on with the block executor of the new consensus:
+//!
+//! ```ignore
+//! cumulus_pallet_parachain_system::register_validate_block! {
+//!     Runtime = Runtime,
+//!     BlockExecutor = cumulus_pallet_consensus_switchover::BlockExecutor::<
+//!         Runtime,
+//!         Executive,
+//!         cumulus_pallet_aura_ext::BlockExecutor::<Runtime, Executive>,
+//!     >,
+//!     CheckInherents = CheckInherents,
+//! }
+//! ```
+//!
+//! The nodes read the switchover block through the [`ConsensusSwitchoverApi`] runtime api at the
+//! parent of the block they import or author, so they always agree with the validation.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{traits::ExecuteBlock, weights::Weight};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor};
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+
+/// Applies the parameters of the new consensus.
+pub trait OnSwitchover<Params> {
+	/// Called in `on_initialize` of the switchover block. Returns the consumed weight.
+	fn on_switchover(params: Params) -> Weight;
+}
+
+impl<Params> OnSwitchover<Params> for () {
+	fn on_switchover(_: Params) -> Weight {
+		0
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	pub use crate::weights::WeightInfo;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin that can schedule the switchover.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The parameters of the new consensus.
+		type SwitchoverParams: Member + Parameter + MaxEncodedLen + Default;
+
+		/// Applies the [`Config::SwitchoverParams`] at the switchover block.
+		type OnSwitchover: OnSwitchover<Self::SwitchoverParams>;
+
+		/// The weight information of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The first block that is validated with the new consensus.
+	///
+	/// If `None`, all blocks are validated with the old consensus.
+	#[pallet::storage]
+	#[pallet::getter(fn switchover_block)]
+	pub type SwitchoverBlock<T: Config> = StorageValue<_, T::BlockNumber, OptionQuery>;
+
+	/// The parameters of the new consensus, applied at the [`SwitchoverBlock`].
+	#[pallet::storage]
+	#[pallet::getter(fn switchover_params)]
+	pub type SwitchoverParams<T: Config> = StorageValue<_, T::SwitchoverParams, OptionQuery>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub switchover_block: Option<T::BlockNumber>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { switchover_block: None }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			SwitchoverBlock::<T>::set(self.switchover_block);
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The switchover was scheduled.
+		SwitchoverScheduled { block: T::BlockNumber, params: T::SwitchoverParams },
+		/// The switchover block was reached and the parameters were applied.
+		SwitchedOver { block: T::BlockNumber },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The switchover block is not in the future.
+		SwitchoverNotInFuture,
+		/// The switchover block was already reached and can not be changed anymore.
+		AlreadySwitched,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			let mut weight = T::DbWeight::get().reads(1);
+			if SwitchoverBlock::<T>::get() == Some(now) {
+				let params = SwitchoverParams::<T>::take().unwrap_or_default();
+				weight = weight
+					.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+					.saturating_add(T::OnSwitchover::on_switchover(params));
+				Self::deposit_event(Event::SwitchedOver { block: now });
+			}
+			weight
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Schedule the switchover to the new consensus at `block` with the given parameters.
+		///
+		/// `block` has to be in the future. Replaces a switchover that was not reached yet and
+		/// fails once it was reached.
+		#[pallet::weight(T::WeightInfo::schedule_switchover())]
+		pub fn schedule_switchover(
+			origin: OriginFor<T>,
+			block: T::BlockNumber,
+			params: T::SwitchoverParams,
+		) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(!Self::is_switched(now), Error::<T>::AlreadySwitched);
+			ensure!(block > now, Error::<T>::SwitchoverNotInFuture);
+
+			SwitchoverBlock::<T>::put(block);
+			SwitchoverParams::<T>::put(params.clone());
+
+			Self::deposit_event(Event::SwitchoverScheduled { block, params });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Returns `true` if the block `number` is validated with the new consensus.
+		pub fn is_switched(number: T::BlockNumber) -> bool {
+			SwitchoverBlock::<T>::get().map_or(false, |switchover| number >= switchover)
+		}
+	}
+}
+
+/// The block executor used when validating a PoV at the relay chain.
+///
+/// Executes the blocks before the [`SwitchoverBlock`] with `Old` and all other blocks with `New`.
+pub struct BlockExecutor<T, Old, New>(sp_std::marker::PhantomData<(T, Old, New)>);
+
+impl<Block, T, Old, New> ExecuteBlock<Block> for BlockExecutor<T, Old, New>
+where
+	Block: BlockT,
+	T: Config<BlockNumber = NumberFor<Block>>,
+	Old: ExecuteBlock<Block>,
+	New: ExecuteBlock<Block>,
+{
+	fn execute_block(block: Block) {
+		if Pallet::<T>::is_switched(*block.header().number()) {
+			New::execute_block(block)
+		} else {
+			Old::execute_block(block)
+		}
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// Runtime api to query the consensus switchover.
+	pub trait ConsensusSwitchoverApi<BlockNumber: codec::Codec> {
+		/// Returns the first block that is validated with the new consensus.
+		fn switchover_block() -> Option<BlockNumber>;
+	}
+}
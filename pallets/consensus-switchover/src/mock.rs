@@ -0,0 +1,129 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use crate as consensus_switchover;
+use frame_support::{
+	parameter_types,
+	traits::{ConstU32, Everything, GenesisBuild},
+};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+pub type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		ConsensusSwitchover: consensus_switchover::{Pallet, Call, Storage, Config<T>, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub storage OldBlocks: Vec<u64> = Vec::new();
+	pub storage NewBlocks: Vec<u64> = Vec::new();
+	pub storage AppliedParams: Vec<u32> = Vec::new();
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+/// Records the applied parameters in [`AppliedParams`].
+pub struct MockOnSwitchover;
+impl OnSwitchover<u32> for MockOnSwitchover {
+	fn on_switchover(params: u32) -> Weight {
+		let mut applied = AppliedParams::get();
+		applied.push(params);
+		AppliedParams::set(&applied);
+		0
+	}
+}
+
+impl Config for Test {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<u64>;
+	type SwitchoverParams = u32;
+	type OnSwitchover = MockOnSwitchover;
+	type WeightInfo = ();
+}
+
+/// Records the executed blocks in [`OldBlocks`].
+pub struct OldExecutor;
+impl ExecuteBlock<Block> for OldExecutor {
+	fn execute_block(block: Block) {
+		let mut blocks = OldBlocks::get();
+		blocks.push(block.header.number);
+		OldBlocks::set(&blocks);
+	}
+}
+
+/// Records the executed blocks in [`NewBlocks`].
+pub struct NewExecutor;
+impl ExecuteBlock<Block> for NewExecutor {
+	fn execute_block(block: Block) {
+		let mut blocks = NewBlocks::get();
+		blocks.push(block.header.number);
+		NewBlocks::set(&blocks);
+	}
+}
+
+pub type Executor = BlockExecutor<Test, OldExecutor, NewExecutor>;
+
+pub fn new_test_ext(switchover_block: Option<u64>) -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+	consensus_switchover::GenesisConfig::<Test> { switchover_block }
+		.assimilate_storage(&mut t)
+		.unwrap();
+
+	let mut ext: sp_io::TestExternalities = t.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,115 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{mock::*, Error, Event as ConsensusSwitchoverEvent};
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{ExecuteBlock, Hooks},
+};
+use sp_runtime::{testing::Header, traits::BadOrigin};
+
+fn block(number: u64) -> Block {
+	Block { header: Header::new_from_number(number), extrinsics: Vec::new() }
+}
+
+#[test]
+fn blocks_are_executed_with_the_consensus_of_their_number() {
+	new_test_ext(Some(10)).execute_with(|| {
+		assert_eq!(ConsensusSwitchover::switchover_block(), Some(10));
+		assert!(!ConsensusSwitchover::is_switched(9));
+		assert!(ConsensusSwitchover::is_switched(10));
+
+		for number in [8, 9, 10, 11] {
+			Executor::execute_block(block(number));
+		}
+
+		assert_eq!(OldBlocks::get(), vec![8, 9]);
+		assert_eq!(NewBlocks::get(), vec![10, 11]);
+	});
+}
+
+#[test]
+fn all_blocks_use_the_old_consensus_without_switchover() {
+	new_test_ext(None).execute_with(|| {
+		assert!(!ConsensusSwitchover::is_switched(u64::MAX));
+
+		Executor::execute_block(block(100));
+		assert_eq!(OldBlocks::get(), vec![100]);
+		assert!(NewBlocks::get().is_empty());
+	});
+}
+
+#[test]
+fn schedule_switchover_works() {
+	new_test_ext(None).execute_with(|| {
+		assert_noop!(ConsensusSwitchover::schedule_switchover(Origin::signed(1), 5, 7), BadOrigin);
+
+		assert_ok!(ConsensusSwitchover::schedule_switchover(Origin::root(), 5, 7));
+		assert_eq!(ConsensusSwitchover::switchover_block(), Some(5));
+		assert_eq!(ConsensusSwitchover::switchover_params(), Some(7));
+		assert!(!ConsensusSwitchover::is_switched(4));
+		assert!(ConsensusSwitchover::is_switched(5));
+		System::assert_last_event(
+			ConsensusSwitchoverEvent::SwitchoverScheduled { block: 5, params: 7 }.into(),
+		);
+
+		// It can be rescheduled before it is reached.
+		assert_ok!(ConsensusSwitchover::schedule_switchover(Origin::root(), 6, 8));
+		assert_eq!(ConsensusSwitchover::switchover_block(), Some(6));
+		assert_eq!(ConsensusSwitchover::switchover_params(), Some(8));
+	});
+}
+
+#[test]
+fn switchover_must_be_in_the_future_and_not_reached() {
+	new_test_ext(None).execute_with(|| {
+		assert_noop!(
+			ConsensusSwitchover::schedule_switchover(Origin::root(), 0, 7),
+			Error::<Test>::SwitchoverNotInFuture,
+		);
+		assert_noop!(
+			ConsensusSwitchover::schedule_switchover(Origin::root(), 1, 7),
+			Error::<Test>::SwitchoverNotInFuture,
+		);
+	});
+
+	new_test_ext(Some(10)).execute_with(|| {
+		System::set_block_number(10);
+		assert_noop!(
+			ConsensusSwitchover::schedule_switchover(Origin::root(), 20, 7),
+			Error::<Test>::AlreadySwitched,
+		);
+	});
+}
+
+#[test]
+fn params_are_applied_at_the_switchover_block() {
+	new_test_ext(None).execute_with(|| {
+		assert_ok!(ConsensusSwitchover::schedule_switchover(Origin::root(), 3, 7));
+
+		ConsensusSwitchover::on_initialize(2);
+		assert!(AppliedParams::get().is_empty());
+
+		System::set_block_number(3);
+		ConsensusSwitchover::on_initialize(3);
+		assert_eq!(AppliedParams::get(), vec![7]);
+		assert_eq!(ConsensusSwitchover::switchover_params(), None);
+		System::assert_last_event(ConsensusSwitchoverEvent::SwitchedOver { block: 3 }.into());
+
+		ConsensusSwitchover::on_initialize(4);
+		assert_eq!(AppliedParams::get(), vec![7]);
+	});
+}
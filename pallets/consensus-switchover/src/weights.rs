@@ -0,0 +1,33 @@
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+// Hand-written estimates, to be replaced by weights generated from `benchmarking.rs`.
+pub trait WeightInfo {
+	fn schedule_switchover() -> Weight;
+}
+
+pub struct SubstrateWeight<T>(PhantomData<T>);
+
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Reads `SwitchoverBlock` and writes it and `SwitchoverParams`.
+	fn schedule_switchover() -> Weight {
+		(15_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+}
+
+impl WeightInfo for () {
+	// Reads `SwitchoverBlock` and writes it and `SwitchoverParams`.
+	fn schedule_switchover() -> Weight {
+		(15_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+}
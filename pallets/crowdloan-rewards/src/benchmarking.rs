@@ -0,0 +1,139 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarking setup for pallet-crowdloan-rewards
+
+use super::*;
+
+use frame_benchmarking::{account, benchmarks, impl_benchmark_test_suite, whitelisted_caller};
+use frame_support::traits::{EnsureOrigin, Get};
+use frame_system::RawOrigin;
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::traits::Bounded;
+
+/// Key type of the relay chain account that signs in the benchmarks.
+const BENCHMARK_KEY_TYPE: KeyTypeId = KeyTypeId(*b"crwd");
+
+/// The reward of every contributor.
+fn reward<T: Config>() -> BalanceOf<T> {
+	T::Currency::minimum_balance().saturating_mul(100u32.into())
+}
+
+/// Fund the account of the pallet, so that it can pay all rewards.
+fn fund_pallet<T: Config>() {
+	T::Currency::make_free_balance_be(
+		&Pallet::<T>::account_id(),
+		BalanceOf::<T>::max_value() / 2u32.into(),
+	);
+}
+
+benchmarks! {
+	initialize_reward_vec {
+		let x in 1 .. T::MaxInitContributors::get();
+
+		// Worst case: every reward is associated with a native account right away.
+		let rewards = (0..x)
+			.map(|i| {
+				(
+					account::<T::RelayChainAccountId>("relay", i, 0),
+					Some(account::<T::AccountId>("native", i, 0)),
+					reward::<T>(),
+				)
+			})
+			.collect::<Vec<_>>();
+		let origin = T::InitializationOrigin::successful_origin();
+	}: _<T::Origin>(origin, rewards)
+	verify {
+		assert_eq!(
+			AccountsPayable::<T>::get(account::<T::AccountId>("native", 0, 0))
+				.map(|info| info.total_reward),
+			Some(reward::<T>()),
+		);
+	}
+
+	complete_initialization {
+		fund_pallet::<T>();
+		InitializedRewardAmount::<T>::put(reward::<T>());
+		let lease_ending_block = T::VestingBlockProvider::current_block_number() + 100;
+		let origin = T::InitializationOrigin::successful_origin();
+	}: _<T::Origin>(origin, lease_ending_block)
+	verify {
+		assert!(Initialized::<T>::get());
+	}
+
+	associate_native_identity {
+		let caller: T::AccountId = whitelisted_caller();
+		let public = sp_io::crypto::sr25519_generate(BENCHMARK_KEY_TYPE, None);
+		let relay_account = T::RelayChainAccountId::decode(&mut &public.0[..])
+			.expect("The relay chain account is 32 bytes");
+		UnassociatedContributions::<T>::insert(&relay_account, reward::<T>());
+
+		let signature = sp_io::crypto::sr25519_sign(
+			BENCHMARK_KEY_TYPE,
+			&public,
+			&Pallet::<T>::association_payload(&caller),
+		)
+		.expect("The key was just generated");
+
+		// Worst case: the caller has a reward already.
+		AccountsPayable::<T>::insert(
+			&caller,
+			RewardInfo { total_reward: reward::<T>(), claimed_reward: Zero::zero() },
+		);
+	}: _(RawOrigin::Signed(caller.clone()), relay_account, signature.into())
+	verify {
+		assert_eq!(
+			AccountsPayable::<T>::get(&caller).map(|info| info.total_reward),
+			Some(reward::<T>().saturating_mul(2u32.into())),
+		);
+	}
+
+	claim {
+		let caller: T::AccountId = whitelisted_caller();
+		fund_pallet::<T>();
+		AccountsPayable::<T>::insert(
+			&caller,
+			RewardInfo { total_reward: reward::<T>(), claimed_reward: Zero::zero() },
+		);
+
+		// The vesting computation is negligible next to the transfer, so the reward is fully
+		// vested to not depend on the relay chain block.
+		let now = T::VestingBlockProvider::current_block_number();
+		InitVestingBlock::<T>::put(now);
+		EndVestingBlock::<T>::put(now);
+		Initialized::<T>::put(true);
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert_eq!(
+			AccountsPayable::<T>::get(&caller).map(|info| info.claimed_reward),
+			Some(reward::<T>()),
+		);
+	}
+
+	update_reward_address {
+		let caller: T::AccountId = whitelisted_caller();
+		let new_account = account::<T::AccountId>("new", 0, 0);
+		AccountsPayable::<T>::insert(
+			&caller,
+			RewardInfo { total_reward: reward::<T>(), claimed_reward: Zero::zero() },
+		);
+	}: _(RawOrigin::Signed(caller), new_account.clone())
+	verify {
+		assert!(AccountsPayable::<T>::contains_key(&new_account));
+	}
+}
+
+impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
@@ -0,0 +1,406 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Crowdloan Rewards pallet.
+//!
+//! Vested rewards for the relay chain crowdloan contributors of a parachain.
+//!
+//! ## Overview
+//!
+//! The rewards of the contributors are initialized by a trusted origin, e.g. from a snapshot of
+//! the crowdloan on the relay chain, with [`Pallet::initialize_reward_vec`] and the initialization
+//! is finished with [`Pallet::complete_initialization`]. The rewards are paid from the account of
+//! [`Config::PalletId`], which has to hold enough funds to pay all rewards.
+//!
+//! Every reward is owned by the relay chain account that contributed. It is paid to a native
+//! account that is either given during the initialization or associated later by the contributor
+//! with [`Pallet::associate_native_identity`], which requires a signature of the relay chain
+//! account over the [`Pallet::association_payload`] of the native account. The payload contains
+//! the genesis hash of the parachain, so a signature can't be replayed on other chains.
+//!
+//! [`Config::InitializationPayment`] of a reward can be claimed right away, the rest vests
+//! linearly over the relay chain blocks until the end of the lease. Vested rewards are paid with
+//! [`Pallet::claim`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use cumulus_primitives_core::relay_chain::BlockNumber as RelayChainBlockNumber;
+use frame_support::{
+	traits::{Currency, ExistenceRequirement},
+	PalletId,
+};
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{AccountIdConversion, BlockNumberProvider, Saturating, Verify, Zero},
+	AccountId32, MultiSignature, Perbill, RuntimeDebug,
+};
+use sp_std::vec::Vec;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+
+type BalanceOf<T> =
+	<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+/// Domain tag at the start of the payload signed in [`Pallet::associate_native_identity`].
+const ASSOCIATION_PAYLOAD_TAG: &[u8] = b"crowdloan-rewards:associate";
+
+/// Prefix polkadot-js adds to the messages it signs.
+const WRAPPED_BYTES_PREFIX: &[u8] = b"<Bytes>";
+
+/// Postfix polkadot-js adds to the messages it signs.
+const WRAPPED_BYTES_POSTFIX: &[u8] = b"</Bytes>";
+
+/// The reward of a native account.
+#[derive(Clone, Default, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct RewardInfo<Balance> {
+	/// The total reward of the account.
+	pub total_reward: Balance,
+	/// The part of `total_reward` that was paid already.
+	pub claimed_reward: Balance,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	pub use crate::weights::WeightInfo;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The currency the rewards are paid in.
+		type Currency: Currency<Self::AccountId>;
+
+		/// The account of a crowdloan contributor on the relay chain.
+		type RelayChainAccountId: Member + Parameter + MaxEncodedLen + Into<AccountId32>;
+
+		/// Origin that can initialize the rewards.
+		type InitializationOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The id of the account the rewards are paid from.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// The part of a reward that can be claimed right away.
+		#[pallet::constant]
+		type InitializationPayment: Get<Perbill>;
+
+		/// Maximum number of rewards that can be initialized in one call.
+		#[pallet::constant]
+		type MaxInitContributors: Get<u32>;
+
+		/// The relay chain block number the rewards vest by.
+		type VestingBlockProvider: BlockNumberProvider<BlockNumber = RelayChainBlockNumber>;
+
+		/// The weight information of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::without_storage_info]
+	pub struct Pallet<T>(_);
+
+	/// The rewards of the native accounts.
+	#[pallet::storage]
+	#[pallet::getter(fn accounts_payable)]
+	pub type AccountsPayable<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, RewardInfo<BalanceOf<T>>>;
+
+	/// The relay chain accounts whose rewards were assigned to a native account.
+	#[pallet::storage]
+	#[pallet::getter(fn claimed_relay_chain_ids)]
+	pub type ClaimedRelayChainIds<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::RelayChainAccountId, ()>;
+
+	/// The rewards of relay chain accounts that were not assigned to a native account yet.
+	#[pallet::storage]
+	#[pallet::getter(fn unassociated_contributions)]
+	pub type UnassociatedContributions<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::RelayChainAccountId, BalanceOf<T>>;
+
+	/// Whether the initialization of the rewards was completed.
+	#[pallet::storage]
+	#[pallet::getter(fn initialized)]
+	pub type Initialized<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	/// The relay chain block at which the rewards start to vest.
+	#[pallet::storage]
+	#[pallet::getter(fn init_vesting_block)]
+	pub type InitVestingBlock<T: Config> = StorageValue<_, RelayChainBlockNumber, ValueQuery>;
+
+	/// The relay chain block at which the rewards are fully vested.
+	#[pallet::storage]
+	#[pallet::getter(fn end_vesting_block)]
+	pub type EndVestingBlock<T: Config> = StorageValue<_, RelayChainBlockNumber, ValueQuery>;
+
+	/// The sum of all initialized rewards.
+	#[pallet::storage]
+	#[pallet::getter(fn initialized_reward_amount)]
+	pub type InitializedRewardAmount<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A reward was paid.
+		RewardsPaid { account: T::AccountId, amount: BalanceOf<T> },
+		/// The reward of a relay chain account was assigned to a native account.
+		NativeIdentityAssociated {
+			relay_account: T::RelayChainAccountId,
+			account: T::AccountId,
+			reward: BalanceOf<T>,
+		},
+		/// The reward of an account was moved to another account.
+		RewardAddressUpdated { old_account: T::AccountId, new_account: T::AccountId },
+		/// The initialization of the rewards was completed.
+		InitializationCompleted { end_vesting_block: RelayChainBlockNumber },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The initialization was completed already.
+		AlreadyInitialized,
+		/// The initialization was not completed yet.
+		NotInitialized,
+		/// More rewards than [`Config::MaxInitContributors`] were given.
+		TooManyContributors,
+		/// The relay chain account has a reward already.
+		DuplicateContributor,
+		/// The relay chain account has no unassociated reward.
+		NoAssociatedClaim,
+		/// The new reward account has a reward already.
+		AlreadyAssociated,
+		/// The signature of the relay chain account is invalid.
+		InvalidClaimSignature,
+		/// The account has no reward.
+		NoRewards,
+		/// The vested reward was claimed already.
+		RewardsAlreadyClaimed,
+		/// The end of the vesting period is not after its start.
+		VestingPeriodNonValid,
+		/// The account of the pallet can not pay all initialized rewards.
+		RewardsDoNotMatchFund,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Initialize the rewards of the given relay chain accounts.
+		///
+		/// Each reward is given as `(relay_account, native_account, reward)`. Rewards without a
+		/// native account can be associated later with [`Pallet::associate_native_identity`].
+		#[pallet::weight(T::WeightInfo::initialize_reward_vec(rewards.len() as u32))]
+		pub fn initialize_reward_vec(
+			origin: OriginFor<T>,
+			rewards: Vec<(T::RelayChainAccountId, Option<T::AccountId>, BalanceOf<T>)>,
+		) -> DispatchResult {
+			T::InitializationOrigin::ensure_origin(origin)?;
+			ensure!(!Initialized::<T>::get(), Error::<T>::AlreadyInitialized);
+			ensure!(
+				rewards.len() as u32 <= T::MaxInitContributors::get(),
+				Error::<T>::TooManyContributors,
+			);
+
+			for (i, (relay_account, _, _)) in rewards.iter().enumerate() {
+				ensure!(
+					!rewards[..i].iter().any(|(other, _, _)| other == relay_account) &&
+						!ClaimedRelayChainIds::<T>::contains_key(relay_account) &&
+						!UnassociatedContributions::<T>::contains_key(relay_account),
+					Error::<T>::DuplicateContributor,
+				);
+			}
+
+			for (relay_account, account, reward) in rewards {
+				InitializedRewardAmount::<T>::mutate(|total| *total = total.saturating_add(reward));
+
+				match account {
+					Some(account) => Self::associate(relay_account, account, reward),
+					None => UnassociatedContributions::<T>::insert(relay_account, reward),
+				}
+			}
+
+			Ok(())
+		}
+
+		/// Complete the initialization, the rewards vest until `lease_ending_block`.
+		#[pallet::weight(T::WeightInfo::complete_initialization())]
+		pub fn complete_initialization(
+			origin: OriginFor<T>,
+			lease_ending_block: RelayChainBlockNumber,
+		) -> DispatchResult {
+			T::InitializationOrigin::ensure_origin(origin)?;
+			ensure!(!Initialized::<T>::get(), Error::<T>::AlreadyInitialized);
+
+			let now = T::VestingBlockProvider::current_block_number();
+			ensure!(lease_ending_block > now, Error::<T>::VestingPeriodNonValid);
+			ensure!(
+				T::Currency::free_balance(&Self::account_id()) >=
+					InitializedRewardAmount::<T>::get(),
+				Error::<T>::RewardsDoNotMatchFund,
+			);
+
+			InitVestingBlock::<T>::put(now);
+			EndVestingBlock::<T>::put(lease_ending_block);
+			Initialized::<T>::put(true);
+
+			Self::deposit_event(Event::InitializationCompleted {
+				end_vesting_block: lease_ending_block,
+			});
+			Ok(())
+		}
+
+		/// Assign the reward of `relay_account` to the sender.
+		///
+		/// The reward is added to the existing reward of the sender, if any.
+		///
+		/// `proof` is the signature of `relay_account` over the [`Pallet::association_payload`]
+		/// of the sender, optionally wrapped in `<Bytes>` and `</Bytes>`.
+		#[pallet::weight(T::WeightInfo::associate_native_identity())]
+		pub fn associate_native_identity(
+			origin: OriginFor<T>,
+			relay_account: T::RelayChainAccountId,
+			proof: MultiSignature,
+		) -> DispatchResult {
+			let account = ensure_signed(origin)?;
+
+			let reward = UnassociatedContributions::<T>::get(&relay_account)
+				.ok_or(Error::<T>::NoAssociatedClaim)?;
+
+			let payload = Self::association_payload(&account);
+			let wrapped_payload =
+				[WRAPPED_BYTES_PREFIX, &payload[..], WRAPPED_BYTES_POSTFIX].concat();
+			let signer = relay_account.clone().into();
+			ensure!(
+				proof.verify(&payload[..], &signer) || proof.verify(&wrapped_payload[..], &signer),
+				Error::<T>::InvalidClaimSignature,
+			);
+
+			UnassociatedContributions::<T>::remove(&relay_account);
+			Self::associate(relay_account, account, reward);
+
+			Ok(())
+		}
+
+		/// Pay the vested but unpaid reward of the sender.
+		#[pallet::weight(T::WeightInfo::claim())]
+		pub fn claim(origin: OriginFor<T>) -> DispatchResult {
+			let account = ensure_signed(origin)?;
+			ensure!(Initialized::<T>::get(), Error::<T>::NotInitialized);
+
+			let mut info = AccountsPayable::<T>::get(&account).ok_or(Error::<T>::NoRewards)?;
+			let vested =
+				Self::vested(info.total_reward, T::VestingBlockProvider::current_block_number());
+			let payable = vested.saturating_sub(info.claimed_reward);
+			ensure!(payable > Zero::zero(), Error::<T>::RewardsAlreadyClaimed);
+
+			T::Currency::transfer(
+				&Self::account_id(),
+				&account,
+				payable,
+				ExistenceRequirement::AllowDeath,
+			)?;
+			info.claimed_reward = vested;
+			AccountsPayable::<T>::insert(&account, info);
+
+			Self::deposit_event(Event::RewardsPaid { account, amount: payable });
+			Ok(())
+		}
+
+		/// Move the reward of the sender to `new_account`.
+		#[pallet::weight(T::WeightInfo::update_reward_address())]
+		pub fn update_reward_address(
+			origin: OriginFor<T>,
+			new_account: T::AccountId,
+		) -> DispatchResult {
+			let old_account = ensure_signed(origin)?;
+
+			let info = AccountsPayable::<T>::get(&old_account).ok_or(Error::<T>::NoRewards)?;
+			ensure!(
+				!AccountsPayable::<T>::contains_key(&new_account),
+				Error::<T>::AlreadyAssociated,
+			);
+
+			AccountsPayable::<T>::remove(&old_account);
+			AccountsPayable::<T>::insert(&new_account, info);
+
+			Self::deposit_event(Event::RewardAddressUpdated { old_account, new_account });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The account the rewards are paid from.
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account()
+		}
+
+		/// Returns the payload a relay chain account signs to assign its reward to `account`.
+		///
+		/// The payload is the domain tag `crowdloan-rewards:associate`, followed by the genesis
+		/// hash of this chain and the encoded `account`.
+		pub fn association_payload(account: &T::AccountId) -> Vec<u8> {
+			let genesis_hash = frame_system::Pallet::<T>::block_hash(T::BlockNumber::zero());
+			[ASSOCIATION_PAYLOAD_TAG, genesis_hash.as_ref(), &account.encode()[..]].concat()
+		}
+
+		/// Add the `reward` of `relay_account` to the reward of `account`.
+		fn associate(
+			relay_account: T::RelayChainAccountId,
+			account: T::AccountId,
+			reward: BalanceOf<T>,
+		) {
+			AccountsPayable::<T>::mutate(&account, |info| {
+				let info = info.get_or_insert_with(Default::default);
+				info.total_reward = info.total_reward.saturating_add(reward);
+			});
+			ClaimedRelayChainIds::<T>::insert(&relay_account, ());
+
+			Self::deposit_event(Event::NativeIdentityAssociated { relay_account, account, reward });
+		}
+
+		/// Returns the part of `total_reward` that is vested at relay chain block `now`.
+		pub fn vested(total_reward: BalanceOf<T>, now: RelayChainBlockNumber) -> BalanceOf<T> {
+			let start = InitVestingBlock::<T>::get();
+			let end = EndVestingBlock::<T>::get();
+			if now >= end {
+				return total_reward
+			}
+
+			let upfront = T::InitializationPayment::get() * total_reward;
+			let vesting = total_reward.saturating_sub(upfront);
+			let elapsed = Perbill::from_rational(now.saturating_sub(start), end - start);
+
+			upfront.saturating_add(elapsed * vesting)
+		}
+	}
+}
@@ -0,0 +1,238 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{mock::*, Error, Event as CrowdloanRewardsEvent, RewardInfo};
+use codec::Encode;
+use frame_support::{assert_noop, assert_ok};
+use sp_core::{Pair, H256};
+use sp_runtime::{traits::BadOrigin, MultiSignature};
+
+fn initialize(rewards: Vec<(u8, Option<u64>, u64)>, lease_ending_block: u32) {
+	let rewards = rewards
+		.into_iter()
+		.map(|(seed, account, reward)| (relay_account(seed), account, reward))
+		.collect();
+
+	assert_ok!(CrowdloanRewards::initialize_reward_vec(Origin::root(), rewards));
+	assert_ok!(CrowdloanRewards::complete_initialization(Origin::root(), lease_ending_block));
+}
+
+#[test]
+fn initialization_works() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			CrowdloanRewards::initialize_reward_vec(Origin::signed(1), Vec::new()),
+			BadOrigin,
+		);
+		assert_noop!(
+			CrowdloanRewards::initialize_reward_vec(
+				Origin::root(),
+				vec![(relay_account(1), None, 100), (relay_account(1), Some(2), 100)],
+			),
+			Error::<Test>::DuplicateContributor,
+		);
+		assert_noop!(
+			CrowdloanRewards::initialize_reward_vec(
+				Origin::root(),
+				(1..=5).map(|seed| (relay_account(seed), None, 100)).collect(),
+			),
+			Error::<Test>::TooManyContributors,
+		);
+
+		assert_ok!(CrowdloanRewards::initialize_reward_vec(
+			Origin::root(),
+			vec![(relay_account(1), Some(1), 1_000), (relay_account(2), None, 2_000)],
+		));
+		assert_eq!(
+			CrowdloanRewards::accounts_payable(1),
+			Some(RewardInfo { total_reward: 1_000, claimed_reward: 0 }),
+		);
+		assert_eq!(CrowdloanRewards::unassociated_contributions(relay_account(2)), Some(2_000));
+		assert_noop!(
+			CrowdloanRewards::initialize_reward_vec(
+				Origin::root(),
+				vec![(relay_account(2), Some(2), 100)],
+			),
+			Error::<Test>::DuplicateContributor,
+		);
+
+		assert_noop!(
+			CrowdloanRewards::complete_initialization(Origin::root(), 100),
+			Error::<Test>::VestingPeriodNonValid,
+		);
+		assert_ok!(CrowdloanRewards::complete_initialization(Origin::root(), 200));
+		assert_noop!(
+			CrowdloanRewards::initialize_reward_vec(Origin::root(), Vec::new()),
+			Error::<Test>::AlreadyInitialized,
+		);
+	});
+}
+
+#[test]
+fn initialization_requires_funds() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(CrowdloanRewards::initialize_reward_vec(
+			Origin::root(),
+			vec![(relay_account(1), Some(1), 20_000)],
+		));
+		assert_noop!(
+			CrowdloanRewards::complete_initialization(Origin::root(), 200),
+			Error::<Test>::RewardsDoNotMatchFund,
+		);
+	});
+}
+
+#[test]
+fn rewards_vest_linearly() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_noop!(CrowdloanRewards::claim(Origin::signed(1)), Error::<Test>::NotInitialized);
+
+		initialize(vec![(1, Some(1), 1_000)], 200);
+		assert_noop!(CrowdloanRewards::claim(Origin::signed(2)), Error::<Test>::NoRewards);
+
+		// The initialization payment is available right away.
+		assert_ok!(CrowdloanRewards::claim(Origin::signed(1)));
+		assert_eq!(Balances::free_balance(1), 200);
+		System::assert_last_event(
+			CrowdloanRewardsEvent::RewardsPaid { account: 1, amount: 200 }.into(),
+		);
+		assert_noop!(
+			CrowdloanRewards::claim(Origin::signed(1)),
+			Error::<Test>::RewardsAlreadyClaimed,
+		);
+
+		RelayChainBlockNumber::set(&150);
+		assert_ok!(CrowdloanRewards::claim(Origin::signed(1)));
+		assert_eq!(Balances::free_balance(1), 600);
+
+		RelayChainBlockNumber::set(&300);
+		assert_ok!(CrowdloanRewards::claim(Origin::signed(1)));
+		assert_eq!(Balances::free_balance(1), 1_000);
+		assert_eq!(Balances::free_balance(CrowdloanRewards::account_id()), 9_000);
+	});
+}
+
+#[test]
+fn associate_native_identity_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		initialize(vec![(1, None, 1_000)], 200);
+
+		let proof: MultiSignature =
+			relay_pair(1).sign(&CrowdloanRewards::association_payload(&2)).into();
+		assert_noop!(
+			CrowdloanRewards::associate_native_identity(
+				Origin::signed(3),
+				relay_account(1),
+				proof.clone(),
+			),
+			Error::<Test>::InvalidClaimSignature,
+		);
+		assert_noop!(
+			CrowdloanRewards::associate_native_identity(
+				Origin::signed(2),
+				relay_account(2),
+				proof.clone(),
+			),
+			Error::<Test>::NoAssociatedClaim,
+		);
+
+		assert_ok!(CrowdloanRewards::associate_native_identity(
+			Origin::signed(2),
+			relay_account(1),
+			proof,
+		));
+		assert_eq!(CrowdloanRewards::unassociated_contributions(relay_account(1)), None);
+		assert_eq!(
+			CrowdloanRewards::accounts_payable(2),
+			Some(RewardInfo { total_reward: 1_000, claimed_reward: 0 }),
+		);
+		System::assert_last_event(
+			CrowdloanRewardsEvent::NativeIdentityAssociated {
+				relay_account: relay_account(1),
+				account: 2,
+				reward: 1_000,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn associate_native_identity_accepts_wrapped_payload() {
+	new_test_ext().execute_with(|| {
+		initialize(vec![(1, None, 1_000)], 200);
+
+		let payload =
+			[&b"<Bytes>"[..], &CrowdloanRewards::association_payload(&2)[..], &b"</Bytes>"[..]]
+				.concat();
+		let proof: MultiSignature = relay_pair(1).sign(&payload).into();
+		assert_ok!(CrowdloanRewards::associate_native_identity(
+			Origin::signed(2),
+			relay_account(1),
+			proof,
+		));
+	});
+}
+
+#[test]
+fn associate_native_identity_rejects_payload_of_other_domains() {
+	new_test_ext().execute_with(|| {
+		initialize(vec![(1, None, 1_000)], 200);
+
+		// A signature over the bare account, which may have been given for another purpose.
+		let proof: MultiSignature = relay_pair(1).sign(&2u64.encode()).into();
+		assert_noop!(
+			CrowdloanRewards::associate_native_identity(Origin::signed(2), relay_account(1), proof),
+			Error::<Test>::InvalidClaimSignature,
+		);
+
+		// A signature for the same account on a chain with another genesis hash.
+		frame_system::BlockHash::<Test>::insert(0, H256::repeat_byte(2));
+		let payload = CrowdloanRewards::association_payload(&2);
+		frame_system::BlockHash::<Test>::insert(0, H256::repeat_byte(1));
+		let proof: MultiSignature = relay_pair(1).sign(&payload).into();
+		assert_noop!(
+			CrowdloanRewards::associate_native_identity(Origin::signed(2), relay_account(1), proof),
+			Error::<Test>::InvalidClaimSignature,
+		);
+	});
+}
+
+#[test]
+fn update_reward_address_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		initialize(vec![(1, Some(1), 1_000), (2, Some(2), 1_000)], 200);
+
+		assert_noop!(
+			CrowdloanRewards::update_reward_address(Origin::signed(1), 2),
+			Error::<Test>::AlreadyAssociated,
+		);
+		assert_noop!(
+			CrowdloanRewards::update_reward_address(Origin::signed(3), 4),
+			Error::<Test>::NoRewards,
+		);
+
+		assert_ok!(CrowdloanRewards::update_reward_address(Origin::signed(1), 3));
+		assert_eq!(CrowdloanRewards::accounts_payable(1), None);
+		assert!(CrowdloanRewards::accounts_payable(3).is_some());
+		System::assert_last_event(
+			CrowdloanRewardsEvent::RewardAddressUpdated { old_account: 1, new_account: 3 }.into(),
+		);
+	});
+}
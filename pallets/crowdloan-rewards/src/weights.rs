@@ -0,0 +1,101 @@
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+// Hand-written estimates, to be replaced by weights generated from `benchmarking.rs`.
+pub trait WeightInfo {
+	fn initialize_reward_vec(x: u32) -> Weight;
+	fn complete_initialization() -> Weight;
+	fn associate_native_identity() -> Weight;
+	fn claim() -> Weight;
+	fn update_reward_address() -> Weight;
+}
+
+pub struct SubstrateWeight<T>(PhantomData<T>);
+
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Reads `Initialized`. For every reward, checks `ClaimedRelayChainIds` and
+	// `UnassociatedContributions`, updates `InitializedRewardAmount` and writes either the
+	// association or the unassociated contribution.
+	fn initialize_reward_vec(x: u32) -> Weight {
+		(12_000_000 as Weight)
+			.saturating_add((31_000_000 as Weight).saturating_mul(x as Weight))
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().reads((4 as Weight).saturating_mul(x as Weight)))
+			.saturating_add(T::DbWeight::get().writes((4 as Weight).saturating_mul(x as Weight)))
+	}
+	// Reads `Initialized`, `InitializedRewardAmount` and the account of the pallet, plus one read
+	// for the `VestingBlockProvider`, which depends on the runtime. Writes the vesting period and
+	// `Initialized`.
+	fn complete_initialization() -> Weight {
+		(24_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	// Takes `UnassociatedContributions`, reads the genesis hash for the signed payload, updates
+	// `AccountsPayable` and writes `ClaimedRelayChainIds`. Dominated by the signature check.
+	fn associate_native_identity() -> Weight {
+		(92_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	// Reads `Initialized` and the vesting period, updates `AccountsPayable` and transfers between
+	// two accounts, plus one read for the `VestingBlockProvider`, which depends on the runtime.
+	fn claim() -> Weight {
+		(56_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(7 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	// Moves the entry of `AccountsPayable` after checking the new account.
+	fn update_reward_address() -> Weight {
+		(27_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+}
+
+impl WeightInfo for () {
+	// Reads `Initialized`. For every reward, checks `ClaimedRelayChainIds` and
+	// `UnassociatedContributions`, updates `InitializedRewardAmount` and writes either the
+	// association or the unassociated contribution.
+	fn initialize_reward_vec(x: u32) -> Weight {
+		(12_000_000 as Weight)
+			.saturating_add((31_000_000 as Weight).saturating_mul(x as Weight))
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((4 as Weight).saturating_mul(x as Weight)))
+			.saturating_add(RocksDbWeight::get().writes((4 as Weight).saturating_mul(x as Weight)))
+	}
+	// Reads `Initialized`, `InitializedRewardAmount` and the account of the pallet, plus one read
+	// for the `VestingBlockProvider`, which depends on the runtime. Writes the vesting period and
+	// `Initialized`.
+	fn complete_initialization() -> Weight {
+		(24_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(4 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	// Takes `UnassociatedContributions`, reads the genesis hash for the signed payload, updates
+	// `AccountsPayable` and writes `ClaimedRelayChainIds`. Dominated by the signature check.
+	fn associate_native_identity() -> Weight {
+		(92_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	// Reads `Initialized` and the vesting period, updates `AccountsPayable` and transfers between
+	// two accounts, plus one read for the `VestingBlockProvider`, which depends on the runtime.
+	fn claim() -> Weight {
+		(56_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(7 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	// Moves the entry of `AccountsPayable` after checking the new account.
+	fn update_reward_address() -> Weight {
+		(27_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+}
@@ -23,6 +23,7 @@
 
 use codec::{Decode, DecodeLimit, Encode};
 use cumulus_primitives_core::{relay_chain::BlockNumber as RelayBlockNumber, DmpMessageHandler};
+use cumulus_primitives_utility::fees::FeeHandler;
 use frame_support::{
 	dispatch::Weight, traits::EnsureOrigin, weights::constants::WEIGHT_PER_MILLIS,
 };
@@ -90,6 +91,9 @@ pub mod pallet {
 
 		/// Origin which is allowed to execute overweight messages.
 		type ExecuteOverweightOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Charges the relay chain for the execution of downward messages.
+		type FeeHandler: FeeHandler<Self::Call>;
 	}
 
 	/// The configuration.
@@ -178,6 +182,10 @@ pub mod pallet {
 		/// Downward message from the overweight queue was executed.
 		/// \[ index, used \]
 		OverweightServiced(OverweightIndex, Weight),
+		/// A fee was charged for the execution of a downward message, the unused weight is already
+		/// refunded.
+		/// \[ id, amount \]
+		FeeCharged(MessageId, u128),
 	}
 
 	impl<T: Config> Pallet<T> {
@@ -240,13 +248,30 @@ pub mod pallet {
 					Self::deposit_event(Event::UnsupportedVersion(id));
 					Ok(0)
 				},
-				Ok(Ok(x)) => {
-					let outcome = T::XcmExecutor::execute_xcm(Parent, x, limit);
+				Ok(Ok(mut x)) => {
+					let origin = MultiLocation::parent();
+					let outcome = match T::FeeHandler::charge_fee(&origin, &mut x, limit) {
+						Err(e) => Outcome::Error(e),
+						Ok(fee) => {
+							let outcome = T::XcmExecutor::execute_xcm(Parent, x, limit);
+							if fee > 0 {
+								let refund =
+									T::FeeHandler::refund_fee(&origin, fee, outcome.weight_used());
+								Self::deposit_event(Event::FeeCharged(
+									id,
+									fee.saturating_sub(refund),
+								));
+							}
+							outcome
+						},
+					};
 					match outcome {
 						Outcome::Error(XcmError::WeightLimitReached(required)) =>
 							Err((id, required)),
 						outcome => {
-							let weight_used = outcome.weight_used();
+							let weight_used = outcome
+								.weight_used()
+								.saturating_add(T::FeeHandler::storage_weight());
 							Self::deposit_event(Event::ExecutedDownward(id, outcome));
 							Ok(weight_used)
 						},
@@ -444,10 +469,56 @@ mod tests {
 		}
 	}
 
+	thread_local! {
+		pub static FEE_BALANCE: RefCell<Option<u128>> = RefCell::new(None);
+	}
+
+	/// Charges the weight required by a message as its fee, if a fee balance is set, and refunds
+	/// the weight it didn't use.
+	pub struct MockFeeHandler;
+	impl FeeHandler<Call> for MockFeeHandler {
+		fn charge_fee(
+			_origin: &MultiLocation,
+			message: &mut Xcm,
+			weight_limit: Weight,
+		) -> Result<u128, XcmError> {
+			let weight = match message.0.first() {
+				Some(Transact { require_weight_at_most, .. }) => *require_weight_at_most,
+				_ => 0,
+			};
+			if weight > weight_limit {
+				return Ok(0)
+			}
+			FEE_BALANCE.with(|b| match &mut *b.borrow_mut() {
+				None => Ok(0),
+				Some(balance) if *balance < weight as u128 => Err(XcmError::TooExpensive),
+				Some(balance) => {
+					*balance -= weight as u128;
+					Ok(weight as u128)
+				},
+			})
+		}
+
+		fn refund_fee(_origin: &MultiLocation, fee: u128, weight_used: Weight) -> u128 {
+			let refund = fee.saturating_sub(weight_used as u128);
+			FEE_BALANCE.with(|b| {
+				if let Some(balance) = &mut *b.borrow_mut() {
+					*balance += refund;
+				}
+			});
+			refund
+		}
+
+		fn storage_weight() -> Weight {
+			0
+		}
+	}
+
 	impl Config for Test {
 		type Event = Event;
 		type XcmExecutor = MockExec;
 		type ExecuteOverweightOrigin = frame_system::EnsureRoot<AccountId>;
+		type FeeHandler = MockFeeHandler;
 	}
 
 	pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
@@ -787,4 +858,49 @@ mod tests {
 			assert_eq!(pages_queued(), 1);
 		});
 	}
+
+	#[test]
+	fn unpaid_messages_are_not_executed() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			FEE_BALANCE.with(|b| *b.borrow_mut() = Some(1500));
+
+			let incoming = vec![msg(1000), msg(1001)];
+			let weight_used = handle_messages(&incoming, 2500);
+			assert_eq!(weight_used, 1000);
+			assert_eq!(take_trace(), vec![msg_complete(1000)]);
+			assert!(queue_is_empty());
+			assert_eq!(FEE_BALANCE.with(|b| *b.borrow()), Some(500));
+
+			let id = |m: &Xcm| {
+				sp_io::hashing::blake2_256(&VersionedXcm::<Call>::from(m.clone()).encode())
+			};
+			System::assert_has_event(Event::DmpQueue(crate::Event::FeeCharged(
+				id(&incoming[0]),
+				1000,
+			)));
+			System::assert_has_event(Event::DmpQueue(crate::Event::ExecutedDownward(
+				id(&incoming[1]),
+				Outcome::Error(XcmError::TooExpensive),
+			)));
+		});
+	}
+
+	#[test]
+	fn unused_weight_is_refunded() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			FEE_BALANCE.with(|b| *b.borrow_mut() = Some(2000));
+
+			// Not supported by the executor, so it only uses 1000 of its 1500 weight.
+			let mut message = msg(1500);
+			message.0.push(ClearOrigin);
+			let weight_used = handle_messages(&[message.clone()], 2500);
+			assert_eq!(weight_used, 1000);
+			assert_eq!(FEE_BALANCE.with(|b| *b.borrow()), Some(1000));
+
+			let id = sp_io::hashing::blake2_256(&VersionedXcm::<Call>::from(message).encode());
+			System::assert_has_event(Event::DmpQueue(crate::Event::FeeCharged(id, 1000)));
+		});
+	}
 }
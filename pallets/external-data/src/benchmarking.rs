@@ -0,0 +1,76 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarking setup for cumulus-pallet-external-data
+
+use super::*;
+
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+use frame_support::{
+	traits::{EnsureOrigin, Get},
+	BoundedVec,
+};
+use frame_system::RawOrigin;
+
+/// Returns `count` new signers, whose keys are in the keystore.
+fn signers<T: Config>(count: u32) -> Vec<T::SignerId> {
+	(0..count).map(|_| T::SignerId::generate_pair(None)).collect()
+}
+
+benchmarks! {
+	set_external_data {
+		let s in 1 .. T::MaxSigners::get();
+		let p in 0 .. T::MaxPayloadSize::get();
+
+		// Worst case: every signer has to sign, so every signature is checked.
+		let signers = signers::<T>(s);
+		Signers::<T>::put(
+			BoundedVec::<_, T::MaxSigners>::try_from(signers.clone())
+				.expect("At most `MaxSigners` signers"),
+		);
+		Threshold::<T>::put(s);
+
+		let mut data = ExternalDataOf::<T> {
+			round: 1,
+			payload: sp_std::vec![1; p as usize],
+			signatures: Vec::new(),
+		};
+		let message = Pallet::<T>::signing_message(&data);
+		data.signatures = signers
+			.into_iter()
+			.map(|signer| {
+				let signature =
+					signer.sign(&message).expect("The key of the signer is in the keystore");
+				(signer, signature)
+			})
+			.collect();
+	}: _(RawOrigin::None, data)
+	verify {
+		assert_eq!(Round::<T>::get(), Some(1));
+	}
+
+	set_signers {
+		let s in 0 .. T::MaxSigners::get();
+
+		let signers = signers::<T>(s);
+		let origin = T::UpdateOrigin::successful_origin();
+	}: _<T::Origin>(origin, signers, s)
+	verify {
+		assert_eq!(Threshold::<T>::get(), s);
+	}
+}
+
+impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
@@ -0,0 +1,357 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! External Data pallet.
+//!
+//! Accepts external data, e.g. a price feed, that is signed by trusted signers.
+//!
+//! ## Overview
+//!
+//! The collator reads the external data with the signatures of the signers from an
+//! [`ExternalDataSource`](cumulus_primitives_external_data::ExternalDataSource) and puts it into
+//! its block through the external data inherent. The inherent is optional, blocks without it
+//! are valid.
+//!
+//! The data is only accepted if at least [`Threshold`] of the [`Signers`] signed its round and
+//! payload for this chain, and its round is newer than the [`Round`] of the last accepted data.
+//! Unknown signers and duplicate signatures are ignored, but the data may not carry more
+//! signatures than [`Config::MaxSigners`]. This way the collator can neither forge data nor replay
+//! old data. It can however still leave the inherent out, or pick which of several newer signed
+//! rounds it includes, so it has to be trusted to keep the data fresh. The signers and the
+//! threshold are set by [`Config::UpdateOrigin`]. Without signers, no external data is accepted.
+//!
+//! The accepted payload is stored in [`Payload`] and passed to [`Config::OnExternalData`], which
+//! decodes and uses it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use cumulus_primitives_external_data::{ExternalData, InherentError, INHERENT_IDENTIFIER};
+use sp_runtime::{traits::Zero, RuntimeAppPublic};
+use sp_std::{collections::btree_set::BTreeSet, vec::Vec};
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+
+/// The [`ExternalData`] of a runtime.
+pub type ExternalDataOf<T> =
+	ExternalData<<T as Config>::SignerId, <<T as Config>::SignerId as RuntimeAppPublic>::Signature>;
+
+/// Something that is notified about accepted external data.
+pub trait OnExternalData {
+	/// Called with the payload of every accepted external data.
+	fn on_external_data(payload: &[u8]);
+}
+
+impl OnExternalData for () {
+	fn on_external_data(_: &[u8]) {}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	pub use crate::weights::WeightInfo;
+	use frame_support::{
+		inherent::{InherentData, InherentIdentifier, ProvideInherent},
+		pallet_prelude::*,
+	};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The identifier of a signer, which is also the key it signs the payload with.
+		type SignerId: Member
+			+ Parameter
+			+ MaxEncodedLen
+			+ MaybeSerializeDeserialize
+			+ Ord
+			+ RuntimeAppPublic;
+
+		/// Origin that can set the signers and the threshold.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Maximum number of signers, and of signatures of external data.
+		#[pallet::constant]
+		type MaxSigners: Get<u32>;
+
+		/// Maximum size of a payload in bytes.
+		#[pallet::constant]
+		type MaxPayloadSize: Get<u32>;
+
+		/// Notified with the payload of the accepted external data.
+		type OnExternalData: OnExternalData;
+
+		/// The weight information of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The signers that are trusted to sign external data.
+	#[pallet::storage]
+	#[pallet::getter(fn signers)]
+	pub type Signers<T: Config> =
+		StorageValue<_, BoundedVec<T::SignerId, T::MaxSigners>, ValueQuery>;
+
+	/// The number of signers that have to sign the payload of external data.
+	#[pallet::storage]
+	#[pallet::getter(fn threshold)]
+	pub type Threshold<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// The payload of the last accepted external data.
+	#[pallet::storage]
+	#[pallet::getter(fn payload)]
+	pub type Payload<T: Config> = StorageValue<_, BoundedVec<u8, T::MaxPayloadSize>, OptionQuery>;
+
+	/// The round of the last accepted external data.
+	#[pallet::storage]
+	#[pallet::getter(fn round)]
+	pub type Round<T: Config> = StorageValue<_, u64, OptionQuery>;
+
+	/// The block the last external data was accepted in.
+	#[pallet::storage]
+	#[pallet::getter(fn last_updated)]
+	pub type LastUpdated<T: Config> = StorageValue<_, T::BlockNumber, OptionQuery>;
+
+	/// Whether external data was accepted in the current block.
+	#[pallet::storage]
+	pub(super) type DidUpdate<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub signers: Vec<T::SignerId>,
+		pub threshold: u32,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { signers: Default::default(), threshold: Default::default() }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			Pallet::<T>::do_set_signers(self.signers.clone(), self.threshold)
+				.expect("Invalid signers in the external data genesis config");
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The signers and the threshold were updated.
+		SignersSet { signers: Vec<T::SignerId>, threshold: u32 },
+		/// External data signed by `signatures` trusted signers was accepted.
+		ExternalDataSet { signatures: u32 },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// External data was already set in this block.
+		AlreadySet,
+		/// The payload is bigger than [`Config::MaxPayloadSize`].
+		PayloadTooBig,
+		/// Fewer than [`Threshold`] trusted signers signed the payload.
+		NotEnoughSignatures,
+		/// The round is not newer than [`Round`].
+		StaleRound,
+		/// More signers than [`Config::MaxSigners`] were given.
+		TooManySigners,
+		/// The same signer was given more than once.
+		DuplicateSigner,
+		/// The threshold is bigger than the number of signers, or zero while there are signers.
+		InvalidThreshold,
+		/// There are more signatures than [`Config::MaxSigners`].
+		TooManySignatures,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_: T::BlockNumber) -> Weight {
+			// Killed in `on_finalize`.
+			T::DbWeight::get().writes(1)
+		}
+
+		fn on_finalize(_: T::BlockNumber) {
+			DidUpdate::<T>::kill();
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Set the external data of the current block.
+		///
+		/// This is an inherent and can only be included by the block author.
+		///
+		/// Data with more signatures or a bigger payload than allowed is rejected, so the weight
+		/// is bounded by [`Config::MaxSigners`] and [`Config::MaxPayloadSize`].
+		#[pallet::weight((
+			T::WeightInfo::set_external_data(
+				(data.signatures.len() as u32).min(T::MaxSigners::get()),
+				(data.payload.len() as u32).min(T::MaxPayloadSize::get()),
+			),
+			DispatchClass::Mandatory,
+		))]
+		pub fn set_external_data(origin: OriginFor<T>, data: ExternalDataOf<T>) -> DispatchResult {
+			ensure_none(origin)?;
+			ensure!(!DidUpdate::<T>::get(), Error::<T>::AlreadySet);
+
+			let round = data.round;
+			let (payload, signatures) = Self::verify(data).map_err(|e| match e {
+				InherentError::PayloadTooBig => Error::<T>::PayloadTooBig,
+				InherentError::StaleRound => Error::<T>::StaleRound,
+				InherentError::TooManySignatures => Error::<T>::TooManySignatures,
+				_ => Error::<T>::NotEnoughSignatures,
+			})?;
+
+			T::OnExternalData::on_external_data(&payload);
+			Payload::<T>::put(payload);
+			Round::<T>::put(round);
+			LastUpdated::<T>::put(frame_system::Pallet::<T>::block_number());
+			DidUpdate::<T>::put(true);
+
+			Self::deposit_event(Event::ExternalDataSet { signatures });
+			Ok(())
+		}
+
+		/// Replace the trusted signers and the threshold.
+		#[pallet::weight(T::WeightInfo::set_signers(signers.len() as u32))]
+		pub fn set_signers(
+			origin: OriginFor<T>,
+			signers: Vec<T::SignerId>,
+			threshold: u32,
+		) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			Self::do_set_signers(signers.clone(), threshold)?;
+
+			Self::deposit_event(Event::SignersSet { signers, threshold });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Validate `signers` and `threshold` and store them.
+		fn do_set_signers(signers: Vec<T::SignerId>, threshold: u32) -> DispatchResult {
+			ensure!(
+				signers.iter().enumerate().all(|(i, signer)| !signers[..i].contains(signer)),
+				Error::<T>::DuplicateSigner,
+			);
+			// A threshold of zero is only allowed without signers, which disables external data.
+			ensure!(
+				threshold as usize <= signers.len() && (threshold > 0 || signers.is_empty()),
+				Error::<T>::InvalidThreshold,
+			);
+
+			let signers = signers.try_into().map_err(|_| Error::<T>::TooManySigners)?;
+			Signers::<T>::put(signers);
+			Threshold::<T>::put(threshold);
+
+			Ok(())
+		}
+
+		/// Returns the message the signers of `data` sign for this chain.
+		pub fn signing_message(data: &ExternalDataOf<T>) -> Vec<u8> {
+			let genesis_hash = frame_system::Pallet::<T>::block_hash(T::BlockNumber::zero());
+			data.signing_message(genesis_hash.as_ref())
+		}
+
+		/// Check that `data` is newer than the last accepted data and that enough trusted signers
+		/// signed its round and payload.
+		///
+		/// Returns the payload and the number of valid signatures of trusted signers.
+		pub fn verify(
+			data: ExternalDataOf<T>,
+		) -> Result<(BoundedVec<u8, T::MaxPayloadSize>, u32), InherentError> {
+			ensure!(
+				data.payload.len() <= T::MaxPayloadSize::get() as usize,
+				InherentError::PayloadTooBig,
+			);
+			ensure!(
+				data.signatures.len() <= T::MaxSigners::get() as usize,
+				InherentError::TooManySignatures,
+			);
+			ensure!(
+				Round::<T>::get().map_or(true, |round| data.round > round),
+				InherentError::StaleRound,
+			);
+
+			let message = Self::signing_message(&data);
+			let trusted = Signers::<T>::get().into_iter().collect::<BTreeSet<_>>();
+			let mut counted = BTreeSet::new();
+			for (signer, signature) in &data.signatures {
+				if trusted.contains(signer) &&
+					!counted.contains(signer) &&
+					signer.verify(&message, signature)
+				{
+					counted.insert(signer);
+				}
+			}
+
+			let signatures = counted.len() as u32;
+			let threshold = Threshold::<T>::get();
+			ensure!(threshold > 0 && signatures >= threshold, InherentError::NotEnoughSignatures);
+
+			let payload = data.payload.try_into().map_err(|_| InherentError::PayloadTooBig)?;
+			Ok((payload, signatures))
+		}
+	}
+
+	#[pallet::inherent]
+	impl<T: Config> ProvideInherent for Pallet<T> {
+		type Call = Call<T>;
+		type Error = InherentError;
+		const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+
+		fn create_inherent(data: &InherentData) -> Option<Self::Call> {
+			// The provider passes the encoded external data as is.
+			let encoded: Vec<u8> = data.get_data(&INHERENT_IDENTIFIER).ok().flatten()?;
+			let data = ExternalDataOf::<T>::decode(&mut &encoded[..]).ok()?;
+
+			// Data that would be rejected is left out, so the block can still be built.
+			Self::verify(data.clone()).ok()?;
+
+			Some(Call::set_external_data { data })
+		}
+
+		fn check_inherent(call: &Self::Call, _: &InherentData) -> Result<(), Self::Error> {
+			match call {
+				Call::set_external_data { data } => Self::verify(data.clone()).map(|_| ()),
+				_ => Ok(()),
+			}
+		}
+
+		fn is_inherent(call: &Self::Call) -> bool {
+			matches!(call, Call::set_external_data { .. })
+		}
+	}
+}
@@ -0,0 +1,120 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use crate as external_data;
+use frame_support::{
+	parameter_types,
+	traits::{ConstU32, Everything, GenesisBuild},
+};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::{Header, TestSignature, UintAuthorityId},
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		ExternalData: external_data::{Pallet, Call, Storage, Config<T>, Event<T>, Inherent},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub storage ReceivedPayloads: Vec<Vec<u8>> = Vec::new();
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+/// Records the received payloads in [`ReceivedPayloads`].
+pub struct MockOnExternalData;
+impl OnExternalData for MockOnExternalData {
+	fn on_external_data(payload: &[u8]) {
+		let mut payloads = ReceivedPayloads::get();
+		payloads.push(payload.to_vec());
+		ReceivedPayloads::set(&payloads);
+	}
+}
+
+impl Config for Test {
+	type Event = Event;
+	type SignerId = UintAuthorityId;
+	type UpdateOrigin = EnsureRoot<u64>;
+	type MaxSigners = ConstU32<4>;
+	type MaxPayloadSize = ConstU32<8>;
+	type OnExternalData = MockOnExternalData;
+	type WeightInfo = ();
+}
+
+/// Returns `payload` of `round` signed by the given signers.
+pub fn signed_data(round: u64, payload: &[u8], signers: &[u64]) -> ExternalDataOf<Test> {
+	let mut data =
+		ExternalDataOf::<Test> { round, payload: payload.to_vec(), signatures: Vec::new() };
+	let message = ExternalData::signing_message(&data);
+	data.signatures = signers
+		.iter()
+		.map(|signer| (UintAuthorityId(*signer), TestSignature(*signer, message.clone())))
+		.collect();
+	data
+}
+
+/// Signers 1, 2 and 3 are trusted, two of them have to sign.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+	external_data::GenesisConfig::<Test> {
+		signers: vec![UintAuthorityId(1), UintAuthorityId(2), UintAuthorityId(3)],
+		threshold: 2,
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	t.into()
+}
@@ -0,0 +1,230 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{mock::*, Call, Error, Event as ExternalDataEvent};
+use codec::Encode;
+use cumulus_primitives_external_data::{InherentError, INHERENT_IDENTIFIER};
+use frame_support::{
+	assert_noop, assert_ok,
+	inherent::{InherentData, ProvideInherent},
+	traits::Hooks,
+};
+use sp_core::H256;
+use sp_runtime::{
+	testing::{TestSignature, UintAuthorityId},
+	traits::BadOrigin,
+};
+
+#[test]
+fn set_external_data_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		assert_ok!(ExternalData::set_external_data(
+			Origin::none(),
+			signed_data(1, b"price", &[1, 2])
+		));
+		assert_eq!(ExternalData::payload().unwrap().into_inner(), b"price".to_vec());
+		assert_eq!(ExternalData::last_updated(), Some(1));
+		assert_eq!(ReceivedPayloads::get(), vec![b"price".to_vec()]);
+		System::assert_last_event(ExternalDataEvent::ExternalDataSet { signatures: 2 }.into());
+
+		assert_noop!(
+			ExternalData::set_external_data(Origin::none(), signed_data(1, b"price", &[1, 2])),
+			Error::<Test>::AlreadySet,
+		);
+
+		ExternalData::on_finalize(1);
+		System::set_block_number(2);
+		assert_ok!(ExternalData::set_external_data(
+			Origin::none(),
+			signed_data(2, b"new", &[2, 3])
+		));
+		assert_eq!(ExternalData::last_updated(), Some(2));
+		assert_eq!(ExternalData::round(), Some(2));
+
+		// Data of the same or an older round is not accepted again.
+		ExternalData::on_finalize(2);
+		System::set_block_number(3);
+		assert_noop!(
+			ExternalData::set_external_data(Origin::none(), signed_data(2, b"new", &[2, 3])),
+			Error::<Test>::StaleRound,
+		);
+		assert_noop!(
+			ExternalData::set_external_data(Origin::none(), signed_data(1, b"price", &[1, 2])),
+			Error::<Test>::StaleRound,
+		);
+	});
+}
+
+#[test]
+fn set_external_data_requires_enough_trusted_signatures() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			ExternalData::set_external_data(Origin::signed(1), signed_data(1, b"price", &[1, 2])),
+			BadOrigin,
+		);
+		assert_noop!(
+			ExternalData::set_external_data(Origin::none(), signed_data(1, b"price", &[1])),
+			Error::<Test>::NotEnoughSignatures,
+		);
+		// Unknown signers and duplicate signatures are not counted.
+		assert_noop!(
+			ExternalData::set_external_data(Origin::none(), signed_data(1, b"price", &[1, 4])),
+			Error::<Test>::NotEnoughSignatures,
+		);
+		assert_noop!(
+			ExternalData::set_external_data(Origin::none(), signed_data(1, b"price", &[1, 1])),
+			Error::<Test>::NotEnoughSignatures,
+		);
+
+		let mut forged = signed_data(1, b"price", &[1, 2]);
+		forged.payload = b"forged".to_vec();
+		assert_noop!(
+			ExternalData::set_external_data(Origin::none(), forged),
+			Error::<Test>::NotEnoughSignatures,
+		);
+
+		let mut forged = signed_data(1, b"price", &[1, 2]);
+		forged.round = 2;
+		assert_noop!(
+			ExternalData::set_external_data(Origin::none(), forged),
+			Error::<Test>::NotEnoughSignatures,
+		);
+
+		assert_noop!(
+			ExternalData::set_external_data(
+				Origin::none(),
+				signed_data(1, b"much too big", &[1, 2, 3])
+			),
+			Error::<Test>::PayloadTooBig,
+		);
+	});
+}
+
+#[test]
+fn set_external_data_rejects_more_signatures_than_signers() {
+	new_test_ext().execute_with(|| {
+		let data = signed_data(1, b"price", &[1, 2, 3, 4, 5]);
+		assert_noop!(
+			ExternalData::set_external_data(Origin::none(), data.clone()),
+			Error::<Test>::TooManySignatures,
+		);
+		assert!(matches!(
+			ExternalData::check_inherent(&Call::set_external_data { data }, &InherentData::new(),),
+			Err(InherentError::TooManySignatures),
+		));
+	});
+}
+
+#[test]
+fn signatures_are_bound_to_the_chain() {
+	new_test_ext().execute_with(|| {
+		// Signed for a chain with another genesis hash.
+		frame_system::BlockHash::<Test>::insert(0, H256::repeat_byte(2));
+		let data = signed_data(1, b"price", &[1, 2]);
+		frame_system::BlockHash::<Test>::insert(0, H256::repeat_byte(1));
+		assert_noop!(
+			ExternalData::set_external_data(Origin::none(), data),
+			Error::<Test>::NotEnoughSignatures,
+		);
+
+		// Signed without the context and the genesis hash.
+		let mut data = signed_data(1, b"price", &[1, 2]);
+		let message = (data.round, &data.payload).encode();
+		for (signer, signature) in &mut data.signatures {
+			*signature = TestSignature(signer.0, message.clone());
+		}
+		assert_noop!(
+			ExternalData::set_external_data(Origin::none(), data),
+			Error::<Test>::NotEnoughSignatures,
+		);
+	});
+}
+
+#[test]
+fn set_signers_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		assert_noop!(
+			ExternalData::set_signers(Origin::signed(1), vec![UintAuthorityId(4)], 1),
+			BadOrigin,
+		);
+		assert_noop!(
+			ExternalData::set_signers(Origin::root(), vec![UintAuthorityId(4)], 2),
+			Error::<Test>::InvalidThreshold,
+		);
+		assert_noop!(
+			ExternalData::set_signers(Origin::root(), vec![UintAuthorityId(4)], 0),
+			Error::<Test>::InvalidThreshold,
+		);
+		assert_noop!(
+			ExternalData::set_signers(Origin::root(), vec![UintAuthorityId(4); 2], 1),
+			Error::<Test>::DuplicateSigner,
+		);
+		assert_noop!(
+			ExternalData::set_signers(Origin::root(), (1..=5).map(UintAuthorityId).collect(), 1),
+			Error::<Test>::TooManySigners,
+		);
+
+		assert_ok!(ExternalData::set_signers(Origin::root(), vec![UintAuthorityId(4)], 1));
+		assert_eq!(ExternalData::threshold(), 1);
+		System::assert_last_event(
+			ExternalDataEvent::SignersSet { signers: vec![UintAuthorityId(4)], threshold: 1 }
+				.into(),
+		);
+		assert_ok!(ExternalData::set_external_data(Origin::none(), signed_data(1, b"price", &[4])));
+
+		// Without signers no external data is accepted.
+		ExternalData::on_finalize(1);
+		assert_ok!(ExternalData::set_signers(Origin::root(), Vec::new(), 0));
+		assert_noop!(
+			ExternalData::set_external_data(Origin::none(), signed_data(2, b"price", &[4])),
+			Error::<Test>::NotEnoughSignatures,
+		);
+	});
+}
+
+#[test]
+fn inherent_is_created_for_valid_data_only() {
+	new_test_ext().execute_with(|| {
+		let mut inherent_data = InherentData::new();
+		assert_eq!(ExternalData::create_inherent(&inherent_data), None);
+		assert!(ExternalData::is_inherent_required(&inherent_data).unwrap().is_none());
+
+		let data = signed_data(1, b"price", &[1, 2]);
+		inherent_data.put_data(INHERENT_IDENTIFIER, &data.encode()).unwrap();
+		assert_eq!(
+			ExternalData::create_inherent(&inherent_data),
+			Some(Call::set_external_data { data }),
+		);
+
+		let mut inherent_data = InherentData::new();
+		inherent_data
+			.put_data(INHERENT_IDENTIFIER, &signed_data(1, b"price", &[1]).encode())
+			.unwrap();
+		assert_eq!(ExternalData::create_inherent(&inherent_data), None);
+
+		assert!(matches!(
+			ExternalData::check_inherent(
+				&Call::set_external_data { data: signed_data(1, b"price", &[3]) },
+				&InherentData::new(),
+			),
+			Err(InherentError::NotEnoughSignatures),
+		));
+	});
+}
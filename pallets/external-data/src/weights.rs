@@ -0,0 +1,52 @@
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+// Hand-written estimates, to be replaced by weights generated from `benchmarking.rs`.
+pub trait WeightInfo {
+	fn set_external_data(s: u32, p: u32) -> Weight;
+	fn set_signers(s: u32) -> Weight;
+}
+
+pub struct SubstrateWeight<T>(PhantomData<T>);
+
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Reads `DidUpdate`, `Round`, `Signers`, `Threshold` and the genesis hash of the signed
+	// message, and writes the accepted data. Dominated by checking one signature per signer.
+	fn set_external_data(s: u32, p: u32) -> Weight {
+		(15_000_000 as Weight)
+			.saturating_add((60_000_000 as Weight).saturating_mul(s as Weight))
+			.saturating_add((2_000 as Weight).saturating_mul(p as Weight))
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	// Writes `Signers` and `Threshold`, the duplicate check grows with the number of signers.
+	fn set_signers(s: u32) -> Weight {
+		(8_000_000 as Weight)
+			.saturating_add((150_000 as Weight).saturating_mul(s as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+}
+
+impl WeightInfo for () {
+	// Reads `DidUpdate`, `Round`, `Signers`, `Threshold` and the genesis hash of the signed
+	// message, and writes the accepted data. Dominated by checking one signature per signer.
+	fn set_external_data(s: u32, p: u32) -> Weight {
+		(15_000_000 as Weight)
+			.saturating_add((60_000_000 as Weight).saturating_mul(s as Weight))
+			.saturating_add((2_000 as Weight).saturating_mul(p as Weight))
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(4 as Weight))
+	}
+	// Writes `Signers` and `Threshold`, the duplicate check grows with the number of signers.
+	fn set_signers(s: u32) -> Weight {
+		(8_000_000 as Weight)
+			.saturating_add((150_000 as Weight).saturating_mul(s as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+}
@@ -0,0 +1,69 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarking setup for cumulus-pallet-hrmp-handler
+
+use super::*;
+
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+use frame_support::traits::EnsureOrigin;
+
+/// Returns a sibling parachain of this parachain.
+fn sibling<T: Config>() -> ParaId {
+	ParaId::from(u32::from(Pallet::<T>::para_id()).wrapping_add(1))
+}
+
+benchmarks! {
+	request_open_channel {
+		let origin = T::UpdateOrigin::successful_origin();
+		let sender = Pallet::<T>::para_id();
+		let recipient = sibling::<T>();
+	}: _<T::Origin>(origin, recipient, 8, 1024)
+	verify {
+		assert_eq!(Channels::<T>::get(sender, recipient), Some(ChannelState::Requested));
+	}
+
+	accept_open_channel {
+		let origin = T::UpdateOrigin::successful_origin();
+		let sender = sibling::<T>();
+		let recipient = Pallet::<T>::para_id();
+	}: _<T::Origin>(origin, sender)
+	verify {
+		assert_eq!(Channels::<T>::get(sender, recipient), Some(ChannelState::Requested));
+	}
+
+	close_channel {
+		let origin = T::UpdateOrigin::successful_origin();
+		let sender = Pallet::<T>::para_id();
+		let recipient = sibling::<T>();
+		Pallet::<T>::insert_channel(sender, recipient, ChannelState::Open);
+	}: _<T::Origin>(origin, sender, recipient)
+	verify {
+		assert_eq!(Channels::<T>::get(sender, recipient), Some(ChannelState::Closing));
+	}
+
+	cancel_open_request {
+		let origin = T::UpdateOrigin::successful_origin();
+		let sender = Pallet::<T>::para_id();
+		let recipient = sibling::<T>();
+		Pallet::<T>::insert_channel(sender, recipient, ChannelState::Requested);
+	}: _<T::Origin>(origin, sender, recipient, 1)
+	verify {
+		assert_eq!(Channels::<T>::get(sender, recipient), None);
+	}
+}
+
+impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
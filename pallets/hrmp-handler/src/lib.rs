@@ -0,0 +1,427 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! HRMP Handler pallet.
+//!
+//! Manages the HRMP channels of the parachain with its sibling parachains.
+//!
+//! ## Overview
+//!
+//! HRMP channels are opened and closed on the relay chain by the parachains themselves, through
+//! calls of the HRMP pallet of the relay chain. This pallet sends these calls as upward messages
+//! that withdraw [`Config::RelayChainFee`] from the sovereign account of the parachain on the
+//! relay chain to pay for their execution. [`Config::RelayChainCalls`] encodes the calls for the
+//! relay chain, [`HrmpPalletCalls`] does this for Polkadot based relay chains.
+//!
+//! The local view of the channels is kept in [`Channels`]. A channel is `Requested` once this
+//! parachain requested or accepted it, `Open` once it shows up in the relay chain state proof
+//! and `Closing` once this parachain requested to close it. Channels are removed as soon as they
+//! are no longer part of the relay chain state proof. Channels opened by the relay chain or the
+//! sibling parachain are picked up from the relay chain state proof as well. A `Requested` channel
+//! that the other side never accepts can be removed with [`Pallet::cancel_open_request`], which
+//! also cancels the request on the relay chain.
+//!
+//! The state is synchronized with the relay chain state proof in `on_finalize`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use cumulus_pallet_parachain_system as parachain_system;
+use cumulus_primitives_core::{relay_chain::v2::HrmpChannelId, ParaId};
+use frame_support::{dispatch::DispatchResult, traits::Get};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_std::{marker::PhantomData, vec, vec::Vec};
+use xcm::{latest::prelude::*, VersionedXcm};
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+
+/// The state of a HRMP channel as seen by this parachain.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum ChannelState {
+	/// This parachain requested or accepted the channel, but it is not open yet.
+	Requested,
+	/// The channel is open.
+	Open,
+	/// This parachain requested to close the channel, but it is still open.
+	Closing,
+}
+
+/// Encodes the calls of the HRMP pallet of the relay chain.
+pub trait HrmpCallEncoder {
+	/// Encode the call that requests to open a channel to `recipient`.
+	fn hrmp_init_open_channel(
+		recipient: ParaId,
+		proposed_max_capacity: u32,
+		proposed_max_message_size: u32,
+	) -> Vec<u8>;
+
+	/// Encode the call that accepts the channel requested by `sender`.
+	fn hrmp_accept_open_channel(sender: ParaId) -> Vec<u8>;
+
+	/// Encode the call that closes the given channel.
+	fn hrmp_close_channel(channel: HrmpChannelId) -> Vec<u8>;
+
+	/// Encode the call that cancels the request to open the given channel.
+	///
+	/// `open_requests` is the number of open requests on the relay chain, an upper bound for the
+	/// weight of the call.
+	fn hrmp_cancel_open_request(channel: HrmpChannelId, open_requests: u32) -> Vec<u8>;
+}
+
+/// Encodes the calls of the HRMP pallet of Polkadot based relay chains, where the pallet has the
+/// index `PalletIndex` in the runtime of the relay chain.
+pub struct HrmpPalletCalls<PalletIndex>(PhantomData<PalletIndex>);
+
+impl<PalletIndex: Get<u8>> HrmpCallEncoder for HrmpPalletCalls<PalletIndex> {
+	fn hrmp_init_open_channel(
+		recipient: ParaId,
+		proposed_max_capacity: u32,
+		proposed_max_message_size: u32,
+	) -> Vec<u8> {
+		(PalletIndex::get(), 0u8, recipient, proposed_max_capacity, proposed_max_message_size)
+			.encode()
+	}
+
+	fn hrmp_accept_open_channel(sender: ParaId) -> Vec<u8> {
+		(PalletIndex::get(), 1u8, sender).encode()
+	}
+
+	fn hrmp_close_channel(channel: HrmpChannelId) -> Vec<u8> {
+		(PalletIndex::get(), 2u8, channel).encode()
+	}
+
+	fn hrmp_cancel_open_request(channel: HrmpChannelId, open_requests: u32) -> Vec<u8> {
+		(PalletIndex::get(), 6u8, channel, open_requests).encode()
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	pub use crate::weights::WeightInfo;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + parachain_system::Config {
+		/// Overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin that can open and close channels.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Encodes the HRMP calls for the relay chain.
+		type RelayChainCalls: HrmpCallEncoder;
+
+		/// The amount of the relay chain currency that is withdrawn from the sovereign account of
+		/// the parachain on the relay chain to pay for a HRMP call.
+		///
+		/// The surplus is deposited back into the sovereign account.
+		#[pallet::constant]
+		type RelayChainFee: Get<u128>;
+
+		/// The weight of a HRMP call on the relay chain.
+		#[pallet::constant]
+		type RelayChainCallWeight: Get<Weight>;
+
+		/// The weight information of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The HRMP channels of this parachain, by sender and recipient.
+	#[pallet::storage]
+	#[pallet::getter(fn channel_state)]
+	pub type Channels<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, ParaId, Twox64Concat, ParaId, ChannelState, OptionQuery>;
+
+	/// The number of entries in [`Channels`].
+	///
+	/// Used to weigh walking all channels in `on_finalize`.
+	#[pallet::storage]
+	pub(super) type ChannelCount<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Opening a channel to `recipient` was requested.
+		OpenChannelRequested { recipient: ParaId, max_capacity: u32, max_message_size: u32 },
+		/// The channel requested by `sender` was accepted.
+		OpenChannelAccepted { sender: ParaId },
+		/// Closing the channel was requested.
+		CloseChannelRequested { sender: ParaId, recipient: ParaId },
+		/// The request to open the channel was canceled.
+		OpenRequestCanceled { sender: ParaId, recipient: ParaId },
+		/// The channel was opened on the relay chain.
+		ChannelOpened { sender: ParaId, recipient: ParaId },
+		/// The channel was closed on the relay chain.
+		ChannelClosed { sender: ParaId, recipient: ParaId },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The channel is already known.
+		ChannelExists,
+		/// The channel is not open.
+		ChannelNotOpen,
+		/// The channel is not requested.
+		ChannelNotRequested,
+		/// The channel does not involve this parachain or connects it to itself.
+		InvalidChannel,
+		/// The upward message could not be sent.
+		SendFailed,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: T::BlockNumber) -> Weight {
+			// `on_finalize` reads the messaging state and the channel count, walks every known
+			// channel and, in the worst case, writes every channel in the messaging state and
+			// every known channel as well as the channel count. The messaging state may be
+			// outdated at this point, but the number of channels only changes slowly.
+			let channels = parachain_system::Pallet::<T>::relevant_messaging_state()
+				.map(|state| state.ingress_channels.len() + state.egress_channels.len())
+				.unwrap_or_default() as Weight;
+			let known = ChannelCount::<T>::get() as Weight;
+
+			T::DbWeight::get().reads_writes(3 + known, channels + known + 1)
+		}
+
+		fn on_finalize(_n: T::BlockNumber) {
+			Self::sync_channels();
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Request to open a channel to `recipient`.
+		///
+		/// The channel opens once `recipient` accepted it and the relay chain enacted it.
+		#[pallet::weight(T::WeightInfo::request_open_channel())]
+		pub fn request_open_channel(
+			origin: OriginFor<T>,
+			recipient: ParaId,
+			max_capacity: u32,
+			max_message_size: u32,
+		) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			let sender = Self::para_id();
+			ensure!(recipient != sender, Error::<T>::InvalidChannel);
+			ensure!(!Channels::<T>::contains_key(sender, recipient), Error::<T>::ChannelExists);
+
+			Self::send_relay_call(T::RelayChainCalls::hrmp_init_open_channel(
+				recipient,
+				max_capacity,
+				max_message_size,
+			))?;
+			Self::insert_channel(sender, recipient, ChannelState::Requested);
+
+			Self::deposit_event(Event::OpenChannelRequested {
+				recipient,
+				max_capacity,
+				max_message_size,
+			});
+			Ok(())
+		}
+
+		/// Accept the channel that `sender` requested to open to this parachain.
+		#[pallet::weight(T::WeightInfo::accept_open_channel())]
+		pub fn accept_open_channel(origin: OriginFor<T>, sender: ParaId) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			let recipient = Self::para_id();
+			ensure!(sender != recipient, Error::<T>::InvalidChannel);
+			ensure!(!Channels::<T>::contains_key(sender, recipient), Error::<T>::ChannelExists);
+
+			Self::send_relay_call(T::RelayChainCalls::hrmp_accept_open_channel(sender))?;
+			Self::insert_channel(sender, recipient, ChannelState::Requested);
+
+			Self::deposit_event(Event::OpenChannelAccepted { sender });
+			Ok(())
+		}
+
+		/// Request to close the open channel from `sender` to `recipient`.
+		///
+		/// Either side of the channel has to be this parachain.
+		#[pallet::weight(T::WeightInfo::close_channel())]
+		pub fn close_channel(
+			origin: OriginFor<T>,
+			sender: ParaId,
+			recipient: ParaId,
+		) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			let para_id = Self::para_id();
+			ensure!(
+				sender != recipient && (sender == para_id || recipient == para_id),
+				Error::<T>::InvalidChannel,
+			);
+			ensure!(
+				Channels::<T>::get(sender, recipient) == Some(ChannelState::Open),
+				Error::<T>::ChannelNotOpen,
+			);
+
+			Self::send_relay_call(T::RelayChainCalls::hrmp_close_channel(HrmpChannelId {
+				sender,
+				recipient,
+			}))?;
+			Channels::<T>::insert(sender, recipient, ChannelState::Closing);
+
+			Self::deposit_event(Event::CloseChannelRequested { sender, recipient });
+			Ok(())
+		}
+
+		/// Cancel the request to open the channel from `sender` to `recipient`, which is not open
+		/// yet.
+		///
+		/// Either side of the channel has to be this parachain. `open_requests` is passed to the
+		/// relay chain, it has to be at least the number of open requests there.
+		#[pallet::weight(T::WeightInfo::cancel_open_request())]
+		pub fn cancel_open_request(
+			origin: OriginFor<T>,
+			sender: ParaId,
+			recipient: ParaId,
+			open_requests: u32,
+		) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			let para_id = Self::para_id();
+			ensure!(
+				sender != recipient && (sender == para_id || recipient == para_id),
+				Error::<T>::InvalidChannel,
+			);
+			ensure!(
+				Channels::<T>::get(sender, recipient) == Some(ChannelState::Requested),
+				Error::<T>::ChannelNotRequested,
+			);
+
+			Self::send_relay_call(T::RelayChainCalls::hrmp_cancel_open_request(
+				HrmpChannelId { sender, recipient },
+				open_requests,
+			))?;
+			Self::remove_channel(sender, recipient);
+
+			Self::deposit_event(Event::OpenRequestCanceled { sender, recipient });
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	fn para_id() -> ParaId {
+		<T as parachain_system::Config>::SelfParaId::get()
+	}
+
+	/// The upward message that executes the encoded `call` on the relay chain, paid from the
+	/// sovereign account of this parachain.
+	pub fn relay_call_message(call: Vec<u8>) -> Xcm<()> {
+		let fee: MultiAsset = (Here, T::RelayChainFee::get()).into();
+
+		Xcm(vec![
+			WithdrawAsset(fee.clone().into()),
+			BuyExecution { fees: fee, weight_limit: Unlimited },
+			Transact {
+				origin_type: OriginKind::Native,
+				require_weight_at_most: T::RelayChainCallWeight::get(),
+				call: call.into(),
+			},
+			RefundSurplus,
+			DepositAsset {
+				assets: All.into(),
+				max_assets: 1,
+				beneficiary: X1(Parachain(Self::para_id().into())).into(),
+			},
+		])
+	}
+
+	/// Insert a channel that is not in [`Channels`] yet.
+	fn insert_channel(sender: ParaId, recipient: ParaId, state: ChannelState) {
+		Channels::<T>::insert(sender, recipient, state);
+		ChannelCount::<T>::mutate(|count| *count = count.saturating_add(1));
+	}
+
+	/// Remove a channel that is in [`Channels`].
+	fn remove_channel(sender: ParaId, recipient: ParaId) {
+		Channels::<T>::remove(sender, recipient);
+		ChannelCount::<T>::mutate(|count| *count = count.saturating_sub(1));
+	}
+
+	fn send_relay_call(call: Vec<u8>) -> DispatchResult {
+		let message = VersionedXcm::from(Self::relay_call_message(call)).encode();
+
+		parachain_system::Pallet::<T>::send_upward_message(message)
+			.map(|_| ())
+			.map_err(|_| Error::<T>::SendFailed.into())
+	}
+
+	/// Update [`Channels`] from the channels in the relay chain state proof of this block.
+	fn sync_channels() {
+		let state = match parachain_system::Pallet::<T>::relevant_messaging_state() {
+			Some(state) => state,
+			None => return,
+		};
+		let para_id = Self::para_id();
+
+		let mut open: Vec<(ParaId, ParaId)> = state
+			.ingress_channels
+			.iter()
+			.map(|(sender, _)| (*sender, para_id))
+			.chain(state.egress_channels.iter().map(|(recipient, _)| (para_id, *recipient)))
+			.collect();
+		open.sort();
+
+		let channels: Vec<_> = Channels::<T>::iter().collect();
+		for (sender, recipient, channel_state) in channels {
+			match (open.binary_search(&(sender, recipient)), channel_state) {
+				(Ok(index), ChannelState::Requested) => {
+					open.remove(index);
+					Channels::<T>::insert(sender, recipient, ChannelState::Open);
+					Self::deposit_event(Event::ChannelOpened { sender, recipient });
+				},
+				(Ok(index), _) => {
+					open.remove(index);
+				},
+				(Err(_), ChannelState::Requested) => {},
+				(Err(_), _) => {
+					Self::remove_channel(sender, recipient);
+					Self::deposit_event(Event::ChannelClosed { sender, recipient });
+				},
+			}
+		}
+
+		// The remaining channels were opened without a request of this parachain.
+		for (sender, recipient) in open {
+			Self::insert_channel(sender, recipient, ChannelState::Open);
+			Self::deposit_event(Event::ChannelOpened { sender, recipient });
+		}
+	}
+}
@@ -0,0 +1,221 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+	mock::*, ChannelCount, ChannelState, Error, Event as HrmpHandlerEvent, HrmpCallEncoder,
+	HrmpPalletCalls,
+};
+use codec::Encode;
+use cumulus_pallet_parachain_system::test_utils::TestRelayContext;
+use cumulus_primitives_core::{relay_chain::v2::HrmpChannelId, ParaId};
+use frame_support::{assert_noop, assert_ok, traits::Hooks};
+use sp_runtime::traits::BadOrigin;
+use xcm::latest::prelude::*;
+
+const SELF: ParaId = ParaId::new(100);
+const SIBLING: ParaId = ParaId::new(200);
+
+/// Run a block on top of the relay parent `relay_parent_number`, finalizing the pallet as well.
+fn run_block(relay: &mut TestRelayContext<Test>, relay_parent_number: u32, f: impl FnOnce()) {
+	relay.run_block_with_relay(relay_parent_number, |_| {
+		f();
+		HrmpHandler::on_finalize(System::block_number());
+	});
+}
+
+#[test]
+fn relay_calls_are_encoded() {
+	type Calls = HrmpPalletCalls<HrmpPalletIndex>;
+
+	assert_eq!(
+		Calls::hrmp_init_open_channel(SIBLING, 8, 1024),
+		(60u8, 0u8, 200u32, 8u32, 1024u32).encode()
+	);
+	assert_eq!(Calls::hrmp_accept_open_channel(SIBLING), (60u8, 1u8, 200u32).encode());
+	assert_eq!(
+		Calls::hrmp_close_channel(HrmpChannelId { sender: SELF, recipient: SIBLING }),
+		(60u8, 2u8, 100u32, 200u32).encode()
+	);
+	assert_eq!(
+		Calls::hrmp_cancel_open_request(HrmpChannelId { sender: SELF, recipient: SIBLING }, 3),
+		(60u8, 6u8, 100u32, 200u32, 3u32).encode()
+	);
+}
+
+#[test]
+fn relay_call_message_pays_from_sovereign_account() {
+	new_test_ext().execute_with(|| {
+		let message = HrmpHandler::relay_call_message(vec![1, 2, 3]);
+
+		assert!(matches!(
+			&message.0[..],
+			[
+				WithdrawAsset(_),
+				BuyExecution { weight_limit: Unlimited, .. },
+				Transact {
+					origin_type: OriginKind::Native,
+					require_weight_at_most: 1_000_000_000,
+					..
+				},
+				RefundSurplus,
+				DepositAsset { .. },
+			]
+		));
+	});
+}
+
+#[test]
+fn outbound_channel_lifecycle() {
+	new_test_ext().execute_with(|| {
+		let mut relay = TestRelayContext::<Test>::new();
+
+		run_block(&mut relay, 10, || {
+			assert_noop!(
+				HrmpHandler::request_open_channel(Origin::signed(1), SIBLING, 8, 1024),
+				BadOrigin,
+			);
+			assert_ok!(HrmpHandler::request_open_channel(Origin::root(), SIBLING, 8, 1024));
+			assert_noop!(
+				HrmpHandler::request_open_channel(Origin::root(), SIBLING, 8, 1024),
+				Error::<Test>::ChannelExists,
+			);
+			assert_noop!(
+				HrmpHandler::close_channel(Origin::root(), SELF, SIBLING),
+				Error::<Test>::ChannelNotOpen,
+			);
+		});
+		assert_eq!(HrmpHandler::channel_state(SELF, SIBLING), Some(ChannelState::Requested));
+		let requested = Event::HrmpHandler(HrmpHandlerEvent::OpenChannelRequested {
+			recipient: SIBLING,
+			max_capacity: 8,
+			max_message_size: 1024,
+		});
+		assert!(System::events().iter().any(|record| record.event == requested));
+
+		relay.sproof_builder_mut().upsert_outbound_channel(SIBLING);
+		run_block(&mut relay, 11, || {});
+		assert_eq!(HrmpHandler::channel_state(SELF, SIBLING), Some(ChannelState::Open));
+		System::assert_last_event(
+			HrmpHandlerEvent::ChannelOpened { sender: SELF, recipient: SIBLING }.into(),
+		);
+
+		run_block(&mut relay, 12, || {
+			assert_ok!(HrmpHandler::close_channel(Origin::root(), SELF, SIBLING));
+		});
+		assert_eq!(HrmpHandler::channel_state(SELF, SIBLING), Some(ChannelState::Closing));
+
+		relay.sproof_builder_mut().hrmp_egress_channel_index = Some(Vec::new());
+		relay.sproof_builder_mut().hrmp_channels.clear();
+		run_block(&mut relay, 13, || {});
+		assert_eq!(HrmpHandler::channel_state(SELF, SIBLING), None);
+		assert_eq!(ChannelCount::<Test>::get(), 0);
+		System::assert_last_event(
+			HrmpHandlerEvent::ChannelClosed { sender: SELF, recipient: SIBLING }.into(),
+		);
+	});
+}
+
+#[test]
+fn open_request_can_be_canceled() {
+	new_test_ext().execute_with(|| {
+		let mut relay = TestRelayContext::<Test>::new();
+
+		run_block(&mut relay, 10, || {
+			assert_noop!(
+				HrmpHandler::cancel_open_request(Origin::root(), SELF, SIBLING, 1),
+				Error::<Test>::ChannelNotRequested,
+			);
+			assert_ok!(HrmpHandler::request_open_channel(Origin::root(), SIBLING, 8, 1024));
+		});
+		assert_eq!(ChannelCount::<Test>::get(), 1);
+
+		// The request stays until it is canceled.
+		run_block(&mut relay, 11, || {});
+		assert_eq!(HrmpHandler::channel_state(SELF, SIBLING), Some(ChannelState::Requested));
+
+		run_block(&mut relay, 12, || {
+			assert_noop!(
+				HrmpHandler::cancel_open_request(Origin::signed(1), SELF, SIBLING, 1),
+				BadOrigin,
+			);
+			assert_noop!(
+				HrmpHandler::cancel_open_request(Origin::root(), SIBLING, ParaId::new(300), 1),
+				Error::<Test>::InvalidChannel,
+			);
+			assert_ok!(HrmpHandler::cancel_open_request(Origin::root(), SELF, SIBLING, 1));
+			System::assert_last_event(
+				HrmpHandlerEvent::OpenRequestCanceled { sender: SELF, recipient: SIBLING }.into(),
+			);
+		});
+		assert_eq!(HrmpHandler::channel_state(SELF, SIBLING), None);
+		assert_eq!(ChannelCount::<Test>::get(), 0);
+	});
+}
+
+#[test]
+fn inbound_channel_is_accepted() {
+	new_test_ext().execute_with(|| {
+		let mut relay = TestRelayContext::<Test>::new();
+
+		run_block(&mut relay, 10, || {
+			assert_ok!(HrmpHandler::accept_open_channel(Origin::root(), SIBLING));
+			System::assert_last_event(
+				HrmpHandlerEvent::OpenChannelAccepted { sender: SIBLING }.into(),
+			);
+		});
+		assert_eq!(HrmpHandler::channel_state(SIBLING, SELF), Some(ChannelState::Requested));
+
+		relay.sproof_builder_mut().upsert_inbound_channel(SIBLING);
+		run_block(&mut relay, 11, || {});
+		assert_eq!(HrmpHandler::channel_state(SIBLING, SELF), Some(ChannelState::Open));
+	});
+}
+
+#[test]
+fn channels_opened_elsewhere_are_tracked() {
+	new_test_ext().execute_with(|| {
+		let mut relay = TestRelayContext::<Test>::new();
+		relay.sproof_builder_mut().upsert_inbound_channel(SIBLING);
+
+		run_block(&mut relay, 10, || {});
+		assert_eq!(HrmpHandler::channel_state(SIBLING, SELF), Some(ChannelState::Open));
+		System::assert_last_event(
+			HrmpHandlerEvent::ChannelOpened { sender: SIBLING, recipient: SELF }.into(),
+		);
+	});
+}
+
+#[test]
+fn invalid_channels_are_rejected() {
+	new_test_ext().execute_with(|| {
+		let mut relay = TestRelayContext::<Test>::new();
+
+		run_block(&mut relay, 10, || {
+			assert_noop!(
+				HrmpHandler::request_open_channel(Origin::root(), SELF, 8, 1024),
+				Error::<Test>::InvalidChannel,
+			);
+			assert_noop!(
+				HrmpHandler::accept_open_channel(Origin::root(), SELF),
+				Error::<Test>::InvalidChannel,
+			);
+			assert_noop!(
+				HrmpHandler::close_channel(Origin::root(), SIBLING, ParaId::new(300)),
+				Error::<Test>::InvalidChannel,
+			);
+		});
+	});
+}
@@ -0,0 +1,80 @@
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+// Hand-written estimates, to be replaced by weights generated from `benchmarking.rs`.
+pub trait WeightInfo {
+	fn request_open_channel() -> Weight;
+	fn accept_open_channel() -> Weight;
+	fn close_channel() -> Weight;
+	fn cancel_open_request() -> Weight;
+}
+
+pub struct SubstrateWeight<T>(PhantomData<T>);
+
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Reads `SelfParaId` and `Channels`, inserts the channel and increments `ChannelCount`, then
+	// checks the message size against `HostConfiguration` and queues the upward message.
+	fn request_open_channel() -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	// Reads `SelfParaId` and `Channels`, inserts the channel and increments `ChannelCount`, then
+	// checks the message size against `HostConfiguration` and queues the upward message.
+	fn accept_open_channel() -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	// Reads `SelfParaId` and `Channels` and updates the channel, then checks the message size
+	// against `HostConfiguration` and queues the upward message.
+	fn close_channel() -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	// Reads `SelfParaId` and `Channels`, removes the channel and decrements `ChannelCount`, then
+	// checks the message size against `HostConfiguration` and queues the upward message.
+	fn cancel_open_request() -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+}
+
+impl WeightInfo for () {
+	// Reads `SelfParaId` and `Channels`, inserts the channel and increments `ChannelCount`, then
+	// checks the message size against `HostConfiguration` and queues the upward message.
+	fn request_open_channel() -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(4 as Weight))
+	}
+	// Reads `SelfParaId` and `Channels`, inserts the channel and increments `ChannelCount`, then
+	// checks the message size against `HostConfiguration` and queues the upward message.
+	fn accept_open_channel() -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(4 as Weight))
+	}
+	// Reads `SelfParaId` and `Channels` and updates the channel, then checks the message size
+	// against `HostConfiguration` and queues the upward message.
+	fn close_channel() -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(4 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	// Reads `SelfParaId` and `Channels`, removes the channel and decrements `ChannelCount`, then
+	// checks the message size against `HostConfiguration` and queues the upward message.
+	fn cancel_open_request() -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(4 as Weight))
+	}
+}
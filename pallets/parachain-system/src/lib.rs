@@ -27,12 +27,12 @@
 //!
 //! Users must ensure that they register this pallet as an inherent provider.
 
-use codec::Encode;
+use codec::{Decode, Encode};
 use cumulus_primitives_core::{
-	relay_chain, AbridgedHostConfiguration, ChannelStatus, CollationInfo, DmpMessageHandler,
-	GetChannelInfo, InboundDownwardMessage, InboundHrmpMessage, MessageSendError,
-	OutboundHrmpMessage, ParaId, PersistedValidationData, UpwardMessage, UpwardMessageSender,
-	XcmpMessageHandler, XcmpMessageSource,
+	relay_chain, AbridgedHostConfiguration, BlockSummary, ChannelStatus, CollationInfo,
+	DmpMessageHandler, GetChannelInfo, InboundDownwardMessage, InboundHrmpMessage,
+	MessageSendError, OutboundHrmpMessage, ParaId, PersistedValidationData, RelayChainStateInfo,
+	UpwardMessage, UpwardMessageSender, XcmpMessageHandler, XcmpMessageSource,
 };
 use cumulus_primitives_parachain_inherent::{MessageQueueChain, ParachainInherentData};
 use frame_support::{
@@ -55,9 +55,12 @@ use sp_runtime::{
 use sp_std::{cmp, collections::btree_map::BTreeMap, prelude::*};
 
 mod migration;
+pub mod mqc;
 mod relay_state_snapshot;
 #[macro_use]
 pub mod validate_block;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 #[cfg(test)]
 mod tests;
 
@@ -84,7 +87,7 @@ mod tests;
 /// # fn main() {}
 /// ```
 pub use cumulus_pallet_parachain_system_proc_macro::register_validate_block;
-pub use relay_state_snapshot::{MessagingStateSnapshot, RelayChainStateProof};
+pub use relay_state_snapshot::{MessagingStateSnapshot, ReadEntryErr, RelayChainStateProof};
 
 pub use pallet::*;
 
@@ -136,6 +139,16 @@ pub mod pallet {
 			migration::on_runtime_upgrade::<T>()
 		}
 
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<(), &'static str> {
+			migration::pre_upgrade::<T>()
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			migration::post_upgrade::<T>()
+		}
+
 		fn on_finalize(_: T::BlockNumber) {
 			<DidSetValidationCode<T>>::kill();
 			<UpgradeRestrictionSignal<T>>::kill();
@@ -167,7 +180,8 @@ pub mod pallet {
 				},
 			};
 
-			<PendingUpwardMessages<T>>::mutate(|up| {
+			let pending_upward_messages = <PendingUpwardMessages<T>>::decode_len().unwrap_or(0);
+			let upward_messages = <PendingUpwardMessages<T>>::mutate(|up| {
 				let (count, size) = relevant_messaging_state.relay_dispatch_queue_size;
 
 				let available_capacity = cmp::min(
@@ -196,8 +210,16 @@ pub mod pallet {
 
 				UpwardMessages::<T>::put(&up[..num]);
 				*up = up.split_off(num);
+				num
 			});
 
+			if upward_messages > 0 {
+				// The pending messages are the last ones that got an index assigned.
+				let first_index =
+					<NextUpwardMessageIndex<T>>::get().wrapping_sub(pending_upward_messages as u32);
+				Self::deposit_event(Event::UpwardMessagesSent(first_index, upward_messages as u32));
+			}
+
 			// Sending HRMP messages is a little bit more involved. There are the following
 			// constraints:
 			//
@@ -217,6 +239,15 @@ pub mod pallet {
 					.map(|(recipient, data)| OutboundHrmpMessage { recipient, data })
 					.collect::<Vec<_>>();
 
+			let summary = BlockSummary {
+				downward_messages: ProcessedDownwardMessages::<T>::get(),
+				horizontal_messages: ProcessedHorizontalMessages::<T>::get(),
+				upward_messages: upward_messages as u32,
+				outbound_horizontal_messages: outbound_messages.len() as u32,
+				upgrade_pending: <PendingValidationCode<T>>::exists(),
+			};
+			frame_system::Pallet::<T>::deposit_log(summary.to_digest_item());
+
 			HrmpOutboundMessages::<T>::put(outbound_messages);
 		}
 
@@ -234,12 +265,13 @@ pub mod pallet {
 			// Remove the validation from the old block.
 			<ValidationData<T>>::kill();
 			ProcessedDownwardMessages::<T>::kill();
+			ProcessedHorizontalMessages::<T>::kill();
 			HrmpWatermark::<T>::kill();
 			UpwardMessages::<T>::kill();
 			HrmpOutboundMessages::<T>::kill();
 			CustomValidationHeadData::<T>::kill();
 
-			weight += T::DbWeight::get().writes(6);
+			weight += T::DbWeight::get().writes(7);
 
 			// Here, in `on_initialize` we must report the weight for both `on_initialize` and
 			// `on_finalize`.
@@ -266,9 +298,10 @@ pub mod pallet {
 			<AnnouncedHrmpMessagesPerCandidate<T>>::put(hrmp_max_message_num_per_candidate);
 
 			// NOTE that the actual weight consumed by `on_finalize` may turn out lower.
+			// The block summary reads three more values and writes the digest.
 			weight += T::DbWeight::get().reads_writes(
-				3 + hrmp_max_message_num_per_candidate as u64,
-				4 + hrmp_max_message_num_per_candidate as u64,
+				6 + hrmp_max_message_num_per_candidate as u64,
+				5 + hrmp_max_message_num_per_candidate as u64,
 			);
 
 			weight
@@ -286,8 +319,10 @@ pub mod pallet {
 		///
 		/// As a side effect, this function upgrades the current validation function
 		/// if the appropriate time has come.
-		#[pallet::weight((0, DispatchClass::Mandatory))]
-		// TODO: This weight should be corrected.
+		///
+		/// The weight is a full block, as the call may write the validation code. The weight that
+		/// was actually used is returned and the rest is refunded.
+		#[pallet::weight((T::BlockWeights::get().max_block, DispatchClass::Mandatory))]
 		pub fn set_validation_data(
 			origin: OriginFor<T>,
 			data: ParachainInherentData,
@@ -307,6 +342,8 @@ pub mod pallet {
 
 			Self::validate_validation_data(&vfp);
 
+			let mut total_weight = T::DbWeight::get().reads_writes(1, 5);
+
 			let relay_state_proof = RelayChainStateProof::new(
 				T::SelfParaId::get(),
 				vfp.relay_parent_storage_root,
@@ -329,11 +366,13 @@ pub mod pallet {
 					let validation_code = <PendingValidationCode<T>>::take();
 
 					Self::put_parachain_code(&validation_code);
+					total_weight += Self::code_weight();
 					<T::OnSystemEvent as OnSystemEvent>::on_validation_code_applied();
 					Self::deposit_event(Event::ValidationFunctionApplied(vfp.relay_parent_number));
 				},
 				Some(relay_chain::v2::UpgradeGoAhead::Abort) => {
 					<PendingValidationCode<T>>::kill();
+					total_weight += T::DbWeight::get().writes(1);
 					Self::deposit_event(Event::ValidationFunctionDiscarded);
 				},
 				None => {},
@@ -356,10 +395,12 @@ pub mod pallet {
 			<RelevantMessagingState<T>>::put(relevant_messaging_state.clone());
 			<HostConfiguration<T>>::put(host_config);
 
+			// Now that we know whether the relay chain accepts an upgrade in this block, hand over
+			// a queued upgrade to it.
+			total_weight += Self::schedule_queued_code_upgrade();
+
 			<T::OnSystemEvent as OnSystemEvent>::on_validation_data(&vfp);
 
-			// TODO: This is more than zero, but will need benchmarking to figure out what.
-			let mut total_weight = 0;
 			total_weight += Self::process_inbound_downward_messages(
 				relevant_messaging_state.dmq_mqc_head,
 				downward_messages,
@@ -393,6 +434,20 @@ pub mod pallet {
 			Ok(())
 		}
 
+		/// Queue a runtime upgrade that is scheduled as soon as the relay chain allows it.
+		///
+		/// Unlike `set_code`, this does not fail when the relay chain currently prohibits an
+		/// upgrade or another upgrade is still pending. The code is kept in
+		/// [`QueuedValidationCode`] and handed to the relay chain in the first block without an
+		/// upgrade restriction, after which the relay chain enacts it once
+		/// `validation_upgrade_delay` has passed.
+		#[pallet::weight((1_000_000, DispatchClass::Operational))]
+		pub fn queue_code_upgrade(origin: OriginFor<T>, code: Vec<u8>) -> DispatchResult {
+			ensure_root(origin)?;
+
+			Self::do_queue_code_upgrade(code)
+		}
+
 		#[pallet::weight(1_000_000)]
 		pub fn enact_authorized_upgrade(
 			_: OriginFor<T>,
@@ -414,6 +469,11 @@ pub mod pallet {
 		ValidationFunctionApplied(RelayChainBlockNumber),
 		/// The relay-chain aborted the upgrade process.
 		ValidationFunctionDiscarded,
+		/// A validation function has been queued until the relay chain allows an upgrade.
+		ValidationFunctionQueued,
+		/// The queued validation function was dropped, because it could not be scheduled.
+		/// \[ error \]
+		QueuedValidationFunctionDropped(DispatchError),
 		/// An upgrade has been authorized.
 		UpgradeAuthorized(T::Hash),
 		/// Some downward messages have been received and will be processed.
@@ -422,6 +482,12 @@ pub mod pallet {
 		/// Downward messages were processed using the given weight.
 		/// \[ weight_used, result_mqc_head \]
 		DownwardMessagesProcessed(Weight, relay_chain::Hash),
+		/// An upward message was queued to be sent to the relay chain.
+		/// \[ index \]
+		UpwardMessageQueued(u32),
+		/// Upward messages were put into the candidate of this block.
+		/// \[ first_index, count \]
+		UpwardMessagesSent(u32, u32),
 	}
 
 	#[pallet::error]
@@ -512,6 +578,9 @@ pub mod pallet {
 	/// before processing of the inherent, e.g. in `on_initialize` this data may be stale.
 	///
 	/// This data is also absent from the genesis.
+	///
+	/// The node can query it through the
+	/// [`HostConfigurationApi`](cumulus_primitives_core::HostConfigurationApi) runtime api.
 	#[pallet::storage]
 	#[pallet::getter(fn host_configuration)]
 	pub(super) type HostConfiguration<T: Config> = StorageValue<_, AbridgedHostConfiguration>;
@@ -537,6 +606,12 @@ pub mod pallet {
 	#[pallet::storage]
 	pub(super) type ProcessedDownwardMessages<T: Config> = StorageValue<_, u32, ValueQuery>;
 
+	/// Number of inbound horizontal messages processed in a block.
+	///
+	/// This will be cleared in `on_initialize` of each new block.
+	#[pallet::storage]
+	pub(super) type ProcessedHorizontalMessages<T: Config> = StorageValue<_, u32, ValueQuery>;
+
 	/// HRMP watermark that was set in a block.
 	///
 	/// This will be cleared in `on_initialize` of each new block.
@@ -562,6 +637,10 @@ pub mod pallet {
 	pub(super) type PendingUpwardMessages<T: Config> =
 		StorageValue<_, Vec<UpwardMessage>, ValueQuery>;
 
+	/// The index that is assigned to the next upward message that is sent.
+	#[pallet::storage]
+	pub(super) type NextUpwardMessageIndex<T: Config> = StorageValue<_, u32, ValueQuery>;
+
 	/// The number of HRMP messages we observed in `on_initialize` and thus used that number for
 	/// announcing the weight of `on_initialize` and `on_finalize`.
 	#[pallet::storage]
@@ -581,6 +660,13 @@ pub mod pallet {
 	#[pallet::storage]
 	pub(super) type AuthorizedUpgrade<T: Config> = StorageValue<_, T::Hash>;
 
+	/// Validation code that waits to be scheduled until the relay chain allows an upgrade.
+	///
+	/// See [`Pallet::queue_code_upgrade`] for more information.
+	#[pallet::storage]
+	#[pallet::getter(fn queued_validation_function)]
+	pub(super) type QueuedValidationCode<T: Config> = StorageValue<_, Vec<u8>, OptionQuery>;
+
 	/// A custom head data that should be returned as result of `validate_block`.
 	///
 	/// See [`Pallet::set_custom_validation_head_data`] for more information.
@@ -740,13 +826,17 @@ impl<T: Config> Pallet<T> {
 	/// # Panics
 	///
 	/// If it turns out that after processing all messages the Message Queue Chain
-	/// hash doesn't match the expected.
+	/// hash doesn't match the expected. The mismatch is logged before.
 	fn process_inbound_downward_messages(
 		expected_dmq_mqc_head: relay_chain::Hash,
 		downward_messages: Vec<InboundDownwardMessage>,
 	) -> Weight {
 		let dm_count = downward_messages.len() as u32;
-		let mut dmq_head = <LastDmqMqcHead<T>>::get();
+		let mut dmq_tracker = mqc::MqcTracker::new(
+			mqc::MqcChannel::Downward,
+			<LastDmqMqcHead<T>>::get(),
+			expected_dmq_mqc_head,
+		);
 
 		let mut weight_used = 0;
 		if dm_count != 0 {
@@ -757,13 +847,10 @@ impl<T: Config> Pallet<T> {
 			let message_iter = downward_messages
 				.into_iter()
 				.inspect(|m| {
-					dmq_head.extend_downward(m);
+					dmq_tracker.extend_downward(m);
 				})
 				.map(|m| (m.sent_at, m.msg));
 			weight_used += T::DmpMessageHandler::handle_dmp_messages(message_iter, max_weight);
-			<LastDmqMqcHead<T>>::put(&dmq_head);
-
-			Self::deposit_event(Event::DownwardMessagesProcessed(weight_used, dmq_head.head()));
 		}
 
 		// After hashing each message in the message queue chain submitted by the collator, we
@@ -771,7 +858,13 @@ impl<T: Config> Pallet<T> {
 		//
 		// A mismatch means that at least some of the submitted messages were altered, omitted or
 		// added improperly.
-		assert_eq!(dmq_head.head(), expected_dmq_mqc_head);
+		let dmq_head = dmq_tracker.finish().unwrap_or_else(|e| mqc::abort_on_mismatch(e));
+
+		if dm_count != 0 {
+			<LastDmqMqcHead<T>>::put(&dmq_head);
+
+			Self::deposit_event(Event::DownwardMessagesProcessed(weight_used, dmq_head.head()));
+		}
 
 		ProcessedDownwardMessages::<T>::put(dm_count);
 
@@ -824,6 +917,14 @@ impl<T: Config> Pallet<T> {
 		});
 
 		let last_mqc_heads = <LastHrmpMqcHeads<T>>::get();
+		let new_tracker =
+			|sender: ParaId, channel: &cumulus_primitives_core::AbridgedHrmpChannel| {
+				mqc::MqcTracker::new(
+					mqc::MqcChannel::Horizontal(sender),
+					last_mqc_heads.get(&sender).cloned().unwrap_or_default(),
+					channel.mqc_head.unwrap_or_default(),
+				)
+			};
 		let mut running_mqc_heads = BTreeMap::new();
 		let mut hrmp_watermark = None;
 
@@ -834,8 +935,14 @@ impl<T: Config> Pallet<T> {
 				}
 
 				running_mqc_heads
-					.entry(sender)
-					.or_insert_with(|| last_mqc_heads.get(&sender).cloned().unwrap_or_default())
+					.entry(*sender)
+					.or_insert_with(|| {
+						let channel = ingress_channels
+							.binary_search_by_key(sender, |&(s, _)| s)
+							.map(|i| &ingress_channels[i].1)
+							.expect("Senders without a channel were rejected above; qed");
+						new_tracker(*sender, channel)
+					})
 					.extend_hrmp(horizontal_message);
 			}
 		}
@@ -854,21 +961,23 @@ impl<T: Config> Pallet<T> {
 		// `running_mqc_heads`. Otherwise, in a block where no messages were sent in a channel
 		// it won't get into next block's `last_mqc_heads` and thus will be all zeros, which
 		// would corrupt the message queue chain.
-		for &(ref sender, ref channel) in ingress_channels {
-			let cur_head = running_mqc_heads
-				.entry(sender)
-				.or_insert_with(|| last_mqc_heads.get(&sender).cloned().unwrap_or_default())
-				.head();
-			let target_head = channel.mqc_head.unwrap_or_default();
-
-			assert!(cur_head == target_head);
+		let mut verified_mqc_heads = BTreeMap::new();
+		for &(sender, ref channel) in ingress_channels {
+			let head = running_mqc_heads
+				.remove(&sender)
+				.unwrap_or_else(|| new_tracker(sender, channel))
+				.finish()
+				.unwrap_or_else(|e| mqc::abort_on_mismatch(e));
+
+			verified_mqc_heads.insert(sender, head);
 		}
 
-		<LastHrmpMqcHeads<T>>::put(running_mqc_heads);
+		<LastHrmpMqcHeads<T>>::put(verified_mqc_heads);
 
 		// If we processed at least one message, then advance watermark to that location or if there
 		// were no messages, set it to the block number of the relay parent.
 		HrmpWatermark::<T>::put(hrmp_watermark.unwrap_or(relay_parent_number));
+		ProcessedHorizontalMessages::<T>::put(horizontal_messages.len() as u32);
 
 		weight_used
 	}
@@ -919,6 +1028,52 @@ impl<T: Config> Pallet<T> {
 		Ok(())
 	}
 
+	/// Queue a runtime upgrade that is scheduled as soon as the relay chain allows it.
+	///
+	/// Only one upgrade can be queued at a time. The code is checked against the maximum code
+	/// size of the relay chain, if that is already known.
+	pub fn do_queue_code_upgrade(validation_function: Vec<u8>) -> DispatchResult {
+		ensure!(!<QueuedValidationCode<T>>::exists(), Error::<T>::OverlappingUpgrades);
+		if let Some(max_code_size) = Self::max_code_size() {
+			ensure!(validation_function.len() <= max_code_size as usize, Error::<T>::TooBig);
+		}
+
+		<QueuedValidationCode<T>>::put(validation_function);
+		Self::deposit_event(Event::ValidationFunctionQueued);
+
+		Ok(())
+	}
+
+	/// Schedule the queued upgrade, if there is one and the relay chain accepts an upgrade.
+	///
+	/// Must be called after the validation data of the current block was set. Returns the
+	/// consumed weight.
+	fn schedule_queued_code_upgrade() -> Weight {
+		let weight = T::DbWeight::get().reads(3);
+		if !<QueuedValidationCode<T>>::exists() ||
+			<UpgradeRestrictionSignal<T>>::get().is_some() ||
+			<PendingValidationCode<T>>::exists()
+		{
+			return weight
+		}
+
+		if let Some(code) = <QueuedValidationCode<T>>::take() {
+			if let Err(error) = Self::schedule_code_upgrade(code) {
+				Self::deposit_event(Event::QueuedValidationFunctionDropped(error));
+			}
+		}
+
+		weight + Self::code_weight()
+	}
+
+	/// The weight of reading and writing the validation code.
+	///
+	/// The code can be several megabytes, so like `set_code` of `frame_system` this is a full
+	/// block.
+	fn code_weight() -> Weight {
+		T::BlockWeights::get().max_block
+	}
+
 	/// Returns the [`CollationInfo`] of the current active block.
 	///
 	/// The given `header` is the header of the built block we are collecting the collation info for.
@@ -940,6 +1095,12 @@ impl<T: Config> Pallet<T> {
 		}
 	}
 
+	/// Returns the hash of the validation code that waits for the go ahead of the relay chain.
+	pub fn pending_validation_code_hash() -> Option<relay_chain::v2::ValidationCodeHash> {
+		let code = <PendingValidationCode<T>>::get();
+		(!code.is_empty()).then(|| relay_chain::v2::ValidationCode(code).hash())
+	}
+
 	/// Set a custom head data that should be returned as result of `validate_block`.
 	///
 	/// This will overwrite the head data that is returned as result of `validate_block` while
@@ -966,6 +1127,11 @@ impl<T: Config> frame_system::SetCode<T> for ParachainSetCode<T> {
 }
 
 impl<T: Config> Pallet<T> {
+	/// Queue an upward message to be sent to the relay chain and return its index.
+	///
+	/// The message is sent with one of the next candidates, as the relay chain limits how many
+	/// messages each candidate can carry. `UpwardMessageQueued` and `UpwardMessagesSent` report
+	/// the index of the message when it is queued and sent.
 	pub fn send_upward_message(message: UpwardMessage) -> Result<u32, MessageSendError> {
 		// Check if the message fits into the relay-chain constraints.
 		//
@@ -997,7 +1163,57 @@ impl<T: Config> Pallet<T> {
 			},
 		};
 		<PendingUpwardMessages<T>>::append(message);
-		Ok(0)
+
+		let index = <NextUpwardMessageIndex<T>>::mutate(|next| {
+			let index = *next;
+			*next = next.wrapping_add(1);
+			index
+		});
+		Self::deposit_event(Event::UpwardMessageQueued(index));
+
+		Ok(index)
+	}
+
+	/// Read the relay chain storage entry at `key` from the relay chain state proof of the
+	/// current block and decode it.
+	///
+	/// Only entries the collator put into the proof can be read. Returns `None` if the validation
+	/// data was not set yet in this block, e.g. in `on_initialize`, if the entry is absent on the
+	/// relay chain, if it is not part of the proof or if it can not be decoded.
+	///
+	/// The proof is decoded on every call, so callers reading several entries should use
+	/// [`Self::relay_chain_state_proof`] instead.
+	pub fn read_relay_entry<V: Decode>(key: &[u8]) -> Option<V> {
+		Self::relay_chain_state_proof()?.read_optional_entry(key).ok().flatten()
+	}
+
+	/// Returns the relay chain state proof of the current block.
+	///
+	/// Returns `None` if the validation data was not set yet in this block.
+	pub fn relay_chain_state_proof() -> Option<RelayChainStateProof> {
+		let validation_data = Self::validation_data()?;
+		let proof = RelayStateProof::<T>::get()?;
+
+		RelayChainStateProof::new(
+			T::SelfParaId::get(),
+			validation_data.relay_parent_storage_root,
+			proof,
+		)
+		.ok()
+	}
+}
+
+impl<T: Config> RelayChainStateInfo for Pallet<T> {
+	fn relay_parent_number() -> Option<RelayChainBlockNumber> {
+		Self::validation_data().map(|d| d.relay_parent_number)
+	}
+
+	fn relay_parent_storage_root() -> Option<relay_chain::Hash> {
+		Self::validation_data().map(|d| d.relay_parent_storage_root)
+	}
+
+	fn relay_state_proof() -> Option<sp_trie::StorageProof> {
+		RelayStateProof::<T>::get()
 	}
 }
 
@@ -1048,3 +1264,19 @@ impl<T: Config> BlockNumberProvider for RelaychainBlockNumberProvider<T> {
 			.unwrap_or_default()
 	}
 }
+
+/// Implements [`Get`] that returns the BABE randomness of the previous relay chain epoch, read
+/// from the relay chain state proof of the current block.
+///
+/// This is the randomness the relay chain itself uses in its current epoch. It was fixed before
+/// the epoch started, so it can not be influenced by the parachain block authors.
+///
+/// Returns `None` when validation data is not available (e.g. within on_initialize) or the entry
+/// is not part of the relay chain state proof.
+pub struct RelayEpochRandomness<T>(sp_std::marker::PhantomData<T>);
+
+impl<T: Config> Get<Option<relay_chain::Hash>> for RelayEpochRandomness<T> {
+	fn get() -> Option<relay_chain::Hash> {
+		Pallet::<T>::read_relay_entry(relay_chain::well_known_keys::ONE_EPOCH_AGO_RANDOMNESS)
+	}
+}
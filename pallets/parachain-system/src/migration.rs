@@ -37,6 +37,51 @@ pub fn on_runtime_upgrade<T: Config>() -> Weight {
 	weight
 }
 
+/// Key of the temporary storage that holds the MQC heads observed before the upgrade.
+#[cfg(feature = "try-runtime")]
+const MQC_HEADS_KEY: &str = "cumulus-pallet-parachain-system::mqc-heads";
+
+/// Remember the message queue chain heads before the runtime upgrade.
+#[cfg(feature = "try-runtime")]
+pub fn pre_upgrade<T: Config>() -> Result<(), &'static str> {
+	use frame_support::traits::OnRuntimeUpgradeHelpersExt;
+
+	Pallet::<T>::set_temp_storage(mqc_heads::<T>(), MQC_HEADS_KEY);
+	Ok(())
+}
+
+/// Ensure that the runtime upgrade did not touch the message queue chain heads.
+///
+/// If the heads were reset or altered, the messages the relay chain has already delivered would
+/// be processed again or the next inherent would be rejected, because its messages do not extend
+/// the stored heads.
+#[cfg(feature = "try-runtime")]
+pub fn post_upgrade<T: Config>() -> Result<(), &'static str> {
+	use frame_support::traits::OnRuntimeUpgradeHelpersExt;
+
+	let before: sp_std::vec::Vec<u8> = Pallet::<T>::get_temp_storage(MQC_HEADS_KEY)
+		.ok_or("MQC heads were not recorded before the upgrade")?;
+
+	frame_support::ensure!(
+		StorageVersion::get::<Pallet<T>>() == STORAGE_VERSION,
+		"Storage version was not updated by the upgrade",
+	);
+	frame_support::ensure!(
+		before == mqc_heads::<T>(),
+		"The upgrade changed the MQC heads of the downward or horizontal message queues",
+	);
+
+	Ok(())
+}
+
+/// The encoded downward and horizontal message queue chain heads.
+#[cfg(feature = "try-runtime")]
+fn mqc_heads<T: Config>() -> sp_std::vec::Vec<u8> {
+	use codec::Encode;
+
+	(crate::LastDmqMqcHead::<T>::get(), crate::LastHrmpMqcHeads::<T>::get()).encode()
+}
+
 /// V1: `LastUpgrade` block number is removed from the storage since the upgrade
 /// mechanism now uses signals instead of block offsets.
 mod v1 {
@@ -48,3 +93,26 @@ mod v1 {
 		T::DbWeight::get().writes(1)
 	}
 }
+
+#[cfg(all(test, feature = "try-runtime"))]
+mod tests {
+	use super::*;
+	use crate::tests::{new_test_ext, Test};
+	use cumulus_primitives_core::InboundDownwardMessage;
+
+	#[test]
+	fn post_upgrade_fails_if_mqc_heads_changed() {
+		new_test_ext().execute_with(|| {
+			STORAGE_VERSION.put::<Pallet<Test>>();
+
+			assert_eq!(pre_upgrade::<Test>(), Ok(()));
+			assert_eq!(post_upgrade::<Test>(), Ok(()));
+
+			crate::LastDmqMqcHead::<Test>::mutate(|head| {
+				head.extend_downward(&InboundDownwardMessage { sent_at: 1, msg: vec![1, 2, 3] });
+			});
+
+			assert!(post_upgrade::<Test>().is_err());
+		});
+	}
+}
@@ -0,0 +1,127 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Verification of inbound messages against the message queue chain (MQC) heads of the relay chain.
+//!
+//! The relay chain only provides the head of each message queue chain, so a mismatch can not be
+//! attributed to a single message in general. However, if the expected head was reached before
+//! all messages were applied, the messages after that point are not part of the relay chain
+//! queue. [`MqcMismatch`] reports this, together with the channel the mismatch occurred in.
+
+use cumulus_primitives_core::{relay_chain, InboundDownwardMessage, InboundHrmpMessage, ParaId};
+use cumulus_primitives_parachain_inherent::MessageQueueChain;
+
+/// The message queue a message queue chain belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MqcChannel {
+	/// The downward message queue.
+	Downward,
+	/// The inbound horizontal channel from the given sender.
+	Horizontal(ParaId),
+}
+
+/// The inbound messages of a channel did not lead to the MQC head expected by the relay chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MqcMismatch {
+	/// The channel the mismatch occurred in.
+	pub channel: MqcChannel,
+	/// The number of messages that were applied to the channel.
+	pub message_count: u32,
+	/// The number of messages after which the expected head was reached, if it was reached.
+	///
+	/// All messages starting at this index are not part of the relay chain queue.
+	pub expected_reached_after: Option<u32>,
+	/// The head provided by the relay chain.
+	pub expected_head: relay_chain::Hash,
+	/// The head computed from the inbound messages.
+	pub computed_head: relay_chain::Hash,
+}
+
+/// Tracks a message queue chain while inbound messages are applied to it.
+pub struct MqcTracker {
+	channel: MqcChannel,
+	head: MessageQueueChain,
+	expected_head: relay_chain::Hash,
+	message_count: u32,
+	expected_reached_after: Option<u32>,
+}
+
+impl MqcTracker {
+	/// Start tracking `channel` from the last known `head` towards the `expected_head`.
+	pub fn new(
+		channel: MqcChannel,
+		head: MessageQueueChain,
+		expected_head: relay_chain::Hash,
+	) -> Self {
+		let expected_reached_after = if head.head() == expected_head { Some(0) } else { None };
+
+		Self { channel, head, expected_head, message_count: 0, expected_reached_after }
+	}
+
+	/// Apply a downward message.
+	pub fn extend_downward(&mut self, message: &InboundDownwardMessage) {
+		self.head.extend_downward(message);
+		self.note_message();
+	}
+
+	/// Apply a horizontal message.
+	pub fn extend_hrmp(&mut self, message: &InboundHrmpMessage) {
+		self.head.extend_hrmp(message);
+		self.note_message();
+	}
+
+	fn note_message(&mut self) {
+		self.message_count += 1;
+
+		if self.expected_reached_after.is_none() && self.head.head() == self.expected_head {
+			self.expected_reached_after = Some(self.message_count);
+		}
+	}
+
+	/// Returns the resulting message queue chain if it ends at the expected head.
+	pub fn finish(self) -> Result<MessageQueueChain, MqcMismatch> {
+		if self.head.head() == self.expected_head {
+			return Ok(self.head)
+		}
+
+		Err(MqcMismatch {
+			channel: self.channel,
+			message_count: self.message_count,
+			expected_reached_after: self.expected_reached_after,
+			expected_head: self.expected_head,
+			computed_head: self.head.head(),
+		})
+	}
+}
+
+/// Log `mismatch` and abort the block.
+///
+/// A mismatch means that the collator altered, omitted or added messages. The block is invalid
+/// and must not be imported.
+pub(crate) fn abort_on_mismatch(mismatch: MqcMismatch) -> ! {
+	log::error!(
+		target: "runtime::parachain-system",
+		"MQC head mismatch in {:?} after {} messages: expected {:?}, computed {:?}, expected head \
+		 reached after {:?} messages",
+		mismatch.channel,
+		mismatch.message_count,
+		mismatch.expected_head,
+		mismatch.computed_head,
+		mismatch.expected_reached_after,
+	);
+
+	panic!("Inbound messages do not match the MQC head of the relay chain: {:?}", mismatch)
+}
@@ -241,6 +241,14 @@ impl RelayChainStateProof {
 			.map_err(Error::Slot)
 	}
 
+	/// Read the entry at `key` from the relay chain state proof and decode it.
+	///
+	/// Returns `Ok(None)` if the entry is absent on the relay chain. Returns an error if the
+	/// entry is not part of the proof or can not be decoded.
+	pub fn read_optional_entry<T: Decode>(&self, key: &[u8]) -> Result<Option<T>, ReadEntryErr> {
+		read_optional_entry(&self.trie_backend, key)
+	}
+
 	/// Read the go-ahead signal for the upgrade from the relay chain state proof.
 	///
 	/// The go-ahead specifies whether the parachain can apply the upgrade or should abort it. If
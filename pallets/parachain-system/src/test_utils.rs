@@ -0,0 +1,140 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Utilities for unit tests of pallets that depend on the validation data of parachain-system.
+
+use crate::{Config, Pallet, ValidationData};
+use cumulus_primitives_core::{relay_chain, PersistedValidationData};
+use cumulus_primitives_parachain_inherent::ParachainInherentData;
+use cumulus_test_relay_sproof_builder::RelayStateSproofBuilder;
+use frame_support::traits::{Get, OnFinalize, OnInitialize};
+use frame_system::RawOrigin;
+use sp_runtime::traits::One;
+
+/// Inject the given validation data into `ext`.
+///
+/// Code that reads the validation data outside of a block, e.g. in unit tests of pallets that
+/// expect it to be present, can be exercised without running the `set_validation_data` inherent.
+/// Use [`TestRelayContext`] for tests that span multiple blocks.
+///
+/// ```ignore
+/// let mut ext = with_validation_data::<Test>(
+///     new_test_ext(),
+///     PersistedValidationData { relay_parent_number: 10, ..Default::default() },
+/// );
+/// ext.execute_with(|| assert!(ParachainSystem::validation_data().is_some()));
+/// ```
+pub fn with_validation_data<T: Config>(
+	mut ext: sp_io::TestExternalities,
+	validation_data: PersistedValidationData,
+) -> sp_io::TestExternalities {
+	ext.execute_with(|| ValidationData::<T>::put(validation_data));
+	ext
+}
+
+/// Drives test blocks that carry coherent relay chain validation data.
+///
+/// Every block that is run through [`Self::run_block_with_relay`] is initialized, gets the
+/// `set_validation_data` inherent of parachain-system applied and is finalized afterwards. Pallets
+/// that read the validation data, e.g. through [`Pallet::validation_data`] or the
+/// [`RelaychainBlockNumberProvider`](crate::RelaychainBlockNumberProvider), see the same data they
+/// would see in a real block.
+///
+/// Only the hooks of `frame-system` and parachain-system are executed. Hooks of the pallet under
+/// test should be called from the closure passed to [`Self::run_block_with_relay`].
+///
+/// ```ignore
+/// new_test_ext().execute_with(|| {
+///     let mut relay = TestRelayContext::<Test>::new();
+///
+///     relay.run_block_with_relay(10, |_| {
+///         assert_eq!(RelaychainBlockNumberProvider::<Test>::current_block_number(), 10);
+///     });
+///     relay.run_block_with_relay(12, |_| MyPallet::on_initialize(2));
+/// });
+/// ```
+pub struct TestRelayContext<T> {
+	sproof_builder: RelayStateSproofBuilder,
+	validation_data: Option<PersistedValidationData>,
+	_config: sp_std::marker::PhantomData<T>,
+}
+
+impl<T: Config> Default for TestRelayContext<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Config> TestRelayContext<T> {
+	/// Create a new instance for the parachain configured as [`Config::SelfParaId`].
+	pub fn new() -> Self {
+		let sproof_builder =
+			RelayStateSproofBuilder { para_id: T::SelfParaId::get(), ..Default::default() };
+
+		Self { sproof_builder, validation_data: None, _config: Default::default() }
+	}
+
+	/// The builder of the relay chain state proof used for all upcoming blocks.
+	///
+	/// This can be used to change the host configuration, signal an upgrade go-ahead etc.
+	pub fn sproof_builder_mut(&mut self) -> &mut RelayStateSproofBuilder {
+		&mut self.sproof_builder
+	}
+
+	/// The validation data of the last block that was run.
+	pub fn validation_data(&self) -> Option<&PersistedValidationData> {
+		self.validation_data.as_ref()
+	}
+
+	/// Run the next parachain block on top of the relay parent with the given number.
+	///
+	/// The block is initialized and the validation data inherent is applied before `within_block`
+	/// is called. The block is finalized after `within_block` returned. The relay parent number
+	/// should not decrease between two calls.
+	pub fn run_block_with_relay<R>(
+		&mut self,
+		relay_parent_number: relay_chain::v2::BlockNumber,
+		within_block: impl FnOnce(&mut Self) -> R,
+	) -> R {
+		let number = frame_system::Pallet::<T>::block_number() + One::one();
+
+		frame_system::Pallet::<T>::reset_events();
+		frame_system::Pallet::<T>::initialize(&number, &Default::default(), &Default::default());
+		Pallet::<T>::on_initialize(number);
+
+		let (validation_data, relay_chain_state) =
+			self.sproof_builder.clone().into_validation_data_and_proof(relay_parent_number);
+
+		Pallet::<T>::set_validation_data(
+			RawOrigin::None.into(),
+			ParachainInherentData {
+				validation_data: validation_data.clone(),
+				relay_chain_state,
+				downward_messages: Default::default(),
+				horizontal_messages: Default::default(),
+			},
+		)
+		.expect("Applies the validation data inherent");
+		self.validation_data = Some(validation_data);
+
+		let result = within_block(self);
+
+		Pallet::<T>::on_finalize(number);
+		frame_system::Pallet::<T>::finalize();
+
+		result
+	}
+}
@@ -116,6 +116,12 @@ std::thread_local! {
 	static HANDLED_DMP_MESSAGES: RefCell<Vec<(relay_chain::BlockNumber, Vec<u8>)>> = RefCell::new(Vec::new());
 	static HANDLED_XCMP_MESSAGES: RefCell<Vec<(ParaId, relay_chain::BlockNumber, Vec<u8>)>> = RefCell::new(Vec::new());
 	static SENT_MESSAGES: RefCell<Vec<(ParaId, Vec<u8>)>> = RefCell::new(Vec::new());
+	static VALIDATION_DATA_WEIGHT: RefCell<Weight> = RefCell::new(0);
+}
+
+/// Returns the weight `set_validation_data` returned in the current block of [`BlockTests`].
+fn validation_data_weight() -> Weight {
+	VALIDATION_DATA_WEIGHT.with(|w| *w.borrow())
 }
 
 fn send_message(dest: ParaId, message: Vec<u8>) {
@@ -175,7 +181,7 @@ impl XcmpMessageHandler for SaveIntoThreadLocal {
 
 // This function basically just builds a genesis storage key/value store according to
 // our desired mockup.
-fn new_test_ext() -> sp_io::TestExternalities {
+pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
 	HANDLED_DMP_MESSAGES.with(|m| m.borrow_mut().clear());
 	HANDLED_XCMP_MESSAGES.with(|m| m.borrow_mut().clear());
 
@@ -349,10 +355,13 @@ impl BlockTests {
 
 				// execute the block
 				ParachainSystem::on_initialize(*n);
-				ParachainSystem::create_inherent(&inherent_data)
+				let post_info = ParachainSystem::create_inherent(&inherent_data)
 					.expect("got an inherent")
 					.dispatch_bypass_filter(RawOrigin::None.into())
 					.expect("dispatch succeeded");
+				VALIDATION_DATA_WEIGHT.with(|w| {
+					*w.borrow_mut() = post_info.actual_weight.expect("returns the actual weight")
+				});
 				within_block();
 				ParachainSystem::on_finalize(*n);
 
@@ -508,6 +517,153 @@ fn checks_size() {
 		});
 }
 
+#[test]
+fn queued_upgrade_waits_for_the_relay_chain() {
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, block_number, builder| {
+			if block_number <= 123 {
+				builder.upgrade_restriction = Some(relay_chain::v2::UpgradeRestriction::Present);
+			}
+			if block_number > 124 {
+				builder.upgrade_go_ahead = Some(relay_chain::v2::UpgradeGoAhead::GoAhead);
+			}
+		})
+		.add_with_post_test(
+			123,
+			|| {
+				assert_eq!(
+					System::set_code(RawOrigin::Root.into(), Default::default()),
+					Err(Error::<Test>::ProhibitedByPolkadot.into()),
+				);
+				assert_ok!(ParachainSystem::queue_code_upgrade(
+					RawOrigin::Root.into(),
+					Default::default()
+				));
+			},
+			|| {
+				assert!(<QueuedValidationCode<Test>>::exists());
+				assert!(!<PendingValidationCode<Test>>::exists());
+				let events = System::events();
+				assert_eq!(
+					events[0].event,
+					Event::ParachainSystem(crate::Event::ValidationFunctionQueued.into())
+				);
+			},
+		)
+		.add_with_post_test(
+			124,
+			|| {},
+			|| {
+				assert!(!<QueuedValidationCode<Test>>::exists());
+				assert!(<PendingValidationCode<Test>>::exists());
+				let events = System::events();
+				assert_eq!(
+					events[0].event,
+					Event::ParachainSystem(crate::Event::ValidationFunctionStored.into())
+				);
+			},
+		)
+		.add_with_post_test(
+			1234,
+			|| {},
+			|| {
+				assert!(!<PendingValidationCode<Test>>::exists());
+				let events = System::events();
+				assert_eq!(
+					events[0].event,
+					Event::ParachainSystem(crate::Event::ValidationFunctionApplied(1234).into())
+				);
+			},
+		);
+}
+
+#[test]
+fn queued_upgrade_waits_for_pending_upgrade() {
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, block_number, builder| {
+			if block_number > 123 {
+				builder.upgrade_go_ahead = Some(relay_chain::v2::UpgradeGoAhead::GoAhead);
+			}
+		})
+		.add(123, || {
+			assert_ok!(System::set_code(RawOrigin::Root.into(), Default::default()));
+			assert_ok!(ParachainSystem::queue_code_upgrade(
+				RawOrigin::Root.into(),
+				Default::default()
+			));
+			assert_eq!(
+				ParachainSystem::queue_code_upgrade(RawOrigin::Root.into(), Default::default()),
+				Err(Error::<Test>::OverlappingUpgrades.into()),
+			);
+		})
+		.add_with_post_test(
+			1234,
+			|| {},
+			|| {
+				assert!(!<QueuedValidationCode<Test>>::exists());
+				assert!(<PendingValidationCode<Test>>::exists());
+				let events = System::events();
+				assert_eq!(
+					events[0].event,
+					Event::ParachainSystem(crate::Event::ValidationFunctionApplied(1234).into())
+				);
+				assert_eq!(
+					events[1].event,
+					Event::ParachainSystem(crate::Event::ValidationFunctionStored.into())
+				);
+			},
+		);
+}
+
+#[test]
+fn validation_data_weight_includes_code_upgrade() {
+	let max_block = <Test as frame_system::Config>::BlockWeights::get().max_block;
+
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, block_number, builder| {
+			if block_number <= 123 {
+				builder.upgrade_restriction = Some(relay_chain::v2::UpgradeRestriction::Present);
+			}
+			if block_number == 1234 {
+				builder.upgrade_go_ahead = Some(relay_chain::v2::UpgradeGoAhead::GoAhead);
+			}
+		})
+		.add(123, move || {
+			assert!(validation_data_weight() < max_block);
+			assert_ok!(ParachainSystem::queue_code_upgrade(
+				RawOrigin::Root.into(),
+				Default::default()
+			));
+		})
+		.add(124, move || {
+			// The queued code was handed over to the relay chain.
+			assert!(<PendingValidationCode<Test>>::exists());
+			assert!(validation_data_weight() >= max_block);
+		})
+		.add(1234, move || {
+			// The pending code was applied.
+			assert!(!<PendingValidationCode<Test>>::exists());
+			assert!(validation_data_weight() >= max_block);
+		})
+		.add(1235, move || {
+			assert!(validation_data_weight() < max_block);
+		});
+}
+
+#[test]
+fn queued_upgrade_checks_size() {
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, _, builder| {
+			builder.host_config.max_code_size = 8;
+		})
+		.add(123, || {
+			assert_eq!(
+				ParachainSystem::queue_code_upgrade(RawOrigin::Root.into(), vec![0; 64]),
+				Err(Error::<Test>::TooBig.into()),
+			);
+		});
+}
+
 #[test]
 fn send_upward_message_num_per_candidate() {
 	BlockTests::new()
@@ -536,6 +692,48 @@ fn send_upward_message_num_per_candidate() {
 		);
 }
 
+#[test]
+fn send_upward_message_reports_indices() {
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, _, sproof| {
+			sproof.host_config.max_upward_message_num_per_candidate = 2;
+			sproof.relay_dispatch_queue_size = None;
+		})
+		.add_with_post_test(
+			1,
+			|| {
+				assert_eq!(ParachainSystem::send_upward_message(b"up 0".to_vec()), Ok(0));
+				assert_eq!(ParachainSystem::send_upward_message(b"up 1".to_vec()), Ok(1));
+				assert_eq!(ParachainSystem::send_upward_message(b"up 2".to_vec()), Ok(2));
+			},
+			|| {
+				let events: Vec<_> = System::events().into_iter().map(|r| r.event).collect();
+				assert_eq!(
+					events,
+					vec![
+						Event::ParachainSystem(crate::Event::UpwardMessageQueued(0)),
+						Event::ParachainSystem(crate::Event::UpwardMessageQueued(1)),
+						Event::ParachainSystem(crate::Event::UpwardMessageQueued(2)),
+						Event::ParachainSystem(crate::Event::UpwardMessagesSent(0, 2)),
+					]
+				);
+			},
+		)
+		.add_with_post_test(
+			2,
+			|| {
+				assert_eq!(ParachainSystem::send_upward_message(b"up 3".to_vec()), Ok(3));
+			},
+			|| {
+				let v = UpwardMessages::<Test>::get();
+				assert_eq!(v, vec![b"up 2".to_vec(), b"up 3".to_vec()]);
+				System::assert_last_event(Event::ParachainSystem(
+					crate::Event::UpwardMessagesSent(2, 2),
+				));
+			},
+		);
+}
+
 #[test]
 fn send_upward_message_relay_bottleneck() {
 	BlockTests::new()
@@ -961,3 +1159,201 @@ fn receive_hrmp_after_pause() {
 			});
 		});
 }
+
+#[test]
+fn with_validation_data_injects_validation_data() {
+	use crate::test_utils::with_validation_data;
+
+	let validation_data = PersistedValidationData { relay_parent_number: 10, ..Default::default() };
+
+	with_validation_data::<Test>(new_test_ext(), validation_data.clone()).execute_with(|| {
+		assert_eq!(ParachainSystem::validation_data(), Some(validation_data));
+		assert_eq!(RelaychainBlockNumberProvider::<Test>::current_block_number(), 10);
+	});
+}
+
+#[test]
+fn read_relay_entry_works() {
+	use crate::test_utils::TestRelayContext;
+
+	new_test_ext().execute_with(|| {
+		let mut relay = TestRelayContext::<Test>::new();
+		assert_eq!(ParachainSystem::read_relay_entry::<u32>(b"key"), None);
+
+		relay.sproof_builder_mut().additional_key_values = vec![(b"key".to_vec(), 42u32.encode())];
+		relay.run_block_with_relay(10, |_| {
+			assert_eq!(ParachainSystem::read_relay_entry::<u32>(b"key"), Some(42));
+			assert_eq!(ParachainSystem::read_relay_entry::<u32>(b"absent"), None);
+			assert_eq!(
+				ParachainSystem::read_relay_entry::<AbridgedHostConfiguration>(
+					relay_chain::well_known_keys::ACTIVE_CONFIG
+				)
+				.map(|config| config.max_code_size),
+				Some(2 * 1024 * 1024),
+			);
+		});
+	});
+}
+
+#[test]
+fn relay_epoch_randomness_is_read_from_the_proof() {
+	use crate::test_utils::TestRelayContext;
+
+	new_test_ext().execute_with(|| {
+		let mut relay = TestRelayContext::<Test>::new();
+		assert_eq!(RelayEpochRandomness::<Test>::get(), None);
+
+		relay.sproof_builder_mut().additional_key_values = vec![(
+			relay_chain::well_known_keys::ONE_EPOCH_AGO_RANDOMNESS.to_vec(),
+			[7u8; 32].encode(),
+		)];
+		relay.run_block_with_relay(10, |_| {
+			assert_eq!(
+				RelayEpochRandomness::<Test>::get(),
+				Some(relay_chain::Hash::repeat_byte(7))
+			);
+		});
+	});
+}
+
+#[test]
+fn relay_chain_state_info_is_read_from_the_validation_data() {
+	use crate::test_utils::TestRelayContext;
+
+	new_test_ext().execute_with(|| {
+		let mut relay = TestRelayContext::<Test>::new();
+		assert_eq!(<ParachainSystem as RelayChainStateInfo>::relay_parent_number(), None);
+
+		relay.run_block_with_relay(10, |ctx| {
+			let validation_data = ctx.validation_data().unwrap();
+			assert_eq!(<ParachainSystem as RelayChainStateInfo>::relay_parent_number(), Some(10));
+			assert_eq!(
+				<ParachainSystem as RelayChainStateInfo>::relay_parent_storage_root(),
+				Some(validation_data.relay_parent_storage_root),
+			);
+			assert!(<ParachainSystem as RelayChainStateInfo>::relay_state_proof().is_some());
+		});
+	});
+}
+
+#[test]
+fn test_relay_context_applies_validation_data() {
+	use crate::test_utils::TestRelayContext;
+
+	new_test_ext().execute_with(|| {
+		let mut relay = TestRelayContext::<Test>::new();
+
+		relay.run_block_with_relay(10, |ctx| {
+			assert_eq!(System::block_number(), 1);
+			assert_eq!(ParachainSystem::validation_data().as_ref(), ctx.validation_data());
+			assert_eq!(RelaychainBlockNumberProvider::<Test>::current_block_number(), 10);
+		});
+
+		relay.sproof_builder_mut().host_config.max_code_size = 42;
+		relay.run_block_with_relay(12, |_| {
+			assert_eq!(System::block_number(), 2);
+			assert_eq!(RelaychainBlockNumberProvider::<Test>::current_block_number(), 12);
+			assert_eq!(ParachainSystem::max_code_size(), Some(42));
+		});
+	});
+}
+
+#[test]
+fn runtime_upgrade_keeps_mqc_heads() {
+	use frame_support::traits::{OnRuntimeUpgrade, StorageVersion};
+
+	const ALICE: ParaId = ParaId::new(300);
+
+	new_test_ext().execute_with(|| {
+		let mut dmq_head = MessageQueueChain::default();
+		dmq_head.extend_downward(&InboundDownwardMessage { sent_at: 1, msg: b"down".to_vec() });
+		let mut hrmp_head = MessageQueueChain::default();
+		hrmp_head.extend_hrmp(&InboundHrmpMessage { sent_at: 1, data: b"horizontal".to_vec() });
+
+		LastDmqMqcHead::<Test>::put(dmq_head.clone());
+		LastHrmpMqcHeads::<Test>::put(BTreeMap::from([(ALICE, hrmp_head.clone())]));
+		StorageVersion::new(0).put::<ParachainSystem>();
+
+		ParachainSystem::on_runtime_upgrade();
+
+		assert_eq!(StorageVersion::get::<ParachainSystem>(), migration::STORAGE_VERSION);
+		assert_eq!(LastDmqMqcHead::<Test>::get().head(), dmq_head.head());
+		assert_eq!(
+			LastHrmpMqcHeads::<Test>::get().get(&ALICE).map(|h| h.head()),
+			Some(hrmp_head.head()),
+		);
+	});
+}
+
+#[test]
+fn mqc_tracker_reports_excess_messages() {
+	use crate::mqc::{MqcChannel, MqcMismatch, MqcTracker};
+
+	let first = InboundDownwardMessage { sent_at: 1, msg: b"first".to_vec() };
+	let second = InboundDownwardMessage { sent_at: 2, msg: b"second".to_vec() };
+
+	let expected_head = MessageQueueChain::default().extend_downward(&first).head();
+	let computed_head = MessageQueueChain::default()
+		.extend_downward(&first)
+		.extend_downward(&second)
+		.head();
+
+	let mut tracker = MqcTracker::new(MqcChannel::Downward, Default::default(), expected_head);
+	tracker.extend_downward(&first);
+	assert_eq!(tracker.finish().map(|h| h.head()), Ok(expected_head));
+
+	let mut tracker = MqcTracker::new(MqcChannel::Downward, Default::default(), expected_head);
+	tracker.extend_downward(&first);
+	tracker.extend_downward(&second);
+	assert_eq!(
+		tracker.finish().map(|h| h.head()),
+		Err(MqcMismatch {
+			channel: MqcChannel::Downward,
+			message_count: 2,
+			expected_reached_after: Some(1),
+			expected_head,
+			computed_head,
+		}),
+	);
+}
+
+#[test]
+fn block_summary_is_deposited() {
+	lazy_static::lazy_static! {
+		static ref MSG: InboundDownwardMessage = InboundDownwardMessage {
+			sent_at: 1,
+			msg: b"down".to_vec(),
+		};
+	}
+
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, _, sproof| {
+			sproof.dmq_mqc_head = Some(MessageQueueChain::default().extend_downward(&MSG).head());
+			sproof.host_config.max_upward_message_num_per_candidate = 1;
+			sproof.relay_dispatch_queue_size = None;
+		})
+		.with_inherent_data(|_, relay_block_num, data| {
+			if relay_block_num == 1 {
+				data.downward_messages.push(MSG.clone());
+			}
+		})
+		.add_with_post_test(
+			1,
+			|| {
+				ParachainSystem::send_upward_message(b"up 1".to_vec()).unwrap();
+				ParachainSystem::send_upward_message(b"up 2".to_vec()).unwrap();
+			},
+			|| {
+				assert_eq!(
+					BlockSummary::extract(&System::digest()),
+					Some(BlockSummary {
+						downward_messages: 1,
+						horizontal_messages: 0,
+						upward_messages: 1,
+						outbound_horizontal_messages: 0,
+						upgrade_pending: false,
+					}),
+				);
+			},
+		);
+}
@@ -0,0 +1,130 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Relay chain randomness pallet.
+//!
+//! Provides randomness to the runtime through the [`Randomness`] trait.
+//!
+//! ## Overview
+//!
+//! Randomness derived from parachain state, e.g. the relay parent number or the parent hash, is
+//! either predictable or can be influenced by the block author. Instead, this pallet sources its
+//! randomness from the BABE randomness of the previous relay chain epoch, which the collators put
+//! into the relay chain state proof of every block. The relay chain fixes this value before its
+//! epoch starts, so parachain block authors can neither predict nor bias it.
+//!
+//! The randomness is read through [`Config::RelayEpochRandomness`], usually
+//! `cumulus_pallet_parachain_system::RelayEpochRandomness`, at the end of every block and is
+//! stored together with the parachain block number in which it changed. The stored value is
+//! what [`Randomness::random`] uses, so all calls within a block see the same randomness, even
+//! in `on_initialize` where the relay chain state proof is not yet available.
+//!
+//! The randomness changes once per relay chain epoch. Consumers that need a value that was
+//! unknown at a given block, e.g. a lottery drawing after tickets were sold, should check the
+//! returned block number.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use cumulus_primitives_core::relay_chain::Hash as RelayChainHash;
+use frame_support::traits::{Get, Randomness};
+use sp_runtime::traits::Hash;
+use sp_std::vec::Vec;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The BABE randomness of the previous relay chain epoch, read from the relay chain state
+		/// proof of the current block.
+		///
+		/// Returns `None` if the randomness is not available.
+		type RelayEpochRandomness: Get<Option<RelayChainHash>>;
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: T::BlockNumber) -> Weight {
+			// Reading the randomness in `on_finalize` reads the validation data, the relay chain
+			// state proof and the stored randomness.
+			T::DbWeight::get().reads_writes(3, 2)
+		}
+
+		fn on_finalize(n: T::BlockNumber) {
+			let randomness = match T::RelayEpochRandomness::get() {
+				Some(randomness) => randomness,
+				None => return,
+			};
+
+			if EpochRandomness::<T>::get() != Some(randomness) {
+				EpochRandomness::<T>::put(randomness);
+				RandomnessKnownSince::<T>::put(n);
+				Self::deposit_event(Event::RandomnessUpdated { randomness });
+			}
+		}
+	}
+
+	/// The last relay chain epoch randomness seen in a relay chain state proof.
+	#[pallet::storage]
+	#[pallet::getter(fn epoch_randomness)]
+	pub type EpochRandomness<T: Config> = StorageValue<_, RelayChainHash, OptionQuery>;
+
+	/// The parachain block in which [`EpochRandomness`] changed to its current value.
+	#[pallet::storage]
+	#[pallet::getter(fn randomness_known_since)]
+	pub type RandomnessKnownSince<T: Config> = StorageValue<_, T::BlockNumber, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A new relay chain epoch randomness was seen.
+		RandomnessUpdated { randomness: RelayChainHash },
+	}
+}
+
+impl<T: Config> Randomness<T::Hash, T::BlockNumber> for Pallet<T> {
+	/// Returns the hash of `subject` and the current relay chain epoch randomness, together with
+	/// the parachain block in which the epoch randomness became known.
+	///
+	/// Until the first epoch randomness was seen, the output only depends on `subject` and must
+	/// not be relied on.
+	fn random(subject: &[u8]) -> (T::Hash, T::BlockNumber) {
+		let randomness = EpochRandomness::<T>::get().unwrap_or_default();
+
+		let mut input = Vec::with_capacity(subject.len() + randomness.as_ref().len());
+		input.extend_from_slice(subject);
+		input.extend_from_slice(randomness.as_ref());
+
+		(T::Hashing::hash(&input), RandomnessKnownSince::<T>::get())
+	}
+}
@@ -0,0 +1,68 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{mock::*, Event as RelayRandomnessEvent};
+use frame_support::traits::{Hooks, Randomness};
+use sp_core::H256;
+use sp_runtime::traits::{BlakeTwo256, Hash};
+
+fn finalize_block(n: u64) {
+	System::set_block_number(n);
+	RelayRandomness::on_finalize(n);
+}
+
+#[test]
+fn randomness_is_taken_from_the_relay_chain() {
+	new_test_ext().execute_with(|| {
+		MockEpochRandomness::set(&Some(H256::repeat_byte(1)));
+		finalize_block(1);
+
+		assert_eq!(RelayRandomness::epoch_randomness(), Some(H256::repeat_byte(1)));
+		assert_eq!(RelayRandomness::randomness_known_since(), 1);
+		System::assert_last_event(
+			RelayRandomnessEvent::RandomnessUpdated { randomness: H256::repeat_byte(1) }.into(),
+		);
+
+		let mut input = b"lottery".to_vec();
+		input.extend_from_slice(&[1; 32]);
+		assert_eq!(RelayRandomness::random(b"lottery"), (BlakeTwo256::hash(&input), 1));
+		assert_ne!(RelayRandomness::random(b"lottery").0, RelayRandomness::random(b"raffle").0);
+	});
+}
+
+#[test]
+fn randomness_is_only_updated_when_it_changes() {
+	new_test_ext().execute_with(|| {
+		MockEpochRandomness::set(&Some(H256::repeat_byte(1)));
+		finalize_block(1);
+		let output = RelayRandomness::random(b"lottery");
+
+		finalize_block(2);
+		assert_eq!(RelayRandomness::random(b"lottery"), output);
+		assert_eq!(System::events().len(), 1);
+
+		// Blocks without a relay chain state proof keep the last randomness.
+		MockEpochRandomness::set(&None);
+		finalize_block(3);
+		assert_eq!(RelayRandomness::random(b"lottery"), output);
+
+		MockEpochRandomness::set(&Some(H256::repeat_byte(2)));
+		finalize_block(4);
+		assert_eq!(RelayRandomness::epoch_randomness(), Some(H256::repeat_byte(2)));
+		assert_eq!(RelayRandomness::randomness_known_since(), 4);
+		assert_ne!(RelayRandomness::random(b"lottery").0, output.0);
+	});
+}
@@ -0,0 +1,223 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! XCM Tracer pallet.
+//!
+//! Records failed XCM executions, so that they can be inspected after the fact.
+//!
+//! ## Overview
+//!
+//! [`TracingXcmExecutor`] wraps the XCM executor of the runtime. Every execution that does not
+//! complete is recorded in [`RecentFailures`] together with the origin, the kinds of the executed
+//! instructions, the weight that was used, the failed instruction and the error. Only the last
+//! [`Config::MaxFailures`] failures are kept, older ones are dropped.
+//!
+//! The XCM executor does not report which instruction failed. It is derived from the weight used
+//! until the failure and the weights of the instructions, as given by the `Weigher` of the
+//! executor.
+//!
+//! Recording a failure reads and writes [`RecentFailures`]. This weight is added to the weight
+//! used of incomplete executions. Executions that were rejected report no weight, so it is
+//! registered as extra weight of the block instead.
+//!
+//! The recorded failures can be queried through the [`XcmTracerApi`] runtime api.
+//!
+//! ## Usage
+//!
+//! Replace the executor wherever it is used, e.g. in the XCMP and DMP queues:
+//!
+//! ```ignore
+//! type XcmExecutor = TracingXcmExecutor<Runtime, XcmExecutor<XcmConfig>, Weigher>;
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use cumulus_primitives_utility::barriers::InstructionKind;
+use frame_support::{traits::Get, weights::DispatchClass};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_std::{marker::PhantomData, vec::Vec};
+use xcm::latest::{
+	Error as XcmError, ExecuteXcm, MultiLocation, Outcome, Weight as XcmWeight, Xcm,
+};
+use xcm_executor::traits::WeightBounds;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// A failed XCM execution.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct XcmFailure<BlockNumber> {
+	/// The block the message was executed in.
+	pub block_number: BlockNumber,
+	/// The origin of the message.
+	pub origin: MultiLocation,
+	/// The kinds of the top-level instructions of the message.
+	///
+	/// At most [`Config::MaxTracedInstructions`] instructions are recorded.
+	pub instructions: Vec<InstructionKind>,
+	/// The number of top-level instructions of the message.
+	pub instruction_count: u32,
+	/// The weight limit the message was executed with.
+	pub weight_limit: XcmWeight,
+	/// The weight used until the execution failed.
+	///
+	/// `None` if the message was rejected before its execution, e.g. by the barrier.
+	pub weight_used: Option<XcmWeight>,
+	/// The index of the top-level instruction that failed.
+	///
+	/// Derived as the first instruction at which the weights of the instructions add up to
+	/// [`Self::weight_used`]. Instructions that refund weight before the failure, e.g.
+	/// `Transact` or `RefundSurplus`, make it point to an earlier instruction. `None` if the
+	/// message was rejected before its execution or the weights do not add up.
+	pub failed_instruction: Option<u32>,
+	/// The error the execution failed with.
+	pub error: XcmError,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Maximum number of failures that are kept.
+		#[pallet::constant]
+		type MaxFailures: Get<u32>;
+
+		/// Maximum number of instructions that are recorded per failure.
+		#[pallet::constant]
+		type MaxTracedInstructions: Get<u32>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::without_storage_info]
+	pub struct Pallet<T>(_);
+
+	/// The most recent failed XCM executions, oldest first.
+	#[pallet::storage]
+	#[pallet::getter(fn recent_failures)]
+	pub type RecentFailures<T: Config> =
+		StorageValue<_, BoundedVec<XcmFailure<T::BlockNumber>, T::MaxFailures>, ValueQuery>;
+
+	impl<T: Config> Pallet<T> {
+		/// Record `failure`, dropping the oldest failure if [`Config::MaxFailures`] is reached.
+		pub(crate) fn note_failure(failure: XcmFailure<T::BlockNumber>) {
+			RecentFailures::<T>::mutate(|failures| {
+				if failures.len() >= T::MaxFailures::get() as usize && !failures.is_empty() {
+					failures.remove(0);
+				}
+
+				// Only fails if `MaxFailures` is zero, in which case nothing should be recorded.
+				let _ = failures.try_push(failure);
+			});
+		}
+	}
+}
+
+/// XCM executor that records failed executions of `Inner` in [`RecentFailures`].
+///
+/// `Weigher` has to be the weigher `Inner` uses, it is used to find the failed instruction.
+pub struct TracingXcmExecutor<T, Inner, Weigher>(PhantomData<(T, Inner, Weigher)>);
+
+impl<T: Config, Inner: ExecuteXcm<Call>, Weigher: WeightBounds<Call>, Call> ExecuteXcm<Call>
+	for TracingXcmExecutor<T, Inner, Weigher>
+{
+	fn execute_xcm_in_credit(
+		origin: impl Into<MultiLocation>,
+		message: Xcm<Call>,
+		weight_limit: XcmWeight,
+		weight_credit: XcmWeight,
+	) -> Outcome {
+		let origin = origin.into();
+		let instruction_count = message.0.len() as u32;
+		let instructions = message
+			.0
+			.iter()
+			.take(T::MaxTracedInstructions::get() as usize)
+			.map(InstructionKind::of)
+			.collect();
+		let instruction_weights = message
+			.0
+			.iter()
+			.map(|instruction| Weigher::instr_weight(instruction).unwrap_or_default())
+			.collect::<Vec<_>>();
+
+		let outcome =
+			Inner::execute_xcm_in_credit(origin.clone(), message, weight_limit, weight_credit);
+
+		let (weight_used, error) = match &outcome {
+			Outcome::Complete(_) => return outcome,
+			Outcome::Incomplete(weight_used, error) => (Some(*weight_used), error.clone()),
+			Outcome::Error(error) => (None, error.clone()),
+		};
+
+		Pallet::<T>::note_failure(XcmFailure {
+			block_number: frame_system::Pallet::<T>::block_number(),
+			origin,
+			instructions,
+			instruction_count,
+			weight_limit,
+			weight_used,
+			failed_instruction: weight_used
+				.and_then(|weight_used| failed_instruction(&instruction_weights, weight_used)),
+			error,
+		});
+
+		// `note_failure` reads and rewrites `RecentFailures`, the oldest failure is dropped in
+		// memory.
+		let trace_weight = T::DbWeight::get().reads_writes(1, 1);
+		match outcome {
+			Outcome::Incomplete(weight_used, error) =>
+				Outcome::Incomplete(weight_used.saturating_add(trace_weight), error),
+			outcome => {
+				frame_system::Pallet::<T>::register_extra_weight_unchecked(
+					trace_weight,
+					DispatchClass::Mandatory,
+				);
+				outcome
+			},
+		}
+	}
+}
+
+/// Returns the index of the first instruction at which `instruction_weights` add up to
+/// `weight_used`.
+fn failed_instruction(instruction_weights: &[XcmWeight], weight_used: XcmWeight) -> Option<u32> {
+	let mut total: XcmWeight = 0;
+	instruction_weights
+		.iter()
+		.position(|weight| {
+			total = total.saturating_add(*weight);
+			total >= weight_used
+		})
+		.map(|index| index as u32)
+}
+
+sp_api::decl_runtime_apis! {
+	/// Runtime api to query failed XCM executions.
+	pub trait XcmTracerApi<BlockNumber: codec::Codec> {
+		/// Returns the most recent failed XCM executions, oldest first.
+		fn recent_failures() -> Vec<XcmFailure<BlockNumber>>;
+	}
+}
@@ -0,0 +1,131 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use crate as xcm_tracer;
+use frame_support::{
+	parameter_types,
+	traits::{ConstU32, Everything},
+	weights::constants::RocksDbWeight,
+};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use xcm::latest::prelude::*;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		XcmTracer: xcm_tracer::{Pallet, Storage},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = RocksDbWeight;
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl Config for Test {
+	type MaxFailures = ConstU32<2>;
+	type MaxTracedInstructions = ConstU32<3>;
+}
+
+/// Weight used by [`MockExecutor`] per instruction.
+pub const INSTRUCTION_WEIGHT: XcmWeight = 10;
+
+/// Executor that rejects messages from outside of the consensus system and fails on `Trap`.
+pub struct MockExecutor;
+
+impl ExecuteXcm<()> for MockExecutor {
+	fn execute_xcm_in_credit(
+		origin: impl Into<MultiLocation>,
+		message: Xcm<()>,
+		_weight_limit: XcmWeight,
+		_weight_credit: XcmWeight,
+	) -> Outcome {
+		if origin.into().parents > 1 {
+			return Outcome::Error(XcmError::Barrier)
+		}
+
+		let mut weight_used = 0;
+		for instruction in message.0 {
+			weight_used += INSTRUCTION_WEIGHT;
+
+			if let Trap(code) = instruction {
+				return Outcome::Incomplete(weight_used, XcmError::Trap(code))
+			}
+		}
+
+		Outcome::Complete(weight_used)
+	}
+}
+
+/// Weighs every instruction with [`INSTRUCTION_WEIGHT`], like [`MockExecutor`] does.
+pub struct MockWeigher;
+
+impl WeightBounds<()> for MockWeigher {
+	fn weight(message: &mut Xcm<()>) -> Result<XcmWeight, ()> {
+		Ok(message.0.len() as XcmWeight * INSTRUCTION_WEIGHT)
+	}
+
+	fn instr_weight(_: &Instruction<()>) -> Result<XcmWeight, ()> {
+		Ok(INSTRUCTION_WEIGHT)
+	}
+}
+
+pub type Executor = TracingXcmExecutor<Test, MockExecutor, MockWeigher>;
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut ext: sp_io::TestExternalities =
+		frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,116 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{mock::*, InstructionKind, XcmFailure};
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, DispatchClass, Weight},
+};
+use xcm::latest::prelude::*;
+
+fn trace_weight() -> Weight {
+	RocksDbWeight::get().reads_writes(1, 1)
+}
+
+#[test]
+fn completed_executions_are_not_recorded() {
+	new_test_ext().execute_with(|| {
+		let outcome = Executor::execute_xcm(Parent, Xcm(vec![ClearOrigin]), 100);
+
+		assert_eq!(outcome, Outcome::Complete(INSTRUCTION_WEIGHT));
+		assert!(XcmTracer::recent_failures().is_empty());
+	});
+}
+
+#[test]
+fn failed_executions_are_recorded() {
+	new_test_ext().execute_with(|| {
+		let outcome = Executor::execute_xcm(Parent, Xcm(vec![ClearOrigin, Trap(7)]), 100);
+
+		assert_eq!(
+			outcome,
+			Outcome::Incomplete(2 * INSTRUCTION_WEIGHT + trace_weight(), XcmError::Trap(7))
+		);
+		assert_eq!(
+			XcmTracer::recent_failures().into_inner(),
+			vec![XcmFailure {
+				block_number: 1,
+				origin: Parent.into(),
+				instructions: vec![InstructionKind::ClearOrigin, InstructionKind::Trap],
+				instruction_count: 2,
+				weight_limit: 100,
+				weight_used: Some(2 * INSTRUCTION_WEIGHT),
+				failed_instruction: Some(1),
+				error: XcmError::Trap(7),
+			}],
+		);
+	});
+}
+
+#[test]
+fn rejected_executions_are_recorded_without_weight() {
+	new_test_ext().execute_with(|| {
+		let origin = MultiLocation::new(2, Here);
+		let outcome = Executor::execute_xcm(origin.clone(), Xcm(vec![ClearOrigin]), 100);
+
+		assert_eq!(outcome, Outcome::Error(XcmError::Barrier));
+		// the weight of recording the failure is registered with the block instead.
+		assert_eq!(System::block_weight().get(DispatchClass::Mandatory), &trace_weight());
+
+		let failures = XcmTracer::recent_failures();
+		assert_eq!(failures.len(), 1);
+		assert_eq!(failures[0].origin, origin);
+		assert_eq!(failures[0].weight_used, None);
+		assert_eq!(failures[0].failed_instruction, None);
+		assert_eq!(failures[0].error, XcmError::Barrier);
+	});
+}
+
+#[test]
+fn instructions_are_truncated() {
+	new_test_ext().execute_with(|| {
+		let message = Xcm(vec![ClearOrigin, ClearOrigin, RefundSurplus, ClearError, Trap(1)]);
+		Executor::execute_xcm(Parent, message, 100);
+
+		let failures = XcmTracer::recent_failures();
+		assert_eq!(
+			failures[0].instructions,
+			vec![
+				InstructionKind::ClearOrigin,
+				InstructionKind::ClearOrigin,
+				InstructionKind::RefundSurplus
+			],
+		);
+		assert_eq!(failures[0].instruction_count, 5);
+		// the failed instruction is found beyond the recorded ones.
+		assert_eq!(failures[0].failed_instruction, Some(4));
+	});
+}
+
+#[test]
+fn oldest_failures_are_dropped() {
+	new_test_ext().execute_with(|| {
+		for code in 1..=3 {
+			Executor::execute_xcm(Parent, Xcm(vec![Trap(code)]), 100);
+		}
+
+		let errors = XcmTracer::recent_failures()
+			.into_iter()
+			.map(|failure| failure.error)
+			.collect::<Vec<_>>();
+		assert_eq!(errors, vec![XcmError::Trap(2), XcmError::Trap(3)]);
+	});
+}
@@ -43,6 +43,7 @@ use cumulus_primitives_core::{
 	relay_chain::BlockNumber as RelayBlockNumber, ChannelStatus, GetChannelInfo, MessageSendError,
 	ParaId, XcmpMessageFormat, XcmpMessageHandler, XcmpMessageSource,
 };
+use cumulus_primitives_utility::fees::FeeHandler;
 use frame_support::{
 	traits::EnsureOrigin,
 	weights::{constants::WEIGHT_PER_MILLIS, Weight},
@@ -52,8 +53,11 @@ use rand_chacha::{
 	ChaChaRng,
 };
 use scale_info::TypeInfo;
-use sp_runtime::{traits::Hash, RuntimeDebug};
-use sp_std::{convert::TryFrom, prelude::*};
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, Hash, Saturating, UniqueSaturatedInto},
+	RuntimeDebug,
+};
+use sp_std::{collections::btree_set::BTreeSet, convert::TryFrom, prelude::*};
 use xcm::{latest::prelude::*, VersionedXcm, WrapVersion, MAX_XCM_DECODE_DEPTH};
 use xcm_executor::traits::ConvertOrigin;
 
@@ -99,6 +103,9 @@ pub mod pallet {
 		/// superuser origin.
 		type ControllerOriginConverter: ConvertOrigin<Self::Origin>;
 
+		/// Charges sibling parachains for the execution of their XCMs.
+		type FeeHandler: FeeHandler<Self::Call>;
+
 		/// The weight information of this pallet.
 		type WeightInfo: WeightInfo;
 	}
@@ -178,6 +185,38 @@ pub mod pallet {
 			Ok(())
 		}
 
+		/// Suspends the execution of XCMs from a single sibling parachain.
+		///
+		/// Messages from `sender` stay in the queue until the channel is resumed. The usual
+		/// suspend signal is sent to `sender` once its queue reaches the suspend threshold.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `sender`: The sibling parachain whose messages should not be executed.
+		#[pallet::weight((T::DbWeight::get().reads_writes(1, 1), DispatchClass::Operational,))]
+		pub fn suspend_inbound_channel(origin: OriginFor<T>, sender: ParaId) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+
+			SuspendedInboundChannels::<T>::mutate(|channels| channels.insert(sender));
+			Self::deposit_event(Event::InboundChannelSuspended(sender));
+
+			Ok(())
+		}
+
+		/// Resumes the execution of XCMs from a sibling parachain that was suspended by
+		/// `suspend_inbound_channel`.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `sender`: The sibling parachain whose messages should be executed again.
+		#[pallet::weight((T::DbWeight::get().reads_writes(1, 1), DispatchClass::Operational,))]
+		pub fn resume_inbound_channel(origin: OriginFor<T>, sender: ParaId) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+
+			SuspendedInboundChannels::<T>::mutate(|channels| channels.remove(&sender));
+			Self::deposit_event(Event::InboundChannelResumed(sender));
+
+			Ok(())
+		}
+
 		/// Overwrites the number of pages of messages which must be in the queue for the other side to be told to
 		/// suspend their sending.
 		///
@@ -257,6 +296,33 @@ pub mod pallet {
 
 			Ok(())
 		}
+
+		/// Overwrite the rate limit of the outbound messages to the sibling parachain `recipient`.
+		///
+		/// The limit is a token bucket over the size of the sent messages: `bytes_per_block` are
+		/// added to the bucket in every block, up to `max_burst`. Messages that don't fit into
+		/// the bucket are rejected. Passing `None` removes the limit.
+		///
+		/// - `origin`: Must pass `Root`.
+		/// - `recipient`: The sibling parachain the limit applies to.
+		/// - `limit`: Desired `(bytes_per_block, max_burst)`, starting with a full bucket.
+		#[pallet::weight((T::DbWeight::get().writes(1), DispatchClass::Operational,))]
+		pub fn set_outbound_rate_limit(
+			origin: OriginFor<T>,
+			recipient: ParaId,
+			limit: Option<(u32, u32)>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+
+			let now = frame_system::Pallet::<T>::block_number();
+			let rate_limit = limit.map(|(bytes_per_block, max_burst)| {
+				OutboundRateLimit::new(bytes_per_block, max_burst, now)
+			});
+			OutboundRateLimits::<T>::set(recipient, rate_limit);
+			Self::deposit_event(Event::OutboundRateLimitSet(recipient, limit));
+
+			Ok(())
+		}
 	}
 
 	#[pallet::event]
@@ -278,6 +344,29 @@ pub mod pallet {
 		OverweightEnqueued(ParaId, RelayBlockNumber, OverweightIndex, Weight),
 		/// An XCM from the overweight queue was executed with the given actual weight used.
 		OverweightServiced(OverweightIndex, Weight),
+		/// A message to a sibling parachain was rejected because it exceeds the rate limit of the
+		/// channel. \[ recipient, message size \]
+		RateLimited(ParaId, u32),
+		/// The rate limit of an outbound channel was changed, `None` if it was removed.
+		/// \[ recipient, (bytes per block, max burst) \]
+		OutboundRateLimitSet(ParaId, Option<(u32, u32)>),
+		/// The execution of XCMs from a sibling parachain was suspended. \[ sender \]
+		InboundChannelSuspended(ParaId),
+		/// The execution of XCMs from a sibling parachain was resumed. \[ sender \]
+		InboundChannelResumed(ParaId),
+		/// The recipient asked to stop sending messages to it. \[ recipient \]
+		ChannelSuspended(ParaId),
+		/// The recipient is able to take messages again. \[ recipient \]
+		ChannelResumed(ParaId),
+		/// A fee was charged for the execution of an XCM, the unused weight is already refunded.
+		/// \[ id, amount \]
+		FeeCharged(Option<T::Hash>, u128),
+		/// Messages were skipped because they were sent before the last processed messages of the
+		/// channel. \[ sender, sent at \]
+		StaleMessagesSkipped(ParaId, RelayBlockNumber),
+		/// An XCM was skipped because an identical XCM sent at the same relay block was already
+		/// processed. \[ sender, sent at, id \]
+		DuplicateXcm(ParaId, RelayBlockNumber, T::Hash),
 	}
 
 	#[pallet::error]
@@ -352,6 +441,43 @@ pub mod pallet {
 	/// Whether or not the XCMP queue is suspended from executing incoming XCMs or not.
 	#[pallet::storage]
 	pub(super) type QueueSuspended<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	/// The sibling parachains whose incoming XCMs are not executed, regardless of
+	/// `QueueSuspended`.
+	#[pallet::storage]
+	pub(super) type SuspendedInboundChannels<T: Config> =
+		StorageValue<_, BTreeSet<ParaId>, ValueQuery>;
+
+	/// The rate limits of the outbound XCMP channels.
+	///
+	/// Channels without an entry are not rate limited.
+	#[pallet::storage]
+	pub(super) type OutboundRateLimits<T: Config> =
+		StorageMap<_, Blake2_128Concat, ParaId, OutboundRateLimit<T::BlockNumber>>;
+
+	/// The relay block number at which the last processed messages from a sibling parachain were
+	/// sent.
+	///
+	/// Messages sent before are skipped, see `Event::StaleMessagesSkipped`.
+	#[pallet::storage]
+	pub(super) type InboundWatermarks<T: Config> =
+		StorageMap<_, Blake2_128Concat, ParaId, RelayBlockNumber>;
+
+	/// The hashes of the XCMs processed from a sibling parachain that were sent at the relay block
+	/// of its `InboundWatermarks`.
+	///
+	/// Identical XCMs are skipped, see `Event::DuplicateXcm`. The entry is removed once the
+	/// watermark moves on.
+	#[pallet::storage]
+	pub(super) type ProcessedXcmHashes<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ParaId,
+		Twox64Concat,
+		RelayBlockNumber,
+		BTreeSet<T::Hash>,
+		ValueQuery,
+	>;
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug, TypeInfo)]
@@ -451,6 +577,50 @@ impl Default for QueueConfigData {
 	}
 }
 
+/// Token bucket limiting the size of the messages sent over an outbound XCMP channel.
+#[derive(Copy, Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct OutboundRateLimit<BlockNumber> {
+	/// The number of bytes added to the bucket in every block.
+	bytes_per_block: u32,
+	/// The maximum number of bytes in the bucket, aka the largest burst that can be sent at once.
+	///
+	/// Messages larger than this can never be sent.
+	max_burst: u32,
+	/// The number of bytes that could be sent at `updated_at`.
+	available: u32,
+	/// The block in which `available` was last updated.
+	updated_at: BlockNumber,
+}
+
+impl<BlockNumber: AtLeast32BitUnsigned + Copy> OutboundRateLimit<BlockNumber> {
+	/// Create a new rate limit with a full bucket.
+	pub fn new(bytes_per_block: u32, max_burst: u32, now: BlockNumber) -> Self {
+		Self { bytes_per_block, max_burst, available: max_burst, updated_at: now }
+	}
+
+	/// Returns the number of bytes that can be sent at `now`.
+	pub fn available(&self, now: BlockNumber) -> u32 {
+		let elapsed: u32 = now.saturating_sub(self.updated_at).unique_saturated_into();
+		self.available
+			.saturating_add(self.bytes_per_block.saturating_mul(elapsed))
+			.min(self.max_burst)
+	}
+
+	/// Take `size` bytes out of the bucket at `now`.
+	///
+	/// Returns `false` and leaves the bucket untouched if there are not enough bytes available.
+	fn try_consume(&mut self, size: u32, now: BlockNumber) -> bool {
+		let available = self.available(now);
+		if size > available {
+			return false
+		}
+
+		self.available = available - size;
+		self.updated_at = now;
+		true
+	}
+}
+
 #[derive(PartialEq, Eq, Copy, Clone, Encode, Decode, TypeInfo)]
 pub enum ChannelSignal {
 	Suspend,
@@ -494,7 +664,24 @@ impl<T: Config> Pallet<T> {
 			return Err(MessageSendError::TooBig)
 		}
 
+		// Refuse new messages while the recipient is unable to take them, so that the sender gets
+		// backpressure instead of the message sitting in the queue indefinitely.
+		if matches!(T::ChannelInfo::get_channel_status(recipient), ChannelStatus::Full) {
+			return Err(MessageSendError::QueueFull)
+		}
 		let mut s = <OutboundXcmpStatus<T>>::get();
+		if s.iter()
+			.any(|item| item.recipient == recipient && item.state == OutboundState::Suspended)
+		{
+			return Err(MessageSendError::QueueFull)
+		}
+
+		let size = data.len().unique_saturated_into();
+		if !Self::consume_outbound_rate_limit(recipient, size) {
+			Self::deposit_event(Event::RateLimited(recipient, size));
+			return Err(MessageSendError::RateLimited)
+		}
+
 		let details = if let Some(details) = s.iter_mut().find(|item| item.recipient == recipient) {
 			details
 		} else {
@@ -530,6 +717,18 @@ impl<T: Config> Pallet<T> {
 		}
 	}
 
+	/// Account for a message of `size` bytes in the rate limit of the channel to `recipient`.
+	///
+	/// Returns `false` if the message exceeds the rate limit.
+	fn consume_outbound_rate_limit(recipient: ParaId, size: u32) -> bool {
+		let now = frame_system::Pallet::<T>::block_number();
+
+		OutboundRateLimits::<T>::mutate(recipient, |limit| match limit {
+			Some(limit) => limit.try_consume(size, now),
+			None => true,
+		})
+	}
+
 	/// Sends a signal to the `dest` chain over XCMP. This is guaranteed to be dispatched on this
 	/// block.
 	fn send_signal(dest: ParaId, signal: ChannelSignal) -> Result<(), ()> {
@@ -597,14 +796,30 @@ impl<T: Config> Pallet<T> {
 		let hash = Encode::using_encoded(&xcm, T::Hashing::hash);
 		log::debug!("Processing XCMP-XCM: {:?}", &hash);
 		let (result, event) = match Xcm::<T::Call>::try_from(xcm) {
-			Ok(xcm) => {
-				let location = (1, Parachain(sender.into()));
-				match T::XcmExecutor::execute_xcm(location, xcm, max_weight) {
-					Outcome::Error(e) => (Err(e), Event::Fail(Some(hash), e)),
-					Outcome::Complete(w) => (Ok(w), Event::Success(Some(hash))),
-					// As far as the caller is concerned, this was dispatched without error, so
-					// we just report the weight used.
-					Outcome::Incomplete(w, e) => (Ok(w), Event::Fail(Some(hash), e)),
+			Ok(mut xcm) => {
+				let location = MultiLocation::new(1, X1(Parachain(sender.into())));
+				match T::FeeHandler::charge_fee(&location, &mut xcm, max_weight) {
+					Err(e) => (Err(e), Event::Fail(Some(hash), e)),
+					Ok(fee) => {
+						let outcome =
+							T::XcmExecutor::execute_xcm(location.clone(), xcm, max_weight);
+						if fee > 0 {
+							let refund =
+								T::FeeHandler::refund_fee(&location, fee, outcome.weight_used());
+							let fee = fee.saturating_sub(refund);
+							Self::deposit_event(Event::FeeCharged(Some(hash), fee));
+						}
+						let fee_weight = T::FeeHandler::storage_weight();
+						match outcome {
+							Outcome::Error(e) => (Err(e), Event::Fail(Some(hash), e)),
+							Outcome::Complete(w) =>
+								(Ok(w.saturating_add(fee_weight)), Event::Success(Some(hash))),
+							// As far as the caller is concerned, this was dispatched without
+							// error, so we just report the weight used.
+							Outcome::Incomplete(w, e) =>
+								(Ok(w.saturating_add(fee_weight)), Event::Fail(Some(hash), e)),
+						}
+					},
 				}
 			},
 			Err(()) => (Err(XcmError::UnhandledXcmVersion), Event::BadVersion(Some(hash))),
@@ -619,7 +834,18 @@ impl<T: Config> Pallet<T> {
 		max_weight: Weight,
 		max_individual_weight: Weight,
 	) -> (Weight, bool) {
+		let watermark = <InboundWatermarks<T>>::get(sender);
+		let mut db_weight = T::DbWeight::get().reads(1);
+		if watermark.map_or(false, |watermark| sent_at < watermark) {
+			Self::deposit_event(Event::StaleMessagesSkipped(sender, sent_at));
+			<InboundXcmpMessages<T>>::remove(sender, sent_at);
+			return (db_weight.saturating_add(T::DbWeight::get().writes(1)), true)
+		}
+
 		let data = <InboundXcmpMessages<T>>::get(sender, sent_at);
+		let mut processed = <ProcessedXcmHashes<T>>::get(sender, sent_at);
+		let processed_count = processed.len();
+		db_weight = db_weight.saturating_add(T::DbWeight::get().reads(1));
 		let mut last_remaining_fragments;
 		let mut remaining_fragments = &data[..];
 		let mut weight_used = 0;
@@ -631,9 +857,17 @@ impl<T: Config> Pallet<T> {
 						MAX_XCM_DECODE_DEPTH,
 						&mut remaining_fragments,
 					) {
+						let hash = Encode::using_encoded(&xcm, T::Hashing::hash);
+						if processed.contains(&hash) {
+							Self::deposit_event(Event::DuplicateXcm(sender, sent_at, hash));
+							continue
+						}
 						let weight = max_weight - weight_used;
 						match Self::handle_xcm_message(sender, sent_at, xcm, weight) {
-							Ok(used) => weight_used = weight_used.saturating_add(used),
+							Ok(used) => {
+								weight_used = weight_used.saturating_add(used);
+								processed.insert(hash);
+							},
 							Err(XcmError::WeightLimitReached(required))
 								if required > max_individual_weight =>
 							{
@@ -646,6 +880,7 @@ impl<T: Config> Pallet<T> {
 								let index = Self::stash_overweight(sender, sent_at, overweight_xcm);
 								let e = Event::OverweightEnqueued(sender, sent_at, index, required);
 								Self::deposit_event(e);
+								processed.insert(hash);
 							},
 							Err(XcmError::WeightLimitReached(required))
 								if required <= max_weight =>
@@ -656,7 +891,11 @@ impl<T: Config> Pallet<T> {
 								break
 							},
 							Err(_) => {
-								// Message looks invalid; don't attempt to retry
+								// Message looks invalid; don't attempt to retry. Its origin may
+								// still have been charged and refunded.
+								weight_used =
+									weight_used.saturating_add(T::FeeHandler::storage_weight());
+								processed.insert(hash);
 							},
 						}
 					} else {
@@ -694,13 +933,25 @@ impl<T: Config> Pallet<T> {
 				remaining_fragments = &b""[..];
 			},
 		}
+		if remaining_fragments.len() < data.len() && watermark != Some(sent_at) {
+			<InboundWatermarks<T>>::insert(sender, sent_at);
+			db_weight = db_weight.saturating_add(T::DbWeight::get().writes(1));
+			if let Some(watermark) = watermark {
+				<ProcessedXcmHashes<T>>::remove(sender, watermark);
+				db_weight = db_weight.saturating_add(T::DbWeight::get().writes(1));
+			}
+		}
+		if processed.len() > processed_count {
+			<ProcessedXcmHashes<T>>::insert(sender, sent_at, processed);
+			db_weight = db_weight.saturating_add(T::DbWeight::get().writes(1));
+		}
 		let is_empty = remaining_fragments.is_empty();
 		if is_empty {
 			<InboundXcmpMessages<T>>::remove(sender, sent_at);
 		} else {
 			<InboundXcmpMessages<T>>::insert(sender, sent_at, remaining_fragments);
 		}
-		(weight_used, is_empty)
+		(weight_used.saturating_add(db_weight), is_empty)
 	}
 
 	/// Puts a given XCM into the list of overweight messages, allowing it to be executed later.
@@ -748,6 +999,7 @@ impl<T: Config> Pallet<T> {
 	/// further.
 	fn service_xcmp_queue(max_weight: Weight) -> Weight {
 		let suspended = QueueSuspended::<T>::get();
+		let suspended_channels = SuspendedInboundChannels::<T>::get();
 
 		let mut status = <InboundXcmpStatus<T>>::get(); // <- sorted.
 		if status.is_empty() {
@@ -787,7 +1039,7 @@ impl<T: Config> Pallet<T> {
 			let is_controller = sender_origin
 				.map_or(false, |origin| T::ControllerOrigin::try_origin(origin).is_ok());
 
-			if suspended && !is_controller {
+			if (suspended && !is_controller) || suspended_channels.contains(&sender) {
 				shuffle_index += 1;
 				continue
 			}
@@ -867,6 +1119,7 @@ impl<T: Config> Pallet<T> {
 				s.push(OutboundChannelDetails::new(target).with_suspended_state());
 			}
 		});
+		Self::deposit_event(Event::ChannelSuspended(target));
 	}
 
 	fn resume_channel(target: ParaId) {
@@ -882,6 +1135,7 @@ impl<T: Config> Pallet<T> {
 				} else {
 					s[index].state = OutboundState::Ok;
 				}
+				Self::deposit_event(Event::ChannelResumed(target));
 			} else {
 				debug_assert!(false, "WARNING: Attempt to resume channel that was not suspended.");
 			}
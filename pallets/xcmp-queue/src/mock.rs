@@ -16,7 +16,7 @@
 use super::*;
 use crate as xcmp_queue;
 use core::marker::PhantomData;
-use cumulus_primitives_core::{IsSystem, ParaId};
+use cumulus_primitives_core::{ChannelStatus, GetChannelInfo, IsSystem, ParaId};
 use frame_support::{parameter_types, traits::OriginTrait};
 use frame_system::EnsureRoot;
 use sp_core::H256;
@@ -180,14 +180,36 @@ impl<Origin: OriginTrait> ConvertOrigin<Origin> for SystemParachainAsSuperuser<O
 	}
 }
 
+/// Maximum size of a message in the channels of [`MockedChannelInfo`].
+pub const MAX_MESSAGE_SIZE: usize = 1024;
+
+/// Sibling parachain whose channel is reported as full by [`MockedChannelInfo`].
+pub const FULL_CHANNEL_PARA_ID: u32 = 3000;
+
+/// Channel information that reports an open channel to every sibling parachain.
+pub struct MockedChannelInfo;
+impl GetChannelInfo for MockedChannelInfo {
+	fn get_channel_status(id: ParaId) -> ChannelStatus {
+		if id == ParaId::from(FULL_CHANNEL_PARA_ID) {
+			return ChannelStatus::Full
+		}
+		ChannelStatus::Ready(MAX_MESSAGE_SIZE, MAX_MESSAGE_SIZE)
+	}
+
+	fn get_channel_max(_id: ParaId) -> Option<usize> {
+		Some(MAX_MESSAGE_SIZE)
+	}
+}
+
 impl Config for Test {
 	type Event = Event;
 	type XcmExecutor = xcm_executor::XcmExecutor<XcmConfig>;
-	type ChannelInfo = ParachainSystem;
+	type ChannelInfo = MockedChannelInfo;
 	type VersionWrapper = ();
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
 	type ControllerOrigin = EnsureRoot<AccountId>;
 	type ControllerOriginConverter = SystemParachainAsSuperuser<Origin>;
+	type FeeHandler = ();
 	type WeightInfo = ();
 }
 
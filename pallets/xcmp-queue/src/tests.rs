@@ -16,7 +16,7 @@
 use super::*;
 use cumulus_primitives_core::XcmpMessageHandler;
 use frame_support::{assert_noop, assert_ok};
-use mock::{new_test_ext, Call, Origin, Test, XcmpQueue};
+use mock::{new_test_ext, Call, Origin, System, Test, XcmpQueue};
 use sp_runtime::traits::BadOrigin;
 
 #[test]
@@ -111,6 +111,38 @@ fn suspend_xcm_execution_works() {
 	});
 }
 
+#[test]
+fn suspend_inbound_channel_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let sender = ParaId::from(2000);
+
+		assert_noop!(XcmpQueue::suspend_inbound_channel(Origin::signed(1), sender), BadOrigin);
+		assert_ok!(XcmpQueue::suspend_inbound_channel(Origin::root(), sender));
+		System::assert_last_event(mock::Event::XcmpQueue(Event::InboundChannelSuspended(sender)));
+
+		let xcm = VersionedXcm::from(Xcm::<Call>(vec![Instruction::<Call>::ClearOrigin])).encode();
+		let mut message_format = XcmpMessageFormat::ConcatenatedVersionedXcm.encode();
+		message_format.extend(xcm.clone());
+
+		// Messages from the suspended channel are kept in the queue.
+		let messages = vec![(sender, 1u32.into(), message_format.as_slice())];
+		XcmpQueue::handle_xcmp_messages(messages.into_iter(), Weight::max_value());
+		assert_eq!(InboundXcmpMessages::<Test>::get(sender, 1u32), xcm);
+
+		// Other channels are not affected.
+		let messages = vec![(ParaId::from(2001), 1u32.into(), message_format.as_slice())];
+		XcmpQueue::handle_xcmp_messages(messages.into_iter(), Weight::max_value());
+		assert!(InboundXcmpMessages::<Test>::get(ParaId::from(2001), 1u32).is_empty());
+
+		// The queued messages are executed once the channel is resumed.
+		assert_ok!(XcmpQueue::resume_inbound_channel(Origin::root(), sender));
+		System::assert_last_event(mock::Event::XcmpQueue(Event::InboundChannelResumed(sender)));
+		XcmpQueue::handle_xcmp_messages(Vec::new().into_iter(), Weight::max_value());
+		assert!(InboundXcmpMessages::<Test>::get(sender, 1u32).is_empty());
+	});
+}
+
 #[test]
 fn update_suspend_threshold_works() {
 	new_test_ext().execute_with(|| {
@@ -194,3 +226,133 @@ fn update_xcmp_max_individual_weight() {
 		assert_eq!(data.xcmp_max_individual_weight, 30 * WEIGHT_PER_MILLIS);
 	});
 }
+
+#[test]
+fn outbound_rate_limit_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let recipient = ParaId::from(2000);
+		// The encoded blob has a one byte length prefix.
+		let blob = vec![0u8; 39];
+
+		assert_noop!(
+			XcmpQueue::set_outbound_rate_limit(Origin::signed(1), recipient, Some((20, 80))),
+			BadOrigin
+		);
+		assert_ok!(XcmpQueue::set_outbound_rate_limit(Origin::root(), recipient, Some((20, 80))));
+		System::assert_last_event(mock::Event::XcmpQueue(Event::OutboundRateLimitSet(
+			recipient,
+			Some((20, 80)),
+		)));
+
+		// The burst allowance is available initially.
+		assert_ok!(XcmpQueue::send_blob_message(recipient, blob.clone()));
+		assert_ok!(XcmpQueue::send_blob_message(recipient, blob.clone()));
+		assert_eq!(
+			XcmpQueue::send_blob_message(recipient, blob.clone()),
+			Err(MessageSendError::RateLimited)
+		);
+		System::assert_last_event(mock::Event::XcmpQueue(Event::RateLimited(recipient, 40)));
+
+		// Other channels are not affected.
+		assert_ok!(XcmpQueue::send_blob_message(ParaId::from(2001), blob.clone()));
+
+		// The allowance is refilled over time.
+		System::set_block_number(2);
+		assert_eq!(
+			XcmpQueue::send_blob_message(recipient, blob.clone()),
+			Err(MessageSendError::RateLimited)
+		);
+		System::set_block_number(3);
+		assert_ok!(XcmpQueue::send_blob_message(recipient, blob.clone()));
+
+		// But never above the burst allowance.
+		System::set_block_number(100);
+		assert_eq!(OutboundRateLimits::<Test>::get(recipient).unwrap().available(100), 80);
+
+		assert_ok!(XcmpQueue::set_outbound_rate_limit(Origin::root(), recipient, None));
+		System::assert_last_event(mock::Event::XcmpQueue(Event::OutboundRateLimitSet(
+			recipient, None,
+		)));
+		assert!(OutboundRateLimits::<Test>::get(recipient).is_none());
+	});
+}
+
+#[test]
+fn outbound_channel_signals_apply_backpressure() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let recipient = ParaId::from(2000);
+		let blob = vec![0u8; 39];
+		let signal = |signal: ChannelSignal| {
+			let mut data = XcmpMessageFormat::Signals.encode();
+			data.extend(signal.encode());
+			data
+		};
+
+		let suspend = signal(ChannelSignal::Suspend);
+		let messages = vec![(recipient, 1, suspend.as_slice())];
+		XcmpQueue::handle_xcmp_messages(messages.into_iter(), Weight::max_value());
+		System::assert_last_event(mock::Event::XcmpQueue(Event::ChannelSuspended(recipient)));
+		assert_eq!(
+			XcmpQueue::send_blob_message(recipient, blob.clone()),
+			Err(MessageSendError::QueueFull)
+		);
+
+		let resume = signal(ChannelSignal::Resume);
+		let messages = vec![(recipient, 2, resume.as_slice())];
+		XcmpQueue::handle_xcmp_messages(messages.into_iter(), Weight::max_value());
+		System::assert_last_event(mock::Event::XcmpQueue(Event::ChannelResumed(recipient)));
+		assert_ok!(XcmpQueue::send_blob_message(recipient, blob.clone()));
+
+		// Channels that are full on the relay chain don't take messages either.
+		assert_eq!(
+			XcmpQueue::send_blob_message(ParaId::from(mock::FULL_CHANNEL_PARA_ID), blob),
+			Err(MessageSendError::QueueFull)
+		);
+	});
+}
+
+#[test]
+fn duplicate_and_stale_messages_are_skipped() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let sender = ParaId::from(2000);
+		let xcm = VersionedXcm::from(Xcm::<Call>(vec![Instruction::<Call>::ClearOrigin]));
+		let hash = Encode::using_encoded(&xcm, <Test as frame_system::Config>::Hashing::hash);
+		let mut message_format = XcmpMessageFormat::ConcatenatedVersionedXcm.encode();
+		message_format.extend(xcm.encode());
+
+		let messages = vec![(sender, 2u32.into(), message_format.as_slice())];
+		XcmpQueue::handle_xcmp_messages(messages.into_iter(), Weight::max_value());
+		assert_eq!(InboundWatermarks::<Test>::get(sender), Some(2));
+		assert!(ProcessedXcmHashes::<Test>::get(sender, 2).contains(&hash));
+
+		// An identical XCM sent at the same relay block is skipped without being executed.
+		System::reset_events();
+		let messages = vec![(sender, 2u32.into(), message_format.as_slice())];
+		XcmpQueue::handle_xcmp_messages(messages.into_iter(), Weight::max_value());
+		assert_eq!(
+			System::events().into_iter().map(|r| r.event).collect::<Vec<_>>(),
+			vec![mock::Event::XcmpQueue(Event::DuplicateXcm(sender, 2, hash))],
+		);
+		assert!(InboundXcmpMessages::<Test>::get(sender, 2).is_empty());
+
+		// Messages sent at an earlier relay block are skipped.
+		let messages = vec![(sender, 1u32.into(), message_format.as_slice())];
+		XcmpQueue::handle_xcmp_messages(messages.into_iter(), Weight::max_value());
+		System::assert_last_event(mock::Event::XcmpQueue(Event::StaleMessagesSkipped(sender, 1)));
+		assert!(InboundXcmpMessages::<Test>::get(sender, 1).is_empty());
+
+		// But not ones sent at a later relay block.
+		let messages = vec![(sender, 3u32.into(), message_format.as_slice())];
+		XcmpQueue::handle_xcmp_messages(messages.into_iter(), Weight::max_value());
+		System::assert_last_event(mock::Event::XcmpQueue(Event::Fail(
+			Some(hash),
+			XcmError::Barrier,
+		)));
+		assert_eq!(InboundWatermarks::<Test>::get(sender), Some(3));
+		assert!(ProcessedXcmHashes::<Test>::get(sender, 2).is_empty());
+		assert!(ProcessedXcmHashes::<Test>::get(sender, 3).contains(&hash));
+	});
+}
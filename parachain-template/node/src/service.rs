@@ -17,7 +17,9 @@ use cumulus_client_service::{
 	prepare_node_config, start_collator, start_full_node, StartCollatorParams, StartFullNodeParams,
 };
 use cumulus_primitives_core::ParaId;
-use cumulus_relay_chain_inprocess_interface::build_inprocess_relay_chain;
+use cumulus_relay_chain_inprocess_interface::{
+	build_inprocess_relay_chain, RelayChainResourceLimits,
+};
 use cumulus_relay_chain_interface::{RelayChainError, RelayChainInterface, RelayChainResult};
 use cumulus_relay_chain_rpc_interface::RelayChainRPCInterface;
 
@@ -172,13 +174,25 @@ async fn build_relay_chain_interface(
 	collator_options: CollatorOptions,
 ) -> RelayChainResult<(Arc<(dyn RelayChainInterface + 'static)>, Option<CollatorPair>)> {
 	match collator_options.relay_chain_rpc_url {
-		Some(relay_chain_url) =>
-			Ok((Arc::new(RelayChainRPCInterface::new(relay_chain_url).await?) as Arc<_>, None)),
+		Some(relay_chain_url) => {
+			let relay_chain_interface = RelayChainRPCInterface::new(
+				relay_chain_url,
+				collator_options.relay_chain_rpc_fallback_urls,
+				parachain_config.prometheus_registry(),
+			)
+			.await?;
+			Ok((Arc::new(relay_chain_interface) as Arc<_>, None))
+		},
 		None => build_inprocess_relay_chain(
 			polkadot_config,
 			parachain_config,
 			telemetry_worker_handle,
 			task_manager,
+			RelayChainResourceLimits {
+				worker_threads: collator_options.relay_chain_worker_threads,
+				nice: collator_options.relay_chain_nice,
+				io_priority: collator_options.relay_chain_io_priority,
+			},
 		),
 	}
 }
@@ -359,6 +373,7 @@ where
 			import_queue,
 			collator_key: collator_key.expect("Command line arguments do not allow this. qed"),
 			relay_chain_slot_duration,
+			last_proof: None,
 		};
 
 		start_collator(params).await?;
@@ -384,10 +384,11 @@ impl cumulus_pallet_xcmp_queue::Config for Runtime {
 	type Event = Event;
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 	type ChannelInfo = ParachainSystem;
-	type VersionWrapper = ();
+	type VersionWrapper = PolkadotXcm;
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
 	type ControllerOrigin = EnsureRoot<AccountId>;
 	type ControllerOriginConverter = XcmOriginToTransactDispatchOrigin;
+	type FeeHandler = ();
 	type WeightInfo = ();
 }
 
@@ -395,6 +396,7 @@ impl cumulus_pallet_dmp_queue::Config for Runtime {
 	type Event = Event;
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
+	type FeeHandler = ();
 }
 
 parameter_types! {
@@ -616,6 +618,23 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl cumulus_primitives_core::HostConfigurationApi<Block> for Runtime {
+		fn host_configuration() -> Option<cumulus_primitives_core::AbridgedHostConfiguration> {
+			ParachainSystem::host_configuration()
+		}
+	}
+
+	impl cumulus_primitives_core::ValidationDataApi<Block> for Runtime {
+		fn validation_data() -> Option<cumulus_primitives_core::PersistedValidationData> {
+			ParachainSystem::validation_data()
+		}
+
+		fn pending_validation_code_hash(
+		) -> Option<cumulus_primitives_core::relay_chain::v2::ValidationCodeHash> {
+			ParachainSystem::pending_validation_code_hash()
+		}
+	}
+
 	#[cfg(feature = "try-runtime")]
 	impl frame_try_runtime::TryRuntime<Block> for Runtime {
 		fn on_runtime_upgrade() -> (Weight, Weight) {
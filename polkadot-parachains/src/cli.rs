@@ -14,21 +14,25 @@
 // You should have received a copy of the GNU General Public License
 // along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::chain_spec;
 use clap::Parser;
 use sc_cli;
 use std::path::PathBuf;
+use url::Url;
 
 /// Sub-commands supported by the collator.
 #[derive(Debug, clap::Subcommand)]
 pub enum Subcommand {
 	/// Export the genesis state of the parachain.
 	#[clap(name = "export-genesis-state")]
-	ExportGenesisState(ExportGenesisStateCommand),
+	ExportGenesisState(cumulus_client_cli::ExportGenesisStateCommand),
 
 	/// Export the genesis wasm of the parachain.
 	#[clap(name = "export-genesis-wasm")]
-	ExportGenesisWasm(ExportGenesisWasmCommand),
+	ExportGenesisWasm(cumulus_client_cli::ExportGenesisWasmCommand),
+
+	/// Measure the validation code of the parachain and check it against the relay chain limits.
+	#[clap(name = "measure-validation-code")]
+	MeasureValidationCode(MeasureValidationCodeCommand),
 
 	/// Build a chain specification.
 	BuildSpec(sc_cli::BuildSpecCmd),
@@ -63,36 +67,24 @@ pub enum Subcommand {
 	Key(sc_cli::KeySubcommand),
 }
 
-/// Command for exporting the genesis state of the parachain
+/// Command for measuring the validation code of the parachain.
+///
+/// Exits with an error if the code exceeds any of the limits, so it can be used to fail CI.
 #[derive(Debug, Parser)]
-pub struct ExportGenesisStateCommand {
-	/// Output file name or stdout if unspecified.
-	#[clap(parse(from_os_str))]
-	pub output: Option<PathBuf>,
-
-	/// Write output in binary. Default is to write in hex.
-	#[clap(short, long)]
-	pub raw: bool,
+pub struct MeasureValidationCodeCommand {
+	/// Wasm file to measure instead of the genesis wasm of the chain.
+	#[clap(long, parse(from_os_str))]
+	pub wasm: Option<PathBuf>,
 
-	/// The name of the chain for that the genesis state should be exported.
+	/// The name of the chain for that the genesis wasm file should be measured.
 	#[clap(long)]
 	pub chain: Option<String>,
-}
 
-/// Command for exporting the genesis wasm file.
-#[derive(Debug, Parser)]
-pub struct ExportGenesisWasmCommand {
-	/// Output file name or stdout if unspecified.
-	#[clap(parse(from_os_str))]
-	pub output: Option<PathBuf>,
-
-	/// Write output in binary. Default is to write in hex.
-	#[clap(short, long)]
-	pub raw: bool,
-
-	/// The name of the chain for that the genesis wasm file should be exported.
+	/// RPC endpoint of a relay chain node to read the active host configuration from.
+	///
+	/// When unspecified, only the limits of the PVF host are checked.
 	#[clap(long)]
-	pub chain: Option<String>,
+	pub relay_chain_rpc_url: Option<Url>,
 }
 
 #[derive(Debug, Parser)]
@@ -107,33 +99,7 @@ pub struct Cli {
 
 	#[clap(flatten)]
 	pub run: cumulus_client_cli::RunCmd,
-
-	/// Relay chain arguments
-	#[clap(raw = true, conflicts_with = "relay-chain-rpc-url")]
-	pub relaychain_args: Vec<String>,
-}
-
-#[derive(Debug)]
-pub struct RelayChainCli {
-	/// The actual relay chain cli object.
-	pub base: polkadot_cli::RunCmd,
-
-	/// Optional chain id that should be passed to the relay chain.
-	pub chain_id: Option<String>,
-
-	/// The base path that should be used by the relay chain.
-	pub base_path: Option<PathBuf>,
 }
 
-impl RelayChainCli {
-	/// Parse the relay chain CLI parameters using the para chain `Configuration`.
-	pub fn new<'a>(
-		para_config: &sc_service::Configuration,
-		relay_chain_args: impl Iterator<Item = &'a String>,
-	) -> Self {
-		let extension = chain_spec::Extensions::try_get(&*para_config.chain_spec);
-		let chain_id = extension.map(|e| e.relay_chain.clone());
-		let base_path = para_config.base_path.as_ref().map(|x| x.path().join("polkadot"));
-		Self { base_path, chain_id, base: polkadot_cli::RunCmd::parse_from(relay_chain_args) }
-	}
-}
+/// The command line of the embedded relay chain node.
+pub type RelayChainCli = cumulus_client_cli::RelayChainCli<Cli>;
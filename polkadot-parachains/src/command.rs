@@ -22,23 +22,24 @@ use crate::{
 		StatemintRuntimeExecutor, WestmintRuntimeExecutor,
 	},
 };
-use codec::Encode;
-use cumulus_client_service::genesis::generate_genesis_block;
-use cumulus_primitives_core::ParaId;
+use codec::{Decode, Encode};
+use cumulus_client_cli::{extract_genesis_wasm, generate_genesis_block};
+use cumulus_primitives_core::{
+	relay_chain::well_known_keys as relay_well_known_keys, AbridgedHostConfiguration, ParaId,
+};
+use cumulus_relay_chain_interface::RelayChainInterface;
+use cumulus_relay_chain_rpc_interface::RelayChainRPCInterface;
 use log::info;
 use parachains_common::{AuraId, StatemintAuraId};
+use parity_wasm::elements::{External, Module};
+use polkadot_node_primitives::VALIDATION_CODE_BOMB_LIMIT;
 use polkadot_parachain::primitives::AccountIdConversion;
-use sc_cli::{
-	ChainSpec, CliConfiguration, DefaultConfigurationValues, ImportParams, KeystoreParams,
-	NetworkParams, Result, RuntimeVersion, SharedParams, SubstrateCli,
-};
-use sc_service::{
-	config::{BasePath, PrometheusConfig},
-	TaskManager,
-};
+use sc_cli::{ChainSpec, Result, RuntimeVersion, SubstrateCli};
+use sc_service::TaskManager;
 use sp_core::hexdisplay::HexDisplay;
 use sp_runtime::traits::Block as BlockT;
-use std::{io::Write, net::SocketAddr};
+use std::borrow::Cow;
+use url::Url;
 
 trait IdentifyChain {
 	fn is_shell(&self) -> bool;
@@ -218,54 +219,112 @@ impl SubstrateCli for Cli {
 	}
 }
 
-impl SubstrateCli for RelayChainCli {
-	fn impl_name() -> String {
-		"Polkadot collator".into()
-	}
+/// Heap pages the PVF executor adds to the initial memory of the validation code.
+///
+/// Mirrors the executor configuration of `polkadot-node-core-pvf`, which does not export it.
+const PVF_EXTRA_HEAP_PAGES: u32 = 2048;
+
+/// Maximum number of memory pages of the PVF executor, its default heap pages estimate of 32 plus
+/// [`PVF_EXTRA_HEAP_PAGES`].
+const PVF_MAX_MEMORY_PAGES: u32 = 32 + PVF_EXTRA_HEAP_PAGES;
+
+/// Sizes and memory requirements of a validation code blob.
+#[derive(Debug, PartialEq)]
+struct ValidationCodeMeasurements {
+	compressed_size: usize,
+	uncompressed_size: usize,
+	initial_pages: u32,
+	maximum_pages: Option<u32>,
+}
 
-	fn impl_version() -> String {
-		env!("SUBSTRATE_CLI_IMPL_VERSION").into()
-	}
+/// Measure the given validation code, which may or may not be compressed.
+///
+/// Fails if the uncompressed code exceeds the limit enforced by the PVF host.
+fn measure_validation_code(code: &[u8]) -> Result<ValidationCodeMeasurements> {
+	let uncompressed = sp_maybe_compressed_blob::decompress(code, VALIDATION_CODE_BOMB_LIMIT)
+		.map_err(|e| format!("Failed to decompress validation code: {}", e))?;
+	let compressed_size = match &uncompressed {
+		Cow::Owned(_) => code.len(),
+		Cow::Borrowed(_) => sp_maybe_compressed_blob::compress(code, VALIDATION_CODE_BOMB_LIMIT)
+			.ok_or_else(|| {
+				format!(
+					"Validation code of {} bytes exceeds the limit of {} bytes",
+					code.len(),
+					VALIDATION_CODE_BOMB_LIMIT,
+				)
+			})?
+			.len(),
+	};
+
+	let module = parity_wasm::deserialize_buffer::<Module>(&uncompressed)
+		.map_err(|e| format!("Failed to parse validation code: {}", e))?;
+	let imported_memory = module.import_section().and_then(|section| {
+		section.entries().iter().find_map(|entry| match entry.external() {
+			External::Memory(memory) => Some(*memory.limits()),
+			_ => None,
+		})
+	});
+	let limits = module
+		.memory_section()
+		.and_then(|section| section.entries().first())
+		.map(|memory| *memory.limits())
+		.or(imported_memory)
+		.ok_or("Validation code does not declare any memory")?;
+
+	Ok(ValidationCodeMeasurements {
+		compressed_size,
+		uncompressed_size: uncompressed.len(),
+		initial_pages: limits.initial(),
+		maximum_pages: limits.maximum(),
+	})
+}
 
-	fn description() -> String {
-		format!(
-			"Polkadot collator\n\nThe command-line arguments provided first will be \
-		passed to the parachain node, while the arguments provided after -- will be passed \
-		to the relay chain node.\n\n\
-		{} [parachain-args] -- [relay_chain-args]",
-			Self::executable_name()
+/// Check the memory of the validation code against the limits of the PVF executor.
+///
+/// The executor instantiates the code with its initial memory plus [`PVF_EXTRA_HEAP_PAGES`] and
+/// never grows the memory beyond [`PVF_MAX_MEMORY_PAGES`].
+fn check_pvf_memory_limits(measurements: &ValidationCodeMeasurements) -> Result<()> {
+	let required_pages = measurements.initial_pages.saturating_add(PVF_EXTRA_HEAP_PAGES);
+	if required_pages > PVF_MAX_MEMORY_PAGES {
+		return Err(format!(
+			"Initial memory of {} pages plus the {} heap pages of the PVF executor exceeds its limit of {} pages",
+			measurements.initial_pages, PVF_EXTRA_HEAP_PAGES, PVF_MAX_MEMORY_PAGES,
 		)
+		.into())
 	}
 
-	fn author() -> String {
-		env!("CARGO_PKG_AUTHORS").into()
-	}
-
-	fn support_url() -> String {
-		"https://github.com/paritytech/cumulus/issues/new".into()
-	}
-
-	fn copyright_start_year() -> i32 {
-		2017
-	}
-
-	fn load_spec(&self, id: &str) -> std::result::Result<Box<dyn sc_service::ChainSpec>, String> {
-		polkadot_cli::Cli::from_iter([RelayChainCli::executable_name().to_string()].iter())
-			.load_spec(id)
-	}
-
-	fn native_runtime_version(chain_spec: &Box<dyn ChainSpec>) -> &'static RuntimeVersion {
-		polkadot_cli::Cli::native_runtime_version(chain_spec)
+	match measurements.maximum_pages {
+		Some(maximum) if maximum < required_pages => Err(format!(
+			"Maximum memory of {} pages leaves no room for the {} heap pages of the PVF executor",
+			maximum, PVF_EXTRA_HEAP_PAGES,
+		)
+		.into()),
+		Some(maximum) if maximum > PVF_MAX_MEMORY_PAGES => Err(format!(
+			"Maximum memory of {} pages exceeds the limit of {} pages of the PVF executor",
+			maximum, PVF_MAX_MEMORY_PAGES,
+		)
+		.into()),
+		_ => Ok(()),
 	}
 }
 
-fn extract_genesis_wasm(chain_spec: &Box<dyn sc_service::ChainSpec>) -> Result<Vec<u8>> {
-	let mut storage = chain_spec.build_storage()?;
-
-	storage
-		.top
-		.remove(sp_core::storage::well_known_keys::CODE)
-		.ok_or_else(|| "Could not find wasm file in genesis state!".into())
+/// Read the active host configuration of the relay chain at its best block.
+async fn relay_host_configuration(url: Url) -> Result<AbridgedHostConfiguration> {
+	let relay_chain = RelayChainRPCInterface::new(url, Vec::new(), None)
+		.await
+		.map_err(|e| format!("Failed to connect to the relay chain: {}", e))?;
+	let best_hash = relay_chain
+		.best_block_hash()
+		.await
+		.map_err(|e| format!("Failed to fetch the relay chain best block: {}", e))?;
+	let raw_config = relay_chain
+		.get_storage_by_key(best_hash, relay_well_known_keys::ACTIVE_CONFIG)
+		.await
+		.map_err(|e| format!("Failed to fetch the relay chain host configuration: {}", e))?
+		.ok_or("Relay chain has no active host configuration")?;
+
+	AbridgedHostConfiguration::decode(&mut &raw_config[..])
+		.map_err(|e| format!("Failed to decode the relay chain host configuration: {}", e).into())
 }
 
 macro_rules! construct_async_run {
@@ -376,9 +435,9 @@ pub fn run() -> Result<()> {
 			runner.sync_run(|config| {
 				let polkadot_cli = RelayChainCli::new(
 					&config,
-					[RelayChainCli::executable_name().to_string()]
-						.iter()
-						.chain(cli.relaychain_args.iter()),
+					chain_spec::Extensions::try_get(&*config.chain_spec)
+						.map(|e| e.relay_chain.clone()),
+					cli.run.relay_chain_args.iter(),
 				);
 
 				let polkadot_config = SubstrateCli::create_configuration(
@@ -401,40 +460,54 @@ pub fn run() -> Result<()> {
 
 			let spec = load_spec(&params.chain.clone().unwrap_or_default())?;
 			let state_version = Cli::native_runtime_version(&spec).state_version();
-
-			let block: crate::service::Block = generate_genesis_block(&spec, state_version)?;
-			let raw_header = block.header().encode();
-			let output_buf = if params.raw {
-				raw_header
-			} else {
-				format!("0x{:?}", HexDisplay::from(&block.header().encode())).into_bytes()
-			};
-
-			if let Some(output) = &params.output {
-				std::fs::write(output, output_buf)?;
-			} else {
-				std::io::stdout().write_all(&output_buf)?;
-			}
-
-			Ok(())
+			params.run::<crate::service::Block>(&spec, state_version)
 		},
 		Some(Subcommand::ExportGenesisWasm(params)) => {
 			let mut builder = sc_cli::LoggerBuilder::new("");
 			builder.with_profiling(sc_tracing::TracingReceiver::Log, "");
 			let _ = builder.init();
 
-			let raw_wasm_blob =
-				extract_genesis_wasm(&cli.load_spec(&params.chain.clone().unwrap_or_default())?)?;
-			let output_buf = if params.raw {
-				raw_wasm_blob
-			} else {
-				format!("0x{:?}", HexDisplay::from(&raw_wasm_blob)).into_bytes()
-			};
+			params.run(&cli.load_spec(&params.chain.clone().unwrap_or_default())?)
+		},
+		Some(Subcommand::MeasureValidationCode(params)) => {
+			let mut builder = sc_cli::LoggerBuilder::new("");
+			builder.with_profiling(sc_tracing::TracingReceiver::Log, "");
+			let _ = builder.init();
 
-			if let Some(output) = &params.output {
-				std::fs::write(output, output_buf)?;
-			} else {
-				std::io::stdout().write_all(&output_buf)?;
+			let code = match &params.wasm {
+				Some(path) => std::fs::read(path)?,
+				None => extract_genesis_wasm(
+					&cli.load_spec(&params.chain.clone().unwrap_or_default())?,
+				)?,
+			};
+			let measurements = measure_validation_code(&code)?;
+
+			println!("Compressed size: {} bytes", measurements.compressed_size);
+			println!("Uncompressed size: {} bytes", measurements.uncompressed_size);
+			println!("Initial memory pages: {}", measurements.initial_pages);
+			match measurements.maximum_pages {
+				Some(pages) => println!("Maximum memory pages: {}", pages),
+				None => println!("Maximum memory pages: unbounded"),
+			}
+			println!(
+				"PVF executor memory: {} heap pages, at most {} pages",
+				PVF_EXTRA_HEAP_PAGES, PVF_MAX_MEMORY_PAGES,
+			);
+
+			check_pvf_memory_limits(&measurements)?;
+
+			if let Some(url) = params.relay_chain_rpc_url.clone() {
+				let config =
+					tokio::runtime::Runtime::new()?.block_on(relay_host_configuration(url))?;
+				println!("Relay chain max code size: {} bytes", config.max_code_size);
+
+				if measurements.compressed_size > config.max_code_size as usize {
+					return Err(format!(
+						"Compressed validation code of {} bytes exceeds the relay chain limit of {} bytes",
+						measurements.compressed_size, config.max_code_size,
+					)
+					.into())
+				}
 			}
 
 			Ok(())
@@ -500,9 +573,9 @@ pub fn run() -> Result<()> {
 
 				let polkadot_cli = RelayChainCli::new(
 					&config,
-					[RelayChainCli::executable_name().to_string()]
-						.iter()
-						.chain(cli.relaychain_args.iter()),
+					chain_spec::Extensions::try_get(&*config.chain_spec)
+						.map(|e| e.relay_chain.clone()),
+					cli.run.relay_chain_args.iter(),
 				);
 
 				let id = ParaId::from(para_id);
@@ -602,139 +675,81 @@ pub fn run() -> Result<()> {
 	}
 }
 
-impl DefaultConfigurationValues for RelayChainCli {
-	fn p2p_listen_port() -> u16 {
-		30334
-	}
-
-	fn rpc_ws_listen_port() -> u16 {
-		9945
-	}
-
-	fn rpc_http_listen_port() -> u16 {
-		9934
-	}
-
-	fn prometheus_listen_port() -> u16 {
-		9616
-	}
-}
-
-impl CliConfiguration<Self> for RelayChainCli {
-	fn shared_params(&self) -> &SharedParams {
-		self.base.base.shared_params()
-	}
-
-	fn import_params(&self) -> Option<&ImportParams> {
-		self.base.base.import_params()
-	}
-
-	fn network_params(&self) -> Option<&NetworkParams> {
-		self.base.base.network_params()
-	}
-
-	fn keystore_params(&self) -> Option<&KeystoreParams> {
-		self.base.base.keystore_params()
-	}
-
-	fn base_path(&self) -> Result<Option<BasePath>> {
-		Ok(self
-			.shared_params()
-			.base_path()
-			.or_else(|| self.base_path.clone().map(Into::into)))
-	}
-
-	fn rpc_http(&self, default_listen_port: u16) -> Result<Option<SocketAddr>> {
-		self.base.base.rpc_http(default_listen_port)
-	}
-
-	fn rpc_ipc(&self) -> Result<Option<String>> {
-		self.base.base.rpc_ipc()
-	}
-
-	fn rpc_ws(&self, default_listen_port: u16) -> Result<Option<SocketAddr>> {
-		self.base.base.rpc_ws(default_listen_port)
-	}
-
-	fn prometheus_config(
-		&self,
-		default_listen_port: u16,
-		chain_spec: &Box<dyn ChainSpec>,
-	) -> Result<Option<PrometheusConfig>> {
-		self.base.base.prometheus_config(default_listen_port, chain_spec)
-	}
-
-	fn init<F>(
-		&self,
-		_support_url: &String,
-		_impl_version: &String,
-		_logger_hook: F,
-		_config: &sc_service::Configuration,
-	) -> Result<()>
-	where
-		F: FnOnce(&mut sc_cli::LoggerBuilder, &sc_service::Configuration),
-	{
-		unreachable!("PolkadotCli is never initialized; qed");
-	}
-
-	fn chain_id(&self, is_dev: bool) -> Result<String> {
-		let chain_id = self.base.base.chain_id(is_dev)?;
-
-		Ok(if chain_id.is_empty() { self.chain_id.clone().unwrap_or_default() } else { chain_id })
-	}
-
-	fn role(&self, is_dev: bool) -> Result<sc_service::Role> {
-		self.base.base.role(is_dev)
-	}
-
-	fn transaction_pool(&self) -> Result<sc_service::config::TransactionPoolOptions> {
-		self.base.base.transaction_pool()
-	}
-
-	fn state_cache_child_ratio(&self) -> Result<Option<usize>> {
-		self.base.base.state_cache_child_ratio()
-	}
-
-	fn rpc_methods(&self) -> Result<sc_service::config::RpcMethods> {
-		self.base.base.rpc_methods()
-	}
-
-	fn rpc_ws_max_connections(&self) -> Result<Option<usize>> {
-		self.base.base.rpc_ws_max_connections()
-	}
-
-	fn rpc_cors(&self, is_dev: bool) -> Result<Option<Vec<String>>> {
-		self.base.base.rpc_cors(is_dev)
-	}
-
-	fn default_heap_pages(&self) -> Result<Option<u64>> {
-		self.base.base.default_heap_pages()
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A wasm module that declares a memory of `initial` and `maximum` pages.
+	fn module_with_memory(initial: u32, maximum: Option<u32>) -> Vec<u8> {
+		let module = parity_wasm::builder::module()
+			.memory()
+			.with_min(initial)
+			.with_max(maximum)
+			.build()
+			.build();
+		parity_wasm::serialize(module).unwrap()
+	}
+
+	fn measurements(initial_pages: u32, maximum_pages: Option<u32>) -> ValidationCodeMeasurements {
+		ValidationCodeMeasurements {
+			compressed_size: 0,
+			uncompressed_size: 0,
+			initial_pages,
+			maximum_pages,
+		}
 	}
 
-	fn force_authoring(&self) -> Result<bool> {
-		self.base.base.force_authoring()
-	}
+	#[test]
+	fn measures_uncompressed_code() {
+		let code = module_with_memory(17, Some(2080));
+		let compressed =
+			sp_maybe_compressed_blob::compress(&code, VALIDATION_CODE_BOMB_LIMIT).unwrap();
 
-	fn disable_grandpa(&self) -> Result<bool> {
-		self.base.base.disable_grandpa()
+		assert_eq!(
+			measure_validation_code(&code).unwrap(),
+			ValidationCodeMeasurements {
+				compressed_size: compressed.len(),
+				uncompressed_size: code.len(),
+				initial_pages: 17,
+				maximum_pages: Some(2080),
+			},
+		);
+	}
+
+	#[test]
+	fn measures_compressed_code() {
+		let code = module_with_memory(17, None);
+		let compressed =
+			sp_maybe_compressed_blob::compress(&code, VALIDATION_CODE_BOMB_LIMIT).unwrap();
+
+		assert_eq!(
+			measure_validation_code(&compressed).unwrap(),
+			ValidationCodeMeasurements {
+				compressed_size: compressed.len(),
+				uncompressed_size: code.len(),
+				initial_pages: 17,
+				maximum_pages: None,
+			},
+		);
 	}
 
-	fn max_runtime_instances(&self) -> Result<Option<usize>> {
-		self.base.base.max_runtime_instances()
-	}
+	#[test]
+	fn code_without_memory_is_rejected() {
+		let code = parity_wasm::serialize(parity_wasm::builder::module().build()).unwrap();
 
-	fn announce_block(&self) -> Result<bool> {
-		self.base.base.announce_block()
+		assert!(measure_validation_code(&code).is_err());
 	}
 
-	fn telemetry_endpoints(
-		&self,
-		chain_spec: &Box<dyn ChainSpec>,
-	) -> Result<Option<sc_telemetry::TelemetryEndpoints>> {
-		self.base.base.telemetry_endpoints(chain_spec)
-	}
+	#[test]
+	fn memory_is_checked_against_pvf_limits() {
+		assert!(check_pvf_memory_limits(&measurements(32, None)).is_ok());
+		assert!(check_pvf_memory_limits(&measurements(17, Some(PVF_MAX_MEMORY_PAGES))).is_ok());
 
-	fn node_name(&self) -> Result<String> {
-		self.base.base.node_name()
+		// too many initial pages to add the heap pages.
+		assert!(check_pvf_memory_limits(&measurements(33, None)).is_err());
+		// no room for the heap pages.
+		assert!(check_pvf_memory_limits(&measurements(17, Some(1024))).is_err());
+		// more pages than the executor allows.
+		assert!(check_pvf_memory_limits(&measurements(17, Some(PVF_MAX_MEMORY_PAGES + 1))).is_err());
 	}
 }
@@ -20,6 +20,17 @@
 
 use std::sync::Arc;
 
+use cumulus_client_candidate_validation::rpc::{CandidateValidationApi, CandidateValidationRpc};
+use cumulus_client_collator::{
+	rpc::{CollatorApi, CollatorRpc},
+	LastCandidateProof,
+};
+use cumulus_client_extrinsic_blocklist::{
+	rpc::{BlocklistApi, BlocklistRpc},
+	Blocklist,
+};
+use cumulus_primitives_core::ParaId;
+use cumulus_relay_chain_interface::RelayChainInterface;
 use pallet_contracts_rpc::{Contracts, ContractsApi};
 use parachains_common::{AccountId, Balance, Block, BlockNumber, Hash, Index as Nonce};
 use sc_client_api::AuxStore;
@@ -28,22 +39,48 @@ use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+use sp_keystore::SyncCryptoStorePtr;
 
 /// A type representing all RPC extensions.
 pub type RpcExtension = jsonrpc_core::IoHandler<sc_rpc::Metadata>;
 
 /// Full client dependencies
-pub struct FullDeps<C, P> {
+pub struct FullDeps<C, P, B> {
 	/// The client instance to use.
 	pub client: Arc<C>,
+	/// The backend instance to use.
+	pub backend: Arc<B>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Extrinsic blocklist of the collator, `None` if the node is not a collator.
+	pub blocklist: Option<Arc<Blocklist<C>>>,
+	/// Storage proof of the latest candidate, `None` if the node is not a collator.
+	pub last_proof: Option<LastCandidateProof<Hash>>,
+}
+
+/// Dependencies of the RPC extensions a downstream node adds to the RPC extensions of the rococo
+/// parachain node.
+///
+/// See [`crate::service::start_rococo_parachain_node_with_rpc_extensions`].
+pub struct ExtensionDeps<C, P> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// Transaction pool instance.
+	pub pool: Arc<P>,
+	/// Interface to the relay chain.
+	pub relay_chain_interface: Arc<dyn RelayChainInterface>,
+	/// The keystore of the node.
+	pub keystore: SyncCryptoStorePtr,
+	/// The id of the parachain.
+	pub para_id: ParaId,
+	/// Whether to deny unsafe calls
+	pub deny_unsafe: DenyUnsafe,
 }
 
 /// Instantiate all RPC extensions.
-pub fn create_full<C, P>(deps: FullDeps<C, P>) -> RpcExtension
+pub fn create_full<C, P, B>(deps: FullDeps<C, P, B>) -> RpcExtension
 where
 	C: ProvideRuntimeApi<Block>
 		+ HeaderBackend<Block>
@@ -56,13 +93,28 @@ where
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: BlockBuilder<Block>,
 	P: TransactionPool + Sync + Send + 'static,
+	B: sc_client_api::Backend<Block> + 'static,
 {
 	use frame_rpc_system::{FullSystem, SystemApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
-	let FullDeps { client, pool, deny_unsafe } = deps;
-
+	let FullDeps { client, backend, pool, deny_unsafe, blocklist, last_proof } = deps;
+
+	if let Some(blocklist) = blocklist {
+		io.extend_with(BlocklistApi::to_delegate(BlocklistRpc::new(
+			blocklist,
+			pool.clone(),
+			deny_unsafe,
+		)));
+	}
+	if let Some(last_proof) = last_proof {
+		io.extend_with(CollatorApi::to_delegate(CollatorRpc::new(last_proof, deny_unsafe)));
+	}
+	io.extend_with(CandidateValidationApi::to_delegate(CandidateValidationRpc::new(
+		backend,
+		deny_unsafe,
+	)));
 	io.extend_with(SystemApi::to_delegate(FullSystem::new(client.clone(), pool, deny_unsafe)));
 	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone())));
 
@@ -70,7 +122,7 @@ where
 }
 
 /// Instantiate all RPCs we want at the canvas-kusama chain.
-pub fn create_canvas_kusama<C, P>(deps: FullDeps<C, P>) -> RpcExtension
+pub fn create_canvas_kusama<C, P, B>(deps: FullDeps<C, P, B>) -> RpcExtension
 where
 	C: ProvideRuntimeApi<Block>
 		+ HeaderBackend<Block>
@@ -84,13 +136,28 @@ where
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber, Hash>,
 	C::Api: BlockBuilder<Block>,
 	P: TransactionPool + Sync + Send + 'static,
+	B: sc_client_api::Backend<Block> + 'static,
 {
 	use frame_rpc_system::{FullSystem, SystemApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
-	let FullDeps { client, pool, deny_unsafe } = deps;
-
+	let FullDeps { client, backend, pool, deny_unsafe, blocklist, last_proof } = deps;
+
+	if let Some(blocklist) = blocklist {
+		io.extend_with(BlocklistApi::to_delegate(BlocklistRpc::new(
+			blocklist,
+			pool.clone(),
+			deny_unsafe,
+		)));
+	}
+	if let Some(last_proof) = last_proof {
+		io.extend_with(CollatorApi::to_delegate(CollatorRpc::new(last_proof, deny_unsafe)));
+	}
+	io.extend_with(CandidateValidationApi::to_delegate(CandidateValidationRpc::new(
+		backend,
+		deny_unsafe,
+	)));
 	io.extend_with(SystemApi::to_delegate(FullSystem::new(client.clone(), pool, deny_unsafe)));
 	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone())));
 	io.extend_with(ContractsApi::to_delegate(Contracts::new(client)));
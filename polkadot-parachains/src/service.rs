@@ -16,19 +16,24 @@
 
 use codec::Codec;
 use cumulus_client_cli::CollatorOptions;
+use cumulus_client_collator::LastCandidateProof;
 use cumulus_client_consensus_aura::{AuraConsensus, BuildAuraConsensusParams, SlotProportion};
 use cumulus_client_consensus_common::{
 	ParachainBlockImport, ParachainCandidate, ParachainConsensus,
 };
+use cumulus_client_extrinsic_blocklist::{run_blocklist, Blocklist};
 use cumulus_client_network::BlockAnnounceValidator;
 use cumulus_client_service::{
 	prepare_node_config, start_collator, start_full_node, StartCollatorParams, StartFullNodeParams,
 };
+use cumulus_pallet_consensus_switchover::ConsensusSwitchoverApi;
 use cumulus_primitives_core::{
 	relay_chain::v2::{Hash as PHash, PersistedValidationData},
 	ParaId,
 };
-use cumulus_relay_chain_inprocess_interface::build_inprocess_relay_chain;
+use cumulus_relay_chain_inprocess_interface::{
+	build_inprocess_relay_chain, RelayChainResourceLimits,
+};
 use cumulus_relay_chain_interface::{RelayChainError, RelayChainInterface, RelayChainResult};
 use cumulus_relay_chain_rpc_interface::RelayChainRPCInterface;
 use polkadot_service::CollatorPair;
@@ -66,6 +71,9 @@ type HostFunctions = sp_io::SubstrateHostFunctions;
 type HostFunctions =
 	(sp_io::SubstrateHostFunctions, frame_benchmarking::benchmarking::HostFunctions);
 
+/// The slot duration of the relay chain.
+const RELAY_CHAIN_SLOT_DURATION: Duration = Duration::from_secs(6);
+
 /// Native executor instance.
 pub struct RococoParachainRuntimeExecutor;
 
@@ -293,17 +301,56 @@ async fn build_relay_chain_interface(
 	collator_options: CollatorOptions,
 ) -> RelayChainResult<(Arc<(dyn RelayChainInterface + 'static)>, Option<CollatorPair>)> {
 	match collator_options.relay_chain_rpc_url {
-		Some(relay_chain_url) =>
-			Ok((Arc::new(RelayChainRPCInterface::new(relay_chain_url).await?) as Arc<_>, None)),
+		Some(relay_chain_url) => {
+			let relay_chain_interface = RelayChainRPCInterface::new(
+				relay_chain_url,
+				collator_options.relay_chain_rpc_fallback_urls,
+				parachain_config.prometheus_registry(),
+			)
+			.await?;
+			Ok((Arc::new(relay_chain_interface) as Arc<_>, None))
+		},
 		None => build_inprocess_relay_chain(
 			polkadot_config,
 			parachain_config,
 			telemetry_worker_handle,
 			task_manager,
+			RelayChainResourceLimits {
+				worker_threads: collator_options.relay_chain_worker_threads,
+				nice: collator_options.relay_chain_nice,
+				io_priority: collator_options.relay_chain_io_priority,
+			},
 		),
 	}
 }
 
+/// Start keeping locally banned extrinsics out of the transaction pool of a collator.
+///
+/// Returns the blocklist that should be exposed over RPC, `None` if the node is no collator.
+fn start_extrinsic_blocklist<C, P>(
+	collator: bool,
+	client: &Arc<C>,
+	transaction_pool: &Arc<P>,
+	task_manager: &TaskManager,
+) -> sc_service::error::Result<Option<Arc<Blocklist<C>>>>
+where
+	C: sc_client_api::AuxStore + Send + Sync + 'static,
+	P: sc_transaction_pool_api::TransactionPool<Hash = Hash> + 'static,
+{
+	if !collator {
+		return Ok(None)
+	}
+
+	let blocklist = Arc::new(Blocklist::new(client.clone())?);
+	task_manager.spawn_handle().spawn(
+		"extrinsic-blocklist",
+		None,
+		run_blocklist(blocklist.clone(), transaction_pool.clone()),
+	);
+
+	Ok(Some(blocklist))
+}
+
 /// Start a shell node with the given parachain `Configuration` and relay chain `Configuration`.
 ///
 /// This is the actual implementation that is abstract over the executor and the runtime api for shell nodes.
@@ -437,7 +484,7 @@ where
 		Arc::new(move |hash, data| network.announce_block(hash, data))
 	};
 
-	let relay_chain_slot_duration = Duration::from_secs(6);
+	let relay_chain_slot_duration = RELAY_CHAIN_SLOT_DURATION;
 
 	if validator {
 		let parachain_consensus = build_consensus(
@@ -466,6 +513,7 @@ where
 			import_queue,
 			collator_key: collator_key.expect("Command line arguments do not allow this. qed"),
 			relay_chain_slot_duration,
+			last_proof: None,
 		};
 
 		start_collator(params).await?;
@@ -498,7 +546,7 @@ async fn start_node_impl<RuntimeApi, RB, BIQ, BIC>(
 	polkadot_config: Configuration,
 	collator_options: CollatorOptions,
 	id: ParaId,
-	_rpc_ext_builder: RB,
+	rpc_ext_builder: RB,
 	build_import_queue: BIQ,
 	build_consensus: BIC,
 ) -> sc_service::error::Result<(
@@ -523,8 +571,14 @@ where
 		+ frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	sc_client_api::StateBackendFor<TFullBackend<Block>, Block>: sp_api::StateBackend<BlakeTwo256>,
 	RB: Fn(
-			Arc<TFullClient<Block, RuntimeApi, WasmExecutor<HostFunctions>>>,
-		) -> Result<jsonrpc_core::IoHandler<sc_rpc::Metadata>, sc_service::Error>
+			rpc::ExtensionDeps<
+				TFullClient<Block, RuntimeApi, WasmExecutor<HostFunctions>>,
+				sc_transaction_pool::FullPool<
+					Block,
+					TFullClient<Block, RuntimeApi, WasmExecutor<HostFunctions>>,
+				>,
+			>,
+		) -> Result<rpc::RpcExtension, sc_service::Error>
 		+ Send
 		+ 'static,
 	BIQ: FnOnce(
@@ -602,18 +656,40 @@ where
 			warp_sync: None,
 		})?;
 
+	let blocklist =
+		start_extrinsic_blocklist(validator, &client, &transaction_pool, &task_manager)?;
+	let last_proof = validator.then(LastCandidateProof::new);
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
+		let backend = backend.clone();
 		let transaction_pool = transaction_pool.clone();
+		let last_proof = last_proof.clone();
+		let relay_chain_interface = relay_chain_interface.clone();
+		let keystore = params.keystore_container.sync_keystore();
 
 		Box::new(move |deny_unsafe, _| {
 			let deps = rpc::FullDeps {
+				client: client.clone(),
+				backend: backend.clone(),
+				pool: transaction_pool.clone(),
+				deny_unsafe,
+				blocklist: blocklist.clone(),
+				last_proof: last_proof.clone(),
+			};
+			let mut io = rpc::create_full(deps);
+
+			let extension_deps = rpc::ExtensionDeps {
 				client: client.clone(),
 				pool: transaction_pool.clone(),
+				relay_chain_interface: relay_chain_interface.clone(),
+				keystore: keystore.clone(),
+				para_id: id,
 				deny_unsafe,
 			};
+			io.extend_with(jsonrpc_core::MetaIoHandler::from(rpc_ext_builder(extension_deps)?));
 
-			Ok(rpc::create_full(deps))
+			Ok(io)
 		})
 	};
 
@@ -635,7 +711,7 @@ where
 		Arc::new(move |hash, data| network.announce_block(hash, data))
 	};
 
-	let relay_chain_slot_duration = Duration::from_secs(6);
+	let relay_chain_slot_duration = RELAY_CHAIN_SLOT_DURATION;
 
 	if validator {
 		let parachain_consensus = build_consensus(
@@ -664,6 +740,7 @@ where
 			import_queue,
 			collator_key: collator_key.expect("Command line arguments do not allow this. qed"),
 			relay_chain_slot_duration,
+			last_proof,
 		};
 
 		start_collator(params).await?;
@@ -744,12 +821,58 @@ pub async fn start_rococo_parachain_node(
 	TaskManager,
 	Arc<TFullClient<Block, rococo_parachain_runtime::RuntimeApi, WasmExecutor<HostFunctions>>>,
 )> {
-	start_node_impl::<rococo_parachain_runtime::RuntimeApi, _, _, _>(
+	start_rococo_parachain_node_with_rpc_extensions(
 		parachain_config,
 		polkadot_config,
 		collator_options,
 		id,
 		|_| Ok(Default::default()),
+	)
+	.await
+}
+
+/// Start a rococo parachain node with additional RPC extensions.
+///
+/// `rpc_ext_builder` is called whenever the RPC extensions of the node are built. The RPC methods
+/// it returns are served in addition to the RPC methods of the node. This allows downstream nodes
+/// to add their own RPC methods without forking the service.
+pub async fn start_rococo_parachain_node_with_rpc_extensions<RB>(
+	parachain_config: Configuration,
+	polkadot_config: Configuration,
+	collator_options: CollatorOptions,
+	id: ParaId,
+	rpc_ext_builder: RB,
+) -> sc_service::error::Result<(
+	TaskManager,
+	Arc<TFullClient<Block, rococo_parachain_runtime::RuntimeApi, WasmExecutor<HostFunctions>>>,
+)>
+where
+	RB: Fn(
+			rpc::ExtensionDeps<
+				TFullClient<
+					Block,
+					rococo_parachain_runtime::RuntimeApi,
+					WasmExecutor<HostFunctions>,
+				>,
+				sc_transaction_pool::FullPool<
+					Block,
+					TFullClient<
+						Block,
+						rococo_parachain_runtime::RuntimeApi,
+						WasmExecutor<HostFunctions>,
+					>,
+				>,
+			>,
+		) -> Result<rpc::RpcExtension, sc_service::Error>
+		+ Send
+		+ 'static,
+{
+	start_node_impl::<rococo_parachain_runtime::RuntimeApi, _, _, _>(
+		parachain_config,
+		polkadot_config,
+		collator_options,
+		id,
+		rpc_ext_builder,
 		rococo_parachain_build_import_queue,
 		|client,
 		 prometheus_registry,
@@ -898,7 +1021,7 @@ where
 		 task_manager,
 		 relay_chain_interface,
 		 transaction_pool,
-		 _,
+		 sync_oracle,
 		 _,
 		 _| {
 			let proposer_factory = sc_basic_authorship::ProposerFactory::with_proof_recording(
@@ -915,6 +1038,14 @@ where
 					proposer_factory,
 					block_import: client.clone(),
 					relay_chain_interface: relay_chain_interface.clone(),
+					sync_oracle,
+					additional_digests_provider: (),
+					block_cadence: (),
+					max_proof_size: None,
+					max_relay_parent_lag: None,
+					relay_slot_duration: RELAY_CHAIN_SLOT_DURATION,
+					prometheus_registry: prometheus_registry.cloned(),
+					telemetry: telemetry.clone(),
 					create_inherent_data_providers: move |_, (relay_parent, validation_data)| {
 						let relay_chain_interface = relay_chain_interface.clone();
 						async move {
@@ -958,6 +1089,25 @@ impl<R> BuildOnAccess<R> {
 	}
 }
 
+/// Returns `true` if the block `number` on top of `parent` should use Aura.
+///
+/// Uses the switchover block scheduled in the runtime of `parent` if there is one. Otherwise
+/// Aura is used once the runtime of `parent` implements the Aura runtime api.
+fn use_aura<Client, AuraId>(client: &Client, number: BlockNumber, parent: Hash) -> bool
+where
+	Client: sp_api::ProvideRuntimeApi<Block>,
+	Client::Api: AuraApi<Block, AuraId> + ConsensusSwitchoverApi<Block, BlockNumber>,
+	AuraId: Codec,
+{
+	let runtime_api = client.runtime_api();
+	let at = BlockId::hash(parent);
+
+	match runtime_api.switchover_block(&at) {
+		Ok(Some(switchover)) => number >= switchover,
+		_ => runtime_api.has_api::<dyn AuraApi<Block, AuraId>>(&at).unwrap_or(false),
+	}
+}
+
 /// Special [`ParachainConsensus`] implementation that waits for the upgrade from
 /// shell to a parachain runtime that implements Aura.
 struct WaitForAuraConsensus<Client, AuraId> {
@@ -982,7 +1132,7 @@ impl<Client, AuraId> Clone for WaitForAuraConsensus<Client, AuraId> {
 impl<Client, AuraId> ParachainConsensus<Block> for WaitForAuraConsensus<Client, AuraId>
 where
 	Client: sp_api::ProvideRuntimeApi<Block> + Send + Sync,
-	Client::Api: AuraApi<Block, AuraId>,
+	Client::Api: AuraApi<Block, AuraId> + ConsensusSwitchoverApi<Block, BlockNumber>,
 	AuraId: Send + Codec + Sync,
 {
 	async fn produce_candidate(
@@ -991,13 +1141,8 @@ where
 		relay_parent: PHash,
 		validation_data: &PersistedValidationData,
 	) -> Option<ParachainCandidate<Block>> {
-		let block_id = BlockId::hash(parent.hash());
-		if self
-			.client
-			.runtime_api()
-			.has_api::<dyn AuraApi<Block, AuraId>>(&block_id)
-			.unwrap_or(false)
-		{
+		let number = *parent.number() + 1;
+		if use_aura::<_, AuraId>(&*self.client, number, parent.hash()) {
 			self.aura_consensus
 				.lock()
 				.await
@@ -1014,6 +1159,7 @@ where
 	}
 }
 
+/// Verifies blocks with Aura or the relay chain consensus, see [`use_aura`].
 struct Verifier<Client, AuraId> {
 	client: Arc<Client>,
 	aura_verifier: BuildOnAccess<Box<dyn VerifierT<Block>>>,
@@ -1025,21 +1171,17 @@ struct Verifier<Client, AuraId> {
 impl<Client, AuraId> VerifierT<Block> for Verifier<Client, AuraId>
 where
 	Client: sp_api::ProvideRuntimeApi<Block> + Send + Sync,
-	Client::Api: AuraApi<Block, AuraId>,
+	Client::Api: AuraApi<Block, AuraId> + ConsensusSwitchoverApi<Block, BlockNumber>,
 	AuraId: Send + Sync + Codec,
 {
 	async fn verify(
 		&mut self,
 		block_import: BlockImportParams<Block, ()>,
 	) -> Result<(BlockImportParams<Block, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
-		let block_id = BlockId::hash(*block_import.header.parent_hash());
-
-		if self
-			.client
-			.runtime_api()
-			.has_api::<dyn AuraApi<Block, AuraId>>(&block_id)
-			.unwrap_or(false)
-		{
+		let number = *block_import.header.number();
+		let parent = *block_import.header.parent_hash();
+
+		if use_aura::<_, AuraId>(&*self.client, number, parent) {
 			self.aura_verifier.get_mut().verify(block_import).await
 		} else {
 			self.relay_chain_verifier.verify(block_import).await
@@ -1073,7 +1215,8 @@ where
 			StateBackend = sc_client_api::StateBackendFor<TFullBackend<Block>, Block>,
 		> + sp_offchain::OffchainWorkerApi<Block>
 		+ sp_block_builder::BlockBuilder<Block>
-		+ sp_consensus_aura::AuraApi<Block, <<AuraId as AppKey>::Pair as Pair>::Public>,
+		+ sp_consensus_aura::AuraApi<Block, <<AuraId as AppKey>::Pair as Pair>::Public>
+		+ ConsensusSwitchoverApi<Block, BlockNumber>,
 	sc_client_api::StateBackendFor<TFullBackend<Block>, Block>: sp_api::StateBackend<BlakeTwo256>,
 	<<AuraId as AppKey>::Pair as Pair>::Signature:
 		TryFrom<Vec<u8>> + std::hash::Hash + sp_runtime::traits::Member + Codec,
@@ -1152,6 +1295,7 @@ where
 		+ sp_block_builder::BlockBuilder<Block>
 		+ cumulus_primitives_core::CollectCollationInfo<Block>
 		+ sp_consensus_aura::AuraApi<Block, <<AuraId as AppKey>::Pair as Pair>::Public>
+		+ ConsensusSwitchoverApi<Block, BlockNumber>
 		+ pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>
 		+ frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	sc_client_api::StateBackendFor<TFullBackend<Block>, Block>: sp_api::StateBackend<BlakeTwo256>,
@@ -1180,6 +1324,7 @@ where
 			let telemetry2 = telemetry.clone();
 			let prometheus_registry2 = prometheus_registry.map(|r| (*r).clone());
 			let relay_chain_for_aura = relay_chain_interface.clone();
+			let sync_oracle2 = sync_oracle.clone();
 			let aura_consensus = BuildOnAccess::Uninitialized(Some(Box::new(move || {
 				let slot_duration =
 					cumulus_client_consensus_aura::slot_duration(&*client2).unwrap();
@@ -1229,7 +1374,7 @@ where
 						block_import: client2.clone(),
 						para_client: client2.clone(),
 						backoff_authoring_blocks: Option::<()>::None,
-						sync_oracle,
+						sync_oracle: sync_oracle2,
 						keystore,
 						force_authoring,
 						slot_duration,
@@ -1257,6 +1402,14 @@ where
 						proposer_factory,
 						block_import: client.clone(),
 						relay_chain_interface: relay_chain_interface.clone(),
+						sync_oracle,
+						additional_digests_provider: (),
+						block_cadence: (),
+						max_proof_size: None,
+						max_relay_parent_lag: None,
+						relay_slot_duration: RELAY_CHAIN_SLOT_DURATION,
+						prometheus_registry: prometheus_registry.cloned(),
+						telemetry: telemetry.clone(),
 						create_inherent_data_providers:
 							move |_, (relay_parent, validation_data)| {
 								let relay_chain_interface = relay_chain_interface.clone();
@@ -1404,15 +1557,24 @@ where
 			warp_sync: None,
 		})?;
 
+	let blocklist =
+		start_extrinsic_blocklist(validator, &client, &transaction_pool, &task_manager)?;
+	let last_proof = validator.then(LastCandidateProof::new);
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
+		let backend = backend.clone();
 		let transaction_pool = transaction_pool.clone();
+		let last_proof = last_proof.clone();
 
 		Box::new(move |deny_unsafe, _| {
 			let deps = crate::rpc::FullDeps {
 				client: client.clone(),
+				backend: backend.clone(),
 				pool: transaction_pool.clone(),
 				deny_unsafe,
+				blocklist: blocklist.clone(),
+				last_proof: last_proof.clone(),
 			};
 
 			Ok(crate::rpc::create_canvas_kusama(deps))
@@ -1437,7 +1599,7 @@ where
 		Arc::new(move |hash, data| network.announce_block(hash, data))
 	};
 
-	let relay_chain_slot_duration = Duration::from_secs(6);
+	let relay_chain_slot_duration = RELAY_CHAIN_SLOT_DURATION;
 
 	if validator {
 		let parachain_consensus = build_consensus(
@@ -1466,6 +1628,7 @@ where
 			import_queue,
 			collator_key: collator_key.expect("Command line arguments do not allow this. qed"),
 			relay_chain_slot_duration,
+			last_proof,
 		};
 
 		start_collator(params).await?;
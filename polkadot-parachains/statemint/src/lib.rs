@@ -432,6 +432,16 @@ impl parachain_info::Config for Runtime {}
 
 impl cumulus_pallet_aura_ext::Config for Runtime {}
 
+impl cumulus_pallet_consensus_switchover::Config for Runtime {
+	type Event = Event;
+	// Statemint has authored with Aura since genesis, so there is no switchover to schedule. The
+	// pallet only backs the `ConsensusSwitchoverApi` that the node queries.
+	type UpdateOrigin = frame_support::traits::NeverEnsureOrigin<()>;
+	type SwitchoverParams = ();
+	type OnSwitchover = ();
+	type WeightInfo = cumulus_pallet_consensus_switchover::weights::SubstrateWeight<Runtime>;
+}
+
 impl cumulus_pallet_xcmp_queue::Config for Runtime {
 	type Event = Event;
 	type XcmExecutor = XcmExecutor<XcmConfig>;
@@ -441,6 +451,7 @@ impl cumulus_pallet_xcmp_queue::Config for Runtime {
 	type ControllerOrigin =
 		EnsureOneOf<EnsureRoot<AccountId>, EnsureXcm<IsMajorityOfBody<DotLocation, ExecutiveBody>>>;
 	type ControllerOriginConverter = XcmOriginToTransactDispatchOrigin;
+	type FeeHandler = ();
 	type WeightInfo = weights::cumulus_pallet_xcmp_queue::WeightInfo<Runtime>;
 }
 
@@ -448,6 +459,7 @@ impl cumulus_pallet_dmp_queue::Config for Runtime {
 	type Event = Event;
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
+	type FeeHandler = ();
 }
 
 parameter_types! {
@@ -570,6 +582,7 @@ construct_runtime!(
 		Session: pallet_session::{Pallet, Call, Storage, Event, Config<T>} = 22,
 		Aura: pallet_aura::{Pallet, Storage, Config<T>} = 23,
 		AuraExt: cumulus_pallet_aura_ext::{Pallet, Storage, Config} = 24,
+		ConsensusSwitchover: cumulus_pallet_consensus_switchover::{Pallet, Call, Storage, Event<T>} = 25,
 
 		// XCM helpers.
 		XcmpQueue: cumulus_pallet_xcmp_queue::{Pallet, Call, Storage, Event<T>} = 30,
@@ -742,6 +755,12 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl cumulus_pallet_consensus_switchover::ConsensusSwitchoverApi<Block, BlockNumber> for Runtime {
+		fn switchover_block() -> Option<BlockNumber> {
+			ConsensusSwitchover::switchover_block()
+		}
+	}
+
 	impl cumulus_primitives_core::CollectCollationInfo<Block> for Runtime {
 		fn collect_collation_info(header: &<Block as BlockT>::Header) -> cumulus_primitives_core::CollationInfo {
 			ParachainSystem::collect_collation_info(header)
@@ -61,6 +61,18 @@ impl<T: frame_system::Config> pallet_collator_selection::WeightInfo for WeightIn
 		(12_277_000 as Weight)
 			.saturating_add(T::DbWeight::get().writes(1 as Weight))
 	}
+	// Not benchmarked yet, uses the weight of `set_candidacy_bond`, which also writes one value.
+	// Storage: CollatorSelection CandidateInactivityPenalty (r:0 w:1)
+	fn set_inactivity_penalty() -> Weight {
+		(12_277_000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Not benchmarked yet, uses the weight of `set_candidacy_bond`, which also writes one value.
+	// Storage: CollatorSelection GracePeriod (r:0 w:1)
+	fn set_grace_period() -> Weight {
+		(12_277_000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
 	// Storage: CollatorSelection Candidates (r:1 w:1)
 	// Storage: CollatorSelection DesiredCandidates (r:1 w:0)
 	// Storage: CollatorSelection Invulnerables (r:1 w:0)
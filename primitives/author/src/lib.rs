@@ -0,0 +1,54 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Cumulus author primitives.
+//!
+//! Defines [`AuthorId`], the session key collators author parachain blocks with. It is meant to
+//! be used as the `AuthorId` of `pallet-author-inherent` and `pallet-author-mapping`, so that
+//! runtimes and nodes do not each have to define their own key type.
+//!
+//! `pallet-author-mapping` maps an [`AuthorId`] to the account that registered it. Runtimes that
+//! use the account of the key itself can convert it with `AccountId32::from`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::AccountId32;
+
+/// The key type of [`AuthorId`].
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"nmbs");
+
+mod app {
+	use sp_application_crypto::{app_crypto, sr25519};
+	app_crypto!(sr25519, super::KEY_TYPE);
+}
+
+sp_application_crypto::with_pair! {
+	/// A key pair of an author.
+	pub type AuthorPair = app::Pair;
+}
+
+/// The identifier of the author of a parachain block.
+pub type AuthorId = app::Public;
+
+/// A signature of the author of a parachain block.
+pub type AuthorSignature = app::Signature;
+
+impl From<AuthorId> for AccountId32 {
+	fn from(author_id: AuthorId) -> Self {
+		sp_core::sr25519::Public::from(author_id).into()
+	}
+}
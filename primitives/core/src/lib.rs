@@ -20,7 +20,7 @@
 
 use codec::{Decode, Encode};
 use polkadot_parachain::primitives::HeadData;
-use sp_runtime::{traits::Block as BlockT, RuntimeDebug};
+use sp_runtime::{traits::Block as BlockT, ConsensusEngineId, Digest, DigestItem, RuntimeDebug};
 use sp_std::prelude::*;
 
 pub use polkadot_core_primitives::InboundDownwardMessage;
@@ -55,6 +55,8 @@ pub enum MessageSendError {
 	TooBig,
 	/// Some other error.
 	Other,
+	/// The message exceeds the rate limit of the channel.
+	RateLimited,
 }
 
 impl From<MessageSendError> for &'static str {
@@ -65,10 +67,55 @@ impl From<MessageSendError> for &'static str {
 			NoChannel => "NoChannel",
 			TooBig => "TooBig",
 			Other => "Other",
+			RateLimited => "RateLimited",
 		}
 	}
 }
 
+/// The [`ConsensusEngineId`] of the [`BlockSummary`] digest item.
+pub const BLOCK_SUMMARY_ENGINE_ID: ConsensusEngineId = *b"CSUM";
+
+/// Summary of the messaging activity of a parachain block.
+///
+/// Deposited by `parachain-system` as a digest item in every block, so that light clients and
+/// indexers can find blocks of interest without executing them.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct BlockSummary {
+	/// Number of downward messages processed in the block.
+	#[codec(compact)]
+	pub downward_messages: u32,
+	/// Number of inbound horizontal messages processed in the block.
+	#[codec(compact)]
+	pub horizontal_messages: u32,
+	/// Number of upward messages sent in the block.
+	#[codec(compact)]
+	pub upward_messages: u32,
+	/// Number of outbound horizontal messages sent in the block.
+	#[codec(compact)]
+	pub outbound_horizontal_messages: u32,
+	/// Whether an upgrade of the validation code is pending at the end of the block.
+	pub upgrade_pending: bool,
+}
+
+impl BlockSummary {
+	/// Convert the summary into its digest item.
+	pub fn to_digest_item(&self) -> DigestItem {
+		DigestItem::Consensus(BLOCK_SUMMARY_ENGINE_ID, self.encode())
+	}
+
+	/// Decode the summary from `item`.
+	///
+	/// Returns `None` if `item` is no block summary.
+	pub fn from_digest_item(item: &DigestItem) -> Option<Self> {
+		item.consensus_try_to(&BLOCK_SUMMARY_ENGINE_ID)
+	}
+
+	/// Extract the summary from the `digest` of a block header.
+	pub fn extract(digest: &Digest) -> Option<Self> {
+		digest.convert_first(Self::from_digest_item)
+	}
+}
+
 /// Information about an XCMP channel.
 pub struct ChannelInfo {
 	/// The maximum number of messages that can be pending in the channel at once.
@@ -92,8 +139,8 @@ pub trait GetChannelInfo {
 
 /// Something that should be called when sending an upward message.
 pub trait UpwardMessageSender {
-	/// Send the given UMP message; return the expected number of blocks before the message will
-	/// be dispatched or an error if the message cannot be sent.
+	/// Send the given UMP message; return the index of the message, which can be used to
+	/// correlate it with later events, or an error if the message cannot be sent.
 	fn send_upward_message(msg: UpwardMessage) -> Result<u32, MessageSendError>;
 }
 impl UpwardMessageSender for () {
@@ -102,6 +149,41 @@ impl UpwardMessageSender for () {
 	}
 }
 
+/// Provides the relay parent of the current block.
+///
+/// Implemented by parachain-system. Pallets that only need the relay parent can depend on this
+/// trait instead of parachain-system, so they can be mocked and used in standalone chains.
+pub trait RelayChainStateInfo {
+	/// The number of the relay parent.
+	///
+	/// `None` if the validation data is not available yet, e.g. in `on_initialize`.
+	fn relay_parent_number() -> Option<relay_chain::BlockNumber>;
+
+	/// The storage root of the relay parent.
+	///
+	/// `None` if the validation data is not available yet, e.g. in `on_initialize`.
+	fn relay_parent_storage_root() -> Option<relay_chain::Hash>;
+
+	/// The proof of the relay chain state at the relay parent that was included in the block.
+	///
+	/// Can be verified against [`Self::relay_parent_storage_root`].
+	fn relay_state_proof() -> Option<sp_trie::StorageProof>;
+}
+
+impl RelayChainStateInfo for () {
+	fn relay_parent_number() -> Option<relay_chain::BlockNumber> {
+		None
+	}
+
+	fn relay_parent_storage_root() -> Option<relay_chain::Hash> {
+		None
+	}
+
+	fn relay_state_proof() -> Option<sp_trie::StorageProof> {
+		None
+	}
+}
+
 /// The status of a channel.
 pub enum ChannelStatus {
 	/// Channel doesn't exist/has been closed.
@@ -255,4 +337,25 @@ sp_api::decl_runtime_apis! {
 		/// we are collecting the collation info for.
 		fn collect_collation_info(header: &Block::Header) -> CollationInfo;
 	}
+
+	/// Runtime api to query the relay chain host configuration the parachain operates under.
+	pub trait HostConfigurationApi {
+		/// Returns the host configuration read from the relay chain state proof of the best block.
+		///
+		/// Returns `None` before the first block with validation data was imported.
+		fn host_configuration() -> Option<AbridgedHostConfiguration>;
+	}
+
+	/// Runtime api to query the validation data and the pending upgrade of a parachain block.
+	///
+	/// The messages sent by the block are available through [`CollectCollationInfo`].
+	pub trait ValidationDataApi {
+		/// Returns the validation data the block was built on.
+		///
+		/// Returns `None` before the first block with validation data was imported.
+		fn validation_data() -> Option<PersistedValidationData>;
+
+		/// Returns the hash of the validation code that waits for the go ahead of the relay chain.
+		fn pending_validation_code_hash() -> Option<relay_chain::v2::ValidationCodeHash>;
+	}
 }
@@ -0,0 +1,108 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Client side code for providing the external data inherent.
+
+use crate::{InherentError, INHERENT_IDENTIFIER};
+use codec::Decode;
+use std::{io::Read, path::PathBuf};
+
+const LOG_TARGET: &str = "external-data-inherent";
+
+/// Where the encoded [`ExternalData`](crate::ExternalData) is read from.
+#[derive(Clone, Debug)]
+pub enum ExternalDataSource {
+	/// A file that is read whenever a block is built.
+	File(PathBuf),
+	/// A unix socket that is connected to whenever a block is built.
+	///
+	/// The data is read until the other side closes the connection.
+	#[cfg(unix)]
+	UnixSocket(PathBuf),
+}
+
+impl ExternalDataSource {
+	/// Read the encoded external data.
+	pub fn read(&self) -> std::io::Result<Vec<u8>> {
+		match self {
+			Self::File(path) => std::fs::read(path),
+			#[cfg(unix)]
+			Self::UnixSocket(path) => {
+				let mut data = Vec::new();
+				std::os::unix::net::UnixStream::connect(path)?.read_to_end(&mut data)?;
+				Ok(data)
+			},
+		}
+	}
+}
+
+/// Provides the external data inherent.
+///
+/// The data is passed to the runtime as is, it is decoded and verified by the runtime. If the
+/// source can not be read, no external data is provided and the block is built without it.
+pub struct InherentDataProvider {
+	data: Option<Vec<u8>>,
+}
+
+impl InherentDataProvider {
+	/// Create a new instance that provides the data currently available from `source`.
+	pub fn from_source(source: &ExternalDataSource) -> Self {
+		let data = source
+			.read()
+			.map_err(|e| {
+				tracing::warn!(
+					target: LOG_TARGET,
+					error = ?e,
+					?source,
+					"Failed to read external data, building the block without it.",
+				)
+			})
+			.ok();
+
+		Self { data }
+	}
+
+	/// Create a new instance that provides the given encoded external data.
+	pub fn new(data: Option<Vec<u8>>) -> Self {
+		Self { data }
+	}
+}
+
+#[async_trait::async_trait]
+impl sp_inherents::InherentDataProvider for InherentDataProvider {
+	fn provide_inherent_data(
+		&self,
+		inherent_data: &mut sp_inherents::InherentData,
+	) -> Result<(), sp_inherents::Error> {
+		match &self.data {
+			Some(data) => inherent_data.put_data(INHERENT_IDENTIFIER, data),
+			None => Ok(()),
+		}
+	}
+
+	async fn try_handle_error(
+		&self,
+		identifier: &sp_inherents::InherentIdentifier,
+		error: &[u8],
+	) -> Option<Result<(), sp_inherents::Error>> {
+		if *identifier != INHERENT_IDENTIFIER {
+			return None
+		}
+
+		let error = InherentError::decode(&mut &error[..]).ok()?;
+		Some(Err(sp_inherents::Error::Application(Box::from(format!("{:?}", error)))))
+	}
+}
@@ -0,0 +1,88 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Cumulus external data primitives.
+//!
+//! External data, e.g. a price feed, is fetched by the collator and put into its blocks through
+//! the external data inherent. The data is signed by signers the runtime trusts and the runtime
+//! only accepts it if enough of them signed it and it is newer than the data it accepted before,
+//! see `cumulus-pallet-external-data`.
+//!
+//! The [`InherentDataProvider`] reads the encoded [`ExternalData`] from an
+//! [`ExternalDataSource`], to which an external process, e.g. an oracle client, writes it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::RuntimeDebug;
+use sp_inherents::{InherentIdentifier, IsFatalError};
+use sp_std::vec::Vec;
+
+#[cfg(feature = "std")]
+mod client_side;
+#[cfg(feature = "std")]
+pub use client_side::*;
+
+/// The identifier of the external data inherent.
+pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"extdata0";
+
+/// Context at the start of the message the signers sign.
+pub const SIGNING_CONTEXT: &[u8] = b"cumulus-external-data";
+
+/// External data and the signatures of the signers that vouch for it.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct ExternalData<SignerId, Signature> {
+	/// The round of the data, the signers increase it with every update.
+	pub round: u64,
+	/// The payload, its encoding is up to the runtime.
+	pub payload: Vec<u8>,
+	/// The signatures of the signers over the [`signing_message`](Self::signing_message).
+	pub signatures: Vec<(SignerId, Signature)>,
+}
+
+impl<SignerId, Signature> ExternalData<SignerId, Signature> {
+	/// The message the signers sign, the encoded [`SIGNING_CONTEXT`], genesis hash of the
+	/// parachain the data is meant for, `round` and `payload`.
+	///
+	/// The context and the genesis hash prevent that signatures made for other purposes or for
+	/// other chains are accepted.
+	pub fn signing_message(&self, genesis_hash: &[u8]) -> Vec<u8> {
+		(SIGNING_CONTEXT, genesis_hash, self.round, &self.payload).encode()
+	}
+}
+
+/// Errors of the external data inherent.
+#[derive(Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode))]
+pub enum InherentError {
+	/// The inherent data could not be decoded.
+	InvalidData,
+	/// The payload is bigger than the runtime accepts.
+	PayloadTooBig,
+	/// Not enough trusted signers signed the payload.
+	NotEnoughSignatures,
+	/// The round is not newer than the round of the last accepted external data.
+	StaleRound,
+	/// There are more signatures than the runtime has signers.
+	TooManySignatures,
+}
+
+impl IsFatalError for InherentError {
+	fn is_fatal_error(&self) -> bool {
+		true
+	}
+}
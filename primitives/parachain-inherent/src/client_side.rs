@@ -19,10 +19,16 @@
 use crate::ParachainInherentData;
 use codec::Decode;
 use cumulus_primitives_core::{
-	relay_chain::{self, v2::HrmpChannelId, Hash as PHash},
+	relay_chain::{
+		self,
+		v2::{HrmpChannelId, OccupiedCoreAssumption},
+		Hash as PHash,
+	},
 	ParaId, PersistedValidationData,
 };
 use cumulus_relay_chain_interface::RelayChainInterface;
+use cumulus_relay_chain_rpc_interface::RelayChainRPCInterface;
+use url::Url;
 
 const LOG_TARGET: &str = "parachain-inherent";
 
@@ -170,6 +176,44 @@ impl ParachainInherentData {
 			relay_chain_state,
 		})
 	}
+
+	/// Create the [`ParachainInherentData`] at the given `relay_parent`, using the relay chain
+	/// full node at `relay_rpc_url`.
+	///
+	/// Unlike [`Self::create_at`], this doesn't need a relay chain client in the same process. The
+	/// validation data is fetched from the relay chain node as well.
+	///
+	/// Returns `None` if the creation failed.
+	pub async fn create_at_with_rpc(
+		relay_rpc_url: Url,
+		relay_parent: PHash,
+		para_id: ParaId,
+	) -> Option<ParachainInherentData> {
+		let relay_chain_interface = RelayChainRPCInterface::new(relay_rpc_url, Vec::new(), None)
+			.await
+			.map_err(|e| {
+				tracing::error!(
+					target: LOG_TARGET,
+					error = ?e,
+					"Cannot connect to the relay chain node.",
+				);
+			})
+			.ok()?;
+		let validation_data = relay_chain_interface
+			.persisted_validation_data(relay_parent, para_id, OccupiedCoreAssumption::TimedOut)
+			.await
+			.map_err(|e| {
+				tracing::error!(
+					target: LOG_TARGET,
+					relay_parent = ?relay_parent,
+					error = ?e,
+					"An error occured during requesting the persisted validation data.",
+				);
+			})
+			.ok()??;
+
+		Self::create_at(relay_parent, &relay_chain_interface, &validation_data, para_id).await
+	}
 }
 
 #[async_trait::async_trait]
@@ -16,9 +16,7 @@
 
 use crate::{ParachainInherentData, INHERENT_IDENTIFIER};
 use codec::Decode;
-use cumulus_primitives_core::{
-	relay_chain, InboundDownwardMessage, InboundHrmpMessage, ParaId, PersistedValidationData,
-};
+use cumulus_primitives_core::{relay_chain, InboundDownwardMessage, InboundHrmpMessage, ParaId};
 use sc_client_api::{Backend, StorageProvider};
 use sp_api::BlockId;
 use sp_core::twox_128;
@@ -178,17 +176,13 @@ impl InherentDataProvider for MockValidationDataInherentDataProvider {
 			sproof_builder.upsert_inbound_channel(*para_id).mqc_head = Some(channel_mqc.head());
 		}
 
-		let (relay_parent_storage_root, proof) = sproof_builder.into_state_root_and_proof();
+		let (validation_data, proof) =
+			sproof_builder.into_validation_data_and_proof(relay_parent_number);
 
 		inherent_data.put_data(
 			INHERENT_IDENTIFIER,
 			&ParachainInherentData {
-				validation_data: PersistedValidationData {
-					parent_head: Default::default(),
-					relay_parent_storage_root,
-					relay_parent_number,
-					max_pov_size: Default::default(),
-				},
+				validation_data,
 				downward_messages,
 				horizontal_messages,
 				relay_chain_state: proof,
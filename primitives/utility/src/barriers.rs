@@ -0,0 +1,333 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! XCM barriers that restrict the instructions an origin may execute.
+//!
+//! Instead of hand-writing a barrier per origin, a runtime declares an [`InstructionAllowList`]
+//! that lists the allowed instructions for the relay chain, sibling parachains and local origins,
+//! and wraps its barrier in [`WithInstructionAllowList`]:
+//!
+//! ```ignore
+//! use cumulus_primitives_utility::barriers::{InstructionAllowList, InstructionKind as I};
+//!
+//! parameter_types! {
+//!     pub const AllowedInstructions: InstructionAllowList = InstructionAllowList {
+//!         relay: &[I::ReceiveTeleportedAsset, I::ClearOrigin, I::BuyExecution, I::DepositAsset],
+//!         sibling: &[I::ReserveAssetDeposited, I::ClearOrigin, I::BuyExecution, I::DepositAsset],
+//!         local: InstructionAllowList::ALL,
+//!     };
+//! }
+//!
+//! pub type Barrier = WithInstructionAllowList<AllowedInstructions, (
+//!     TakeWeightCredit,
+//!     AllowTopLevelPaidExecutionFrom<Everything>,
+//! )>;
+//! ```
+
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_std::marker::PhantomData;
+use xcm::latest::{prelude::*, Weight as XcmWeight};
+use xcm_executor::traits::ShouldExecute;
+
+/// The class of an XCM origin, as seen from a parachain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OriginClass {
+	/// The relay chain or a location inside of it that is not a parachain.
+	Relay,
+	/// A sibling parachain or a location inside of it.
+	Sibling,
+	/// A location inside of this parachain.
+	Local,
+}
+
+impl OriginClass {
+	/// Classify `origin`.
+	///
+	/// Returns `None` for origins outside of the relay chain consensus system.
+	pub fn of(origin: &MultiLocation) -> Option<Self> {
+		match origin {
+			MultiLocation { parents: 0, .. } => Some(Self::Local),
+			MultiLocation { parents: 1, interior } => match interior.first() {
+				Some(Parachain(_)) => Some(Self::Sibling),
+				_ => Some(Self::Relay),
+			},
+			_ => None,
+		}
+	}
+}
+
+/// The kind of an XCM [`Instruction`], without its operands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub enum InstructionKind {
+	WithdrawAsset,
+	ReserveAssetDeposited,
+	ReceiveTeleportedAsset,
+	QueryResponse,
+	TransferAsset,
+	TransferReserveAsset,
+	Transact,
+	HrmpNewChannelOpenRequest,
+	HrmpChannelAccepted,
+	HrmpChannelClosing,
+	ClearOrigin,
+	DescendOrigin,
+	ReportError,
+	DepositAsset,
+	DepositReserveAsset,
+	ExchangeAsset,
+	InitiateReserveWithdraw,
+	InitiateTeleport,
+	QueryHolding,
+	BuyExecution,
+	RefundSurplus,
+	SetErrorHandler,
+	SetAppendix,
+	ClearError,
+	ClaimAsset,
+	Trap,
+	SubscribeVersion,
+	UnsubscribeVersion,
+}
+
+impl InstructionKind {
+	/// Returns the kind of `instruction`.
+	pub fn of<Call>(instruction: &Instruction<Call>) -> Self {
+		match instruction {
+			WithdrawAsset(..) => Self::WithdrawAsset,
+			ReserveAssetDeposited(..) => Self::ReserveAssetDeposited,
+			ReceiveTeleportedAsset(..) => Self::ReceiveTeleportedAsset,
+			QueryResponse { .. } => Self::QueryResponse,
+			TransferAsset { .. } => Self::TransferAsset,
+			TransferReserveAsset { .. } => Self::TransferReserveAsset,
+			Transact { .. } => Self::Transact,
+			HrmpNewChannelOpenRequest { .. } => Self::HrmpNewChannelOpenRequest,
+			HrmpChannelAccepted { .. } => Self::HrmpChannelAccepted,
+			HrmpChannelClosing { .. } => Self::HrmpChannelClosing,
+			ClearOrigin => Self::ClearOrigin,
+			DescendOrigin(..) => Self::DescendOrigin,
+			ReportError { .. } => Self::ReportError,
+			DepositAsset { .. } => Self::DepositAsset,
+			DepositReserveAsset { .. } => Self::DepositReserveAsset,
+			ExchangeAsset { .. } => Self::ExchangeAsset,
+			InitiateReserveWithdraw { .. } => Self::InitiateReserveWithdraw,
+			InitiateTeleport { .. } => Self::InitiateTeleport,
+			QueryHolding { .. } => Self::QueryHolding,
+			BuyExecution { .. } => Self::BuyExecution,
+			RefundSurplus => Self::RefundSurplus,
+			SetErrorHandler(..) => Self::SetErrorHandler,
+			SetAppendix(..) => Self::SetAppendix,
+			ClearError => Self::ClearError,
+			ClaimAsset { .. } => Self::ClaimAsset,
+			Trap(..) => Self::Trap,
+			SubscribeVersion { .. } => Self::SubscribeVersion,
+			UnsubscribeVersion => Self::UnsubscribeVersion,
+		}
+	}
+}
+
+/// The instructions that each [`OriginClass`] is allowed to execute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstructionAllowList {
+	/// Instructions allowed for [`OriginClass::Relay`].
+	pub relay: &'static [InstructionKind],
+	/// Instructions allowed for [`OriginClass::Sibling`].
+	pub sibling: &'static [InstructionKind],
+	/// Instructions allowed for [`OriginClass::Local`].
+	pub local: &'static [InstructionKind],
+}
+
+impl InstructionAllowList {
+	/// All instructions.
+	pub const ALL: &'static [InstructionKind] = {
+		use InstructionKind::*;
+		&[
+			WithdrawAsset,
+			ReserveAssetDeposited,
+			ReceiveTeleportedAsset,
+			QueryResponse,
+			TransferAsset,
+			TransferReserveAsset,
+			Transact,
+			HrmpNewChannelOpenRequest,
+			HrmpChannelAccepted,
+			HrmpChannelClosing,
+			ClearOrigin,
+			DescendOrigin,
+			ReportError,
+			DepositAsset,
+			DepositReserveAsset,
+			ExchangeAsset,
+			InitiateReserveWithdraw,
+			InitiateTeleport,
+			QueryHolding,
+			BuyExecution,
+			RefundSurplus,
+			SetErrorHandler,
+			SetAppendix,
+			ClearError,
+			ClaimAsset,
+			Trap,
+			SubscribeVersion,
+			UnsubscribeVersion,
+		]
+	};
+
+	/// Returns the instructions allowed for `class`.
+	pub fn for_class(&self, class: OriginClass) -> &'static [InstructionKind] {
+		match class {
+			OriginClass::Relay => self.relay,
+			OriginClass::Sibling => self.sibling,
+			OriginClass::Local => self.local,
+		}
+	}
+
+	/// Returns `true` if `origin` may execute all `instructions`.
+	///
+	/// The instructions of nested programs, i.e. of error handlers and appendices, are checked as
+	/// well.
+	pub fn allows<Call>(&self, origin: &MultiLocation, instructions: &[Instruction<Call>]) -> bool {
+		match OriginClass::of(origin) {
+			Some(class) => self.allows_for_class(class, instructions),
+			None => false,
+		}
+	}
+
+	fn allows_for_class<Call>(
+		&self,
+		class: OriginClass,
+		instructions: &[Instruction<Call>],
+	) -> bool {
+		let allowed = self.for_class(class);
+
+		instructions.iter().all(|instruction| {
+			allowed.contains(&InstructionKind::of(instruction)) &&
+				match instruction {
+					SetErrorHandler(xcm) | SetAppendix(xcm) => self.allows_for_class(class, &xcm.0),
+					_ => true,
+				}
+		})
+	}
+}
+
+/// Barrier that rejects messages containing instructions not allowed for their origin.
+///
+/// Messages that pass the [`InstructionAllowList`] given by `AllowList` are handed to the `Inner`
+/// barrier, which decides whether they are executed.
+pub struct WithInstructionAllowList<AllowList, Inner>(PhantomData<(AllowList, Inner)>);
+
+impl<AllowList, Inner> ShouldExecute for WithInstructionAllowList<AllowList, Inner>
+where
+	AllowList: frame_support::traits::Get<InstructionAllowList>,
+	Inner: ShouldExecute,
+{
+	fn should_execute<Call>(
+		origin: &MultiLocation,
+		message: &mut Xcm<Call>,
+		max_weight: XcmWeight,
+		weight_credit: &mut XcmWeight,
+	) -> Result<(), ()> {
+		if !AllowList::get().allows(origin, &message.0) {
+			return Err(())
+		}
+
+		Inner::should_execute(origin, message, max_weight, weight_credit)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{InstructionKind as K, *};
+	use frame_support::parameter_types;
+
+	parameter_types! {
+		pub const AllowedInstructions: InstructionAllowList = InstructionAllowList {
+			relay: &[K::ReceiveTeleportedAsset, K::ClearOrigin, K::SetAppendix, K::DepositAsset],
+			sibling: &[K::ReserveAssetDeposited, K::BuyExecution],
+			local: InstructionAllowList::ALL,
+		};
+	}
+
+	struct AllowAll;
+	impl ShouldExecute for AllowAll {
+		fn should_execute<Call>(
+			_: &MultiLocation,
+			_: &mut Xcm<Call>,
+			_: XcmWeight,
+			_: &mut XcmWeight,
+		) -> Result<(), ()> {
+			Ok(())
+		}
+	}
+
+	type Barrier = WithInstructionAllowList<AllowedInstructions, AllowAll>;
+
+	fn should_execute(origin: MultiLocation, instructions: Vec<Instruction<()>>) -> bool {
+		Barrier::should_execute(&origin, &mut Xcm(instructions), 100, &mut 0).is_ok()
+	}
+
+	fn sibling() -> MultiLocation {
+		MultiLocation::new(1, X1(Parachain(1000)))
+	}
+
+	#[test]
+	fn classifies_origins() {
+		assert_eq!(OriginClass::of(&MultiLocation::parent()), Some(OriginClass::Relay));
+		assert_eq!(
+			OriginClass::of(&MultiLocation::new(
+				1,
+				X1(Plurality { id: BodyId::Executive, part: BodyPart::Voice })
+			)),
+			Some(OriginClass::Relay),
+		);
+		assert_eq!(OriginClass::of(&sibling()), Some(OriginClass::Sibling));
+		assert_eq!(OriginClass::of(&MultiLocation::here()), Some(OriginClass::Local));
+		assert_eq!(
+			OriginClass::of(&MultiLocation::new(0, X1(PalletInstance(10)))),
+			Some(OriginClass::Local),
+		);
+		assert_eq!(OriginClass::of(&MultiLocation::new(2, Here)), None);
+	}
+
+	#[test]
+	fn filters_instructions_per_origin_class() {
+		let teleport = vec![ReceiveTeleportedAsset(MultiAssets::new()), ClearOrigin];
+
+		assert!(should_execute(MultiLocation::parent(), teleport.clone()));
+		assert!(!should_execute(sibling(), teleport.clone()));
+		assert!(should_execute(MultiLocation::here(), teleport));
+
+		let reserve = vec![ReserveAssetDeposited(MultiAssets::new())];
+
+		assert!(!should_execute(MultiLocation::parent(), reserve.clone()));
+		assert!(should_execute(sibling(), reserve));
+		assert!(!should_execute(MultiLocation::new(2, Here), vec![ClearOrigin]));
+	}
+
+	#[test]
+	fn filters_nested_instructions() {
+		assert!(should_execute(MultiLocation::parent(), vec![SetAppendix(Xcm(vec![ClearOrigin]))]));
+		assert!(!should_execute(MultiLocation::parent(), vec![SetAppendix(Xcm(vec![Trap(1)]))]));
+	}
+
+	#[test]
+	fn all_contains_every_instruction_once() {
+		let all = InstructionAllowList::ALL;
+
+		assert_eq!(all.len(), 28);
+		assert!(all.iter().enumerate().all(|(i, kind)| !all[..i].contains(kind)));
+	}
+}
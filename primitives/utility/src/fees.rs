@@ -0,0 +1,384 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Fees charged for the execution of inbound XCMP and DMP messages.
+//!
+//! The message queue pallets ask their [`FeeHandler`] to charge the origin of a message before
+//! handing the message to the XCM executor. A message whose fee can't be paid isn't executed.
+//! After the execution, the origin is refunded for the weight the message didn't use.
+
+use frame_support::{
+	traits::{ExistenceRequirement, Get, WithdrawReasons},
+	weights::{RuntimeDbWeight, WeightToFeePolynomial},
+};
+use sp_runtime::traits::{Saturating, UniqueSaturatedInto};
+use sp_std::marker::PhantomData;
+use xcm::latest::{prelude::*, Weight as XcmWeight};
+use xcm_executor::traits::{Convert, WeightBounds};
+
+/// Charges the origin of an inbound message for its execution.
+pub trait FeeHandler<Call> {
+	/// Charge `origin` for the execution of `message`, which may use up to `weight_limit`.
+	///
+	/// Returns the charged amount. An error prevents the message from being executed, and
+	/// `XcmError::WeightLimitReached` is handled as if it had been returned by the executor.
+	fn charge_fee(
+		origin: &MultiLocation,
+		message: &mut Xcm<Call>,
+		weight_limit: XcmWeight,
+	) -> Result<u128, XcmError>;
+
+	/// Refund `origin`, which was charged `fee` for a message that used `weight_used`.
+	///
+	/// Called after the execution of every message with a non-zero fee, also if the execution
+	/// failed. Returns the refunded amount.
+	fn refund_fee(origin: &MultiLocation, fee: u128, weight_used: XcmWeight) -> u128;
+
+	/// The weight of the storage accesses of charging and refunding one message.
+	///
+	/// Added by the message queues to the weight of every message they try to charge.
+	fn storage_weight() -> XcmWeight;
+}
+
+impl<Call> FeeHandler<Call> for () {
+	fn charge_fee(_: &MultiLocation, _: &mut Xcm<Call>, _: XcmWeight) -> Result<u128, XcmError> {
+		Ok(0)
+	}
+
+	fn refund_fee(_: &MultiLocation, _: u128, _: XcmWeight) -> u128 {
+		0
+	}
+
+	fn storage_weight() -> XcmWeight {
+		0
+	}
+}
+
+/// Returns `true` if `message` pays for all of its `weight` upfront.
+///
+/// This is the prefix `AllowTopLevelPaidExecutionFrom` checks: an instruction that puts assets
+/// into the holding register, an optional `ClearOrigin` and a `BuyExecution` of at least
+/// `weight`. Unlike that barrier, the weight limit of `BuyExecution` is not raised to `weight`,
+/// so an `Unlimited` limit, which the executor doesn't charge for, is not accepted.
+fn buys_execution_upfront<Call>(message: &Xcm<Call>, weight: XcmWeight) -> bool {
+	let mut instructions = message.0.iter();
+	if !matches!(
+		instructions.next(),
+		Some(
+			ReceiveTeleportedAsset(..) |
+				WithdrawAsset(..) |
+				ReserveAssetDeposited(..) |
+				ClaimAsset { .. }
+		)
+	) {
+		return false
+	}
+	let mut next = instructions.next();
+	if matches!(next, Some(ClearOrigin)) {
+		next = instructions.next();
+	}
+	matches!(next, Some(BuyExecution { weight_limit: Limited(limit), .. }) if *limit >= weight)
+}
+
+/// Withdraws the fee for the weight of a message from the account of its origin and burns it.
+///
+/// The weight is determined by `Weigher` and converted to a fee with `WeightToFee`. Messages that
+/// would exceed the weight limit are not charged. The fee for the weight a message didn't use,
+/// e.g. because its execution failed, is refunded afterwards.
+///
+/// This replaces paying with `BuyExecution`, so it is meant for barriers that let the charged
+/// origins execute without paying, e.g. `AllowUnpaidExecutionFrom`. Only messages that start by
+/// buying execution for all of their weight are not charged, as they already pay through the
+/// `Trader` of the executor. A `BuyExecution` anywhere else doesn't make a message free, as the
+/// instructions before it would run unpaid.
+///
+/// `DbWeight` is used to weigh the account and total issuance updates of charging and refunding.
+pub struct BurnFromOriginAccount<
+	Weigher,
+	WeightToFee,
+	AccountIdConverter,
+	AccountId,
+	Currency,
+	DbWeight,
+>(PhantomData<(Weigher, WeightToFee, AccountIdConverter, AccountId, Currency, DbWeight)>);
+impl<
+		Call,
+		Weigher: WeightBounds<Call>,
+		WeightToFee: WeightToFeePolynomial<Balance = Currency::Balance>,
+		AccountIdConverter: Convert<MultiLocation, AccountId>,
+		AccountId,
+		Currency: frame_support::traits::Currency<AccountId>,
+		DbWeight: Get<RuntimeDbWeight>,
+	> FeeHandler<Call>
+	for BurnFromOriginAccount<Weigher, WeightToFee, AccountIdConverter, AccountId, Currency, DbWeight>
+{
+	fn charge_fee(
+		origin: &MultiLocation,
+		message: &mut Xcm<Call>,
+		weight_limit: XcmWeight,
+	) -> Result<u128, XcmError> {
+		let weight = Weigher::weight(message).map_err(|()| XcmError::WeightNotComputable)?;
+		if weight > weight_limit {
+			return Err(XcmError::WeightLimitReached(weight))
+		}
+		if buys_execution_upfront(message, weight) {
+			return Ok(0)
+		}
+		let who = AccountIdConverter::convert_ref(origin)
+			.map_err(|()| XcmError::FailedToTransactAsset("AccountIdConversionFailed"))?;
+		let fee = WeightToFee::calc(&weight);
+		// Dropping the imbalance burns the fee.
+		let _ =
+			Currency::withdraw(&who, fee, WithdrawReasons::FEE, ExistenceRequirement::KeepAlive)
+				.map_err(|_| XcmError::TooExpensive)?;
+		Ok(fee.unique_saturated_into())
+	}
+
+	fn refund_fee(origin: &MultiLocation, fee: u128, weight_used: XcmWeight) -> u128 {
+		let who = match AccountIdConverter::convert_ref(origin) {
+			Ok(who) => who,
+			Err(()) => return 0,
+		};
+		let fee: Currency::Balance = fee.unique_saturated_into();
+		let refund = fee.saturating_sub(WeightToFee::calc(&weight_used));
+		// Minting the refund undoes the burn of the unused part of the fee.
+		match Currency::deposit_into_existing(&who, refund) {
+			Ok(_) => refund.unique_saturated_into(),
+			Err(_) => 0,
+		}
+	}
+
+	fn storage_weight() -> XcmWeight {
+		// The account and the total issuance, once for the withdrawal and once for the refund.
+		DbWeight::get().reads_writes(4, 4)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::{
+		parameter_types,
+		traits::{ConstU32, ConstU64, Everything},
+		weights::IdentityFee,
+	};
+	use sp_core::H256;
+	use sp_runtime::{
+		testing::Header,
+		traits::{BlakeTwo256, IdentityLookup},
+	};
+	use sp_std::borrow::Borrow;
+
+	type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+	type Block = frame_system::mocking::MockBlock<Test>;
+
+	frame_support::construct_runtime!(
+		pub enum Test where
+			Block = Block,
+			NodeBlock = Block,
+			UncheckedExtrinsic = UncheckedExtrinsic,
+		{
+			System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+			Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		}
+	);
+
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+	}
+
+	impl frame_system::Config for Test {
+		type BaseCallFilter = Everything;
+		type BlockWeights = ();
+		type BlockLength = ();
+		type DbWeight = ();
+		type Origin = Origin;
+		type Call = Call;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = Event;
+		type BlockHashCount = BlockHashCount;
+		type Version = ();
+		type PalletInfo = PalletInfo;
+		type AccountData = pallet_balances::AccountData<u64>;
+		type OnNewAccount = ();
+		type OnKilledAccount = ();
+		type SystemWeightInfo = ();
+		type SS58Prefix = ();
+		type OnSetCode = ();
+		type MaxConsumers = ConstU32<16>;
+	}
+
+	impl pallet_balances::Config for Test {
+		type Balance = u64;
+		type Event = Event;
+		type DustRemoval = ();
+		type ExistentialDeposit = ConstU64<1>;
+		type AccountStore = System;
+		type WeightInfo = ();
+		type MaxLocks = ();
+		type MaxReserves = ();
+		type ReserveIdentifier = [u8; 8];
+	}
+
+	/// Every instruction weighs 100.
+	struct MockWeigher;
+	impl WeightBounds<()> for MockWeigher {
+		fn weight(message: &mut Xcm<()>) -> Result<XcmWeight, ()> {
+			Ok(message.0.len() as XcmWeight * 100)
+		}
+
+		fn instr_weight(_: &Instruction<()>) -> Result<XcmWeight, ()> {
+			Ok(100)
+		}
+	}
+
+	/// Maps sibling parachains to the account with the same id.
+	struct SiblingToAccountId;
+	impl Convert<MultiLocation, u64> for SiblingToAccountId {
+		fn convert_ref(location: impl Borrow<MultiLocation>) -> Result<u64, ()> {
+			match location.borrow() {
+				MultiLocation { parents: 1, interior: X1(Parachain(id)) } => Ok(*id as u64),
+				_ => Err(()),
+			}
+		}
+	}
+
+	type Handler =
+		BurnFromOriginAccount<MockWeigher, IdentityFee<u64>, SiblingToAccountId, u64, Balances, ()>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_balances::GenesisConfig::<Test> { balances: vec![(1000, 1000)] }
+			.assimilate_storage(&mut t)
+			.unwrap();
+		t.into()
+	}
+
+	fn sibling(id: u32) -> MultiLocation {
+		MultiLocation::new(1, X1(Parachain(id)))
+	}
+
+	fn charge_fee(
+		origin: MultiLocation,
+		instructions: Vec<Instruction<()>>,
+		weight_limit: XcmWeight,
+	) -> Result<u128, XcmError> {
+		<Handler as FeeHandler<()>>::charge_fee(&origin, &mut Xcm(instructions), weight_limit)
+	}
+
+	fn refund_fee(origin: MultiLocation, fee: u128, weight_used: XcmWeight) -> u128 {
+		<Handler as FeeHandler<()>>::refund_fee(&origin, fee, weight_used)
+	}
+
+	#[test]
+	fn charges_and_burns_the_fee() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(charge_fee(sibling(1000), vec![ClearOrigin, ClearOrigin], 1000), Ok(200));
+			assert_eq!(Balances::free_balance(1000), 800);
+			assert_eq!(Balances::total_issuance(), 800);
+		});
+	}
+
+	#[test]
+	fn rejects_messages_that_can_not_be_paid_or_are_overweight() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(
+				charge_fee(sibling(1000), vec![ClearOrigin; 3], 200),
+				Err(XcmError::WeightLimitReached(300)),
+			);
+			assert_eq!(
+				charge_fee(sibling(2000), vec![ClearOrigin; 3], 1000),
+				Err(XcmError::TooExpensive),
+			);
+			assert_eq!(
+				charge_fee(MultiLocation::parent(), vec![ClearOrigin; 3], 1000),
+				Err(XcmError::FailedToTransactAsset("AccountIdConversionFailed")),
+			);
+
+			// The account is kept alive.
+			assert_eq!(
+				charge_fee(sibling(1000), vec![ClearOrigin; 10], 1000),
+				Err(XcmError::TooExpensive),
+			);
+			assert_eq!(Balances::free_balance(1000), 1000);
+		});
+	}
+
+	#[test]
+	fn does_not_charge_messages_that_buy_execution_upfront() {
+		new_test_ext().execute_with(|| {
+			let instructions = vec![
+				WithdrawAsset(MultiAssets::new()),
+				ClearOrigin,
+				BuyExecution { fees: (Here, 100).into(), weight_limit: Limited(300) },
+			];
+			assert_eq!(charge_fee(sibling(1000), instructions, 1000), Ok(0));
+			assert_eq!(Balances::free_balance(1000), 1000);
+		});
+	}
+
+	#[test]
+	fn charges_messages_that_do_not_buy_execution_upfront() {
+		new_test_ext().execute_with(|| {
+			// A trailing `BuyExecution` would only run after the rest of the message.
+			let instructions = vec![
+				WithdrawAsset(MultiAssets::new()),
+				ClearOrigin,
+				BuyExecution { fees: (Here, 100).into(), weight_limit: Limited(300) },
+			];
+			let mut trailing = vec![ClearOrigin];
+			trailing.extend(instructions);
+			assert_eq!(charge_fee(sibling(1000), trailing, 1000), Ok(400));
+
+			// The executor doesn't charge for an `Unlimited` `BuyExecution`.
+			let instructions = vec![
+				WithdrawAsset(MultiAssets::new()),
+				BuyExecution { fees: (Here, 100).into(), weight_limit: Unlimited },
+			];
+			assert_eq!(charge_fee(sibling(1000), instructions, 1000), Ok(200));
+
+			// Neither does buying less than the weight of the message.
+			let instructions = vec![
+				WithdrawAsset(MultiAssets::new()),
+				BuyExecution { fees: (Here, 100).into(), weight_limit: Limited(100) },
+			];
+			assert_eq!(charge_fee(sibling(1000), instructions, 1000), Ok(200));
+
+			assert_eq!(Balances::free_balance(1000), 200);
+		});
+	}
+
+	#[test]
+	fn refunds_unused_weight() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(charge_fee(sibling(1000), vec![ClearOrigin; 3], 1000), Ok(300));
+
+			assert_eq!(refund_fee(sibling(1000), 300, 100), 200);
+			assert_eq!(Balances::free_balance(1000), 900);
+			assert_eq!(Balances::total_issuance(), 900);
+
+			// Nothing is refunded if all of the weight was used.
+			assert_eq!(refund_fee(sibling(1000), 100, 100), 0);
+			assert_eq!(Balances::free_balance(1000), 900);
+		});
+	}
+}
@@ -24,6 +24,9 @@ use cumulus_primitives_core::UpwardMessageSender;
 use sp_std::marker::PhantomData;
 use xcm::{latest::prelude::*, WrapVersion};
 
+pub mod barriers;
+pub mod fees;
+
 /// Xcm router which recognises the `Parent` destination and handles it by sending the message into
 /// the given UMP `UpwardMessageSender` implementation. Thus this essentially adapts an
 /// `UpwardMessageSender` trait impl into a `SendXcm` trait impl.
@@ -15,13 +15,16 @@
 // along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::{Backend, Client};
+use cumulus_primitives_author::AuthorPair;
 use cumulus_primitives_core::{ParachainBlockData, PersistedValidationData};
 use cumulus_primitives_parachain_inherent::{ParachainInherentData, INHERENT_IDENTIFIER};
 use cumulus_test_relay_sproof_builder::RelayStateSproofBuilder;
 use cumulus_test_runtime::{Block, GetLastTimestamp, Hash, Header};
+use pallet_author_inherent::AuthorInherentData;
 use polkadot_primitives::v2::{BlockNumber as PBlockNumber, Hash as PHash};
 use sc_block_builder::{BlockBuilder, BlockBuilderProvider};
 use sp_api::ProvideRuntimeApi;
+use sp_core::Pair;
 use sp_runtime::{
 	generic::BlockId,
 	traits::{Block as BlockT, Header as HeaderT},
@@ -32,7 +35,7 @@ pub trait InitBlockBuilder {
 	/// Init a specific block builder that works for the test runtime.
 	///
 	/// This will automatically create and push the inherents for you to make the block
-	/// valid for the test runtime.
+	/// valid for the test runtime. The block is authored by Alice.
 	///
 	/// You can use the relay chain state sproof builder to arrange required relay chain state or
 	/// just use a default one.
@@ -95,6 +98,27 @@ fn init_block_builder<'a>(
 	);
 	validation_data.relay_parent_storage_root = relay_parent_storage_root;
 
+	let author = AuthorPair::from(sp_keyring::Sr25519Keyring::Alice.pair());
+	let message = AuthorInherentData {
+		author: author.public(),
+		relay_parent_storage_root,
+		relay_parent_number: validation_data.relay_parent_number,
+		signature: (),
+	}
+	.signing_message();
+
+	inherent_data
+		.put_data(
+			pallet_author_inherent::INHERENT_IDENTIFIER,
+			&AuthorInherentData {
+				author: author.public(),
+				relay_parent_storage_root,
+				relay_parent_number: validation_data.relay_parent_number,
+				signature: author.sign(&message),
+			},
+		)
+		.expect("Put author failed");
+
 	inherent_data
 		.put_data(
 			INHERENT_IDENTIFIER,
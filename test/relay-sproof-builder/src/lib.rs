@@ -15,9 +15,9 @@
 // along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
 
 use cumulus_primitives_core::{
-	relay_chain, AbridgedHostConfiguration, AbridgedHrmpChannel, ParaId,
+	relay_chain, AbridgedHostConfiguration, AbridgedHrmpChannel, ParaId, PersistedValidationData,
 };
-use polkadot_primitives::v2::UpgradeGoAhead;
+use polkadot_primitives::v2::{UpgradeGoAhead, UpgradeRestriction};
 use sp_runtime::traits::HashFor;
 use sp_state_machine::MemoryDB;
 use sp_std::collections::btree_map::BTreeMap;
@@ -38,11 +38,15 @@ pub struct RelayStateSproofBuilder {
 	pub host_config: AbridgedHostConfiguration,
 	pub dmq_mqc_head: Option<relay_chain::Hash>,
 	pub upgrade_go_ahead: Option<UpgradeGoAhead>,
+	pub upgrade_restriction: Option<UpgradeRestriction>,
 	pub relay_dispatch_queue_size: Option<(u32, u32)>,
 	pub hrmp_ingress_channel_index: Option<Vec<ParaId>>,
 	pub hrmp_egress_channel_index: Option<Vec<ParaId>>,
 	pub hrmp_channels: BTreeMap<relay_chain::v2::HrmpChannelId, AbridgedHrmpChannel>,
 	pub current_slot: relay_chain::v2::Slot,
+
+	/// Additional raw key-value pairs that should be injected.
+	pub additional_key_values: Vec<(Vec<u8>, Vec<u8>)>,
 }
 
 impl Default for RelayStateSproofBuilder {
@@ -62,11 +66,13 @@ impl Default for RelayStateSproofBuilder {
 			},
 			dmq_mqc_head: None,
 			upgrade_go_ahead: None,
+			upgrade_restriction: None,
 			relay_dispatch_queue_size: None,
 			hrmp_ingress_channel_index: None,
 			hrmp_egress_channel_index: None,
 			hrmp_channels: BTreeMap::new(),
 			current_slot: 0.into(),
+			additional_key_values: Vec::new(),
 		}
 	}
 }
@@ -95,6 +101,46 @@ impl RelayStateSproofBuilder {
 			})
 	}
 
+	/// Returns a mutable reference to HRMP channel metadata for a channel (`self.para_id`, `recipient`).
+	///
+	/// If there is no channel, a new default one is created.
+	///
+	/// It also updates the `hrmp_egress_channel_index`, creating it if needed.
+	pub fn upsert_outbound_channel(&mut self, recipient: ParaId) -> &mut AbridgedHrmpChannel {
+		let out_index = self.hrmp_egress_channel_index.get_or_insert_with(Vec::new);
+		if let Err(idx) = out_index.binary_search(&recipient) {
+			out_index.insert(idx, recipient);
+		}
+
+		self.hrmp_channels
+			.entry(relay_chain::v2::HrmpChannelId { sender: self.para_id, recipient })
+			.or_insert_with(|| AbridgedHrmpChannel {
+				max_capacity: 0,
+				max_total_size: 0,
+				max_message_size: 0,
+				msg_count: 0,
+				total_size: 0,
+				mqc_head: None,
+			})
+	}
+
+	/// Build the relay state proof, and the [`PersistedValidationData`] of a parachain block that
+	/// is built on that state at `relay_parent_number`.
+	///
+	/// The other fields of the validation data are left at their defaults.
+	pub fn into_validation_data_and_proof(
+		self,
+		relay_parent_number: relay_chain::v2::BlockNumber,
+	) -> (PersistedValidationData, sp_state_machine::StorageProof) {
+		let (relay_parent_storage_root, proof) = self.into_state_root_and_proof();
+		let validation_data = PersistedValidationData {
+			relay_parent_number,
+			relay_parent_storage_root,
+			..Default::default()
+		};
+		(validation_data, proof)
+	}
+
 	pub fn into_state_root_and_proof(
 		self,
 	) -> (polkadot_primitives::v2::Hash, sp_state_machine::StorageProof) {
@@ -130,6 +176,12 @@ impl RelayStateSproofBuilder {
 					upgrade_go_ahead.encode(),
 				);
 			}
+			if let Some(upgrade_restriction) = self.upgrade_restriction {
+				insert(
+					relay_chain::well_known_keys::upgrade_restriction_signal(self.para_id),
+					upgrade_restriction.encode(),
+				);
+			}
 			if let Some(hrmp_ingress_channel_index) = self.hrmp_ingress_channel_index {
 				let mut sorted = hrmp_ingress_channel_index.clone();
 				sorted.sort();
@@ -155,6 +207,10 @@ impl RelayStateSproofBuilder {
 			}
 
 			insert(relay_chain::well_known_keys::CURRENT_SLOT.to_vec(), self.current_slot.encode());
+
+			for (key, value) in self.additional_key_values {
+				insert(key, value);
+			}
 		}
 
 		let root = backend.root().clone();
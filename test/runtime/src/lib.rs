@@ -29,12 +29,15 @@ pub mod wasm_spec_version_incremented {
 
 mod test_pallet;
 
-use frame_support::traits::OnRuntimeUpgrade;
+use cumulus_primitives_author::AuthorId;
+use cumulus_primitives_core::relay_chain::BlockNumber as RelayBlockNumber;
+use frame_support::traits::{ConstU32, OnRuntimeUpgrade};
+use frame_system::EnsureRoot;
 use sp_api::{decl_runtime_apis, impl_runtime_apis};
-use sp_core::OpaqueMetadata;
+use sp_core::{sr25519, OpaqueMetadata};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
-	traits::{BlakeTwo256, Block as BlockT, IdentifyAccount, IdentityLookup, Verify},
+	traits::{BlakeTwo256, Block as BlockT, Convert, IdentifyAccount, IdentityLookup, Verify},
 	transaction_validity::{TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
 };
@@ -54,6 +57,7 @@ pub use frame_support::{
 	StorageValue,
 };
 use frame_system::limits::{BlockLength, BlockWeights};
+pub use pallet_author_slot_filter::Call as AuthorSlotFilterCall;
 pub use pallet_balances::Call as BalancesCall;
 pub use pallet_sudo::Call as SudoCall;
 pub use pallet_timestamp::Call as TimestampCall;
@@ -273,6 +277,34 @@ parameter_types! {
 
 impl test_pallet::Config for Runtime {}
 
+impl pallet_author_inherent::Config for Runtime {
+	type AuthorId = AuthorId;
+	type RelayChainState = ParachainSystem;
+	type CanAuthor = AuthorSlotFilter;
+	type AccountLookup = ();
+	type EventHandler = ();
+	type MaxRecentAuthors = ConstU32<16>;
+	type WeightInfo = ();
+}
+
+/// Uses the author key itself as VRF key.
+pub struct AuthorVrfKey;
+impl Convert<AuthorId, Option<sr25519::Public>> for AuthorVrfKey {
+	fn convert(author: AuthorId) -> Option<sr25519::Public> {
+		Some(author.into())
+	}
+}
+
+impl pallet_author_slot_filter::Config for Runtime {
+	type Event = Event;
+	type AuthorId = AuthorId;
+	type UpdateOrigin = EnsureRoot<AccountId>;
+	type MaxAuthors = ConstU32<8>;
+	type AuthorVrfKey = AuthorVrfKey;
+	type RelayChainState = ParachainSystem;
+	type WeightInfo = ();
+}
+
 construct_runtime! {
 	pub enum Runtime where
 		Block = Block,
@@ -286,6 +318,8 @@ construct_runtime! {
 		Sudo: pallet_sudo,
 		TransactionPayment: pallet_transaction_payment,
 		TestPallet: test_pallet,
+		AuthorInherent: pallet_author_inherent,
+		AuthorSlotFilter: pallet_author_slot_filter,
 	}
 }
 
@@ -439,6 +473,28 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_author_slot_filter::AuthorFilterApi<Block, AuthorId> for Runtime {
+		fn can_author(author: AuthorId, relay_parent: RelayBlockNumber) -> bool {
+			AuthorSlotFilter::can_author(&author, relay_parent)
+		}
+
+		fn eligible_authors(relay_parent: RelayBlockNumber) -> Vec<AuthorId> {
+			AuthorSlotFilter::eligible_authors(relay_parent)
+		}
+
+		fn eligibility_mode() -> pallet_author_slot_filter::EligibilityMode {
+			AuthorSlotFilter::eligibility_mode()
+		}
+
+		fn eligible_ratio() -> sp_runtime::Percent {
+			AuthorSlotFilter::eligible_ratio()
+		}
+
+		fn block_cadence() -> u32 {
+			AuthorSlotFilter::block_cadence()
+		}
+	}
+
 	impl cumulus_primitives_core::CollectCollationInfo<Block> for Runtime {
 		fn collect_collation_info(header: &<Block as BlockT>::Header) -> cumulus_primitives_core::CollationInfo {
 			ParachainSystem::collect_collation_info(header)
@@ -16,13 +16,17 @@
 
 #![allow(missing_docs)]
 
+use cumulus_primitives_author::AuthorId;
 use cumulus_primitives_core::ParaId;
 use cumulus_test_runtime::{AccountId, Signature};
 use sc_chain_spec::{ChainSpecExtension, ChainSpecGroup};
 use sc_service::ChainType;
 use serde::{Deserialize, Serialize};
 use sp_core::{sr25519, Pair, Public};
-use sp_runtime::traits::{IdentifyAccount, Verify};
+use sp_runtime::{
+	traits::{IdentifyAccount, Verify},
+	Percent,
+};
 
 /// Specialized `ChainSpec` for the normal parachain runtime.
 pub type ChainSpec = sc_service::GenericChainSpec<GenesisExt, Extensions>;
@@ -113,12 +117,21 @@ pub fn local_testnet_genesis() -> cumulus_test_runtime::GenesisConfig {
 			get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
 			get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
 		],
+		vec![
+			get_from_seed::<AuthorId>("Alice"),
+			get_from_seed::<AuthorId>("Bob"),
+			get_from_seed::<AuthorId>("Charlie"),
+			get_from_seed::<AuthorId>("Dave"),
+			get_from_seed::<AuthorId>("Eve"),
+			get_from_seed::<AuthorId>("Ferdie"),
+		],
 	)
 }
 
 fn testnet_genesis(
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
+	authors: Vec<AuthorId>,
 ) -> cumulus_test_runtime::GenesisConfig {
 	cumulus_test_runtime::GenesisConfig {
 		system: cumulus_test_runtime::SystemConfig {
@@ -133,5 +146,11 @@ fn testnet_genesis(
 		},
 		sudo: cumulus_test_runtime::SudoConfig { key: Some(root_key) },
 		transaction_payment: Default::default(),
+		// Every author is eligible for every relay parent, so that any collator can author blocks.
+		author_slot_filter: cumulus_test_runtime::AuthorSlotFilterConfig {
+			authors,
+			eligible_ratio: Percent::from_percent(100),
+			..Default::default()
+		},
 	}
 }
@@ -30,10 +30,12 @@ use url::Url;
 
 use cumulus_client_cli::CollatorOptions;
 use cumulus_client_consensus_common::{ParachainCandidate, ParachainConsensus};
+use cumulus_client_consensus_relay_chain::AuthorSelector;
 use cumulus_client_network::BlockAnnounceValidator;
 use cumulus_client_service::{
 	prepare_node_config, start_collator, start_full_node, StartCollatorParams, StartFullNodeParams,
 };
+use cumulus_primitives_author::{AuthorId, KEY_TYPE};
 use cumulus_primitives_core::ParaId;
 use cumulus_relay_chain_inprocess_interface::RelayChainInProcessInterface;
 use cumulus_relay_chain_interface::{RelayChainError, RelayChainInterface, RelayChainResult};
@@ -42,7 +44,10 @@ use cumulus_test_runtime::{Hash, Header, NodeBlock as Block, RuntimeApi};
 use parking_lot::Mutex;
 
 use frame_system_rpc_runtime_api::AccountNonceApi;
-use polkadot_primitives::v2::{CollatorPair, Hash as PHash, PersistedValidationData};
+use pallet_author_slot_filter::AuthorFilterApi;
+use polkadot_primitives::v2::{
+	BlockNumber as PBlockNumber, CollatorPair, Hash as PHash, PersistedValidationData,
+};
 use polkadot_service::ProvideRuntimeApi;
 use sc_client_api::execution_extensions::ExecutionStrategies;
 use sc_network::{config::TransportConfig, multiaddr, NetworkService};
@@ -58,7 +63,8 @@ use sp_arithmetic::traits::SaturatedConversion;
 use sp_blockchain::HeaderBackend;
 use sp_core::{Pair, H256};
 use sp_keyring::Sr25519Keyring;
-use sp_runtime::{codec::Encode, generic, traits::BlakeTwo256};
+use sp_keystore::SyncCryptoStore;
+use sp_runtime::{codec::Encode, generic, generic::BlockId, traits::BlakeTwo256};
 use sp_state_machine::BasicExternalities;
 use sp_trie::PrefixedMemoryDB;
 use std::sync::Arc;
@@ -182,7 +188,13 @@ async fn build_relay_chain_interface(
 	task_manager: &mut TaskManager,
 ) -> RelayChainResult<Arc<dyn RelayChainInterface + 'static>> {
 	if let Some(relay_chain_url) = collator_options.relay_chain_rpc_url {
-		return Ok(Arc::new(RelayChainRPCInterface::new(relay_chain_url).await?) as Arc<_>)
+		let relay_chain_interface = RelayChainRPCInterface::new(
+			relay_chain_url,
+			collator_options.relay_chain_rpc_fallback_urls,
+			None,
+		)
+		.await?;
+		return Ok(Arc::new(relay_chain_interface) as Arc<_>)
 	}
 
 	let relay_chain_full_node = polkadot_test_service::new_full(
@@ -234,8 +246,10 @@ where
 	}
 
 	let mut parachain_config = prepare_node_config(parachain_config);
+	let author_seed = parachain_config.dev_key_seed.clone();
 
 	let params = new_partial(&mut parachain_config)?;
+	let keystore = params.keystore_container.sync_keystore();
 
 	let transaction_pool = params.transaction_pool.clone();
 	let mut task_manager = params.task_manager;
@@ -284,7 +298,7 @@ where
 		transaction_pool: transaction_pool.clone(),
 		task_manager: &mut task_manager,
 		config: parachain_config,
-		keystore: params.keystore_container.sync_keystore(),
+		keystore: keystore.clone(),
 		backend,
 		network: network.clone(),
 		system_rpc_tx,
@@ -300,10 +314,37 @@ where
 		.map(|w| (w)(announce_block.clone()))
 		.unwrap_or_else(|| announce_block);
 
+	let relay_chain_slot_duration = Duration::from_secs(6);
+
 	let relay_chain_interface_for_closure = relay_chain_interface.clone();
 	if let Some(collator_key) = collator_key {
 		let parachain_consensus: Box<dyn ParachainConsensus<Block>> = match consensus {
-			Consensus::RelayChain => {
+			Consensus::RelayChain { ignore_author_eligibility } => {
+				// The collator authors its blocks with the author key of its dev seed.
+				if let Some(seed) = author_seed {
+					SyncCryptoStore::sr25519_generate_new(&*keystore, KEY_TYPE, Some(&seed))
+						.map_err(|e| format!("Failed to insert the author key: {:?}", e))?;
+				}
+
+				let author_selector = {
+					let client = client.clone();
+					AuthorSelector::new(
+						keystore,
+						move |parent: Hash, author: &AuthorId, relay_parent: PBlockNumber| {
+							ignore_author_eligibility ||
+								client
+									.runtime_api()
+									.can_author(
+										&BlockId::Hash(parent),
+										author.clone(),
+										relay_parent,
+									)
+									.unwrap_or(false)
+						},
+					)
+				};
+				let author_selector2 = author_selector.clone();
+
 				let proposer_factory = sc_basic_authorship::ProposerFactory::with_proof_recording(
 					task_manager.spawn_handle(),
 					client.clone(),
@@ -313,31 +354,55 @@ where
 				);
 				let relay_chain_interface2 = relay_chain_interface_for_closure.clone();
 				Box::new(cumulus_client_consensus_relay_chain::RelayChainConsensus::new(
-					para_id,
-					proposer_factory,
-					move |_, (relay_parent, validation_data)| {
-						let relay_chain_interface = relay_chain_interface_for_closure.clone();
-						async move {
-							let parachain_inherent =
-							cumulus_primitives_parachain_inherent::ParachainInherentData::create_at(
-								relay_parent,
-								&relay_chain_interface,
-								&validation_data,
-								para_id,
-							).await;
-
-							let time = sp_timestamp::InherentDataProvider::from_system_time();
-
-							let parachain_inherent = parachain_inherent.ok_or_else(|| {
-								Box::<dyn std::error::Error + Send + Sync>::from(String::from(
-									"error",
-								))
-							})?;
-							Ok((time, parachain_inherent))
-						}
+					cumulus_client_consensus_relay_chain::BuildRelayChainConsensusParams {
+						para_id,
+						proposer_factory,
+						create_inherent_data_providers:
+							move |parent, (relay_parent, validation_data)| {
+								let relay_chain_interface =
+									relay_chain_interface_for_closure.clone();
+								let author = author_selector2.inherent_data_provider(
+									parent,
+									relay_parent,
+									&validation_data,
+								);
+								async move {
+									let parachain_inherent =
+										cumulus_primitives_parachain_inherent::ParachainInherentData::create_at(
+											relay_parent,
+											&relay_chain_interface,
+											&validation_data,
+											para_id,
+										).await;
+
+									let time =
+										sp_timestamp::InherentDataProvider::from_system_time();
+
+									let parachain_inherent =
+										parachain_inherent.ok_or_else(|| {
+											Box::<dyn std::error::Error + Send + Sync>::from(
+												String::from("error"),
+											)
+										})?;
+									let author = author.ok_or_else(|| {
+										Box::<dyn std::error::Error + Send + Sync>::from(
+											String::from("No author may author the block"),
+										)
+									})?;
+									Ok((time, parachain_inherent, author))
+								}
+							},
+						block_import: client.clone(),
+						relay_chain_interface: relay_chain_interface2,
+						sync_oracle: network.clone(),
+						additional_digests_provider: author_selector,
+						block_cadence: (),
+						max_proof_size: None,
+						max_relay_parent_lag: None,
+						relay_slot_duration: relay_chain_slot_duration,
+						prometheus_registry: prometheus_registry.clone(),
+						telemetry: None,
 					},
-					client.clone(),
-					relay_chain_interface2,
 				))
 			},
 			Consensus::Null => Box::new(NullConsensus),
@@ -354,7 +419,8 @@ where
 			relay_chain_interface,
 			collator_key,
 			import_queue,
-			relay_chain_slot_duration: Duration::from_secs(6),
+			relay_chain_slot_duration,
+			last_proof: None,
 		};
 
 		start_collator(params).await?;
@@ -400,7 +466,10 @@ pub struct TestNode {
 
 enum Consensus {
 	/// Use the relay-chain provided consensus.
-	RelayChain,
+	RelayChain {
+		/// Claim blocks with the author key even if it is not eligible.
+		ignore_author_eligibility: bool,
+	},
 	/// Use the null consensus that will never produce any block.
 	Null,
 }
@@ -439,7 +508,7 @@ impl TestNodeBuilder {
 			wrap_announce_block: None,
 			storage_update_func_parachain: None,
 			storage_update_func_relay_chain: None,
-			consensus: Consensus::RelayChain,
+			consensus: Consensus::RelayChain { ignore_author_eligibility: false },
 			relay_chain_full_node_url: None,
 		}
 	}
@@ -532,6 +601,15 @@ impl TestNodeBuilder {
 		self
 	}
 
+	/// Use the relay-chain consensus, but claim every block with the author key of this node,
+	/// whether the author is eligible or not.
+	///
+	/// The runtime rejects the blocks the author is not eligible for.
+	pub fn ignore_author_eligibility(mut self) -> Self {
+		self.consensus = Consensus::RelayChain { ignore_author_eligibility: true };
+		self
+	}
+
 	/// Connect to full node via RPC.
 	pub fn use_external_relay_chain_node_at_url(mut self, network_address: Url) -> Self {
 		self.relay_chain_full_node_url = Some(network_address);
@@ -568,8 +646,13 @@ impl TestNodeBuilder {
 			false,
 		);
 
-		let collator_options =
-			CollatorOptions { relay_chain_rpc_url: self.relay_chain_full_node_url };
+		let collator_options = CollatorOptions {
+			relay_chain_rpc_url: self.relay_chain_full_node_url,
+			relay_chain_rpc_fallback_urls: Vec::new(),
+			relay_chain_worker_threads: None,
+			relay_chain_nice: None,
+			relay_chain_io_priority: None,
+		};
 
 		relay_chain_config.network.node_name =
 			format!("{} (relay chain)", relay_chain_config.network.node_name);
@@ -0,0 +1,167 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use codec::{Decode, Encode};
+use cumulus_primitives_author::AuthorId;
+use cumulus_primitives_core::ParaId;
+use cumulus_test_service::{
+	construct_extrinsic, initial_head_data, run_relay_chain_validator_node, runtime, Keyring::*,
+};
+use futures::StreamExt;
+use pallet_author_slot_filter::AuthorFilterApi;
+use polkadot_service::ProvideRuntimeApi;
+use sc_client_api::{BlockBackend, BlockchainEvents};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Header as HeaderT, Percent};
+use substrate_test_client::RpcHandlersExt;
+
+/// Number of parachain blocks that are checked after the author filter was changed.
+const CHECKED_BLOCKS: u32 = 10;
+
+#[substrate_test_utils::test]
+#[ignore]
+async fn only_eligible_authors_author_blocks() {
+	let mut builder = sc_cli::LoggerBuilder::new("");
+	builder.with_colors(false);
+	let _ = builder.init();
+
+	let para_id = ParaId::from(100);
+	let tokio_handle = tokio::runtime::Handle::current();
+
+	// start alice
+	let alice =
+		run_relay_chain_validator_node(tokio_handle.clone(), Alice, || {}, Vec::new(), None);
+
+	// start bob
+	let bob = run_relay_chain_validator_node(
+		tokio_handle.clone(),
+		Bob,
+		|| {},
+		vec![alice.addr.clone()],
+		None,
+	);
+
+	// register parachain
+	alice
+		.register_parachain(
+			para_id,
+			cumulus_test_runtime::WASM_BINARY
+				.expect("You need to build the WASM binary to run this test!")
+				.to_vec(),
+			initial_head_data(para_id),
+		)
+		.await
+		.unwrap();
+
+	// run cumulus charlie (a parachain collator that only claims blocks it is eligible for)
+	let charlie =
+		cumulus_test_service::TestNodeBuilder::new(para_id, tokio_handle.clone(), Charlie)
+			.enable_collator()
+			.connect_to_relay_chain_nodes(vec![&alice, &bob])
+			.build()
+			.await;
+	charlie.wait_for_blocks(2).await;
+
+	// Only charlie and dave may author, one of them for every relay parent.
+	let calls: [runtime::Call; 2] = [
+		runtime::AuthorSlotFilterCall::<runtime::Runtime>::set_authors {
+			authors: vec![AuthorId::from(Charlie.public()), AuthorId::from(Dave.public())],
+		}
+		.into(),
+		runtime::AuthorSlotFilterCall::<runtime::Runtime>::set_eligible_ratio {
+			ratio: Percent::from_percent(50),
+		}
+		.into(),
+	];
+	for (nonce, call) in calls.into_iter().enumerate() {
+		let extrinsic = construct_extrinsic(
+			&charlie.client,
+			runtime::SudoCall::sudo { call: Box::new(call) },
+			Alice.pair(),
+			Some(nonce as u32),
+		);
+		charlie.rpc_handlers.send_transaction(extrinsic.into()).await.unwrap();
+	}
+
+	let mut import_stream = charlie.client.import_notification_stream();
+	let changed_at = loop {
+		let notification = import_stream.next().await.expect("Import notifications never end");
+		let ratio = charlie
+			.client
+			.runtime_api()
+			.eligible_ratio(&BlockId::Hash(notification.hash))
+			.expect("Eligible ratio exists");
+
+		if ratio == Percent::from_percent(50) {
+			break *notification.header.number()
+		}
+	};
+
+	// run cumulus dave (a parachain collator that claims every block, eligible or not)
+	let dave = cumulus_test_service::TestNodeBuilder::new(para_id, tokio_handle.clone(), Dave)
+		.enable_collator()
+		.ignore_author_eligibility()
+		.connect_to_parachain_node(&charlie)
+		.connect_to_relay_chain_nodes(vec![&alice, &bob])
+		.build()
+		.await;
+
+	// run cumulus eve (a parachain full node) and wait for it to import the blocks to check
+	let eve = cumulus_test_service::TestNodeBuilder::new(para_id, tokio_handle, Eve)
+		.connect_to_parachain_nodes([&charlie, &dave].into_iter())
+		.connect_to_relay_chain_nodes(vec![&alice, &bob])
+		.build()
+		.await;
+
+	let mut import_stream = eve.client.import_notification_stream();
+	while eve.client.info().best_number < changed_at + CHECKED_BLOCKS {
+		import_stream.next().await.expect("Import notifications never end");
+	}
+
+	// Every block eve imported must be claimed by an author that was eligible for its relay
+	// parent. The blocks dave claimed without being eligible must have been rejected.
+	for number in 1..=eve.client.info().best_number {
+		let data = eve
+			.client
+			.block_body(&BlockId::Number(number))
+			.expect("Reads the block body")
+			.expect("Imported blocks have a body")
+			.into_iter()
+			.find_map(|extrinsic| {
+				match runtime::UncheckedExtrinsic::decode(&mut &extrinsic.encode()[..])
+					.ok()?
+					.function
+				{
+					runtime::Call::AuthorInherent(pallet_author_inherent::Call::set_author {
+						data,
+					}) => Some(data),
+					_ => None,
+				}
+			})
+			.expect("Every block has an author");
+
+		let eligible = eve
+			.client
+			.runtime_api()
+			.can_author(&BlockId::Number(number - 1), data.author.clone(), data.relay_parent_number)
+			.expect("Checks the eligibility");
+		assert!(
+			eligible,
+			"Block {} was claimed by {:?}, which was not eligible",
+			number, data.author
+		);
+	}
+}